@@ -10,6 +10,7 @@ use ckb_notify::{ForkBlocks, NotifyController};
 use ckb_shared::error::SharedError;
 use ckb_shared::index::ChainIndex;
 use ckb_shared::shared::{ChainProvider, ChainState, Shared};
+use ckb_shared::snapshot::{self, SnapshotImport};
 use ckb_shared::txo_set::TxoSetDiff;
 use ckb_verification::{BlockVerifier, TransactionsVerifier, Verifier};
 use crossbeam_channel::{self, select, Receiver, Sender};
@@ -25,7 +26,8 @@ use stop_handler::{SignalSender, StopHandler};
 
 #[derive(Clone)]
 pub struct ChainController {
-    process_block_sender: Sender<Request<Arc<Block>, Result<(), ProcessBlockError>>>,
+    process_block_sender: Sender<Request<(Arc<Block>, bool), Result<(), ProcessBlockError>>>,
+    import_snapshot_sender: Sender<Request<SnapshotImport, Result<(), SharedError>>>,
     stop: StopHandler<()>,
 }
 
@@ -37,12 +39,26 @@ impl Drop for ChainController {
 
 impl ChainController {
     pub fn process_block(&self, block: Arc<Block>) -> Result<(), ProcessBlockError> {
-        Request::call(&self.process_block_sender, block).expect("process_block() failed")
+        Request::call(&self.process_block_sender, (block, false)).expect("process_block() failed")
+    }
+
+    // The block is the last checkpoint block or one of its ancestors, its transactions
+    // are assumed valid, only inputs are resolved and no script is executed.
+    pub fn process_checkpointed_block(&self, block: Arc<Block>) -> Result<(), ProcessBlockError> {
+        Request::call(&self.process_block_sender, (block, true))
+            .expect("process_checkpointed_block() failed")
+    }
+
+    // Replaces a chain at genesis with a verified snapshot, blocks after the snapshot block
+    // are then processed as usual.
+    pub fn import_snapshot(&self, import: SnapshotImport) -> Result<(), SharedError> {
+        Request::call(&self.import_snapshot_sender, import).expect("import_snapshot() failed")
     }
 }
 
 struct ChainReceivers {
-    process_block_receiver: Receiver<Request<Arc<Block>, Result<(), ProcessBlockError>>>,
+    process_block_receiver: Receiver<Request<(Arc<Block>, bool), Result<(), ProcessBlockError>>>,
+    import_snapshot_receiver: Receiver<Request<SnapshotImport, Result<(), SharedError>>>,
 }
 
 #[derive(Debug, Clone)]
@@ -103,6 +119,8 @@ impl<CI: ChainIndex + 'static> ChainService<CI> {
             crossbeam_channel::bounded::<()>(SIGNAL_CHANNEL_SIZE);
         let (process_block_sender, process_block_receiver) =
             crossbeam_channel::bounded(DEFAULT_CHANNEL_SIZE);
+        let (import_snapshot_sender, import_snapshot_receiver) =
+            crossbeam_channel::bounded(DEFAULT_CHANNEL_SIZE);
 
         // Mainly for test: give a empty thread_name
         let mut thread_builder = thread::Builder::new();
@@ -112,6 +130,7 @@ impl<CI: ChainIndex + 'static> ChainService<CI> {
 
         let receivers = ChainReceivers {
             process_block_receiver,
+            import_snapshot_receiver,
         };
         let thread = thread_builder
             .spawn(move || loop {
@@ -120,13 +139,22 @@ impl<CI: ChainIndex + 'static> ChainService<CI> {
                         break;
                     },
                     recv(receivers.process_block_receiver) -> msg => match msg {
-                        Ok(Request { responder, arguments: block }) => {
-                            let _ = responder.send(self.process_block(block));
+                        Ok(Request { responder, arguments: (block, checkpointed) }) => {
+                            let _ = responder.send(self.process_block(block, checkpointed));
                         },
                         _ => {
                             error!(target: "chain", "process_block_receiver closed");
                             break;
                         },
+                    },
+                    recv(receivers.import_snapshot_receiver) -> msg => match msg {
+                        Ok(Request { responder, arguments: import }) => {
+                            let _ = responder.send(self.import_snapshot(import));
+                        },
+                        _ => {
+                            error!(target: "chain", "import_snapshot_receiver closed");
+                            break;
+                        },
                     }
                 }
             })
@@ -135,14 +163,25 @@ impl<CI: ChainIndex + 'static> ChainService<CI> {
 
         ChainController {
             process_block_sender,
+            import_snapshot_sender,
             stop,
         }
     }
 
     // process_block will do block verify
     // but invoker should guarantee block header be verified
-    pub(crate) fn process_block(&mut self, block: Arc<Block>) -> Result<(), ProcessBlockError> {
+    pub(crate) fn process_block(
+        &mut self,
+        block: Arc<Block>,
+        checkpointed: bool,
+    ) -> Result<(), ProcessBlockError> {
         debug!(target: "chain", "begin processing block: {}", block.header().hash());
+        // only headers are stored up to an imported snapshot, forks there can't be verified
+        if let Some(snapshot_number) = self.shared.store().get_snapshot_number() {
+            if block.header().number() <= snapshot_number {
+                return Err(ProcessBlockError::Shared(SharedError::BelowSnapshot));
+            }
+        }
         if self.verification {
             let block_verifier = BlockVerifier::new(self.shared.clone());
             block_verifier
@@ -150,15 +189,69 @@ impl<CI: ChainIndex + 'static> ChainService<CI> {
                 .map_err(ProcessBlockError::Verification)?
         }
         let insert_result = self
-            .insert_block(&block)
+            .insert_block(&block, checkpointed)
             .map_err(ProcessBlockError::Shared)?;
         self.post_insert_result(block, insert_result);
         debug!(target: "chain", "finish processing block");
         Ok(())
     }
 
+    pub(crate) fn import_snapshot(&mut self, import: SnapshotImport) -> Result<(), SharedError> {
+        let SnapshotImport {
+            headers,
+            blocks,
+            transactions,
+        } = import;
+        let mut chain_state = self.shared.chain_state().write();
+        if chain_state.tip_number() != 0 {
+            return Err(SharedError::InvalidSnapshot);
+        }
+        let (tip, tip_ext) = headers
+            .last()
+            .cloned()
+            .ok_or(SharedError::InvalidSnapshot)?;
+        // the snapshot block itself must be stored in full to continue from it
+        if blocks.last().map(|block| block.header().hash()) != Some(tip.hash()) {
+            return Err(SharedError::InvalidSnapshot);
+        }
+
+        let store = self.shared.store();
+        store.save_with_batch(|batch| {
+            for (header, ext) in &headers {
+                let hash = header.hash();
+                store.insert_header(batch, header);
+                store.insert_block_ext(batch, &hash, ext);
+                store.insert_block_hash(batch, header.number(), &hash);
+                store.insert_block_number(batch, &hash, header.number());
+            }
+            for block in &blocks {
+                store.insert_block(batch, block);
+                store.insert_transaction_address(
+                    batch,
+                    &block.header().hash(),
+                    block.commit_transactions(),
+                );
+            }
+            store.insert_snapshot(batch, tip.number(), &transactions);
+            store.insert_tip_header(batch, &tip);
+            Ok(())
+        })?;
+
+        debug!(target: "chain", "snapshot imported at block {} {}", tip.number(), tip.hash());
+        *chain_state = ChainState::new(
+            tip,
+            tip_ext.total_difficulty,
+            snapshot::txo_set(&transactions),
+        );
+        Ok(())
+    }
+
     #[allow(clippy::op_ref)]
-    pub(crate) fn insert_block(&self, block: &Block) -> Result<BlockInsertionResult, SharedError> {
+    pub(crate) fn insert_block(
+        &self,
+        block: &Block,
+        checkpointed: bool,
+    ) -> Result<BlockInsertionResult, SharedError> {
         let mut new_best_block = false;
         let mut total_difficulty = U256::zero();
 
@@ -208,8 +301,14 @@ impl<CI: ChainIndex + 'static> ChainService<CI> {
                     &cannon_total_difficulty - current_total_difficulty
                 );
 
-                let (di, fo) =
-                    self.reconcile_main_chain(batch, tip_number, block, ext, &*chain_state)?;
+                let (di, fo) = self.reconcile_main_chain(
+                    batch,
+                    tip_number,
+                    block,
+                    ext,
+                    &*chain_state,
+                    checkpointed,
+                )?;
 
                 txo_set_diff = di;
                 fork = fo;
@@ -256,8 +355,9 @@ impl<CI: ChainIndex + 'static> ChainService<CI> {
             if log_enabled!(target: "chain", log::Level::Debug) {
                 self.print_chain(10);
             }
-        } else {
-            self.notify.notify_new_uncle(block);
+        } else if !block.commit_transactions().is_empty() {
+            self.notify
+                .notify_new_uncle(Arc::new(block.as_ref().clone().into()));
         }
     }
 
@@ -405,6 +505,7 @@ impl<CI: ChainIndex + 'static> ChainService<CI> {
         block: &Block,
         ext: BlockExt,
         chain_state: &ChainState,
+        checkpointed: bool,
     ) -> Result<(TxoSetDiff, Fork), SharedError> {
         let skip_verify = !self.verification;
 
@@ -456,8 +557,9 @@ impl<CI: ChainIndex + 'static> ChainService<CI> {
         }
 
         let mut txs_cache = self.shared.txs_verify_cache().write();
-        // The verify function
-        let txs_verifier = TransactionsVerifier::new(self.shared.consensus().max_block_cycles());
+        // The verify function, the ancestors of a checkpointed block are checkpointed too
+        let txs_verifier =
+            TransactionsVerifier::new(self.shared.consensus().max_block_cycles(), checkpointed);
 
         let mut found_error = false;
         // verify transaction
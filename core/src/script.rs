@@ -1,3 +1,5 @@
+use crate::transaction::OutPoint;
+use bincode::serialize;
 use faster_hex::hex_encode;
 use hash::sha3_256;
 use numext_fixed_hash::H256;
@@ -7,6 +9,24 @@ use std::fmt;
 use std::io::Write;
 use std::mem;
 
+// Reference of the built-in type id script. It doesn't point at any cell,
+// the type id rule is enforced natively by the script verifier:
+// * A type id script uses a single signed arg, which is the type id itself.
+// * At most one input and one output of a transaction can use the same type id.
+// * When a type id is created, i.e., no input uses it, the type id must equal
+// `type_id_args(first input's previous output, output index)`, which ensures
+// a type id can never be created twice.
+// * Genesis system cells use `genesis_type_id_args(output index)` instead, its
+// preimage can't be produced by any later transaction.
+// This way, a cell using a type id script keeps its identity(the type hash)
+// while the cell itself, including its data, gets replaced.
+pub const TYPE_ID_REFERENCE: H256 = H256([
+    b'T', b'Y', b'P', b'E', b'_', b'I', b'D', 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0,
+]);
+
+const GENESIS_TYPE_ID_SEED: &[u8] = b"GENESIS_TYPE_ID";
+
 // TODO: when flatbuffer work is done, remove Serialize/Deserialize here and
 // implement proper From trait
 #[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
@@ -26,6 +46,10 @@ pub struct Script {
     // in space, which is not ideal to put in every tx input.
     // Note that the referenced cell here might also be included in transaction's
     // deps part, otherwise CKB will fail to verify the script.
+    // A reference can either be the data hash of the referenced cell, or the
+    // type hash of its type script. Referencing a cell with a type id script
+    // by type hash means the script keeps working after the referenced cell
+    // gets upgraded to a new binary.
     // CKB only enforces that reference and binary cannot both be
     // None, when they both contains actual value(though this is not recommended),
     // binary will be used.
@@ -144,32 +168,81 @@ impl Script {
         (version, args, reference, binary, signed_args)
     }
 
+    /// Builds the type id script for the output at `output_index`,
+    /// `first_input` is the previous output of the transaction's first input.
+    pub fn type_id(first_input: &OutPoint, output_index: u64) -> Self {
+        let id = Self::type_id_args(first_input, output_index);
+        Script::new(
+            0,
+            vec![],
+            Some(TYPE_ID_REFERENCE),
+            None,
+            vec![id.as_bytes().to_vec()],
+        )
+    }
+
+    pub fn type_id_args(first_input: &OutPoint, output_index: u64) -> H256 {
+        let mut bytes = serialize(first_input).expect("serialize out point");
+        bytes.write_all(&output_index.to_le_bytes()).unwrap();
+        sha3_256(bytes).into()
+    }
+
+    /// Builds the type id script for the genesis system cell at `output_index`.
+    pub fn genesis_type_id(output_index: u64) -> Self {
+        let id = Self::genesis_type_id_args(output_index);
+        Script::new(
+            0,
+            vec![],
+            Some(TYPE_ID_REFERENCE),
+            None,
+            vec![id.as_bytes().to_vec()],
+        )
+    }
+
+    // The preimage length differs from the one of `type_id_args`, so no
+    // transaction can create a type id colliding with a genesis one.
+    pub fn genesis_type_id_args(output_index: u64) -> H256 {
+        let mut bytes = GENESIS_TYPE_ID_SEED.to_vec();
+        bytes.write_all(&output_index.to_le_bytes()).unwrap();
+        sha3_256(bytes).into()
+    }
+
+    pub fn is_type_id(&self) -> bool {
+        self.binary.is_none() && self.reference == Some(TYPE_ID_REFERENCE)
+    }
+
     pub fn type_hash(&self) -> H256 {
+        let mut bytes = vec![];
+        // TODO: switch to flatbuffer serialization once we
+        // can do stable serialization using flatbuffer.
+        if let Some(ref data) = self.reference {
+            bytes.write_all(data.as_bytes()).unwrap();
+        }
+        // A separator is used here to prevent the rare case
+        // that some binary might contain the exactly
+        // same data as reference. In this case we might
+        // still want to distinguish between the 2 script in
+        // the hash. Note this might not solve every problem,
+        // when flatbuffer change is done, we can leverage flatbuffer
+        // serialization directly, which will be more reliable.
+        bytes.write_all(b"|").unwrap();
+        if let Some(ref data) = self.binary {
+            bytes.write_all(&data).unwrap()
+        }
+        for argument in &self.signed_args {
+            bytes.write_all(argument).unwrap();
+        }
+        let hash = sha3_256(bytes);
         match self.version {
-            0 => {
-                let mut bytes = vec![];
-                // TODO: switch to flatbuffer serialization once we
-                // can do stable serialization using flatbuffer.
-                if let Some(ref data) = self.reference {
-                    bytes.write_all(data.as_bytes()).unwrap();
-                }
-                // A separator is used here to prevent the rare case
-                // that some binary might contain the exactly
-                // same data as reference. In this case we might
-                // still want to distinguish between the 2 script in
-                // the hash. Note this might not solve every problem,
-                // when flatbuffer change is done, we can leverage flatbuffer
-                // serialization directly, which will be more reliable.
-                bytes.write_all(b"|").unwrap();
-                if let Some(ref data) = self.binary {
-                    bytes.write_all(&data).unwrap()
-                }
-                for argument in &self.signed_args {
-                    bytes.write_all(argument).unwrap();
-                }
+            0 => hash.into(),
+            // Later versions hash the version together with the version 0
+            // hash, so the same script under different versions never shares
+            // a type hash, and no version hashes to zero.
+            version => {
+                let mut bytes = vec![version];
+                bytes.write_all(&hash).unwrap();
                 sha3_256(bytes).into()
             }
-            _ => H256::zero(),
         }
     }
 }
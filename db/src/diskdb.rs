@@ -1,7 +1,7 @@
 use crate::batch::{Batch, Col, Operation};
 use crate::config::DBConfig;
 use crate::kvdb::{ErrorKind, KeyValueDB, Result};
-use rocksdb::{ColumnFamily, Options, WriteBatch, DB};
+use rocksdb::{ColumnFamily, IteratorMode, Options, WriteBatch, DB};
 use std::ops::Range;
 
 struct Inner {
@@ -104,6 +104,16 @@ impl KeyValueDB for RocksDB {
         .map(|v| v.and_then(|vi| vi.get(range.start..range.end).map(|slice| slice.to_vec())))
         .map_err(Into::into)
     }
+
+    fn read_all(&self, col: Col) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let iter = match self.cf_handle(col)? {
+            Some(cf) => self.inner.db.iterator_cf(cf, IteratorMode::Start)?,
+            None => self.inner.db.iterator(IteratorMode::Start),
+        };
+        Ok(iter
+            .map(|(key, value)| (key.to_vec(), value.to_vec()))
+            .collect())
+    }
 }
 
 #[cfg(test)]
@@ -218,4 +228,23 @@ mod tests {
             db.partial_read(None, &[0, 0], &(1..4)).unwrap()
         );
     }
+
+    #[test]
+    fn write_and_read_all() {
+        let db = setup_db("write_and_read_all", 2);
+        let mut batch = Batch::default();
+        batch.insert(Some(1), vec![1, 1], vec![1, 1, 1]);
+        batch.insert(Some(1), vec![2, 2], vec![2, 2, 2]);
+        batch.insert(Some(0), vec![0, 0], vec![0, 0, 0]);
+        db.write(batch).unwrap();
+
+        let mut pairs = db.read_all(Some(1)).unwrap();
+        pairs.sort();
+        assert_eq!(
+            pairs,
+            vec![(vec![1, 1], vec![1, 1, 1]), (vec![2, 2], vec![2, 2, 2])]
+        );
+        // return err when col doesn't exist
+        assert!(db.read_all(Some(2)).is_err());
+    }
 }
@@ -31,6 +31,8 @@ pub trait KeyValueDB: Sync + Send {
     fn read(&self, col: Col, key: &[u8]) -> Result<Option<Vec<u8>>>;
     fn len(&self, col: Col, key: &[u8]) -> Result<Option<usize>>;
     fn partial_read(&self, col: Col, key: &[u8], range: &Range<usize>) -> Result<Option<Vec<u8>>>;
+    /// All key value pairs of the column, only meant for small columns
+    fn read_all(&self, col: Col) -> Result<Vec<(Vec<u8>, Vec<u8>)>>;
     fn cols(&self) -> u32;
     fn batch(&self) -> Batch {
         Batch::new()
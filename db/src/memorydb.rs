@@ -77,6 +77,18 @@ impl KeyValueDB for MemoryKeyValueDB {
                 .map(|slice| slice.to_vec())),
         }
     }
+
+    fn read_all(&self, col: Col) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let db = self.db.read();
+
+        match db.get(&col) {
+            None => Err(ErrorKind::DBError(format!("column {:?} not found ", col))),
+            Some(map) => Ok(map
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect()),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -141,4 +153,23 @@ mod tests {
             db.partial_read(None, &[0, 0], &(1..4)).unwrap()
         );
     }
+
+    #[test]
+    fn write_and_read_all() {
+        let db = MemoryKeyValueDB::open(2);
+        let mut batch = Batch::default();
+        batch.insert(Some(1), vec![1, 1], vec![1, 1, 1]);
+        batch.insert(Some(1), vec![2, 2], vec![2, 2, 2]);
+        batch.insert(Some(0), vec![0, 0], vec![0, 0, 0]);
+        db.write(batch).unwrap();
+
+        let mut pairs = db.read_all(Some(1)).unwrap();
+        pairs.sort();
+        assert_eq!(
+            pairs,
+            vec![(vec![1, 1], vec![1, 1, 1]), (vec![2, 2], vec![2, 2, 2])]
+        );
+        // return err when col doesn't exist
+        assert!(db.read_all(Some(2)).is_err());
+    }
 }
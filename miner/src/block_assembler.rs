@@ -1,4 +1,3 @@
-use ckb_core::block::Block;
 use ckb_core::header::Header;
 use ckb_core::service::{Request, DEFAULT_CHANNEL_SIZE, SIGNAL_CHANNEL_SIZE};
 use ckb_core::transaction::{CellInput, CellOutput, Transaction, TransactionBuilder};
@@ -11,6 +10,7 @@ use ckb_pool::PoolEntry;
 use ckb_shared::error::SharedError;
 use ckb_shared::index::ChainIndex;
 use ckb_shared::shared::{ChainProvider, Shared};
+use ckb_shared::store::ChainStore;
 use ckb_util::Mutex;
 use crossbeam_channel::{self, select, Receiver, Sender};
 use faketime::unix_time_as_millis;
@@ -87,7 +87,7 @@ impl BlockAssemblerController {
 pub struct BlockAssembler<CI> {
     shared: Shared<CI>,
     tx_pool: TransactionPoolController,
-    candidate_uncles: LruCache<H256, Arc<Block>>,
+    candidate_uncles: LruCache<H256, Arc<UncleBlock>>,
     type_hash: H256,
     work_id: AtomicUsize,
     last_uncles_updated_at: AtomicUsize,
@@ -96,7 +96,7 @@ pub struct BlockAssembler<CI> {
 
 impl<CI: ChainIndex + 'static> BlockAssembler<CI> {
     pub fn new(shared: Shared<CI>, tx_pool: TransactionPoolController, type_hash: H256) -> Self {
-        Self {
+        let mut block_assembler = Self {
             shared,
             tx_pool,
             type_hash,
@@ -104,7 +104,49 @@ impl<CI: ChainIndex + 'static> BlockAssembler<CI> {
             work_id: AtomicUsize::new(0),
             last_uncles_updated_at: AtomicUsize::new(0),
             template_caches: Mutex::new(LruCache::new(TEMPLATE_CACHE_SIZE)),
+        };
+        block_assembler.load_candidate_uncles();
+        block_assembler
+    }
+
+    // Candidates are persisted in the store, reload the ones young enough to be included
+    // by the next block and drop the others.
+    fn load_candidate_uncles(&mut self) {
+        let tip_number = self.shared.chain_state().read().tip_number();
+        let max_uncles_age = self.shared.consensus().max_uncles_age() as u64;
+        let store = self.shared.store();
+        let (mut candidates, stale): (Vec<_>, Vec<_>) = store
+            .get_uncle_candidates()
+            .into_iter()
+            .partition(|uncle| uncle.header().number() + max_uncles_age > tip_number);
+        let mut dropped: Vec<H256> = stale.iter().map(|uncle| uncle.header().hash()).collect();
+        // the latest candidates are the most recently used ones
+        candidates.sort_by_key(|uncle| uncle.header().number());
+        for uncle in candidates {
+            dropped.extend(self.insert_candidate_uncle(Arc::new(uncle)));
         }
+        if !dropped.is_empty() {
+            let _ = store.save_with_batch(|batch| {
+                for hash in &dropped {
+                    store.delete_uncle_candidate(batch, hash);
+                }
+                Ok(())
+            });
+        }
+    }
+
+    // returns the least recently used candidate evicted to make room
+    fn insert_candidate_uncle(&mut self, uncle: Arc<UncleBlock>) -> Option<H256> {
+        let hash = uncle.header().hash();
+        let evicted = if !self.candidate_uncles.contains_key(&hash)
+            && self.candidate_uncles.len() >= MAX_CANDIDATE_UNCLES
+        {
+            self.candidate_uncles.remove_lru().map(|(hash, _)| hash)
+        } else {
+            None
+        };
+        self.candidate_uncles.insert(hash, uncle);
+        evicted
     }
 
     pub fn start<S: ToString>(
@@ -136,8 +178,15 @@ impl<CI: ChainIndex + 'static> BlockAssembler<CI> {
                     }
                     recv(new_uncle_receiver) -> msg => match msg {
                         Ok(uncle_block) => {
-                            let hash = uncle_block.header().hash().clone();
-                            self.candidate_uncles.insert(hash, uncle_block);
+                            let evicted = self.insert_candidate_uncle(Arc::clone(&uncle_block));
+                            let store = self.shared.store();
+                            let _ = store.save_with_batch(|batch| {
+                                store.insert_uncle_candidate(batch, &uncle_block);
+                                if let Some(hash) = evicted {
+                                    store.delete_uncle_candidate(batch, &hash);
+                                }
+                                Ok(())
+                            });
                             self.last_uncles_updated_at
                                 .store(unix_time_as_millis() as usize, Ordering::SeqCst);
                         }
@@ -239,7 +288,7 @@ impl<CI: ChainIndex + 'static> BlockAssembler<CI> {
         let chain_state = self.shared.chain_state().read();
         let header = chain_state.tip_header();
         let number = chain_state.tip_number() + 1;
-        let current_time = cmp::max(unix_time_as_millis(), header.timestamp() + 1);
+        let current_time = cmp::max(self.shared.net_time().now(), header.timestamp() + 1);
 
         let mut template_caches = self.template_caches.lock();
 
@@ -264,9 +313,17 @@ impl<CI: ChainIndex + 'static> BlockAssembler<CI> {
 
         let (uncles, bad_uncles) = self.prepare_uncles(&header);
         if !bad_uncles.is_empty() {
-            for bad in bad_uncles {
-                self.candidate_uncles.remove(&bad);
+            for bad in &bad_uncles {
+                self.candidate_uncles.remove(bad);
             }
+            // relayed uncle candidates are stored apart from blocks, drop them as well
+            let store = self.shared.store();
+            let _ = store.save_with_batch(|batch| {
+                for bad in &bad_uncles {
+                    store.delete_uncle_candidate(batch, bad);
+                }
+                Ok(())
+            });
         }
 
         // dummy cellbase
@@ -367,39 +424,31 @@ impl<CI: ChainIndex + 'static> BlockAssembler<CI> {
         let mut uncles = Vec::with_capacity(max_uncles_len);
         let mut bad_uncles = Vec::new();
         let current_number = tip.number() + 1;
-        for (hash, block) in self.candidate_uncles.iter() {
-            if uncles.len() == max_uncles_len {
-                break;
-            }
-
-            let block_difficulty_epoch =
-                block.header().number() / self.shared.consensus().difficulty_adjustment_interval();
+        // all candidates are checked, so the ones too old or already included are pruned
+        for (hash, uncle) in self.candidate_uncles.iter() {
+            let uncle_difficulty_epoch =
+                uncle.header().number() / self.shared.consensus().difficulty_adjustment_interval();
 
             // uncle must be same difficulty epoch with tip
-            if block.header().difficulty() != tip.difficulty()
-                || block_difficulty_epoch != tip_difficulty_epoch
+            if uncle.header().difficulty() != tip.difficulty()
+                || uncle_difficulty_epoch != tip_difficulty_epoch
             {
                 bad_uncles.push(hash.clone());
                 continue;
             }
 
-            let depth = current_number.saturating_sub(block.header().number());
+            // Uncles included by recent main chain blocks are excluded and dropped here. Uncles
+            // put in a template stay candidates, the template may never be mined.
+            let depth = current_number.saturating_sub(uncle.header().number());
             if depth > max_uncles_age as u64
                 || depth < 1
                 || included.contains(hash)
                 || excluded.contains(hash)
             {
                 bad_uncles.push(hash.clone());
-            } else if let Some(cellbase) = block.commit_transactions().first() {
-                let uncle = UncleBlock {
-                    header: block.header().clone(),
-                    cellbase: cellbase.clone(),
-                    proposal_transactions: block.proposal_transactions().to_vec(),
-                };
-                uncles.push(uncle);
+            } else if uncles.len() < max_uncles_len {
+                uncles.push(uncle.as_ref().clone());
                 included.insert(hash.clone());
-            } else {
-                bad_uncles.push(hash.clone());
             }
         }
         (uncles, bad_uncles)
@@ -414,6 +463,8 @@ mod tests {
     use ckb_chain_spec::consensus::Consensus;
     use ckb_core::block::BlockBuilder;
     use ckb_core::header::HeaderBuilder;
+    use ckb_core::transaction::{CellInput, CellOutput, TransactionBuilder};
+    use ckb_core::uncle::UncleBlock;
     use ckb_db::memorydb::MemoryKeyValueDB;
     use ckb_notify::{NotifyController, NotifyService};
     use ckb_pool::txs_pool::{PoolConfig, TransactionPoolController, TransactionPoolService};
@@ -421,7 +472,7 @@ mod tests {
     use ckb_shared::index::ChainIndex;
     use ckb_shared::shared::Shared;
     use ckb_shared::shared::SharedBuilder;
-    use ckb_shared::store::ChainKVStore;
+    use ckb_shared::store::{ChainKVStore, ChainStore};
     use ckb_verification::{BlockVerifier, HeaderResolverWrapper, HeaderVerifier, Verifier};
     use jsonrpc_types::{BlockTemplate, CellbaseTemplate};
     use numext_fixed_hash::H256;
@@ -524,4 +575,45 @@ mod tests {
         let block_verify = BlockVerifier::new(shared.clone());
         assert!(block_verify.verify(&block).is_ok());
     }
+
+    #[test]
+    fn test_load_candidate_uncles() {
+        let uncle: UncleBlock = BlockBuilder::default()
+            .commit_transaction(
+                TransactionBuilder::default()
+                    .input(CellInput::new_cellbase_input(1))
+                    .output(CellOutput::new(0, vec![], H256::zero(), None))
+                    .build(),
+            )
+            .with_header_builder(HeaderBuilder::default().number(1))
+            .into();
+        let hash = uncle.header().hash();
+        let insert_candidate = |shared: &Shared<ChainKVStore<MemoryKeyValueDB>>| {
+            let store = shared.store();
+            store
+                .save_with_batch(|batch| {
+                    store.insert_uncle_candidate(batch, &uncle);
+                    Ok(())
+                })
+                .expect("save uncle candidate");
+        };
+
+        // candidates stored before a restart are reloaded
+        let (_chain_controller, shared, notify) = start_chain(None, None);
+        insert_candidate(&shared);
+        let tx_pool_controller = setup_tx_pool(shared.clone(), notify.clone());
+        let block_assembler = setup_block_assembler(tx_pool_controller, shared, H256::zero());
+        assert!(block_assembler.candidate_uncles.contains_key(&hash));
+
+        // candidates too old to be included by the next block are dropped
+        let mut consensus = Consensus::default();
+        consensus.max_uncles_age = 0;
+        let (_chain_controller, shared, notify) = start_chain(Some(consensus), None);
+        insert_candidate(&shared);
+        let tx_pool_controller = setup_tx_pool(shared.clone(), notify.clone());
+        let block_assembler =
+            setup_block_assembler(tx_pool_controller, shared.clone(), H256::zero());
+        assert!(!block_assembler.candidate_uncles.contains_key(&hash));
+        assert!(shared.store().get_uncle_candidate(&hash).is_none());
+    }
 }
@@ -0,0 +1,202 @@
+use crate::ckb_protocol_handler::{CKBProtocolContext, CKBProtocolHandler};
+use crate::peer_store::{Behaviour, PeerStore};
+use crate::{PeerIndex, ProtocolId, TimerToken};
+use ckb_util::{Mutex, RwLock};
+use fnv::FnvHashMap;
+use libp2p::core::Multiaddr;
+use libp2p::PeerId;
+use log::{debug, trace};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+pub const ADDR_GOSSIP_PROTOCOL_ID: ProtocolId = *b"adr";
+// Addresses sent to a peer, or accepted from it, in one gossip interval
+pub(crate) const MAX_GOSSIP_ADDRS: usize = 32;
+const GOSSIP_TOKEN: TimerToken = 0;
+const GOSSIP_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Default)]
+pub(crate) struct GossipPeer {
+    last_sent: Option<Instant>,
+    window_start: Option<Instant>,
+    // addresses accepted since window_start
+    received: usize,
+}
+
+impl GossipPeer {
+    // returns how many of `count` addresses are accepted in the current window
+    pub(crate) fn accept(&mut self, count: usize, now: Instant, interval: Duration) -> usize {
+        match self.window_start {
+            Some(start) if now < start + interval => {}
+            _ => {
+                self.window_start = Some(now);
+                self.received = 0;
+            }
+        }
+        let accepted = count.min(MAX_GOSSIP_ADDRS - self.received);
+        self.received += accepted;
+        accepted
+    }
+
+    fn is_due(&self, now: Instant, interval: Duration) -> bool {
+        self.last_sent
+            .map(|last_sent| now >= last_sent + interval)
+            .unwrap_or(true)
+    }
+}
+
+// Peers exchange known good addresses of other peers every `interval`, the
+// received ones go to the new bucket of the peer store.
+pub struct AddrGossipProtocol {
+    local_peer_id: PeerId,
+    peer_store: Arc<RwLock<dyn PeerStore>>,
+    interval: Duration,
+    peers: Mutex<FnvHashMap<PeerIndex, GossipPeer>>,
+}
+
+impl AddrGossipProtocol {
+    pub fn new(
+        local_peer_id: PeerId,
+        peer_store: Arc<RwLock<dyn PeerStore>>,
+        interval: Duration,
+    ) -> Self {
+        AddrGossipProtocol {
+            local_peer_id,
+            peer_store,
+            interval,
+            peers: Mutex::new(FnvHashMap::default()),
+        }
+    }
+
+    fn send_addrs(&self, nc: &CKBProtocolContext) {
+        let now = Instant::now();
+        let due_peers: Vec<PeerIndex> = self
+            .peers
+            .lock()
+            .iter_mut()
+            .filter(|(_, gossip_peer)| gossip_peer.is_due(now, self.interval))
+            .map(|(peer, gossip_peer)| {
+                gossip_peer.last_sent = Some(now);
+                *peer
+            })
+            .collect();
+        for peer in due_peers {
+            let remote_peer_id = match nc.session_info(peer) {
+                Some(session) => session.peer.peer_id,
+                None => continue,
+            };
+            let addrs: Vec<(PeerId, Multiaddr)> = self
+                .peer_store
+                .read()
+                .good_addrs(MAX_GOSSIP_ADDRS as u32)
+                .into_iter()
+                .filter(|(peer_id, _)| peer_id != &remote_peer_id)
+                .collect();
+            if addrs.is_empty() {
+                continue;
+            }
+            trace!(target: "network", "gossip {} addrs to peer={}", addrs.len(), peer);
+            let _ = nc.send(peer, encode_addrs(&addrs));
+        }
+    }
+}
+
+impl CKBProtocolHandler for AddrGossipProtocol {
+    fn initialize(&self, nc: Box<CKBProtocolContext>) {
+        let _ = nc.register_timer(GOSSIP_TOKEN, GOSSIP_CHECK_INTERVAL);
+    }
+
+    fn received(&self, nc: Box<CKBProtocolContext>, peer: PeerIndex, data: &[u8]) {
+        let addrs = match decode_addrs(data) {
+            Some(addrs) => addrs,
+            None => {
+                debug!(target: "network", "peer={} sends malformed addrs", peer);
+                nc.report(peer, Behaviour::MalformedMessage);
+                return;
+            }
+        };
+        let accepted = self
+            .peers
+            .lock()
+            .entry(peer)
+            .or_insert_with(GossipPeer::default)
+            .accept(addrs.len(), Instant::now(), self.interval);
+        if accepted < addrs.len() {
+            debug!(target: "network", "peer={} exceeds addr gossip limit", peer);
+            nc.report(peer, Behaviour::TooManyMessages);
+        }
+        let mut peer_store = self.peer_store.write();
+        for (peer_id, addr) in addrs.into_iter().take(accepted) {
+            if peer_id == self.local_peer_id || peer_store.is_addr_banned(&addr) {
+                continue;
+            }
+            let _ = peer_store.add_discovered_address(&peer_id, addr);
+        }
+    }
+
+    fn connected(&self, _nc: Box<CKBProtocolContext>, peer: PeerIndex) {
+        self.peers.lock().insert(peer, GossipPeer::default());
+    }
+
+    fn disconnected(&self, _nc: Box<CKBProtocolContext>, peer: PeerIndex) {
+        self.peers.lock().remove(&peer);
+    }
+
+    fn timer_triggered(&self, nc: Box<CKBProtocolContext>, timer: TimerToken) {
+        if timer == GOSSIP_TOKEN {
+            self.send_addrs(nc.as_ref());
+        }
+    }
+}
+
+// Message layout: u16 count, then count pairs of length prefixed peer id and
+// address bytes, integers are little endian.
+pub(crate) fn encode_addrs(addrs: &[(PeerId, Multiaddr)]) -> Vec<u8> {
+    fn put_bytes(data: &mut Vec<u8>, bytes: &[u8]) {
+        data.extend_from_slice(&(bytes.len() as u16).to_le_bytes());
+        data.extend_from_slice(bytes);
+    }
+    let mut data = Vec::new();
+    data.extend_from_slice(&(addrs.len() as u16).to_le_bytes());
+    for (peer_id, addr) in addrs {
+        put_bytes(&mut data, peer_id.as_bytes());
+        put_bytes(&mut data, &addr.to_bytes());
+    }
+    data
+}
+
+pub(crate) fn decode_addrs(data: &[u8]) -> Option<Vec<(PeerId, Multiaddr)>> {
+    fn take_u16(data: &mut &[u8]) -> Option<usize> {
+        if data.len() < 2 {
+            return None;
+        }
+        let value = u16::from_le_bytes([data[0], data[1]]) as usize;
+        *data = &data[2..];
+        Some(value)
+    }
+    fn take_bytes(data: &mut &[u8]) -> Option<Vec<u8>> {
+        let len = take_u16(data)?;
+        if data.len() < len {
+            return None;
+        }
+        let bytes = data[..len].to_vec();
+        *data = &data[len..];
+        Some(bytes)
+    }
+    let mut data = data;
+    let count = take_u16(&mut data)?;
+    if count > MAX_GOSSIP_ADDRS {
+        return None;
+    }
+    let mut addrs = Vec::with_capacity(count);
+    for _ in 0..count {
+        let peer_id = PeerId::from_bytes(take_bytes(&mut data)?).ok()?;
+        let addr = Multiaddr::from_bytes(take_bytes(&mut data)?).ok()?;
+        addrs.push((peer_id, addr));
+    }
+    if data.is_empty() {
+        Some(addrs)
+    } else {
+        None
+    }
+}
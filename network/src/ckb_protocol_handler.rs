@@ -1,4 +1,6 @@
 use crate::errors::{Error, ErrorKind};
+use crate::peer_store::Behaviour;
+use crate::traffic_stats::UNKNOWN_MESSAGE_TYPE;
 use crate::{Network, SessionInfo, Timer};
 use crate::{PeerIndex, ProtocolId, TimerToken};
 use ckb_util::Mutex;
@@ -24,6 +26,9 @@ pub trait CKBProtocolContext: Send {
     ) -> Result<(), Error>;
     // TODO combinate this interface with peer score
     fn report_peer(&self, peer_index: PeerIndex, reason: Severity);
+    // report peer behaviour to peer store, peer is disconnected and banned once its score
+    // drops below the ban score
+    fn report(&self, peer_index: PeerIndex, behaviour: Behaviour);
     fn ban_peer(&self, peer_index: PeerIndex, timeout: Duration);
     fn disconnect(&self, peer_index: PeerIndex);
     fn register_timer(&self, token: TimerToken, delay: Duration) -> Result<(), Error>;
@@ -40,6 +45,8 @@ pub trait CKBProtocolContext: Send {
             .collect()
     }
     fn connected_peers(&self) -> Vec<PeerIndex>;
+    // whitelisted peers skip rate limits and always get transactions relayed
+    fn is_whitelisted(&self, peer_index: PeerIndex) -> bool;
 }
 
 pub(crate) struct DefaultCKBProtocolContext {
@@ -88,10 +95,20 @@ impl CKBProtocolContext for DefaultCKBProtocolContext {
         info!(target: "network", "report peer {} reason: {:?}", peer_index, reason);
         self.disconnect(peer_index);
     }
+    fn report(&self, peer_index: PeerIndex, behaviour: Behaviour) {
+        debug!(target: "network", "report peer {} behaviour: {:?}", peer_index, behaviour);
+        if let Some(peer_id) = self.network.get_peer_id(peer_index) {
+            self.network.report(&peer_id, behaviour)
+        }
+    }
     // ban peer
     fn ban_peer(&self, peer_index: PeerIndex, timeout: Duration) {
         if let Some(peer_id) = self.network.get_peer_id(peer_index) {
-            self.network.ban_peer(&peer_id, timeout)
+            let reason = format!(
+                "banned by protocol {}",
+                String::from_utf8_lossy(&self.protocol_id)
+            );
+            self.network.ban_peer(&peer_id, timeout, reason)
         }
     }
     // disconnect from peer
@@ -147,6 +164,13 @@ impl CKBProtocolContext for DefaultCKBProtocolContext {
     fn connected_peers(&self) -> Vec<PeerIndex> {
         self.network.peers_indexes()
     }
+
+    fn is_whitelisted(&self, peer_index: PeerIndex) -> bool {
+        self.network
+            .get_peer_id(peer_index)
+            .map(|peer_id| self.network.is_whitelisted(&peer_id))
+            .unwrap_or(false)
+    }
 }
 
 pub trait CKBProtocolHandler: Sync + Send {
@@ -155,4 +179,8 @@ pub trait CKBProtocolHandler: Sync + Send {
     fn connected(&self, _nc: Box<dyn CKBProtocolContext>, _peer: PeerIndex);
     fn disconnected(&self, _nc: Box<dyn CKBProtocolContext>, _peer: PeerIndex);
     fn timer_triggered(&self, _nc: Box<dyn CKBProtocolContext>, _timer: TimerToken) {}
+    // name of the message payload, used by traffic stats
+    fn message_type(&self, _data: &[u8]) -> &'static str {
+        UNKNOWN_MESSAGE_TYPE
+    }
 }
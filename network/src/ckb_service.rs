@@ -79,7 +79,10 @@ impl CKBService {
                     });
                     let protocol_handler = Arc::clone(&protocol_handler);
                     let network = Arc::clone(&network);
+                    let peer_id = peer_id.clone();
                     let handle_received = future::lazy(move || {
+                        let message_type = protocol_handler.message_type(&data);
+                        network.record_received(&peer_id, protocol_id, message_type, data.len());
                         protocol_handler.received(
                             Box::new(DefaultCKBProtocolContext::new(network, protocol_id)),
                             peer_index,
@@ -30,6 +30,7 @@ pub struct IdentifyService {
     pub protocol_version: String,
     pub identify_timeout: Duration,
     pub identify_interval: Duration,
+    pub addr_announce_interval: Duration,
 }
 
 impl IdentifyService {
@@ -48,6 +49,7 @@ impl IdentifyService {
                 protocol_version: info.protocol_version.clone(),
                 supported_protocols: info.protocols.clone(),
                 count_of_known_listen_addrs: info.listen_addrs.len(),
+                identified_at: Instant::now(),
             };
             if network
                 .set_peer_identify_info(&peer_id, identify_info)
@@ -77,11 +79,17 @@ impl IdentifyService {
             }
         }
 
-        // update peer addrs in peerstore
+        // update peer addrs in peerstore, limit addresses relayed in one announcement
+        let listen_addrs = info
+            .listen_addrs
+            .iter()
+            .take(MAX_LISTENED_ADDRS)
+            .cloned()
+            .collect();
         let _ = network
             .peer_store()
             .write()
-            .add_discovered_addresses(peer_id, info.listen_addrs.clone());
+            .add_discovered_addresses(peer_id, listen_addrs);
         Ok(())
     }
 }
@@ -198,11 +206,13 @@ where
         .for_each({
             let transport = transport.clone();
             let _identify_timeout = self.identify_timeout;
+            let addr_announce_interval = self.addr_announce_interval;
             let network = Arc::clone(&network);
             move |_| {
                 for peer_id in network.peers() {
+                    // peers announce their addresses periodically
                     if let Some(ref identify_info) = network.get_peer_identify_info(&peer_id) {
-                        if identify_info.count_of_known_listen_addrs > 0 {
+                        if identify_info.identified_at.elapsed() < addr_announce_interval {
                             continue;
                         }
                     }
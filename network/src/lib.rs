@@ -1,10 +1,12 @@
 #![type_length_limit = "2097152"]
 
+mod addr_gossip;
 mod ckb_protocol;
 mod ckb_protocol_handler;
 mod ckb_service;
 mod errors;
 mod identify_service;
+mod memory_transport;
 mod network;
 mod network_config;
 mod network_group;
@@ -18,14 +20,23 @@ mod protocol_service;
 #[cfg(test)]
 mod tests;
 mod timer_service;
+mod traffic_stats;
+mod traffic_stats_service;
 mod transport;
+mod whitelist;
 
+pub use crate::addr_gossip::ADDR_GOSSIP_PROTOCOL_ID;
 pub use crate::ckb_protocol::{CKBProtocol, CKBProtocols};
 pub use crate::ckb_protocol_handler::{CKBProtocolContext, CKBProtocolHandler, Severity};
 pub use crate::errors::{Error, ErrorKind};
-pub use crate::network::{Network, PeerInfo, SessionInfo};
-pub use crate::network_config::NetworkConfig;
+pub use crate::memory_transport::{MemoryNetwork, RETRANSMIT_TIMEOUT};
+pub use crate::network::{ConnectedPeer, Network, PeerInfo, SessionInfo};
+pub use crate::network_config::{NetworkConfig, PeerStoreType, TransportType};
 pub use crate::network_service::NetworkService;
+pub use crate::traffic_stats::{
+    sum_message_stats, MessageStats, MessageStatsMap, TrafficStats, UNKNOWN_MESSAGE_TYPE,
+};
+pub use crate::whitelist::Whitelist;
 pub use libp2p::{
     core::Endpoint, multiaddr::AddrComponent, multiaddr::ToMultiaddr, Multiaddr, PeerId,
 };
@@ -54,12 +65,17 @@ pub struct Config {
     pub bootnodes: Vec<String>,
     /// List of reserved node addresses.
     pub reserved_nodes: Vec<String>,
+    /// Peer ids or IP subnets of privileged peers, e.g. "192.168.0.0/16"
+    #[serde(default)]
+    pub whitelist: Vec<String>,
     /// The non-reserved peer mode.
     pub non_reserved_mode: Option<String>,
     /// Minimum number of connected peers to maintain
     pub max_peers: u32,
     pub outbound_peers_ratio: Option<u32>,
     pub config_dir_path: Option<String>,
+    /// "Sqlite" or "Memory", default is "Sqlite"
+    pub peer_store: Option<PeerStoreType>,
 }
 
 impl Config {
@@ -82,6 +98,7 @@ impl From<Config> for NetworkConfig {
         cfg.listen_addresses = config.listen_addresses;
         cfg.bootnodes = config.bootnodes;
         cfg.reserved_peers = config.reserved_nodes;
+        cfg.whitelist = config.whitelist;
         if let Some(value) = config.non_reserved_mode {
             cfg.reserved_only = match value.as_str() {
                 "Accept" => false,
@@ -89,9 +106,13 @@ impl From<Config> for NetworkConfig {
                 _ => false,
             };
         }
+        if let Some(peer_store_type) = config.peer_store {
+            cfg.peer_store_type = peer_store_type;
+        }
         if let Some(dir_path) = config.config_dir_path {
             cfg.config_dir_path = Some(dir_path.clone());
-            cfg.secret_key_path = Some(format!("{}/secret_key", dir_path))
+            cfg.secret_key_path = Some(format!("{}/secret_key", dir_path));
+            cfg.anchors_path = Some(format!("{}/anchors", dir_path));
         }
         cfg.client_version = "ckb network".to_string();
         match cfg.read_secret_key() {
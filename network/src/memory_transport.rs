@@ -0,0 +1,385 @@
+use bytes::Bytes;
+use ckb_util::Mutex;
+use fnv::{FnvHashMap, FnvHashSet};
+use futures::future::{self, FutureResult};
+use futures::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use futures::{Async, Future, Poll, Stream};
+use libp2p::core::{AddrComponent, Multiaddr, Transport};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::fmt;
+use std::io::{self, Error as IoError, ErrorKind as IoErrorKind, Read, Write};
+use std::net::Ipv4Addr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::timer::Delay;
+
+// a dropped chunk is delivered again after this timeout, like a TCP retransmission
+pub const RETRANSMIT_TIMEOUT: Duration = Duration::from_millis(200);
+// ports assigned to the dialer side of connections
+const EPHEMERAL_PORT_START: u16 = 49152;
+
+#[derive(Debug)]
+struct Chunk {
+    data: Bytes,
+    deliver_at: Instant,
+}
+
+struct MemoryNetworkState {
+    // node name -> address, each node gets its own network group
+    nodes: FnvHashMap<String, Ipv4Addr>,
+    listeners: FnvHashMap<(Ipv4Addr, u16), UnboundedSender<(MemorySocket, Multiaddr)>>,
+    partitions: FnvHashSet<(Ipv4Addr, Ipv4Addr)>,
+    latency: Duration,
+    drop_rate: f64,
+    rng: StdRng,
+    next_port: u16,
+}
+
+// An in-process network which connects nodes by name, used to run multiple
+// nodes in one process with injected latency, drop rate and partitions.
+//
+// Nodes are addressed by the IPv4 address returned from `node_ip`, so they
+// look like ordinary tcp peers to the rest of ckb-network.
+pub struct MemoryNetwork {
+    state: Mutex<MemoryNetworkState>,
+}
+
+impl fmt::Debug for MemoryNetwork {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let state = self.state.lock();
+        f.debug_struct("MemoryNetwork")
+            .field("nodes", &state.nodes)
+            .field("latency", &state.latency)
+            .field("drop_rate", &state.drop_rate)
+            .finish()
+    }
+}
+
+impl Default for MemoryNetwork {
+    fn default() -> Self {
+        MemoryNetwork::with_seed([0; 32])
+    }
+}
+
+impl MemoryNetwork {
+    // the seed makes dropped chunks reproducible
+    pub fn with_seed(seed: [u8; 32]) -> Self {
+        MemoryNetwork {
+            state: Mutex::new(MemoryNetworkState {
+                nodes: FnvHashMap::default(),
+                listeners: FnvHashMap::default(),
+                partitions: FnvHashSet::default(),
+                latency: Duration::from_secs(0),
+                drop_rate: 0.0,
+                rng: StdRng::from_seed(seed),
+                next_port: EPHEMERAL_PORT_START,
+            }),
+        }
+    }
+
+    // return the address of node, a new address is assigned for unknown names
+    pub fn node_ip(&self, name: &str) -> Ipv4Addr {
+        let mut state = self.state.lock();
+        let index = state.nodes.len();
+        *state
+            .nodes
+            .entry(name.to_owned())
+            .or_insert_with(|| Ipv4Addr::new(10 + (index / 256) as u8, (index % 256) as u8, 0, 1))
+    }
+
+    pub fn node_addr(&self, name: &str, port: u16) -> Multiaddr {
+        tcp_addr(self.node_ip(name), port)
+    }
+
+    pub fn set_latency(&self, latency: Duration) {
+        self.state.lock().latency = latency;
+    }
+
+    // probability in [0, 1] of a written chunk being dropped and retransmitted
+    pub fn set_drop_rate(&self, drop_rate: f64) {
+        self.state.lock().drop_rate = drop_rate.max(0.0).min(1.0);
+    }
+
+    // disconnect two nodes, connections between them are reset and dials fail
+    pub fn partition(&self, a: &str, b: &str) {
+        let (a, b) = (self.node_ip(a), self.node_ip(b));
+        let mut state = self.state.lock();
+        state.partitions.insert((a, b));
+        state.partitions.insert((b, a));
+    }
+
+    pub fn heal(&self, a: &str, b: &str) {
+        let (a, b) = (self.node_ip(a), self.node_ip(b));
+        let mut state = self.state.lock();
+        state.partitions.remove(&(a, b));
+        state.partitions.remove(&(b, a));
+    }
+
+    pub fn heal_all(&self) {
+        self.state.lock().partitions.clear();
+    }
+
+    fn is_partitioned(&self, local: Ipv4Addr, remote: Ipv4Addr) -> bool {
+        self.state.lock().partitions.contains(&(local, remote))
+    }
+
+    // chunks are delivered in order, a dropped chunk also delays the following ones
+    fn deliver_at(&self, last_deliver_at: Instant) -> Instant {
+        let mut state = self.state.lock();
+        let drop_rate = state.drop_rate;
+        let mut deliver_at = Instant::now() + state.latency;
+        if drop_rate > 0.0 && state.rng.gen_bool(drop_rate) {
+            deliver_at += RETRANSMIT_TIMEOUT;
+        }
+        deliver_at.max(last_deliver_at)
+    }
+
+    fn listen(
+        &self,
+        ip: Ipv4Addr,
+        port: u16,
+    ) -> Result<(UnboundedReceiver<(MemorySocket, Multiaddr)>, Multiaddr), IoError> {
+        let mut state = self.state.lock();
+        let port = if port == 0 {
+            state.next_port = state.next_port.wrapping_add(1).max(EPHEMERAL_PORT_START);
+            state.next_port
+        } else {
+            port
+        };
+        if state.listeners.contains_key(&(ip, port)) {
+            return Err(IoErrorKind::AddrInUse.into());
+        }
+        let (sender, receiver) = mpsc::unbounded();
+        state.listeners.insert((ip, port), sender);
+        Ok((receiver, tcp_addr(ip, port)))
+    }
+
+    fn dial(
+        network: &Arc<MemoryNetwork>,
+        local: Ipv4Addr,
+        remote: Ipv4Addr,
+        port: u16,
+    ) -> Result<MemorySocket, IoError> {
+        let mut state = network.state.lock();
+        if state.partitions.contains(&(local, remote)) {
+            return Err(IoErrorKind::ConnectionRefused.into());
+        }
+        let sender = match state.listeners.get(&(remote, port)) {
+            Some(sender) => sender.clone(),
+            None => return Err(IoErrorKind::ConnectionRefused.into()),
+        };
+        let local_port = state.next_port;
+        state.next_port = state.next_port.wrapping_add(1).max(EPHEMERAL_PORT_START);
+        let (dialer, listener) = MemorySocket::pair(network, local, remote);
+        if sender
+            .unbounded_send((listener, tcp_addr(local, local_port)))
+            .is_err()
+        {
+            // listener is dropped
+            state.listeners.remove(&(remote, port));
+            return Err(IoErrorKind::ConnectionRefused.into());
+        }
+        Ok(dialer)
+    }
+}
+
+fn tcp_addr(ip: Ipv4Addr, port: u16) -> Multiaddr {
+    vec![AddrComponent::IP4(ip), AddrComponent::TCP(port)]
+        .into_iter()
+        .collect()
+}
+
+fn parse_tcp_addr(addr: &Multiaddr) -> Option<(Ipv4Addr, u16)> {
+    let mut iter = addr.iter();
+    match (iter.next(), iter.next(), iter.next()) {
+        (Some(AddrComponent::IP4(ip)), Some(AddrComponent::TCP(port)), None) => Some((ip, port)),
+        _ => None,
+    }
+}
+
+// One end of an in-process connection
+pub struct MemorySocket {
+    network: Arc<MemoryNetwork>,
+    local: Ipv4Addr,
+    remote: Ipv4Addr,
+    sender: Option<UnboundedSender<Chunk>>,
+    receiver: UnboundedReceiver<Chunk>,
+    // data of the chunk which is partially read or waiting for its delivery time
+    pending: Option<Bytes>,
+    delay: Option<Delay>,
+    last_deliver_at: Instant,
+}
+
+impl MemorySocket {
+    fn pair(
+        network: &Arc<MemoryNetwork>,
+        a: Ipv4Addr,
+        b: Ipv4Addr,
+    ) -> (MemorySocket, MemorySocket) {
+        let (a_sender, b_receiver) = mpsc::unbounded();
+        let (b_sender, a_receiver) = mpsc::unbounded();
+        let now = Instant::now();
+        let socket_a = MemorySocket {
+            network: Arc::clone(network),
+            local: a,
+            remote: b,
+            sender: Some(a_sender),
+            receiver: a_receiver,
+            pending: None,
+            delay: None,
+            last_deliver_at: now,
+        };
+        let socket_b = MemorySocket {
+            network: Arc::clone(network),
+            local: b,
+            remote: a,
+            sender: Some(b_sender),
+            receiver: b_receiver,
+            pending: None,
+            delay: None,
+            last_deliver_at: now,
+        };
+        (socket_a, socket_b)
+    }
+
+    fn check_partition(&self) -> Result<(), IoError> {
+        if self.network.is_partitioned(self.local, self.remote) {
+            Err(IoErrorKind::ConnectionReset.into())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Read for MemorySocket {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.check_partition()?;
+        loop {
+            if let Some(ref mut delay) = self.delay {
+                match delay.poll() {
+                    Ok(Async::Ready(())) => (),
+                    Ok(Async::NotReady) => return Err(IoErrorKind::WouldBlock.into()),
+                    Err(err) => return Err(IoError::new(IoErrorKind::Other, err)),
+                }
+            }
+            self.delay = None;
+            if let Some(ref mut data) = self.pending {
+                let len = buf.len().min(data.len());
+                buf[..len].copy_from_slice(&data.split_to(len));
+                if data.is_empty() {
+                    self.pending = None;
+                }
+                return Ok(len);
+            }
+            match self.receiver.poll() {
+                Ok(Async::Ready(Some(chunk))) => {
+                    if chunk.deliver_at > Instant::now() {
+                        self.delay = Some(Delay::new(chunk.deliver_at));
+                    }
+                    self.pending = Some(chunk.data);
+                }
+                Ok(Async::NotReady) => return Err(IoErrorKind::WouldBlock.into()),
+                // remote end is closed
+                Ok(Async::Ready(None)) | Err(()) => return Ok(0),
+            }
+        }
+    }
+}
+
+impl Write for MemorySocket {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.check_partition()?;
+        // an empty chunk would be read as end of stream
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let deliver_at = self.network.deliver_at(self.last_deliver_at);
+        let chunk = Chunk {
+            data: Bytes::from(buf),
+            deliver_at,
+        };
+        match self.sender {
+            Some(ref sender) if sender.unbounded_send(chunk).is_ok() => {
+                self.last_deliver_at = deliver_at;
+                Ok(buf.len())
+            }
+            _ => Err(IoErrorKind::BrokenPipe.into()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl AsyncRead for MemorySocket {}
+
+impl AsyncWrite for MemorySocket {
+    fn shutdown(&mut self) -> Poll<(), IoError> {
+        self.sender.take();
+        Ok(Async::Ready(()))
+    }
+}
+
+// Transport of a node in MemoryNetwork, all addresses are rejected when
+// no network is set, so it can be combined with the tcp transport.
+#[derive(Clone, Default, Debug)]
+pub struct MemoryTransport {
+    node: Option<(Arc<MemoryNetwork>, Ipv4Addr)>,
+}
+
+impl MemoryTransport {
+    pub fn new(network: Arc<MemoryNetwork>, name: &str) -> Self {
+        let ip = network.node_ip(name);
+        MemoryTransport {
+            node: Some((network, ip)),
+        }
+    }
+}
+
+impl Transport for MemoryTransport {
+    type Output = MemorySocket;
+    type MultiaddrFuture = FutureResult<Multiaddr, IoError>;
+    type Listener = Box<Stream<Item = Self::ListenerUpgrade, Error = IoError> + Send>;
+    type ListenerUpgrade = FutureResult<(Self::Output, Self::MultiaddrFuture), IoError>;
+    type Dial = FutureResult<(Self::Output, Self::MultiaddrFuture), IoError>;
+
+    // the ip of listen address is replaced by the node address
+    fn listen_on(self, addr: Multiaddr) -> Result<(Self::Listener, Multiaddr), (Self, Multiaddr)> {
+        let listened = match self.node {
+            Some((ref network, ip)) => {
+                parse_tcp_addr(&addr).and_then(|(_ip, port)| network.listen(ip, port).ok())
+            }
+            None => None,
+        };
+        match listened {
+            Some((receiver, listened_addr)) => {
+                let listener = receiver
+                    .map(|(socket, remote_addr)| future::ok((socket, future::ok(remote_addr))))
+                    .map_err(|()| IoError::new(IoErrorKind::Other, "memory listener closed"));
+                Ok((Box::new(listener), listened_addr))
+            }
+            None => Err((self, addr)),
+        }
+    }
+
+    // every address is taken by a memory node, so dials never leak to the real network
+    fn dial(self, addr: Multiaddr) -> Result<Self::Dial, (Self, Multiaddr)> {
+        let (network, local) = match self.node {
+            Some((ref network, ip)) => (Arc::clone(network), ip),
+            None => return Err((self, addr)),
+        };
+        let result = match parse_tcp_addr(&addr) {
+            Some((remote, port)) => MemoryNetwork::dial(&network, local, remote, port)
+                .map(|socket| (socket, future::ok(addr))),
+            None => Err(IoErrorKind::AddrNotAvailable.into()),
+        };
+        Ok(future::result(result))
+    }
+
+    fn nat_traversal(&self, _server: &Multiaddr, _observed: &Multiaddr) -> Option<Multiaddr> {
+        None
+    }
+}
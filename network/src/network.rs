@@ -1,23 +1,30 @@
 #![allow(clippy::needless_pass_by_value)]
 
+use crate::addr_gossip::{AddrGossipProtocol, ADDR_GOSSIP_PROTOCOL_ID};
 use crate::ckb_protocol::{CKBProtocol, CKBProtocols};
 use crate::ckb_protocol_handler::CKBProtocolHandler;
 use crate::ckb_protocol_handler::DefaultCKBProtocolContext;
 use crate::ckb_service::CKBService;
 use crate::identify_service::IdentifyService;
+use crate::network_config::PeerStoreType;
+use crate::network_group::{Group, MultiaddrExt};
 use crate::outbound_peer_service::OutboundPeerService;
-use crate::peer_store::{Behaviour, PeerStore, SqlitePeerStore};
+use crate::peer_store::{
+    BanRecord, Behaviour, IpSubnet, MemoryPeerStore, PeerStore, Score, SqlitePeerStore,
+};
 use crate::peers_registry::{ConnectionStatus, PeerConnection, PeerIdentifyInfo, PeersRegistry};
 use crate::ping_service::PingService;
 use crate::protocol::Protocol;
 use crate::protocol_service::ProtocolService;
 use crate::timer_service::TimerService;
+use crate::traffic_stats::{TrafficStats, UNKNOWN_MESSAGE_TYPE};
+use crate::traffic_stats_service::TrafficStatsService;
 use crate::transport::{new_transport, TransportOutput};
 use crate::NetworkConfig;
 use crate::{Error, ErrorKind, PeerIndex, ProtocolId};
 use bytes::Bytes;
 use ckb_util::{Mutex, RwLock};
-use fnv::FnvHashMap;
+use fnv::{FnvHashMap, FnvHashSet};
 use futures::future::{self, select_all, Future};
 use futures::sync::mpsc::UnboundedSender;
 use futures::sync::oneshot;
@@ -36,6 +43,7 @@ use tokio::io::{AsyncRead, AsyncWrite};
 
 const DIAL_BOOTNODE_TIMEOUT: u64 = 20;
 const PEER_ADDRS_COUNT: u32 = 5;
+const ANCHOR_PEERS_COUNT: usize = 2;
 
 #[derive(Debug, Clone)]
 pub struct SessionInfo {
@@ -48,11 +56,26 @@ pub struct PeerInfo {
     pub peer_id: PeerId,
     pub endpoint_role: Endpoint,
     pub last_ping_time: Option<u64>,
+    // ping round-trip time in milliseconds
+    pub ping: Option<u64>,
     pub connected_addr: Multiaddr,
+    pub connected_time: Option<u64>,
     pub identify_info: Option<PeerIdentifyInfo>,
 }
 
 impl PeerInfo {
+    fn new(peer_id: &PeerId, peer: &PeerConnection) -> Self {
+        PeerInfo {
+            peer_id: peer_id.to_owned(),
+            endpoint_role: peer.endpoint_role,
+            last_ping_time: peer.last_ping_time,
+            ping: peer.ping,
+            connected_addr: peer.connected_addr.clone(),
+            connected_time: peer.connected_time,
+            identify_info: peer.identify_info.clone(),
+        }
+    }
+
     #[inline]
     pub fn is_outbound(&self) -> bool {
         self.endpoint_role == Endpoint::Dialer
@@ -64,6 +87,24 @@ impl PeerInfo {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct ConnectedPeer {
+    pub peer_index: PeerIndex,
+    pub peer: PeerInfo,
+    pub network_group: String,
+    pub score: Score,
+}
+
+fn new_peer_store<P: PeerStore + 'static>(
+    mut peer_store: P,
+    bootnodes: Vec<(PeerId, Multiaddr)>,
+) -> Arc<RwLock<dyn PeerStore>> {
+    for (peer_id, addr) in bootnodes {
+        peer_store.add_bootnode(peer_id, addr);
+    }
+    Arc::new(RwLock::new(peer_store))
+}
+
 pub struct Network {
     peers_registry: RwLock<PeersRegistry>,
     peer_store: Arc<RwLock<dyn PeerStore>>,
@@ -72,11 +113,25 @@ pub struct Network {
     pub(crate) ckb_protocols: CKBProtocols<Arc<CKBProtocolHandler>>,
     local_private_key: secio::SecioKeyPair,
     local_peer_id: PeerId,
+    traffic_stats: Mutex<TrafficStats>,
 }
 
 impl Network {
     pub fn report(&self, peer_id: &PeerId, behaviour: Behaviour) {
-        self.peer_store.write().report(peer_id, behaviour);
+        if self.is_whitelisted(peer_id) {
+            debug!(target: "network", "ignore {:?} of whitelisted peer {:?}", behaviour, peer_id);
+            return;
+        }
+        let report_result = self.peer_store.write().report(peer_id, behaviour);
+        // peer store already banned the peer address, we only need to disconnect it
+        if report_result.is_banned() {
+            info!(target: "network", "ban peer {:?} after {:?}", peer_id, behaviour);
+            self.drop_peer(peer_id);
+        }
+    }
+
+    pub fn is_whitelisted(&self, peer_id: &PeerId) -> bool {
+        self.peers_registry.read().is_whitelisted(peer_id)
     }
 
     pub fn drop_peer(&self, peer_id: &PeerId) {
@@ -179,6 +234,45 @@ impl Network {
         peers.into_iter()
     }
 
+    pub(crate) fn connected_peers(&self) -> Vec<ConnectedPeer> {
+        let peers_registry = self.peers_registry.read();
+        let peer_store = self.peer_store.read();
+        peers_registry
+            .peers_iter()
+            .filter_map(|(peer_id, peer)| {
+                peer.peer_index.map(|peer_index| ConnectedPeer {
+                    peer_index,
+                    peer: PeerInfo::new(peer_id, peer),
+                    network_group: format!("{:?}", peer.network_group()),
+                    score: peer_store.peer_score_or_default(peer_id),
+                })
+            })
+            .collect()
+    }
+
+    pub(crate) fn record_received(
+        &self,
+        peer_id: &PeerId,
+        protocol_id: ProtocolId,
+        message_type: &'static str,
+        bytes: usize,
+    ) {
+        self.traffic_stats
+            .lock()
+            .record_received(peer_id, protocol_id, message_type, bytes);
+    }
+
+    pub(crate) fn traffic_stats(&self) -> TrafficStats {
+        let peers_registry = self.peers_registry.read();
+        let mut traffic_stats = self.traffic_stats.lock();
+        traffic_stats.retain_peers(|peer_id| peers_registry.get(peer_id).is_some());
+        traffic_stats.clone()
+    }
+
+    pub(crate) fn outbound_network_groups(&self) -> FnvHashSet<Group> {
+        self.peers_registry.read().outbound_network_groups()
+    }
+
     pub(crate) fn peers_indexes(&self) -> Vec<PeerIndex> {
         let peers_registry = self.peers_registry.read();
         let iter = peers_registry.connected_peers_indexes();
@@ -186,10 +280,50 @@ impl Network {
     }
 
     #[inline]
-    pub(crate) fn ban_peer(&self, peer_id: &PeerId, timeout: Duration) {
+    pub(crate) fn ban_peer(&self, peer_id: &PeerId, timeout: Duration, reason: String) {
         let mut peers_registry = self.peers_registry.write();
+        if peers_registry.is_whitelisted(peer_id) {
+            debug!(target: "network", "ignore ban of whitelisted peer {:?}", peer_id);
+            return;
+        }
         peers_registry.drop_peer(peer_id);
-        self.peer_store.write().ban_peer(peer_id, timeout);
+        self.peer_store.write().ban_peer(peer_id, timeout, reason);
+    }
+
+    pub(crate) fn ban_subnet(&self, subnet: IpSubnet, timeout: Duration, reason: String) {
+        self.peer_store.write().ban_subnet(subnet, timeout, reason);
+        // disconnect connected peers in the subnet
+        let mut peers_registry = self.peers_registry.write();
+        let banned_peers = peers_registry
+            .peers_iter()
+            .filter(|(peer_id, peer)| {
+                peer.connected_addr
+                    .extract_ip_addr()
+                    .map(|ip| subnet.contains(ip))
+                    .unwrap_or(false)
+                    && !peers_registry.is_whitelisted(peer_id)
+            })
+            .map(|(peer_id, _peer)| peer_id.to_owned())
+            .collect::<Vec<_>>();
+        for peer_id in banned_peers {
+            peers_registry.drop_peer(&peer_id);
+        }
+    }
+
+    pub(crate) fn unban_peer(&self, peer_id: &PeerId) -> bool {
+        self.peer_store.write().unban_peer(peer_id)
+    }
+
+    pub(crate) fn unban_subnet(&self, subnet: &IpSubnet) -> bool {
+        self.peer_store.write().unban_subnet(subnet)
+    }
+
+    pub(crate) fn banned_list(&self) -> Vec<BanRecord> {
+        self.peer_store.read().banned_list()
+    }
+
+    pub(crate) fn clear_banned(&self) {
+        self.peer_store.write().clear_banned()
     }
 
     #[inline]
@@ -239,9 +373,18 @@ impl Network {
                 .and_then(|(_, protocol_connec)| protocol_connec.poll())
                 .map(|(sender, _)| sender)
             {
+                let message_type = self
+                    .ckb_protocols
+                    .find_protocol(protocol_id)
+                    .map(|protocol| protocol.protocol_handler().message_type(&data))
+                    .unwrap_or(UNKNOWN_MESSAGE_TYPE);
+                let bytes = data.len();
                 sender.unbounded_send(data).map_err(|err| {
                     Error::from(ErrorKind::Other(format!("send to error: {:?}", err)))
                 })?;
+                self.traffic_stats
+                    .lock()
+                    .record_sent(peer_id, protocol_id, message_type, bytes);
                 Ok(())
             } else {
                 Err(ErrorKind::Other(format!(
@@ -281,10 +424,9 @@ impl Network {
         // get peer protocol_connection
         match peers_registry.try_outbound_peer(peer_id.clone(), connected_addr.clone()) {
             Ok(_) => {
-                let _ = self
-                    .peer_store()
+                self.peer_store()
                     .write()
-                    .add_discovered_address(peer_id, connected_addr);
+                    .add_tried_address(peer_id, connected_addr);
                 let peer = peers_registry.get_mut(&peer_id).unwrap();
                 Ok(self.ckb_protocol_connec(peer, protocol_id))
             }
@@ -335,13 +477,7 @@ impl Network {
                         None => None,
                     };
                 let session = SessionInfo {
-                    peer: PeerInfo {
-                        peer_id: peer_id.to_owned(),
-                        endpoint_role: peer.endpoint_role,
-                        last_ping_time: peer.last_ping_time,
-                        connected_addr: peer.connected_addr.clone(),
-                        identify_info: peer.identify_info.clone(),
-                    },
+                    peer: PeerInfo::new(peer_id, peer),
                     protocol_version,
                 };
                 Some(session)
@@ -485,7 +621,7 @@ impl Network {
 
     pub(crate) fn inner_build(
         config: &NetworkConfig,
-        ckb_protocols: Vec<CKBProtocol<Arc<CKBProtocolHandler>>>,
+        mut ckb_protocols: Vec<CKBProtocol<Arc<CKBProtocolHandler>>>,
     ) -> Result<Arc<Self>, Error> {
         let local_private_key = match config.fetch_private_key() {
             Some(private_key) => private_key?,
@@ -497,13 +633,10 @@ impl Network {
             .iter()
             .map(|addr| (addr.to_owned(), std::u8::MAX))
             .collect();
-        let peer_store: Arc<RwLock<dyn PeerStore>> = {
-            let mut peer_store = SqlitePeerStore::default();
-            let bootnodes = config.bootnodes()?;
-            for (peer_id, addr) in bootnodes {
-                peer_store.add_bootnode(peer_id, addr);
-            }
-            Arc::new(RwLock::new(peer_store))
+        let bootnodes = config.bootnodes()?;
+        let peer_store = match config.peer_store_type {
+            PeerStoreType::Sqlite => new_peer_store(SqlitePeerStore::default(), bootnodes),
+            PeerStoreType::Memory => new_peer_store(MemoryPeerStore::default(), bootnodes),
         };
         let reserved_peers = config
             .reserved_peers()?
@@ -516,7 +649,19 @@ impl Network {
             config.max_outbound_peers,
             config.reserved_only,
             reserved_peers,
+            config.whitelist()?,
+        );
+        let addr_gossip = AddrGossipProtocol::new(
+            local_private_key.to_peer_id(),
+            Arc::clone(&peer_store),
+            config.addr_announce_interval,
         );
+        ckb_protocols.push(CKBProtocol::new(
+            "ckb".to_string(),
+            Arc::new(addr_gossip) as Arc<_>,
+            ADDR_GOSSIP_PROTOCOL_ID,
+            &[1][..],
+        ));
         let network: Arc<Network> = Arc::new(Network {
             peers_registry: RwLock::new(peers_registry),
             peer_store: Arc::clone(&peer_store),
@@ -525,6 +670,7 @@ impl Network {
             ckb_protocols: CKBProtocols(ckb_protocols),
             local_private_key: local_private_key.clone(),
             local_peer_id: local_private_key.to_peer_id(),
+            traffic_stats: Mutex::new(TrafficStats::default()),
         });
         Ok(network)
     }
@@ -537,11 +683,12 @@ impl Network {
         let local_private_key = network.local_private_key().to_owned();
         let local_peer_id: PeerId = local_private_key.to_peer_id();
         let basic_transport_timeout = config.transport_timeout;
+        let transport_type = &config.transport_type;
         let client_version = config.client_version.clone();
         let protocol_version = config.protocol_version.clone();
         let max_outbound = config.max_outbound_peers as usize;
         let basic_transport = {
-            let basic_transport = new_transport(local_private_key, basic_transport_timeout)
+            let basic_transport = new_transport(local_private_key, basic_transport_timeout, transport_type)
                 .map_err_dial({
                     move |err, addr| {
                         trace!(target: "network", "Failed to connect to peer {}, error: {:?}", addr, err);
@@ -586,6 +733,7 @@ impl Network {
             protocol_version,
             identify_timeout: config.identify_timeout,
             identify_interval: config.identify_interval,
+            addr_announce_interval: config.addr_announce_interval,
         });
 
         let ckb_protocol_service = Arc::new(CKBService {});
@@ -596,6 +744,9 @@ impl Network {
             try_connect_interval: config.try_outbound_connect_interval,
             timeout: config.try_outbound_connect_timeout,
         });
+        let traffic_stats_service = Arc::new(TrafficStatsService {
+            log_interval: config.traffic_stats_log_interval,
+        });
         // Transport used to handling received connections
         let handling_transport = {
             let transport = basic_transport.clone();
@@ -678,6 +829,17 @@ impl Network {
         {
             let network = Arc::clone(&network);
             let dial_timeout = Duration::from_secs(DIAL_BOOTNODE_TIMEOUT);
+            // dial anchor peers first, they were our outbound peers before last shutdown
+            for (peer_id, addr) in config.read_anchors() {
+                debug!(target: "network", "dial anchor peer {:?} {:?}", peer_id, addr);
+                network.dial_to_peer(
+                    basic_transport.clone(),
+                    &addr,
+                    &peer_id,
+                    &swarm_controller,
+                    dial_timeout,
+                );
+            }
             // dial reserved_nodes
             for (peer_id, addr) in config.reserved_peers()? {
                 network.dial_to_peer(
@@ -736,22 +898,31 @@ impl Network {
                 swarm_controller.clone(),
                 basic_transport.clone(),
             ),
+            traffic_stats_service.start_protocol(
+                Arc::clone(&network),
+                swarm_controller.clone(),
+                basic_transport.clone(),
+            ),
             Box::new(close_rx.map_err(|err| IoError::new(IoErrorKind::Other, err))),
         ];
-        let service_futures = select_all(futures)
-            .and_then({
-                let network = Arc::clone(&network);
-                move |_| {
-                    let mut peers_registry = network.peers_registry.write();
-                    debug!(target: "network", "drop all connections...");
-                    peers_registry.drop_all();
-                    Ok(())
+        let service_futures = select_all(futures).then({
+            let network = Arc::clone(&network);
+            let config = config.clone();
+            move |result| {
+                // save anchors whether the services exit normally or with an error
+                let mut peers_registry = network.peers_registry.write();
+                let anchors = peers_registry.anchor_peers(ANCHOR_PEERS_COUNT);
+                if let Err(err) = config.write_anchors(&anchors) {
+                    warn!(target: "network", "failed to save anchor peers: {:?}", err);
                 }
-            })
-            .map_err(|(err, _, _)| {
-                debug!(target: "network", "network exit, error {:?}", err);
-                err
-            });
+                debug!(target: "network", "drop all connections...");
+                peers_registry.drop_all();
+                result.map(|_| ()).map_err(|(err, _, _)| {
+                    debug!(target: "network", "network exit, error {:?}", err);
+                    err
+                })
+            }
+        });
         let service_futures =
             Box::new(service_futures) as Box<Future<Item = (), Error = IoError> + Send>;
         Ok(service_futures)
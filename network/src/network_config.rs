@@ -1,3 +1,5 @@
+use crate::memory_transport::MemoryNetwork;
+use crate::whitelist::Whitelist;
 use crate::PeerId;
 use crate::{Error, ErrorKind};
 use bytes::Bytes;
@@ -7,14 +9,33 @@ use libp2p::secio;
 use log::info;
 use rand;
 use rand::Rng;
+use serde_derive::Deserialize;
 use std::fs;
 use std::io::Read;
 use std::io::Write;
 use std::io::{Error as IoError, ErrorKind as IoErrorKind};
 use std::iter;
 use std::net::Ipv4Addr;
+use std::sync::Arc;
 use std::time::Duration;
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum PeerStoreType {
+    Sqlite,
+    // nothing is persisted, used by tests and ephemeral nodes
+    Memory,
+}
+
+#[derive(Clone, Debug)]
+pub enum TransportType {
+    Tcp,
+    // connects to nodes of the same MemoryNetwork in process, used by multi-node tests
+    Memory {
+        network: Arc<MemoryNetwork>,
+        node: String,
+    },
+}
+
 #[derive(Clone, Debug)]
 pub struct NetworkConfig {
     pub listen_addresses: Vec<Multiaddr>,
@@ -26,11 +47,17 @@ pub struct NetworkConfig {
     pub max_inbound_peers: u32,
     pub max_outbound_peers: u32,
     pub reserved_peers: Vec<String>,
+    // peer ids or IP subnets of privileged peers
+    pub whitelist: Vec<String>,
     pub secret_key: Option<Bytes>,
     pub secret_key_path: Option<String>,
+    // outbound peers saved at shutdown and reconnected first at startup
+    pub anchors_path: Option<String>,
     // peer_store path
     pub config_dir_path: Option<String>,
     pub bootnodes: Vec<String>,
+    pub peer_store_type: PeerStoreType,
+    pub transport_type: TransportType,
     pub ping_interval: Duration,
     pub ping_timeout: Duration,
     pub discovery_timeout: Duration,
@@ -38,8 +65,11 @@ pub struct NetworkConfig {
     pub discovery_interval: Duration,
     pub identify_timeout: Duration,
     pub identify_interval: Duration,
+    // identify connected peers again to refresh their addresses
+    pub addr_announce_interval: Duration,
     pub try_outbound_connect_timeout: Duration,
     pub try_outbound_connect_interval: Duration,
+    pub traffic_stats_log_interval: Duration,
 }
 
 impl NetworkConfig {
@@ -106,37 +136,66 @@ impl NetworkConfig {
     }
 
     pub fn reserved_peers(&self) -> Result<Vec<(PeerId, Multiaddr)>, Error> {
-        let mut peers = Vec::with_capacity(self.reserved_peers.len());
-        for addr_str in &self.reserved_peers {
-            let mut addr = addr_str
-                .to_multiaddr()
-                .map_err(|_| ErrorKind::ParseAddress)?;
-            let peer_id = match addr.pop() {
-                Some(AddrComponent::P2P(key)) => {
-                    PeerId::from_bytes(key.into_bytes()).map_err(|_| ErrorKind::ParseAddress)?
-                }
-                _ => return Err(ErrorKind::ParseAddress.into()),
-            };
-            peers.push((peer_id, addr))
-        }
-        Ok(peers)
+        self.reserved_peers
+            .iter()
+            .map(|addr_str| parse_peer_addr(addr_str))
+            .collect()
+    }
+
+    pub fn whitelist(&self) -> Result<Whitelist, Error> {
+        Whitelist::parse(&self.whitelist)
     }
 
     pub fn bootnodes(&self) -> Result<Vec<(PeerId, Multiaddr)>, Error> {
-        let mut peers = Vec::with_capacity(self.bootnodes.len());
-        for addr_str in &self.bootnodes {
-            let mut addr = addr_str
-                .to_multiaddr()
-                .map_err(|_| ErrorKind::ParseAddress)?;
-            let peer_id = match addr.pop() {
-                Some(AddrComponent::P2P(key)) => {
-                    PeerId::from_bytes(key.into_bytes()).map_err(|_| ErrorKind::ParseAddress)?
-                }
-                _ => return Err(ErrorKind::ParseAddress.into()),
-            };
-            peers.push((peer_id, addr));
+        self.bootnodes
+            .iter()
+            .map(|addr_str| parse_peer_addr(addr_str))
+            .collect()
+    }
+
+    // invalid lines are ignored, anchors are only a hint for the first connections
+    pub fn read_anchors(&self) -> Vec<(PeerId, Multiaddr)> {
+        let content = match self.anchors_path {
+            Some(ref path) => fs::read_to_string(path).unwrap_or_default(),
+            None => return Vec::new(),
+        };
+        content
+            .lines()
+            .filter_map(|line| parse_peer_addr(line.trim()).ok())
+            .collect()
+    }
+
+    pub fn write_anchors(&self, anchors: &[(PeerId, Multiaddr)]) -> Result<(), IoError> {
+        if let Some(ref anchors_path) = self.anchors_path {
+            let content = anchors
+                .iter()
+                .map(|(peer_id, addr)| {
+                    // connected addresses may already end with the peer id
+                    let mut addr = addr.clone();
+                    if let Some(AddrComponent::P2P(_)) = addr.iter().last() {
+                        addr.pop();
+                    }
+                    format!("{}/p2p/{}\n", addr, peer_id.to_base58())
+                })
+                .collect::<String>();
+            return fs::write(anchors_path, content);
+        }
+        Ok(())
+    }
+}
+
+// parse address like "/ip4/127.0.0.1/tcp/30333/p2p/QmXXX"
+fn parse_peer_addr(addr_str: &str) -> Result<(PeerId, Multiaddr), Error> {
+    let mut addr = addr_str
+        .to_multiaddr()
+        .map_err(|_| ErrorKind::ParseAddress)?;
+    match addr.pop() {
+        Some(AddrComponent::P2P(key)) => {
+            let peer_id =
+                PeerId::from_bytes(key.into_bytes()).map_err(|_| ErrorKind::ParseAddress)?;
+            Ok((peer_id, addr))
         }
-        Ok(peers)
+        _ => Err(ErrorKind::ParseAddress.into()),
     }
 }
 
@@ -154,10 +213,14 @@ impl Default for NetworkConfig {
             max_outbound_peers: 15,
             max_inbound_peers: 10,
             reserved_peers: vec![],
+            whitelist: vec![],
             secret_key: None,
             secret_key_path: None,
+            anchors_path: None,
             bootnodes: vec![],
             config_dir_path: None,
+            peer_store_type: PeerStoreType::Sqlite,
+            transport_type: TransportType::Tcp,
             // protocol services config
             ping_interval: Duration::from_secs(30),
             ping_timeout: Duration::from_secs(30),
@@ -166,8 +229,10 @@ impl Default for NetworkConfig {
             discovery_interval: Duration::from_secs(15),
             identify_timeout: Duration::from_secs(30),
             identify_interval: Duration::from_secs(15),
+            addr_announce_interval: Duration::from_secs(600),
             try_outbound_connect_timeout: Duration::from_secs(30),
             try_outbound_connect_interval: Duration::from_secs(15),
+            traffic_stats_log_interval: Duration::from_secs(60),
         }
     }
 }
@@ -9,6 +9,17 @@ pub enum Group {
     IP6([u8; 4]),
 }
 
+impl Group {
+    // only groups of routable IP prefixes are considered for peers diversity,
+    // local nodes and unknown addresses are not limited
+    pub fn is_routable(&self) -> bool {
+        match self {
+            Group::IP4(_) | Group::IP6(_) => true,
+            Group::NoGroup | Group::LocalNetwork => false,
+        }
+    }
+}
+
 pub trait NetworkGroup {
     fn network_group(&self) -> Group;
 }
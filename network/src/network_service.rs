@@ -1,15 +1,18 @@
 use crate::ckb_protocol::CKBProtocol;
 use crate::ckb_protocol_handler::CKBProtocolHandler;
 use crate::ckb_protocol_handler::{CKBProtocolContext, DefaultCKBProtocolContext};
-use crate::network::Network;
+use crate::network::{ConnectedPeer, Network};
+use crate::peer_store::{BanRecord, IpSubnet};
+use crate::traffic_stats::TrafficStats;
 use crate::NetworkConfig;
-use crate::{Error, ErrorKind, ProtocolId};
+use crate::{Error, ErrorKind, PeerId, ProtocolId};
 use ckb_util::Mutex;
 use futures::future::Future;
 use futures::sync::oneshot;
 use log::{debug, info};
 use std::sync::Arc;
 use std::thread;
+use std::time::Duration;
 use tokio::runtime;
 
 pub struct StopHandler {
@@ -47,6 +50,45 @@ impl NetworkService {
         self.network.node_id()
     }
 
+    pub fn connected_peers(&self) -> Vec<ConnectedPeer> {
+        self.network.connected_peers()
+    }
+
+    // Stats of disconnected peers are dropped, protocol stats are kept
+    pub fn traffic_stats(&self) -> TrafficStats {
+        self.network.traffic_stats()
+    }
+
+    // Connected peer is disconnected immediately after banned
+    pub fn ban_peer(&self, peer_id: &PeerId, timeout: Duration, reason: String) {
+        self.network.ban_peer(peer_id, timeout, reason)
+    }
+
+    pub fn unban_peer(&self, peer_id: &PeerId) -> bool {
+        self.network.unban_peer(peer_id)
+    }
+
+    // Connected peers in the subnet are disconnected immediately after banned
+    pub fn ban_subnet(&self, subnet: IpSubnet, timeout: Duration, reason: String) {
+        self.network.ban_subnet(subnet, timeout, reason)
+    }
+
+    pub fn unban_subnet(&self, subnet: &IpSubnet) -> bool {
+        self.network.unban_subnet(subnet)
+    }
+
+    pub fn banned_list(&self) -> Vec<BanRecord> {
+        self.network.banned_list()
+    }
+
+    pub fn clear_banned(&self) {
+        self.network.clear_banned()
+    }
+
+    pub fn is_banned(&self, peer_id: &PeerId) -> bool {
+        self.network.peer_store().read().is_banned(peer_id)
+    }
+
     pub fn with_protocol_context<F, T>(&self, protocol_id: ProtocolId, f: F) -> Option<T>
     where
         F: FnOnce(&CKBProtocolContext) -> T,
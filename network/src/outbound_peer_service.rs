@@ -1,3 +1,4 @@
+use crate::network_group::NetworkGroup;
 use crate::protocol::Protocol;
 use crate::protocol_service::ProtocolService;
 use crate::transport::TransportOutput;
@@ -8,7 +9,7 @@ use futures::Stream;
 use libp2p::core::Multiaddr;
 use libp2p::core::MuxedTransport;
 use libp2p::core::SwarmController;
-use log::warn;
+use log::{debug, warn};
 use std::boxed::Box;
 use std::io::{Error as IoError, ErrorKind as IoErrorKind};
 use std::sync::Arc;
@@ -18,6 +19,8 @@ use std::usize;
 use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::timer::Interval;
 
+const ATTEMPT_CANDIDATES_RATIO: usize = 3;
+
 pub struct OutboundPeerService {
     pub try_connect_interval: Duration,
     pub timeout: Duration,
@@ -82,26 +85,41 @@ impl<T: Send + 'static> ProtocolService<T> for OutboundPeerService {
             let timeout = self.timeout;
             let network = Arc::clone(&network);
             move |_| {
+                let evicted = network.peer_store().write().evict_stale_addrs();
+                if evicted > 0 {
+                    debug!(target: "network", "evict {} stale addresses", evicted);
+                }
                 let connection_status = network.connection_status();
                 let new_outbound = (connection_status.max_outbound
                     - connection_status.unreserved_outbound)
                     as usize;
                 if new_outbound > 0 {
+                    // fetch more candidates, some of them are skipped by network group
                     let attempt_peers = network
                         .peer_store()
                         .read()
-                        .peers_to_attempt(new_outbound as u32);
-                    for (peer_id, addr) in attempt_peers.iter().filter_map(|(peer_id, addr)| {
-                        if network.local_peer_id() != peer_id {
-                            Some((peer_id.clone(), addr.clone()))
-                        } else {
-                            None
-                        }
-                    }) {
+                        .peers_to_attempt((new_outbound * ATTEMPT_CANDIDATES_RATIO) as u32);
+                    let mut network_groups = network.outbound_network_groups();
+                    for (peer_id, addr) in attempt_peers
+                        .iter()
+                        .filter(|(peer_id, addr)| {
+                            if network.local_peer_id() == peer_id {
+                                return false;
+                            }
+                            // dial at most one peer in each network group
+                            let group = addr.network_group();
+                            !group.is_routable() || network_groups.insert(group)
+                        })
+                        .take(new_outbound)
+                    {
+                        network
+                            .peer_store()
+                            .write()
+                            .mark_addr_attempted(peer_id, addr);
                         network.dial_to_peer(
                             transport.clone(),
-                            &addr,
-                            &peer_id,
+                            addr,
+                            peer_id,
                             &swarm_controller,
                             timeout,
                         );
@@ -1,5 +1,9 @@
+mod ban;
 mod db;
+pub(crate) mod memory_peer_store;
 pub mod sqlite;
+pub use crate::peer_store::ban::{BanRecord, IpSubnet};
+pub use crate::peer_store::memory_peer_store::MemoryPeerStore;
 pub use crate::peer_store::sqlite_peer_store::SqlitePeerStore;
 #[cfg(db_trace)]
 pub mod db_trace;
@@ -8,8 +12,21 @@ pub(crate) mod sqlite_peer_store;
 use crate::PeerId;
 use fnv::FnvHashMap;
 use libp2p::core::{Endpoint, Multiaddr};
+use rand::{thread_rng, Rng};
 use std::time::Duration;
 
+pub(crate) const PEER_STORE_LIMIT: u32 = 8192;
+pub(crate) const PEER_NOT_SEEN_TIMEOUT_SECS: u32 = 14 * 24 * 3600;
+// address relay limit, new addresses of a peer are ignored after reaching it
+pub(crate) const ADDRS_PER_PEER_LIMIT: usize = 16;
+pub(crate) const ADDR_NOT_SEEN_TIMEOUT_SECS: u32 = 30 * 24 * 3600;
+// don't attempt the same address again within this interval
+pub(crate) const ADDR_RETRY_INTERVAL_SECS: u32 = 600;
+// a new address is evicted after these failed attempts
+pub(crate) const ADDR_MAX_RETRIES: u32 = 3;
+// a tried address is evicted after these continuous failed attempts
+pub(crate) const ADDR_MAX_FAILURES: u32 = 10;
+
 #[allow(dead_code)]
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub enum Behaviour {
@@ -18,6 +35,13 @@ pub enum Behaviour {
     Ping,
     Connect,
     UnexpectedDisconnect,
+    // Protocol misbehaviours, reported by sync and relay protocols
+    MalformedMessage,
+    UnconnectedHeaders,
+    InvalidHeader,
+    InvalidBlock,
+    InvalidCompactBlock,
+    TooManyMessages,
 }
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum Status {
@@ -56,6 +80,72 @@ impl ReportResult {
 
 pub type Score = i32;
 
+// Addresses are kept in two buckets like bitcoin's addrman:
+// tried addresses have been connected successfully, others are new.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddrInfo {
+    pub addr: Multiaddr,
+    pub last_seen: Duration,
+    pub last_attempt: Duration,
+    // failed attempts since last success
+    pub attempts: u32,
+    pub tried: bool,
+}
+
+impl AddrInfo {
+    pub fn new(addr: Multiaddr, now: Duration) -> Self {
+        AddrInfo {
+            addr,
+            last_seen: now,
+            last_attempt: Duration::from_secs(0),
+            attempts: 0,
+            tried: false,
+        }
+    }
+
+    // terrible addresses are never attempted and will be evicted
+    pub fn is_terrible(&self, now: Duration) -> bool {
+        self.last_seen + Duration::from_secs(ADDR_NOT_SEEN_TIMEOUT_SECS.into()) < now
+            || (!self.tried && self.attempts >= ADDR_MAX_RETRIES)
+            || self.attempts >= ADDR_MAX_FAILURES
+    }
+
+    pub fn can_attempt(&self, now: Duration) -> bool {
+        !self.is_terrible(now)
+            && self.last_attempt + Duration::from_secs(ADDR_RETRY_INTERVAL_SECS.into()) <= now
+    }
+}
+
+// pick peers from tried and new buckets with equal chance, each peer is picked once
+pub(crate) fn select_from_buckets(
+    mut tried: Vec<(PeerId, Multiaddr)>,
+    mut new: Vec<(PeerId, Multiaddr)>,
+    count: u32,
+) -> Vec<(PeerId, Multiaddr)> {
+    let mut rng = thread_rng();
+    let mut peers: Vec<(PeerId, Multiaddr)> = Vec::with_capacity(count as usize);
+    while peers.len() < count as usize {
+        let bucket = match (tried.is_empty(), new.is_empty()) {
+            (true, true) => break,
+            (false, true) => &mut tried,
+            (true, false) => &mut new,
+            (false, false) => {
+                if rng.gen_bool(0.5) {
+                    &mut tried
+                } else {
+                    &mut new
+                }
+            }
+        };
+        if let Some((peer_id, addr)) = bucket.pop() {
+            if peers.iter().all(|(id, _)| id != &peer_id) {
+                peers.push((peer_id, addr));
+            }
+        }
+    }
+    peers
+}
+
 pub struct ScoringSchema {
     schema: FnvHashMap<Behaviour, Score>,
     peer_init_score: Score,
@@ -71,6 +161,15 @@ impl ScoringSchema {
             (Behaviour::Ping, 5),
             (Behaviour::Connect, 10),
             (Behaviour::UnexpectedDisconnect, -20),
+            (Behaviour::MalformedMessage, -40),
+            // headers may not connect because of a race with our own sync
+            (Behaviour::UnconnectedHeaders, -10),
+            (Behaviour::InvalidHeader, -40),
+            (Behaviour::InvalidBlock, -60),
+            // a reconstructed block may be invalid due to short id collisions
+            (Behaviour::InvalidCompactBlock, -20),
+            // over-limit messages are already dropped
+            (Behaviour::TooManyMessages, -10),
         ]
         .iter()
         .cloned()
@@ -110,12 +209,20 @@ pub trait PeerStore: Send + Sync {
     // initial or update peer_info in peer_store
     fn new_connected_peer(&mut self, peer_id: &PeerId, address: Multiaddr, endpoint: Endpoint);
     // add peer discovered addresses, return numbers of new inserted line, return Err if peer not exists
+    // last seen time of known addresses is refreshed
     fn add_discovered_address(&mut self, peer_id: &PeerId, address: Multiaddr) -> Result<(), ()>;
     fn add_discovered_addresses(
         &mut self,
         peer_id: &PeerId,
         address: Vec<Multiaddr>,
     ) -> Result<usize, ()>;
+    // move the address to tried bucket after an outbound connection succeeded
+    fn add_tried_address(&mut self, peer_id: &PeerId, address: Multiaddr);
+    // count an outbound attempt, it's reset by add_tried_address
+    fn mark_addr_attempted(&mut self, peer_id: &PeerId, address: &Multiaddr);
+    // remove terrible addresses, return numbers of removed addresses
+    fn evict_stale_addrs(&mut self) -> usize;
+    fn peer_addr_infos(&self, peer_id: &PeerId) -> Vec<AddrInfo>;
     fn report(&mut self, peer_id: &PeerId, behaviour: Behaviour) -> ReportResult;
     fn update_status(&mut self, peer_id: &PeerId, status: Status);
     fn peer_status(&self, peer_id: &PeerId) -> Status;
@@ -124,9 +231,22 @@ pub trait PeerStore: Send + Sync {
     // should return high scored nodes if possible, otherwise, return boostrap nodes
     fn bootnodes(&self, count: u32) -> Vec<(PeerId, Multiaddr)>;
     fn peer_addrs(&self, peer_id: &PeerId, count: u32) -> Option<Vec<Multiaddr>>;
+    // select not connected peers from tried and new buckets
     fn peers_to_attempt(&self, count: u32) -> Vec<(PeerId, Multiaddr)>;
-    fn ban_peer(&mut self, peer_id: &PeerId, timeout: Duration);
+    // random tried addresses without failed attempts, they are gossiped to other peers
+    fn good_addrs(&self, count: u32) -> Vec<(PeerId, Multiaddr)>;
+    // ban the connected address of peer
+    fn ban_peer(&mut self, peer_id: &PeerId, timeout: Duration, reason: String);
+    // unban the connected address of peer, return false if it's not banned
+    fn unban_peer(&mut self, peer_id: &PeerId) -> bool;
+    fn ban_subnet(&mut self, subnet: IpSubnet, timeout: Duration, reason: String);
+    // return false if the subnet is not banned
+    fn unban_subnet(&mut self, subnet: &IpSubnet) -> bool;
+    // return all unexpired bans
+    fn banned_list(&self) -> Vec<BanRecord>;
+    fn clear_banned(&mut self);
     fn is_banned(&self, peer_id: &PeerId) -> bool;
+    fn is_addr_banned(&self, addr: &Multiaddr) -> bool;
     fn scoring_schema(&self) -> &ScoringSchema;
     fn peer_score_or_default(&self, peer_id: &PeerId) -> Score {
         self.peer_score(peer_id)
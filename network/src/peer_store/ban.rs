@@ -0,0 +1,107 @@
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// IP subnet used by ban list, the address is masked by the prefix length.
+/// A single IP is a subnet with full prefix length.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct IpSubnet {
+    addr: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpSubnet {
+    // return None if prefix_len is longer than the address
+    pub fn new(addr: IpAddr, prefix_len: u8) -> Option<Self> {
+        if prefix_len > max_prefix_len(addr) {
+            return None;
+        }
+        Some(IpSubnet {
+            addr: mask(addr, prefix_len),
+            prefix_len,
+        })
+    }
+
+    pub fn from_ip(addr: IpAddr) -> Self {
+        IpSubnet {
+            addr,
+            prefix_len: max_prefix_len(addr),
+        }
+    }
+
+    pub fn addr(&self) -> IpAddr {
+        self.addr
+    }
+
+    pub fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip) {
+            (IpAddr::V4(_), IpAddr::V4(_)) | (IpAddr::V6(_), IpAddr::V6(_)) => {
+                mask(ip, self.prefix_len) == self.addr
+            }
+            _ => false,
+        }
+    }
+}
+
+fn max_prefix_len(addr: IpAddr) -> u8 {
+    match addr {
+        IpAddr::V4(_) => 32,
+        IpAddr::V6(_) => 128,
+    }
+}
+
+fn mask(addr: IpAddr, prefix_len: u8) -> IpAddr {
+    match addr {
+        IpAddr::V4(ip) => {
+            let mask = u32::max_value()
+                .checked_shl(32 - u32::from(prefix_len))
+                .unwrap_or(0);
+            IpAddr::V4(Ipv4Addr::from(u32::from(ip) & mask))
+        }
+        IpAddr::V6(ip) => {
+            let mask = u128::max_value()
+                .checked_shl(128 - u32::from(prefix_len))
+                .unwrap_or(0);
+            IpAddr::V6(Ipv6Addr::from(u128::from(ip) & mask))
+        }
+    }
+}
+
+impl FromStr for IpSubnet {
+    type Err = ();
+
+    // parse "192.168.0.1" or "192.168.0.0/16"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(2, '/');
+        let addr = parts
+            .next()
+            .and_then(|addr| addr.parse::<IpAddr>().ok())
+            .ok_or(())?;
+        match parts.next() {
+            Some(prefix_len) => {
+                let prefix_len = prefix_len.parse::<u8>().map_err(|_| ())?;
+                IpSubnet::new(addr, prefix_len).ok_or(())
+            }
+            None => Ok(IpSubnet::from_ip(addr)),
+        }
+    }
+}
+
+impl fmt::Display for IpSubnet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix_len)
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BanRecord {
+    pub subnet: IpSubnet,
+    // unix time when the ban expires
+    pub ban_until: Duration,
+    pub reason: String,
+}
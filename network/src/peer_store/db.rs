@@ -1,4 +1,7 @@
-use super::{Multiaddr, PeerId, Score, Status};
+use super::{
+    AddrInfo, BanRecord, IpSubnet, Multiaddr, PeerId, Score, Status, ADDRS_PER_PEER_LIMIT,
+    ADDR_MAX_FAILURES, ADDR_MAX_RETRIES, ADDR_NOT_SEEN_TIMEOUT_SECS, ADDR_RETRY_INTERVAL_SECS,
+};
 use crate::network_group::{Group, NetworkGroup};
 use crate::peer_store::sqlite::Error as SqliteError;
 use libp2p::core::Endpoint;
@@ -6,10 +9,60 @@ use rusqlite::types::ToSql;
 use rusqlite::OptionalExtension;
 use rusqlite::{Connection, NO_PARAMS};
 use std::iter::FromIterator;
+use std::net::IpAddr;
 use std::time::Duration;
 
 type DBResult<T> = Result<T, SqliteError>;
 
+// Schema changes applied on top of the tables created by `create_tables`,
+// `PRAGMA user_version` records how many of them a database has applied.
+const MIGRATIONS: &[&str] = &[
+    // 1: ban list stores IP subnets with a reason, the UNIQUE constraint on ip
+    // can't be dropped by ALTER TABLE so the table is rebuilt
+    r#"
+    CREATE TABLE ban_list_new (
+    id INTEGER PRIMARY KEY NOT NULL,
+    ip BINARY NOT NULL,
+    prefix_len INTEGER NOT NULL,
+    ban_time INTEGER NOT NULL,
+    reason TEXT NOT NULL
+    );
+    INSERT INTO ban_list_new (id, ip, prefix_len, ban_time, reason)
+    SELECT id, ip, length(ip) * 8, ban_time, '' FROM ban_list;
+    DROP TABLE ban_list;
+    ALTER TABLE ban_list_new RENAME TO ban_list;
+    CREATE UNIQUE INDEX IF NOT EXISTS idx_ip_prefix_len_on_ban_list ON ban_list (ip, prefix_len);
+    "#,
+    // 2: address freshness and attempts, existing addresses count as seen now
+    r#"
+    ALTER TABLE peer_addr ADD COLUMN last_seen INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE peer_addr ADD COLUMN last_attempt INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE peer_addr ADD COLUMN attempts INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE peer_addr ADD COLUMN tried INTEGER NOT NULL DEFAULT 0;
+    UPDATE peer_addr SET last_seen = CAST(strftime('%s', 'now') AS INTEGER);
+    "#,
+];
+
+fn migrate(conn: &Connection) -> DBResult<()> {
+    conn.execute_batch("BEGIN IMMEDIATE")?;
+    let result = conn
+        .query_row("PRAGMA user_version", NO_PARAMS, |row| row.get::<_, i64>(0))
+        .and_then(|version| {
+            for (index, sql) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+                conn.execute_batch(sql)?;
+                conn.execute_batch(&format!("PRAGMA user_version = {}", index + 1))?;
+            }
+            Ok(())
+        });
+    match result {
+        Ok(()) => conn.execute_batch("COMMIT").map_err(Into::into),
+        Err(err) => {
+            let _ = conn.execute_batch("ROLLBACK");
+            Err(err.into())
+        }
+    }
+}
+
 pub fn create_tables(conn: &Connection) -> DBResult<()> {
     let sql = r#"
     CREATE TABLE IF NOT EXISTS peer_info (
@@ -41,7 +94,8 @@ pub fn create_tables(conn: &Connection) -> DBResult<()> {
     ban_time INTEGER NOT NULL
     );
     "#;
-    conn.execute_batch(sql).map_err(Into::into)
+    conn.execute_batch(sql)?;
+    migrate(conn)
 }
 
 #[derive(Debug)]
@@ -150,6 +204,19 @@ impl PeerInfo {
         Result::from_iter(rows).map_err(Into::into)
     }
 
+    // the never connected peer whose addresses are seen least recently
+    pub fn oldest_never_connected(conn: &Connection) -> DBResult<Option<u32>> {
+        conn.query_row(
+            "SELECT peer_info.id FROM peer_info LEFT JOIN peer_addr ON peer_addr.peer_info_id = peer_info.id
+            WHERE length(peer_info.connected_addr) = 0
+            GROUP BY peer_info.id ORDER BY MAX(peer_addr.last_seen) LIMIT 1",
+            NO_PARAMS,
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(Into::into)
+    }
+
     pub fn count(conn: &Connection) -> DBResult<u32> {
         conn.query_row::<u32, _, _>("SELECT COUNT(*) FROM peer_info", NO_PARAMS, |r| r.get(0))
             .map_err(Into::into)
@@ -159,18 +226,103 @@ impl PeerInfo {
 pub struct PeerAddr;
 
 impl PeerAddr {
-    pub fn insert(conn: &Connection, peer_info_id: u32, addr: &Multiaddr) -> DBResult<usize> {
+    // refresh last_seen of a known address, return numbers of new inserted line
+    pub fn insert(
+        conn: &Connection,
+        peer_info_id: u32,
+        addr: &Multiaddr,
+        now: Duration,
+    ) -> DBResult<usize> {
+        let mut stmt = conn.prepare(
+            "UPDATE peer_addr SET last_seen=:last_seen WHERE peer_info_id=:peer_info_id AND addr=:addr",
+        )?;
+        let updated = stmt.execute_named(&[
+            (":last_seen", &duration_to_secs(now)),
+            (":peer_info_id", &peer_info_id),
+            (":addr", &addr.to_bytes()),
+        ])?;
+        if updated > 0 {
+            return Ok(0);
+        }
+        let addrs_count = conn.query_row::<u32, _, _>(
+            "SELECT COUNT(*) FROM peer_addr WHERE peer_info_id=?1",
+            &[peer_info_id],
+            |r| r.get(0),
+        )?;
+        if addrs_count as usize >= ADDRS_PER_PEER_LIMIT {
+            return Ok(0);
+        }
         let mut stmt = conn.prepare(
-            "INSERT OR IGNORE INTO peer_addr (peer_info_id, addr)
-                     VALUES(:peer_info_id, :addr)",
+            "INSERT OR IGNORE INTO peer_addr (peer_info_id, addr, last_seen, last_attempt, attempts, tried)
+                     VALUES(:peer_info_id, :addr, :last_seen, 0, 0, 0)",
         )?;
         stmt.execute_named(&[
             (":peer_info_id", &peer_info_id),
             (":addr", &addr.to_bytes()),
+            (":last_seen", &duration_to_secs(now)),
         ])
         .map_err(Into::into)
     }
 
+    pub fn mark_tried(conn: &Connection, peer_info_id: u32, addr: &Multiaddr) -> DBResult<usize> {
+        let mut stmt = conn.prepare(
+            "UPDATE peer_addr SET tried=1, attempts=0 WHERE peer_info_id=:peer_info_id AND addr=:addr",
+        )?;
+        stmt.execute_named(&[
+            (":peer_info_id", &peer_info_id),
+            (":addr", &addr.to_bytes()),
+        ])
+        .map_err(Into::into)
+    }
+
+    pub fn mark_attempted(
+        conn: &Connection,
+        peer_info_id: u32,
+        addr: &Multiaddr,
+        now: Duration,
+    ) -> DBResult<usize> {
+        let mut stmt = conn.prepare(
+            "UPDATE peer_addr SET attempts=attempts+1, last_attempt=:last_attempt WHERE peer_info_id=:peer_info_id AND addr=:addr",
+        )?;
+        stmt.execute_named(&[
+            (":last_attempt", &duration_to_secs(now)),
+            (":peer_info_id", &peer_info_id),
+            (":addr", &addr.to_bytes()),
+        ])
+        .map_err(Into::into)
+    }
+
+    // same as AddrInfo::is_terrible
+    pub fn delete_terrible(conn: &Connection, now: Duration) -> DBResult<usize> {
+        let mut stmt = conn.prepare(
+            "DELETE FROM peer_addr WHERE last_seen < :seen_after OR attempts >= :max_failures
+                     OR (tried = 0 AND attempts >= :max_retries)",
+        )?;
+        stmt.execute_named(&[
+            (
+                ":seen_after",
+                &duration_to_secs(now).saturating_sub(ADDR_NOT_SEEN_TIMEOUT_SECS) as &ToSql,
+            ),
+            (":max_failures", &ADDR_MAX_FAILURES),
+            (":max_retries", &ADDR_MAX_RETRIES),
+        ])
+        .map_err(Into::into)
+    }
+
+    pub fn get_addr_infos(conn: &Connection, id: u32) -> DBResult<Vec<AddrInfo>> {
+        let mut stmt = conn.prepare(
+            "SELECT addr, last_seen, last_attempt, attempts, tried FROM peer_addr WHERE peer_info_id == :id",
+        )?;
+        let rows = stmt.query_map_named(&[(":id", &id)], |row| AddrInfo {
+            addr: Multiaddr::from_bytes(row.get(0)).expect("parse multiaddr"),
+            last_seen: secs_to_duration(row.get(1)),
+            last_attempt: secs_to_duration(row.get(2)),
+            attempts: row.get(3),
+            tried: row.get(4),
+        })?;
+        Result::from_iter(rows).map_err(Into::into)
+    }
+
     pub fn get_addrs(conn: &Connection, id: u32, count: u32) -> DBResult<Vec<Multiaddr>> {
         let mut stmt =
             conn.prepare("SELECT addr FROM peer_addr WHERE peer_info_id == :id LIMIT :count")?;
@@ -186,59 +338,155 @@ impl PeerAddr {
     }
 }
 
-pub fn get_peers_to_attempt(conn: &Connection, count: u32) -> DBResult<Vec<(PeerId, Multiaddr)>> {
-    // random select peers
-    let mut stmt = conn.prepare("SELECT id, peer_id FROM peer_info WHERE status != :connected_status AND ban_time < strftime('%s','now') ORDER BY RANDOM() LIMIT :count")?;
+// random select peers from tried or new bucket, with one attemptable address of each peer
+pub fn get_peers_to_attempt(
+    conn: &Connection,
+    count: u32,
+    tried: bool,
+    now: Duration,
+) -> DBResult<Vec<(PeerId, Multiaddr)>> {
+    let max_attempts = if tried {
+        ADDR_MAX_FAILURES
+    } else {
+        ADDR_MAX_RETRIES
+    };
+    let now = duration_to_secs(now);
+    let mut stmt = conn.prepare(
+        "SELECT peer_info.peer_id, peer_addr.addr FROM peer_addr JOIN peer_info ON peer_addr.peer_info_id = peer_info.id
+        WHERE peer_info.status != :connected_status AND peer_info.ban_time < strftime('%s','now')
+        AND peer_addr.tried = :tried AND peer_addr.attempts < :max_attempts
+        AND peer_addr.last_seen >= :seen_after AND peer_addr.last_attempt <= :attempt_before
+        GROUP BY peer_info.id ORDER BY RANDOM() LIMIT :count",
+    )?;
     let rows = stmt.query_map_named(
         &[
             (
                 ":connected_status",
                 &status_to_u8(Status::Connected) as &ToSql,
             ),
+            (":tried", &tried),
+            (":max_attempts", &max_attempts),
+            (
+                ":seen_after",
+                &now.saturating_sub(ADDR_NOT_SEEN_TIMEOUT_SECS),
+            ),
+            (
+                ":attempt_before",
+                &now.saturating_sub(ADDR_RETRY_INTERVAL_SECS),
+            ),
             (":count", &count),
         ],
         |row| {
             (
-                row.get::<_, u32>(0),
-                PeerId::from_bytes(row.get(1)).expect("parse peer_id"),
+                PeerId::from_bytes(row.get(0)).expect("parse peer_id"),
+                Multiaddr::from_bytes(row.get(1)).expect("parse multiaddr"),
             )
         },
     )?;
+    Result::from_iter(rows).map_err(Into::into)
+}
 
-    let mut peers = Vec::with_capacity(count as usize);
-    for row in rows {
-        let (id, peer_id) = row?;
-        let mut addrs = PeerAddr::get_addrs(conn, id, 1)?;
-        if let Some(addr) = addrs.pop() {
-            peers.push((peer_id, addr));
-        }
-    }
-    Ok(peers)
+pub fn get_good_addrs(
+    conn: &Connection,
+    count: u32,
+    now: Duration,
+) -> DBResult<Vec<(PeerId, Multiaddr)>> {
+    let now = duration_to_secs(now);
+    let mut stmt = conn.prepare(
+        "SELECT peer_info.peer_id, peer_addr.addr FROM peer_addr JOIN peer_info ON peer_addr.peer_info_id = peer_info.id
+        WHERE peer_addr.tried = 1 AND peer_addr.attempts = 0 AND peer_addr.last_seen >= :seen_after
+        ORDER BY RANDOM() LIMIT :count",
+    )?;
+    let rows = stmt.query_map_named(
+        &[
+            (
+                ":seen_after",
+                &now.saturating_sub(ADDR_NOT_SEEN_TIMEOUT_SECS) as &ToSql,
+            ),
+            (":count", &count),
+        ],
+        |row| {
+            (
+                PeerId::from_bytes(row.get(0)).expect("parse peer_id"),
+                Multiaddr::from_bytes(row.get(1)).expect("parse multiaddr"),
+            )
+        },
+    )?;
+    Result::from_iter(rows).map_err(Into::into)
+}
+
+pub fn insert_ban_record(conn: &Connection, record: &BanRecord) -> DBResult<usize> {
+    let mut stmt = conn.prepare("INSERT OR REPLACE INTO ban_list (ip, prefix_len, ban_time, reason) VALUES(:ip, :prefix_len, :ban_time, :reason);")?;
+    stmt.execute_named(&[
+        (":ip", &ip_to_bytes(record.subnet.addr())),
+        (":prefix_len", &record.subnet.prefix_len()),
+        (":ban_time", &duration_to_secs(record.ban_until)),
+        (":reason", &record.reason),
+    ])
+    .map_err(Into::into)
 }
 
-pub fn insert_ban_record(conn: &Connection, ip: &[u8], ban_time: Duration) -> DBResult<usize> {
-    let mut stmt =
-        conn.prepare("INSERT OR REPLACE INTO ban_list (ip, ban_time) VALUES(:ip, :ban_time);")?;
-    stmt.execute_named(&[(":ip", &ip), (":ban_time", &duration_to_secs(ban_time))])
+pub fn delete_ban_record(conn: &Connection, subnet: &IpSubnet) -> DBResult<usize> {
+    let mut stmt = conn.prepare("DELETE FROM ban_list WHERE ip=:ip AND prefix_len=:prefix_len")?;
+    stmt.execute_named(&[
+        (":ip", &ip_to_bytes(subnet.addr())),
+        (":prefix_len", &subnet.prefix_len()),
+    ])
+    .map_err(Into::into)
+}
+
+pub fn clear_ban_records(conn: &Connection) -> DBResult<usize> {
+    conn.execute("DELETE FROM ban_list", NO_PARAMS)
         .map_err(Into::into)
 }
 
-pub fn get_ban_records(conn: &Connection, now: Duration) -> DBResult<Vec<(Vec<u8>, Duration)>> {
-    let mut stmt = conn.prepare("SELECT ip, ban_time FROM ban_list WHERE ban_time > :now")?;
+pub fn get_ban_records(conn: &Connection, now: Duration) -> DBResult<Vec<BanRecord>> {
+    let mut stmt = conn
+        .prepare("SELECT ip, prefix_len, ban_time, reason FROM ban_list WHERE ban_time > :now")?;
     let rows = stmt.query_map_named(&[(":now", &duration_to_secs(now))], |row| {
-        (row.get::<_, Vec<u8>>(0), secs_to_duration(row.get(1)))
+        bytes_to_subnet(&row.get::<_, Vec<u8>>(0), row.get(1)).map(|subnet| BanRecord {
+            subnet,
+            ban_until: secs_to_duration(row.get(2)),
+            reason: row.get(3),
+        })
     })?;
-    Result::from_iter(rows).map_err(Into::into)
+    let records: Vec<Option<BanRecord>> = Result::from_iter(rows)?;
+    Ok(records.into_iter().filter_map(|record| record).collect())
 }
 
-pub fn clear_expires_banned_ip(conn: &Connection, now: Duration) -> DBResult<Vec<Vec<u8>>> {
-    let mut stmt = conn.prepare("SELECT ip FROM ban_list WHERE ban_time < :now")?;
+pub fn clear_expires_banned_ip(conn: &Connection, now: Duration) -> DBResult<Vec<IpSubnet>> {
+    let mut stmt = conn.prepare("SELECT ip, prefix_len FROM ban_list WHERE ban_time < :now")?;
     let rows = stmt.query_map_named(&[(":now", &duration_to_secs(now))], |row| {
-        row.get::<_, Vec<u8>>(0)
+        bytes_to_subnet(&row.get::<_, Vec<u8>>(0), row.get(1))
     })?;
+    let subnets: Vec<Option<IpSubnet>> = Result::from_iter(rows)?;
     let mut stmt = conn.prepare("DELETE FROM ban_list WHERE ban_time < :now")?;
     stmt.execute_named(&[(":now", &duration_to_secs(now))])?;
-    Result::from_iter(rows).map_err(Into::into)
+    Ok(subnets.into_iter().filter_map(|subnet| subnet).collect())
+}
+
+fn ip_to_bytes(ip: IpAddr) -> Vec<u8> {
+    match ip {
+        IpAddr::V4(ipv4) => ipv4.octets().to_vec(),
+        IpAddr::V6(ipv6) => ipv6.octets().to_vec(),
+    }
+}
+
+fn bytes_to_subnet(bytes: &[u8], prefix_len: u8) -> Option<IpSubnet> {
+    let ip = match bytes.len() {
+        4 => {
+            let mut octets = [0u8; 4];
+            octets.copy_from_slice(bytes);
+            IpAddr::V4(octets.into())
+        }
+        16 => {
+            let mut octets = [0u8; 16];
+            octets.copy_from_slice(bytes);
+            IpAddr::V6(octets.into())
+        }
+        _ => return None,
+    };
+    IpSubnet::new(ip, prefix_len)
 }
 
 fn status_to_u8(status: Status) -> u8 {
@@ -0,0 +1,394 @@
+use super::{
+    select_from_buckets, AddrInfo, BanRecord, Behaviour, IpSubnet, Multiaddr, PeerId, PeerStore,
+    ReportResult, Score, ScoringSchema, Status, ADDRS_PER_PEER_LIMIT, PEER_NOT_SEEN_TIMEOUT_SECS,
+    PEER_STORE_LIMIT,
+};
+use crate::network_group::{Group, MultiaddrExt, NetworkGroup};
+use faketime::unix_time;
+use fnv::FnvHashMap;
+use libp2p::core::Endpoint;
+use log::debug;
+use rand::seq::SliceRandom;
+use rand::thread_rng;
+use std::time::Duration;
+
+struct PeerInfo {
+    connected_addr: Multiaddr,
+    score: Score,
+    status: Status,
+    connected_time: Duration,
+    // discovered addresses in insertion order
+    addrs: Vec<AddrInfo>,
+}
+
+// A PeerStore keeps everything in memory, it behaves the same as SqlitePeerStore
+// but nothing is persisted, suitable for tests and ephemeral nodes.
+pub struct MemoryPeerStore {
+    bootnodes: Vec<(PeerId, Multiaddr)>,
+    schema: ScoringSchema,
+    ban_list: FnvHashMap<IpSubnet, BanRecord>,
+    peers: FnvHashMap<PeerId, PeerInfo>,
+}
+
+impl Default for MemoryPeerStore {
+    fn default() -> Self {
+        MemoryPeerStore {
+            bootnodes: Vec::new(),
+            schema: Default::default(),
+            ban_list: Default::default(),
+            peers: Default::default(),
+        }
+    }
+}
+
+impl MemoryPeerStore {
+    fn ban_ip(&mut self, addr: &Multiaddr, timeout: Duration, reason: String) {
+        if let Some(ip) = addr.extract_ip_addr() {
+            self.ban_subnet(IpSubnet::from_ip(ip), timeout, reason);
+        }
+    }
+
+    fn clear_expires_banned_ip(&mut self) {
+        let now = unix_time();
+        self.ban_list.retain(|_, record| record.ban_until > now);
+    }
+
+    // check and try to delete peer_info if peer_infos reach limit
+    fn check_store_limit(&mut self) -> Result<(), ()> {
+        if self.peers.len() < PEER_STORE_LIMIT as usize {
+            return Ok(());
+        }
+        // discovered peers we never connected to are evicted first, so peer ids
+        // gossiped by others can't take the place of the peers we know
+        let never_connected = self
+            .peers
+            .iter()
+            .filter(|(_, peer)| peer.connected_addr.iter().next().is_none())
+            .min_by_key(|(_, peer)| peer.addrs.iter().map(|info| info.last_seen).max())
+            .map(|(peer_id, _)| peer_id.to_owned());
+        if let Some(peer_id) = never_connected {
+            self.peers.remove(&peer_id);
+            return Ok(());
+        }
+        let candidate_peer = {
+            let not_seen_timeout =
+                unix_time() - Duration::from_secs(PEER_NOT_SEEN_TIMEOUT_SECS.into());
+            let mut groups: FnvHashMap<Group, Vec<(&PeerId, &PeerInfo)>> = FnvHashMap::default();
+            for (peer_id, peer) in &self.peers {
+                groups
+                    .entry(peer.connected_addr.network_group())
+                    .or_insert_with(Vec::new)
+                    .push((peer_id, peer));
+            }
+            let largest_group = groups
+                .into_iter()
+                .map(|(_, peers)| peers)
+                .max_by_key(|peers| peers.len())
+                .unwrap_or_else(Vec::new);
+            match largest_group
+                .into_iter()
+                .filter(|(_, peer)| peer.connected_time < not_seen_timeout)
+                .min_by_key(|(_, peer)| peer.score)
+            {
+                Some((peer_id, peer)) if peer.score < self.schema.peer_init_score() => {
+                    peer_id.to_owned()
+                }
+                _ => return Err(()),
+            }
+        };
+        self.peers.remove(&candidate_peer);
+        Ok(())
+    }
+
+    fn get_or_insert_peer_info(&mut self, peer_id: &PeerId) -> &mut PeerInfo {
+        let peer_init_score = self.schema.peer_init_score();
+        self.peers
+            .entry(peer_id.to_owned())
+            .or_insert_with(|| PeerInfo {
+                connected_addr: Multiaddr::from_bytes(Vec::new()).expect("null multiaddr"),
+                score: peer_init_score,
+                status: Status::Unknown,
+                connected_time: unix_time(),
+                addrs: Vec::new(),
+            })
+    }
+
+    // return false if the address is known or the peer reaches ADDRS_PER_PEER_LIMIT
+    fn insert_addr(peer: &mut PeerInfo, addr: Multiaddr) -> bool {
+        let now = unix_time();
+        if let Some(addr_info) = peer.addrs.iter_mut().find(|info| info.addr == addr) {
+            addr_info.last_seen = now;
+            return false;
+        }
+        if peer.addrs.len() >= ADDRS_PER_PEER_LIMIT {
+            return false;
+        }
+        peer.addrs.push(AddrInfo::new(addr, now));
+        true
+    }
+
+    fn get_addr_info_mut(&mut self, peer_id: &PeerId, addr: &Multiaddr) -> Option<&mut AddrInfo> {
+        self.peers
+            .get_mut(peer_id)
+            .and_then(|peer| peer.addrs.iter_mut().find(|info| &info.addr == addr))
+    }
+}
+
+impl PeerStore for MemoryPeerStore {
+    // endpoint is not used by peer store queries, so it's not stored
+    fn new_connected_peer(&mut self, peer_id: &PeerId, addr: Multiaddr, _endpoint: Endpoint) {
+        if self.check_store_limit().is_err() {
+            return;
+        }
+        let peer = self.get_or_insert_peer_info(peer_id);
+        peer.connected_addr = addr;
+        peer.connected_time = unix_time();
+    }
+
+    fn add_discovered_address(&mut self, peer_id: &PeerId, addr: Multiaddr) -> Result<(), ()> {
+        self.check_store_limit()?;
+        let peer = self.get_or_insert_peer_info(peer_id);
+        if Self::insert_addr(peer, addr) {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+
+    fn add_discovered_addresses(
+        &mut self,
+        peer_id: &PeerId,
+        addrs: Vec<Multiaddr>,
+    ) -> Result<usize, ()> {
+        self.check_store_limit()?;
+        let peer = self.get_or_insert_peer_info(peer_id);
+        let mut count = 0;
+        for addr in addrs {
+            if Self::insert_addr(peer, addr) {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    fn add_tried_address(&mut self, peer_id: &PeerId, addr: Multiaddr) {
+        // unknown address is inserted first
+        let _ = self.add_discovered_address(peer_id, addr.clone());
+        if let Some(addr_info) = self.get_addr_info_mut(peer_id, &addr) {
+            addr_info.tried = true;
+            addr_info.attempts = 0;
+        }
+    }
+
+    fn mark_addr_attempted(&mut self, peer_id: &PeerId, addr: &Multiaddr) {
+        if let Some(addr_info) = self.get_addr_info_mut(peer_id, addr) {
+            addr_info.attempts = addr_info.attempts.saturating_add(1);
+            addr_info.last_attempt = unix_time();
+        }
+    }
+
+    fn evict_stale_addrs(&mut self) -> usize {
+        let now = unix_time();
+        let mut count = 0;
+        for peer in self.peers.values_mut() {
+            let len = peer.addrs.len();
+            peer.addrs.retain(|addr_info| !addr_info.is_terrible(now));
+            count += len - peer.addrs.len();
+        }
+        count
+    }
+
+    fn peer_addr_infos(&self, peer_id: &PeerId) -> Vec<AddrInfo> {
+        self.peers
+            .get(peer_id)
+            .map(|peer| peer.addrs.clone())
+            .unwrap_or_default()
+    }
+
+    fn report(&mut self, peer_id: &PeerId, behaviour: Behaviour) -> ReportResult {
+        if self.is_banned(peer_id) {
+            return ReportResult::Banned;
+        }
+        let behaviour_score = match self.schema.get_score(behaviour) {
+            Some(score) => score,
+            None => {
+                debug!(target: "network", "behaviour {:?} is undefined", behaviour);
+                return ReportResult::Ok;
+            }
+        };
+        let score = self
+            .get_or_insert_peer_info(peer_id)
+            .score
+            .saturating_add(behaviour_score);
+        if score < self.schema.ban_score() {
+            let ban_timeout = self.schema.default_ban_timeout();
+            self.ban_peer(peer_id, ban_timeout, format!("{:?}", behaviour));
+            return ReportResult::Banned;
+        }
+        self.get_or_insert_peer_info(peer_id).score = score;
+        ReportResult::Ok
+    }
+
+    fn update_status(&mut self, peer_id: &PeerId, status: Status) {
+        if let Some(peer) = self.peers.get_mut(peer_id) {
+            peer.status = status;
+        }
+    }
+
+    fn peer_status(&self, peer_id: &PeerId) -> Status {
+        self.peers
+            .get(peer_id)
+            .map(|peer| peer.status)
+            .unwrap_or_else(|| Status::Unknown)
+    }
+
+    fn peer_score(&self, peer_id: &PeerId) -> Option<Score> {
+        self.peers.get(peer_id).map(|peer| peer.score)
+    }
+
+    fn add_bootnode(&mut self, peer_id: PeerId, addr: Multiaddr) {
+        self.new_connected_peer(&peer_id, addr.clone(), Endpoint::Dialer);
+        self.bootnodes.push((peer_id, addr));
+    }
+
+    // should return high scored nodes if possible, otherwise, return boostrap nodes
+    fn bootnodes(&self, count: u32) -> Vec<(PeerId, Multiaddr)> {
+        let mut peers = self.peers_to_attempt(count);
+        if peers.len() < count as usize {
+            for (peer_id, addr) in &self.bootnodes {
+                let peer = (peer_id.to_owned(), addr.to_owned());
+                if !peers.contains(&peer) {
+                    peers.push(peer);
+                }
+            }
+        }
+        peers
+    }
+
+    fn peer_addrs(&self, peer_id: &PeerId, count: u32) -> Option<Vec<Multiaddr>> {
+        self.peers.get(peer_id).map(|peer| {
+            peer.addrs
+                .iter()
+                .take(count as usize)
+                .map(|addr_info| addr_info.addr.to_owned())
+                .collect()
+        })
+    }
+
+    // random select peers which are not connected or banned
+    fn peers_to_attempt(&self, count: u32) -> Vec<(PeerId, Multiaddr)> {
+        let now = unix_time();
+        let mut tried = Vec::new();
+        let mut new = Vec::new();
+        for (peer_id, peer) in &self.peers {
+            if peer.status == Status::Connected || self.is_addr_banned(&peer.connected_addr) {
+                continue;
+            }
+            // a peer is in both buckets if it has both tried and new addresses
+            let mut addrs = peer
+                .addrs
+                .iter()
+                .filter(|addr_info| addr_info.can_attempt(now));
+            if let Some(addr_info) = addrs.clone().find(|addr_info| addr_info.tried) {
+                tried.push((peer_id.to_owned(), addr_info.addr.to_owned()));
+            }
+            if let Some(addr_info) = addrs.find(|addr_info| !addr_info.tried) {
+                new.push((peer_id.to_owned(), addr_info.addr.to_owned()));
+            }
+        }
+        let mut rng = thread_rng();
+        tried.shuffle(&mut rng);
+        new.shuffle(&mut rng);
+        select_from_buckets(tried, new, count)
+    }
+
+    fn good_addrs(&self, count: u32) -> Vec<(PeerId, Multiaddr)> {
+        let now = unix_time();
+        let mut addrs: Vec<(PeerId, Multiaddr)> = self
+            .peers
+            .iter()
+            .flat_map(|(peer_id, peer)| {
+                peer.addrs
+                    .iter()
+                    .filter(move |addr_info| {
+                        addr_info.tried && addr_info.attempts == 0 && !addr_info.is_terrible(now)
+                    })
+                    .map(move |addr_info| (peer_id.to_owned(), addr_info.addr.to_owned()))
+            })
+            .filter(|(_, addr)| !self.is_addr_banned(addr))
+            .collect();
+        addrs.shuffle(&mut thread_rng());
+        addrs.truncate(count as usize);
+        addrs
+    }
+
+    fn ban_peer(&mut self, peer_id: &PeerId, timeout: Duration, reason: String) {
+        if let Some(addr) = self
+            .peers
+            .get(peer_id)
+            .map(|peer| peer.connected_addr.clone())
+        {
+            self.ban_ip(&addr, timeout, reason);
+        }
+    }
+
+    fn unban_peer(&mut self, peer_id: &PeerId) -> bool {
+        match self
+            .peers
+            .get(peer_id)
+            .and_then(|peer| peer.connected_addr.extract_ip_addr())
+        {
+            Some(ip) => self.unban_subnet(&IpSubnet::from_ip(ip)),
+            None => false,
+        }
+    }
+
+    fn ban_subnet(&mut self, subnet: IpSubnet, timeout: Duration, reason: String) {
+        let record = BanRecord {
+            subnet,
+            ban_until: unix_time() + timeout,
+            reason,
+        };
+        self.ban_list.insert(subnet, record);
+        self.clear_expires_banned_ip();
+    }
+
+    fn unban_subnet(&mut self, subnet: &IpSubnet) -> bool {
+        self.ban_list.remove(subnet).is_some()
+    }
+
+    fn banned_list(&self) -> Vec<BanRecord> {
+        let now = unix_time();
+        self.ban_list
+            .values()
+            .filter(|record| record.ban_until > now)
+            .cloned()
+            .collect()
+    }
+
+    fn clear_banned(&mut self) {
+        self.ban_list.clear();
+    }
+
+    fn is_banned(&self, peer_id: &PeerId) -> bool {
+        if let Some(peer) = self.peers.get(peer_id) {
+            return self.is_addr_banned(&peer.connected_addr);
+        }
+        false
+    }
+
+    fn is_addr_banned(&self, addr: &Multiaddr) -> bool {
+        let ip = match addr.extract_ip_addr() {
+            Some(ip) => ip,
+            None => return false,
+        };
+        let now = unix_time();
+        self.ban_list
+            .values()
+            .any(|record| record.ban_until > now && record.subnet.contains(ip))
+    }
+
+    fn scoring_schema(&self) -> &ScoringSchema {
+        &self.schema
+    }
+}
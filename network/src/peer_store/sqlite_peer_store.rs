@@ -1,4 +1,7 @@
-use super::{Behaviour, Multiaddr, PeerId, PeerStore, ReportResult, Score, ScoringSchema, Status};
+use super::{
+    select_from_buckets, AddrInfo, BanRecord, Behaviour, IpSubnet, Multiaddr, PeerId, PeerStore,
+    ReportResult, Score, ScoringSchema, Status, PEER_NOT_SEEN_TIMEOUT_SECS, PEER_STORE_LIMIT,
+};
 use crate::network_group::MultiaddrExt;
 use crate::peer_store::db;
 use crate::peer_store::sqlite::{self, ConnectionPool, ConnectionPoolExt};
@@ -6,11 +9,8 @@ use faketime::unix_time;
 use fnv::FnvHashMap;
 use libp2p::core::Endpoint;
 use log::debug;
-use std::net::IpAddr;
 use std::time::Duration;
 
-pub(crate) const PEER_STORE_LIMIT: u32 = 8192;
-pub(crate) const PEER_NOT_SEEN_TIMEOUT_SECS: u32 = 14 * 24 * 3600;
 const BAN_LIST_CLEAR_EXPIRES_SIZE: usize = 255;
 const DEFAULT_POOL_SIZE: u32 = 16;
 
@@ -24,7 +24,7 @@ const DEFAULT_POOL_SIZE: u32 = 16;
 pub struct SqlitePeerStore {
     bootnodes: Vec<(PeerId, Multiaddr)>,
     schema: ScoringSchema,
-    ban_list: FnvHashMap<Vec<u8>, Duration>,
+    ban_list: FnvHashMap<IpSubnet, BanRecord>,
     pool: ConnectionPool,
 }
 
@@ -70,50 +70,25 @@ impl SqlitePeerStore {
         self.clear_expires_banned_ip()?;
         let now = unix_time();
         let ban_records = self.pool.fetch(|conn| db::get_ban_records(conn, now))?;
-        for (ip, ban_time) in ban_records {
-            self.ban_list.insert(ip, ban_time);
+        for record in ban_records {
+            self.ban_list.insert(record.subnet, record);
         }
         Ok(())
     }
 
-    fn ban_ip(&mut self, addr: &Multiaddr, timeout: Duration) {
-        let ip = match addr.extract_ip_addr() {
-            Some(IpAddr::V4(ipv4)) => ipv4.octets().to_vec(),
-            Some(IpAddr::V6(ipv6)) => ipv6.octets().to_vec(),
-            None => return,
-        };
-        let ban_time = unix_time() + timeout;
-        {
-            self.pool
-                .fetch(|conn| db::insert_ban_record(&conn, &ip, ban_time))
-                .expect("ban ip");
-        }
-        self.ban_list.insert(ip, ban_time);
-        if self.ban_list.len() > BAN_LIST_CLEAR_EXPIRES_SIZE {
-            self.clear_expires_banned_ip().expect("clear ban list");
-        }
-    }
-
-    fn is_addr_banned(&self, addr: &Multiaddr) -> bool {
-        let ip = match addr.extract_ip_addr() {
-            Some(IpAddr::V4(ipv4)) => ipv4.octets().to_vec(),
-            Some(IpAddr::V6(ipv6)) => ipv6.octets().to_vec(),
-            None => return false,
-        };
-        let now = unix_time();
-        match self.ban_list.get(&ip) {
-            Some(ban_time) => *ban_time > now,
-            None => false,
+    fn ban_ip(&mut self, addr: &Multiaddr, timeout: Duration, reason: String) {
+        if let Some(ip) = addr.extract_ip_addr() {
+            self.ban_subnet(IpSubnet::from_ip(ip), timeout, reason);
         }
     }
 
     fn clear_expires_banned_ip(&mut self) -> Result<(), sqlite::Error> {
         let now = unix_time();
-        let ips = self
+        let subnets = self
             .pool
             .fetch(|conn| db::clear_expires_banned_ip(conn, now))?;
-        for ip in ips {
-            self.ban_list.remove(&ip);
+        for subnet in subnets {
+            self.ban_list.remove(&subnet);
         }
         Ok(())
     }
@@ -127,6 +102,16 @@ impl SqlitePeerStore {
         if peer_info_count < PEER_STORE_LIMIT {
             return Ok(());
         }
+        // discovered peers we never connected to are evicted first, so peer ids
+        // gossiped by others can't take the place of the peers we know
+        let never_connected = self
+            .pool
+            .fetch(|conn| db::PeerInfo::oldest_never_connected(conn))
+            .expect("query never connected peer");
+        if let Some(id) = never_connected {
+            self.delete_peer_info(id);
+            return Ok(());
+        }
         let candidate_peers = {
             let peers = self
                 .pool
@@ -146,15 +131,19 @@ impl SqlitePeerStore {
         if candidate_peer.score >= self.schema.peer_init_score() {
             return Err(());
         }
+        self.delete_peer_info(candidate_peer.id);
+        Ok(())
+    }
+
+    fn delete_peer_info(&mut self, id: u32) {
         self.pool
             .fetch(|conn| {
                 let tx = conn.transaction().expect("db tx");
-                db::PeerInfo::delete(&tx, candidate_peer.id)?;
-                db::PeerAddr::delete_by_peer_id(&tx, candidate_peer.id)?;
+                db::PeerInfo::delete(&tx, id)?;
+                db::PeerAddr::delete_by_peer_id(&tx, id)?;
                 tx.commit().map_err(Into::into)
             })
             .expect("delete peer");
-        Ok(())
     }
 
     fn get_and_upsert_peer_info_with(
@@ -236,9 +225,10 @@ impl PeerStore for SqlitePeerStore {
     fn add_discovered_address(&mut self, peer_id: &PeerId, addr: Multiaddr) -> Result<(), ()> {
         self.check_store_limit()?;
         let id = self.get_or_insert_peer_info(peer_id).id;
+        let now = unix_time();
         let inserted = self
             .pool
-            .fetch(|conn| db::PeerAddr::insert(&conn, id, &addr))
+            .fetch(|conn| db::PeerAddr::insert(&conn, id, &addr, now))
             .expect("insert addr");
         if inserted > 0 {
             Ok(())
@@ -254,12 +244,13 @@ impl PeerStore for SqlitePeerStore {
     ) -> Result<usize, ()> {
         self.check_store_limit()?;
         let id = self.get_or_insert_peer_info(peer_id).id;
+        let now = unix_time();
         let count = self
             .pool
             .fetch(|conn| {
                 let mut count = 0;
                 for addr in &addrs {
-                    count += db::PeerAddr::insert(&conn, id, &addr)?;
+                    count += db::PeerAddr::insert(&conn, id, &addr, now)?;
                 }
                 Ok(count)
             })
@@ -267,6 +258,42 @@ impl PeerStore for SqlitePeerStore {
         Ok(count)
     }
 
+    fn add_tried_address(&mut self, peer_id: &PeerId, addr: Multiaddr) {
+        // unknown address is inserted first
+        let _ = self.add_discovered_address(peer_id, addr.clone());
+        if let Some(peer) = self.get_peer_info(peer_id) {
+            self.pool
+                .fetch(|conn| db::PeerAddr::mark_tried(&conn, peer.id, &addr))
+                .expect("mark addr tried");
+        }
+    }
+
+    fn mark_addr_attempted(&mut self, peer_id: &PeerId, addr: &Multiaddr) {
+        if let Some(peer) = self.get_peer_info(peer_id) {
+            let now = unix_time();
+            self.pool
+                .fetch(|conn| db::PeerAddr::mark_attempted(&conn, peer.id, addr, now))
+                .expect("mark addr attempted");
+        }
+    }
+
+    fn evict_stale_addrs(&mut self) -> usize {
+        let now = unix_time();
+        self.pool
+            .fetch(|conn| db::PeerAddr::delete_terrible(&conn, now))
+            .expect("evict stale addrs")
+    }
+
+    fn peer_addr_infos(&self, peer_id: &PeerId) -> Vec<AddrInfo> {
+        match self.get_peer_info(peer_id) {
+            Some(peer) => self
+                .pool
+                .fetch(|conn| db::PeerAddr::get_addr_infos(&conn, peer.id))
+                .expect("get peer addr infos"),
+            None => Vec::new(),
+        }
+    }
+
     fn report(&mut self, peer_id: &PeerId, behaviour: Behaviour) -> ReportResult {
         if self.is_banned(peer_id) {
             return ReportResult::Banned;
@@ -279,11 +306,10 @@ impl PeerStore for SqlitePeerStore {
             }
         };
         let peer = self.get_or_insert_peer_info(peer_id);
-        let now = unix_time();
         let score = peer.score.saturating_add(behaviour_score);
         if score < self.schema.ban_score() {
-            let ban_time = self.schema.default_ban_timeout() + now;
-            self.ban_peer(peer_id, ban_time);
+            let ban_timeout = self.schema.default_ban_timeout();
+            self.ban_peer(peer_id, ban_timeout, format!("{:?}", behaviour));
             return ReportResult::Banned;
         }
         self.pool
@@ -316,10 +342,7 @@ impl PeerStore for SqlitePeerStore {
     }
     // should return high scored nodes if possible, otherwise, return boostrap nodes
     fn bootnodes(&self, count: u32) -> Vec<(PeerId, Multiaddr)> {
-        let mut peers = self
-            .pool
-            .fetch(|conn| db::get_peers_to_attempt(&conn, count))
-            .expect("get peers to attempt");
+        let mut peers = self.peers_to_attempt(count);
         if peers.len() < count as usize {
             for (peer_id, addr) in &self.bootnodes {
                 let peer = (peer_id.to_owned(), addr.to_owned());
@@ -339,17 +362,83 @@ impl PeerStore for SqlitePeerStore {
     }
 
     fn peers_to_attempt(&self, count: u32) -> Vec<(PeerId, Multiaddr)> {
+        let now = unix_time();
+        let (tried, new) = self
+            .pool
+            .fetch(|conn| {
+                Ok((
+                    db::get_peers_to_attempt(&conn, count, true, now)?,
+                    db::get_peers_to_attempt(&conn, count, false, now)?,
+                ))
+            })
+            .expect("get peers to attempt");
+        select_from_buckets(tried, new, count)
+    }
+
+    fn good_addrs(&self, count: u32) -> Vec<(PeerId, Multiaddr)> {
+        let now = unix_time();
         self.pool
-            .fetch(|conn| db::get_peers_to_attempt(&conn, count))
-            .expect("get peers to attempt")
+            .fetch(|conn| db::get_good_addrs(&conn, count, now))
+            .expect("get good addrs")
+            .into_iter()
+            .filter(|(_, addr)| !self.is_addr_banned(addr))
+            .collect()
     }
 
-    fn ban_peer(&mut self, peer_id: &PeerId, timeout: Duration) {
+    fn ban_peer(&mut self, peer_id: &PeerId, timeout: Duration, reason: String) {
         if let Some(peer) = self.get_peer_info(peer_id) {
-            self.ban_ip(&peer.connected_addr, timeout);
+            self.ban_ip(&peer.connected_addr, timeout, reason);
+        }
+    }
+
+    fn unban_peer(&mut self, peer_id: &PeerId) -> bool {
+        match self
+            .get_peer_info(peer_id)
+            .and_then(|peer| peer.connected_addr.extract_ip_addr())
+        {
+            Some(ip) => self.unban_subnet(&IpSubnet::from_ip(ip)),
+            None => false,
         }
     }
 
+    fn ban_subnet(&mut self, subnet: IpSubnet, timeout: Duration, reason: String) {
+        let record = BanRecord {
+            subnet,
+            ban_until: unix_time() + timeout,
+            reason,
+        };
+        self.pool
+            .fetch(|conn| db::insert_ban_record(&conn, &record))
+            .expect("ban subnet");
+        self.ban_list.insert(subnet, record);
+        if self.ban_list.len() > BAN_LIST_CLEAR_EXPIRES_SIZE {
+            self.clear_expires_banned_ip().expect("clear ban list");
+        }
+    }
+
+    fn unban_subnet(&mut self, subnet: &IpSubnet) -> bool {
+        self.pool
+            .fetch(|conn| db::delete_ban_record(&conn, subnet))
+            .expect("unban subnet");
+        self.ban_list.remove(subnet).is_some()
+    }
+
+    fn banned_list(&self) -> Vec<BanRecord> {
+        let now = unix_time();
+        self.ban_list
+            .values()
+            .filter(|record| record.ban_until > now)
+            .cloned()
+            .collect()
+    }
+
+    fn clear_banned(&mut self) {
+        self.pool
+            .fetch(|conn| db::clear_ban_records(&conn))
+            .expect("clear ban list");
+        self.ban_list.clear();
+    }
+
     fn is_banned(&self, peer_id: &PeerId) -> bool {
         if let Some(peer) = self.get_peer_info(peer_id) {
             return self.is_addr_banned(&peer.connected_addr);
@@ -357,6 +446,17 @@ impl PeerStore for SqlitePeerStore {
         false
     }
 
+    fn is_addr_banned(&self, addr: &Multiaddr) -> bool {
+        let ip = match addr.extract_ip_addr() {
+            Some(ip) => ip,
+            None => return false,
+        };
+        let now = unix_time();
+        self.ban_list
+            .values()
+            .any(|record| record.ban_until > now && record.subnet.contains(ip))
+    }
+
     fn scoring_schema(&self) -> &ScoringSchema {
         &self.schema
     }
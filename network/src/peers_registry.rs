@@ -1,5 +1,6 @@
 use crate::network_group::{Group, NetworkGroup};
 use crate::peer_store::PeerStore;
+use crate::whitelist::Whitelist;
 use crate::{Error, ErrorKind, PeerId, PeerIndex, ProtocolId};
 use bytes::Bytes;
 use ckb_util::RwLock;
@@ -13,6 +14,7 @@ use rand::seq::SliceRandom;
 use rand::thread_rng;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
 
 pub(crate) const EVICTION_PROTECT_PEERS: usize = 8;
 
@@ -90,6 +92,7 @@ pub struct PeerIdentifyInfo {
     pub protocol_version: String,
     pub supported_protocols: Vec<String>,
     pub count_of_known_listen_addrs: usize,
+    pub identified_at: Instant,
 }
 
 type ProtocolConnec = (ProtocolId, UniqueConnec<(UnboundedSender<Bytes>, u8)>);
@@ -136,9 +139,8 @@ impl PeerConnection {
         !self.is_outbound()
     }
 
-    #[allow(dead_code)]
     #[inline]
-    fn network_group(&self) -> Group {
+    pub(crate) fn network_group(&self) -> Group {
         self.connected_addr.network_group()
     }
 }
@@ -162,6 +164,7 @@ pub(crate) struct PeersRegistry {
     // Only reserved peers or allow all peers.
     reserved_only: bool,
     reserved_peers: FnvHashSet<PeerId>,
+    whitelist: Whitelist,
 }
 
 fn find_most_peers_in_same_network_group<'a>(
@@ -199,6 +202,7 @@ impl PeersRegistry {
         max_outbound: u32,
         reserved_only: bool,
         reserved_peers: Vec<PeerId>,
+        whitelist: Whitelist,
     ) -> Self {
         let mut reserved_peers_set =
             FnvHashSet::with_capacity_and_hasher(reserved_peers.len(), Default::default());
@@ -212,6 +216,7 @@ impl PeersRegistry {
             max_inbound,
             max_outbound,
             reserved_only,
+            whitelist,
         }
     }
 
@@ -224,6 +229,19 @@ impl PeersRegistry {
         self.reserved_peers.contains(&peer_id)
     }
 
+    // check peer id and connected address of a connected peer, or only peer id otherwise
+    pub fn is_whitelisted(&self, peer_id: &PeerId) -> bool {
+        match self.peers.get(peer_id) {
+            Some(peer) => self.whitelist.contains(peer_id, &peer.connected_addr),
+            None => self.whitelist.contains_peer_id(peer_id),
+        }
+    }
+
+    // reserved and whitelisted peers are not counted in connection limits and never evicted
+    fn is_privileged(&self, peer_id: &PeerId, peer: &PeerConnection) -> bool {
+        self.is_reserved(peer_id) || self.whitelist.contains(peer_id, &peer.connected_addr)
+    }
+
     pub fn accept_inbound_peer(&mut self, peer_id: PeerId, addr: Multiaddr) -> Result<(), Error> {
         if self.peers.get(&peer_id).is_some() {
             return Ok(());
@@ -236,22 +254,28 @@ impl PeersRegistry {
                 ))
                 .into());
             }
-            if self.peer_store.read().is_banned(&peer_id) {
-                return Err(
-                    ErrorKind::InvalidNewPeer(format!("peer {:?} is denied", peer_id)).into(),
-                );
-            }
-
-            let connection_status = self.connection_status();
-            // check peers connection limitation
-            if connection_status.unreserved_inbound >= self.max_inbound
-                && !self.try_evict_inbound_peer()
-            {
-                return Err(ErrorKind::InvalidNewPeer(format!(
-                    "reach max inbound peers limitation, reject peer {:?}",
-                    peer_id
-                ))
-                .into());
+            if !self.whitelist.contains(&peer_id, &addr) {
+                let is_banned = {
+                    let peer_store = self.peer_store.read();
+                    peer_store.is_banned(&peer_id) || peer_store.is_addr_banned(&addr)
+                };
+                if is_banned {
+                    return Err(
+                        ErrorKind::InvalidNewPeer(format!("peer {:?} is denied", peer_id)).into(),
+                    );
+                }
+
+                let connection_status = self.connection_status();
+                // check peers connection limitation
+                if connection_status.unreserved_inbound >= self.max_inbound
+                    && !self.try_evict_inbound_peer()
+                {
+                    return Err(ErrorKind::InvalidNewPeer(format!(
+                        "reach max inbound peers limitation, reject peer {:?}",
+                        peer_id
+                    ))
+                    .into());
+                }
             }
         }
         self.new_peer(peer_id, addr, Endpoint::Listener);
@@ -263,7 +287,7 @@ impl PeersRegistry {
             let mut candidate_peers = self
                 .peers
                 .iter()
-                .filter(|(peer_id, peer)| peer.is_inbound() && !self.is_reserved(peer_id))
+                .filter(|(peer_id, peer)| peer.is_inbound() && !self.is_privileged(peer_id, peer))
                 .collect::<Vec<_>>();
             let peer_store = self.peer_store.read();
             // Protect peers based on characteristics that an attacker hard to simulate or manipulate
@@ -348,20 +372,35 @@ impl PeersRegistry {
                 ))
                 .into());
             }
-            if self.peer_store.read().is_banned(&peer_id) {
-                return Err(
-                    ErrorKind::InvalidNewPeer(format!("peer {:?} is denied", peer_id)).into(),
-                );
-            }
-            let connection_status = self.connection_status();
-            // check peers connection limitation
-            // TODO: implement extra outbound peer logic
-            if connection_status.unreserved_outbound >= self.max_outbound {
-                return Err(ErrorKind::InvalidNewPeer(format!(
-                    "reach max outbound peers limitation, reject peer {:?}",
-                    peer_id
-                ))
-                .into());
+            if !self.whitelist.contains(&peer_id, &addr) {
+                let is_banned = {
+                    let peer_store = self.peer_store.read();
+                    peer_store.is_banned(&peer_id) || peer_store.is_addr_banned(&addr)
+                };
+                if is_banned {
+                    return Err(
+                        ErrorKind::InvalidNewPeer(format!("peer {:?} is denied", peer_id)).into(),
+                    );
+                }
+                let connection_status = self.connection_status();
+                // check peers connection limitation
+                // TODO: implement extra outbound peer logic
+                if connection_status.unreserved_outbound >= self.max_outbound {
+                    return Err(ErrorKind::InvalidNewPeer(format!(
+                        "reach max outbound peers limitation, reject peer {:?}",
+                        peer_id
+                    ))
+                    .into());
+                }
+                // only one outbound peer in each network group, resist eclipse attacks
+                let group = addr.network_group();
+                if group.is_routable() && self.outbound_network_groups().contains(&group) {
+                    return Err(ErrorKind::InvalidNewPeer(format!(
+                        "already has outbound peer in network group {:?}, reject peer {:?}",
+                        group, peer_id
+                    ))
+                    .into());
+                }
             }
         }
         self.new_peer(peer_id, addr, Endpoint::Dialer);
@@ -407,7 +446,7 @@ impl PeersRegistry {
         let mut unreserved_outbound: u32 = 0;
         for (peer_id, peer_connection) in self.peers.iter() {
             total += 1;
-            if self.is_reserved(peer_id) {
+            if self.is_privileged(peer_id, peer_connection) {
                 continue;
             }
             if peer_connection.is_outbound() {
@@ -425,6 +464,30 @@ impl PeersRegistry {
         }
     }
 
+    // network groups of unreserved outbound peers
+    pub fn outbound_network_groups(&self) -> FnvHashSet<Group> {
+        self.peers
+            .iter()
+            .filter(|(peer_id, peer)| peer.is_outbound() && !self.is_privileged(peer_id, peer))
+            .map(|(_, peer)| peer.network_group())
+            .collect()
+    }
+
+    // unreserved outbound peers which have the longest connection time
+    pub fn anchor_peers(&self, count: usize) -> Vec<(PeerId, Multiaddr)> {
+        let mut peers = self
+            .peers
+            .iter()
+            .filter(|(peer_id, peer)| peer.is_outbound() && !self.is_reserved(peer_id))
+            .collect::<Vec<_>>();
+        peers.sort_by_key(|(_, peer)| peer.connected_time.unwrap_or_else(|| std::u64::MAX));
+        peers
+            .into_iter()
+            .take(count)
+            .map(|(peer_id, peer)| (peer_id.to_owned(), peer.connected_addr.clone()))
+            .collect()
+    }
+
     #[inline]
     pub fn connected_peers_indexes(&self) -> impl Iterator<Item = PeerIndex> + '_ {
         self.peers.peer_id_by_index.iter().map(|(k, _v)| *k)
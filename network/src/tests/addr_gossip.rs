@@ -0,0 +1,107 @@
+use crate::{
+    addr_gossip::{decode_addrs, encode_addrs, GossipPeer, MAX_GOSSIP_ADDRS},
+    peer_store::{MemoryPeerStore, PeerStore, SqlitePeerStore, PEER_STORE_LIMIT},
+    random_peer_id, Endpoint, ToMultiaddr,
+};
+use std::time::{Duration, Instant};
+
+#[test]
+fn test_encode_and_decode_addrs() {
+    let addrs = vec![
+        (
+            random_peer_id().unwrap(),
+            "/ip4/192.168.0.1/tcp/8115".to_multiaddr().unwrap(),
+        ),
+        (
+            random_peer_id().unwrap(),
+            "/ip6/::1/tcp/8115".to_multiaddr().unwrap(),
+        ),
+    ];
+    let data = encode_addrs(&addrs);
+    assert_eq!(decode_addrs(&data), Some(addrs));
+
+    // truncated or trailing bytes
+    assert_eq!(decode_addrs(&data[..data.len() - 1]), None);
+    let mut trailing = data.clone();
+    trailing.push(0);
+    assert_eq!(decode_addrs(&trailing), None);
+
+    let too_many = vec![
+        (
+            random_peer_id().unwrap(),
+            "/ip4/192.168.0.1".to_multiaddr().unwrap(),
+        );
+        MAX_GOSSIP_ADDRS + 1
+    ];
+    assert_eq!(decode_addrs(&encode_addrs(&too_many)), None);
+}
+
+#[test]
+fn test_gossip_rate_limit() {
+    let interval = Duration::from_secs(600);
+    let now = Instant::now();
+    let mut gossip_peer = GossipPeer::default();
+    assert_eq!(
+        gossip_peer.accept(MAX_GOSSIP_ADDRS - 1, now, interval),
+        MAX_GOSSIP_ADDRS - 1
+    );
+    assert_eq!(gossip_peer.accept(2, now, interval), 1);
+    assert_eq!(gossip_peer.accept(1, now + interval / 2, interval), 0);
+    // a new window starts after the interval
+    assert_eq!(gossip_peer.accept(2, now + interval, interval), 2);
+}
+
+fn check_good_addrs(mut peer_store: Box<dyn PeerStore>) {
+    let peer_id = random_peer_id().unwrap();
+    let new_addr = "/ip4/192.168.0.1".to_multiaddr().unwrap();
+    let tried_addr = "/ip4/192.168.0.2".to_multiaddr().unwrap();
+    peer_store
+        .add_discovered_address(&peer_id, new_addr)
+        .expect("add discovered address");
+    peer_store.add_tried_address(&peer_id, tried_addr.clone());
+    assert_eq!(
+        peer_store.good_addrs(2),
+        vec![(peer_id.clone(), tried_addr.clone())]
+    );
+
+    // failed addresses are not gossiped
+    peer_store.mark_addr_attempted(&peer_id, &tried_addr);
+    assert!(peer_store.good_addrs(2).is_empty());
+}
+
+#[test]
+fn test_good_addrs() {
+    check_good_addrs(Box::new(MemoryPeerStore::default()));
+    check_good_addrs(Box::new(SqlitePeerStore::temp()));
+}
+
+fn check_gossip_flood(mut peer_store: Box<dyn PeerStore>) {
+    let known_peer = random_peer_id().unwrap();
+    let known_addr = "/ip4/192.168.0.1/tcp/8115".to_multiaddr().unwrap();
+    peer_store.new_connected_peer(&known_peer, known_addr.clone(), Endpoint::Dialer);
+    peer_store.add_tried_address(&known_peer, known_addr);
+
+    // gossip of more made up peer ids than the store can hold
+    for i in 0..PEER_STORE_LIMIT {
+        let addr = format!("/ip4/10.0.{}.{}/tcp/8115", i / 256, i % 256)
+            .to_multiaddr()
+            .unwrap();
+        assert!(peer_store
+            .add_discovered_address(&random_peer_id().unwrap(), addr)
+            .is_ok());
+    }
+
+    // gossiped peers are evicted for the peers we connect to
+    let new_peer = random_peer_id().unwrap();
+    let new_addr = "/ip4/192.168.0.2/tcp/8115".to_multiaddr().unwrap();
+    peer_store.new_connected_peer(&new_peer, new_addr, Endpoint::Listener);
+    assert!(peer_store.peer_score(&new_peer).is_some());
+    assert!(peer_store.peer_score(&known_peer).is_some());
+    assert_eq!(peer_store.peer_addr_infos(&known_peer).len(), 1);
+}
+
+#[test]
+fn test_gossip_flood() {
+    check_gossip_flood(Box::new(MemoryPeerStore::default()));
+    check_gossip_flood(Box::new(SqlitePeerStore::temp()));
+}
@@ -0,0 +1,21 @@
+use crate::peer_store::IpSubnet;
+
+#[test]
+fn test_parse_subnet() {
+    let subnet = "192.168.1.10/16".parse::<IpSubnet>().unwrap();
+    assert_eq!(subnet.to_string(), "192.168.0.0/16");
+    assert!(subnet.contains("192.168.200.1".parse().unwrap()));
+    assert!(!subnet.contains("192.169.0.1".parse().unwrap()));
+    assert!(!subnet.contains("::1".parse().unwrap()));
+
+    let ip = "192.168.1.10".parse::<IpSubnet>().unwrap();
+    assert_eq!(ip.to_string(), "192.168.1.10/32");
+    assert!(ip.contains("192.168.1.10".parse().unwrap()));
+    assert!(!ip.contains("192.168.1.11".parse().unwrap()));
+
+    let all = "::/0".parse::<IpSubnet>().unwrap();
+    assert!(all.contains("2001:db8::1".parse().unwrap()));
+
+    assert!("192.168.1.10/33".parse::<IpSubnet>().is_err());
+    assert!("localhost".parse::<IpSubnet>().is_err());
+}
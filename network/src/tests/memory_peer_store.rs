@@ -0,0 +1,234 @@
+use crate::{
+    peer_store::{
+        Behaviour, IpSubnet, MemoryPeerStore, PeerStore, Status, ADDRS_PER_PEER_LIMIT,
+        ADDR_MAX_RETRIES, ADDR_NOT_SEEN_TIMEOUT_SECS, PEER_NOT_SEEN_TIMEOUT_SECS, PEER_STORE_LIMIT,
+    },
+    random_peer_id, Endpoint, ToMultiaddr,
+};
+use std::time::Duration;
+
+#[test]
+fn test_add_discovered_address() {
+    let mut peer_store: Box<dyn PeerStore> = Box::new(MemoryPeerStore::default());
+    let peer_id = random_peer_id().unwrap();
+    peer_store.new_connected_peer(
+        &peer_id,
+        "/ip4/127.0.0.1".to_multiaddr().unwrap(),
+        Endpoint::Dialer,
+    );
+    assert_eq!(
+        peer_store.peer_score(&peer_id).unwrap(),
+        peer_store.scoring_schema().peer_init_score()
+    );
+    assert_eq!(peer_store.peer_addrs(&peer_id, 1).unwrap().len(), 0);
+    peer_store
+        .add_discovered_address(&peer_id, "/ip4/127.0.0.1".to_multiaddr().unwrap())
+        .expect("add discovered address");
+    assert!(peer_store
+        .add_discovered_address(&peer_id, "/ip4/127.0.0.1".to_multiaddr().unwrap())
+        .is_err());
+    assert_eq!(
+        peer_store.add_discovered_addresses(
+            &peer_id,
+            vec![
+                "/ip4/127.0.0.1".to_multiaddr().unwrap(),
+                "/ip4/192.168.2.2".to_multiaddr().unwrap(),
+            ],
+        ),
+        Ok(1)
+    );
+    assert_eq!(peer_store.peer_addrs(&peer_id, 4).unwrap().len(), 2);
+}
+
+#[test]
+fn test_report_misbehaviour_until_banned() {
+    let mut peer_store: Box<dyn PeerStore> = Box::new(MemoryPeerStore::default());
+    let peer_id = random_peer_id().unwrap();
+    assert!(peer_store.report(&peer_id, Behaviour::Ping).is_ok());
+    assert!(
+        peer_store.peer_score_or_default(&peer_id) > peer_store.scoring_schema().peer_init_score()
+    );
+    let addr = "/ip4/127.0.0.1".to_multiaddr().unwrap();
+    peer_store.new_connected_peer(&peer_id, addr, Endpoint::Listener);
+    assert!(peer_store.report(&peer_id, Behaviour::InvalidBlock).is_ok());
+    assert!(!peer_store.is_banned(&peer_id));
+    assert!(peer_store
+        .report(&peer_id, Behaviour::InvalidBlock)
+        .is_banned());
+    assert!(peer_store.is_banned(&peer_id));
+    assert!(peer_store.report(&peer_id, Behaviour::Ping).is_banned());
+}
+
+#[test]
+fn test_ban_subnet() {
+    let mut peer_store: Box<dyn PeerStore> = Box::new(MemoryPeerStore::default());
+    let peer_id = random_peer_id().unwrap();
+    let addr = "/ip4/192.168.1.2".to_multiaddr().unwrap();
+    peer_store.new_connected_peer(&peer_id, addr, Endpoint::Listener);
+    let subnet = "192.168.0.0/16".parse::<IpSubnet>().unwrap();
+    peer_store.ban_subnet(subnet, Duration::from_secs(10), "test".to_string());
+    assert!(peer_store.is_banned(&peer_id));
+    assert_eq!(peer_store.banned_list().len(), 1);
+    assert!(peer_store.unban_subnet(&subnet));
+    assert!(!peer_store.is_banned(&peer_id));
+
+    peer_store.ban_peer(&peer_id, Duration::from_secs(10), "test".to_string());
+    assert!(peer_store.is_banned(&peer_id));
+    assert!(peer_store.unban_peer(&peer_id));
+    assert!(!peer_store.is_banned(&peer_id));
+
+    peer_store.ban_subnet(subnet, Duration::from_secs(10), "test".to_string());
+    peer_store.clear_banned();
+    assert!(peer_store.banned_list().is_empty());
+}
+
+#[test]
+fn test_peers_to_attempt() {
+    let mut peer_store: Box<dyn PeerStore> = Box::new(MemoryPeerStore::default());
+    assert!(peer_store.peers_to_attempt(1).is_empty());
+    let peer_id = random_peer_id().unwrap();
+    let addr = "/ip4/127.0.0.1".to_multiaddr().unwrap();
+    peer_store.add_bootnode(peer_id.clone(), addr.clone());
+    assert!(peer_store.peers_to_attempt(1).is_empty());
+    assert_eq!(
+        peer_store.bootnodes(2),
+        vec![(peer_id.clone(), addr.clone())]
+    );
+
+    let peer_id2 = random_peer_id().unwrap();
+    peer_store
+        .add_discovered_address(&peer_id2, addr.clone())
+        .expect("add discovered address");
+    assert_eq!(
+        peer_store.bootnodes(3),
+        vec![(peer_id2.clone(), addr.clone()), (peer_id, addr.clone())]
+    );
+    assert_eq!(peer_store.peers_to_attempt(2).len(), 1);
+    peer_store.update_status(&peer_id2, Status::Connected);
+    assert!(peer_store.peers_to_attempt(1).is_empty());
+
+    // banned peers are not attempted
+    let peer_id3 = random_peer_id().unwrap();
+    let addr3 = "/ip4/192.168.1.2".to_multiaddr().unwrap();
+    peer_store.new_connected_peer(&peer_id3, addr3.clone(), Endpoint::Listener);
+    peer_store
+        .add_discovered_address(&peer_id3, addr3)
+        .expect("add discovered address");
+    assert_eq!(peer_store.peers_to_attempt(2).len(), 1);
+    peer_store.ban_peer(&peer_id3, Duration::from_secs(10), "test".to_string());
+    assert!(peer_store.peers_to_attempt(2).is_empty());
+}
+
+#[test]
+fn test_delete_peer_info() {
+    let mut peer_store = MemoryPeerStore::default();
+    let addr1 = "/ip4/127.0.0.1".to_multiaddr().unwrap();
+    let addr2 = "/ip4/192.163.1.1".to_multiaddr().unwrap();
+    for _ in 0..(PEER_STORE_LIMIT - 2) {
+        peer_store.new_connected_peer(
+            &random_peer_id().unwrap(),
+            addr1.clone(),
+            Endpoint::Listener,
+        );
+    }
+    let evict_target = random_peer_id().unwrap();
+    let fake_target = random_peer_id().unwrap();
+    {
+        // make sure these 2 peers become candidate in eviction
+        let recent_not_seen_time =
+            faketime::unix_time() - Duration::from_secs(u64::from(PEER_NOT_SEEN_TIMEOUT_SECS + 1));
+        let faketime_file = faketime::millis_tempfile(recent_not_seen_time.as_secs() * 1000)
+            .expect("create faketime file");
+        faketime::enable(&faketime_file);
+        peer_store.new_connected_peer(&evict_target, addr1.clone(), Endpoint::Listener);
+        peer_store.new_connected_peer(&fake_target, addr2, Endpoint::Listener);
+    }
+    peer_store.report(&evict_target, Behaviour::FailedToPing);
+    peer_store.report(&fake_target, Behaviour::FailedToPing);
+    peer_store.report(&fake_target, Behaviour::FailedToPing);
+    // should evict evict_target in the largest network group
+    peer_store.new_connected_peer(&random_peer_id().unwrap(), addr1, Endpoint::Listener);
+    assert_eq!(
+        peer_store.peer_score_or_default(&evict_target),
+        peer_store.scoring_schema().peer_init_score()
+    );
+    assert!(peer_store.peer_score(&fake_target).is_some());
+}
+
+#[test]
+fn test_addrs_per_peer_limit() {
+    let mut peer_store: Box<dyn PeerStore> = Box::new(MemoryPeerStore::default());
+    let peer_id = random_peer_id().unwrap();
+    let addrs = (0..=ADDRS_PER_PEER_LIMIT)
+        .map(|i| format!("/ip4/192.168.0.{}", i).to_multiaddr().unwrap())
+        .collect();
+    assert_eq!(
+        peer_store.add_discovered_addresses(&peer_id, addrs),
+        Ok(ADDRS_PER_PEER_LIMIT)
+    );
+    assert_eq!(
+        peer_store.peer_addr_infos(&peer_id).len(),
+        ADDRS_PER_PEER_LIMIT
+    );
+}
+
+#[test]
+fn test_tried_and_new_addrs() {
+    let mut peer_store: Box<dyn PeerStore> = Box::new(MemoryPeerStore::default());
+    let peer_id = random_peer_id().unwrap();
+    let new_addr = "/ip4/192.168.0.1".to_multiaddr().unwrap();
+    let tried_addr = "/ip4/192.168.0.2".to_multiaddr().unwrap();
+    peer_store
+        .add_discovered_address(&peer_id, new_addr.clone())
+        .expect("add discovered address");
+    peer_store.add_tried_address(&peer_id, tried_addr.clone());
+    // the peer is in both buckets
+    let peers = peer_store.peers_to_attempt(2);
+    assert_eq!(peers.len(), 1);
+    assert!(peers[0].1 == new_addr || peers[0].1 == tried_addr);
+
+    // attempted addresses are not attempted again immediately
+    peer_store.mark_addr_attempted(&peer_id, &tried_addr);
+    assert_eq!(
+        peer_store.peers_to_attempt(1),
+        vec![(peer_id.clone(), new_addr.clone())]
+    );
+    for _ in 0..ADDR_MAX_RETRIES {
+        peer_store.mark_addr_attempted(&peer_id, &new_addr);
+    }
+    assert!(peer_store.peers_to_attempt(1).is_empty());
+
+    // new address is evicted after too many failures, tried address is kept
+    assert_eq!(peer_store.evict_stale_addrs(), 1);
+    let addr_infos = peer_store.peer_addr_infos(&peer_id);
+    assert_eq!(addr_infos.len(), 1);
+    assert_eq!(addr_infos[0].addr, tried_addr);
+    assert!(addr_infos[0].tried);
+    assert_eq!(addr_infos[0].attempts, 1);
+    peer_store.add_tried_address(&peer_id, tried_addr);
+    assert_eq!(peer_store.peer_addr_infos(&peer_id)[0].attempts, 0);
+}
+
+#[test]
+fn test_evict_not_seen_addrs() {
+    let mut peer_store: Box<dyn PeerStore> = Box::new(MemoryPeerStore::default());
+    let peer_id = random_peer_id().unwrap();
+    let addr1 = "/ip4/192.168.0.1".to_multiaddr().unwrap();
+    let addr2 = "/ip4/192.168.0.2".to_multiaddr().unwrap();
+    {
+        let not_seen_time =
+            faketime::unix_time() - Duration::from_secs(u64::from(ADDR_NOT_SEEN_TIMEOUT_SECS + 1));
+        let faketime_file = faketime::millis_tempfile(not_seen_time.as_secs() * 1000)
+            .expect("create faketime file");
+        faketime::enable(&faketime_file);
+        peer_store
+            .add_discovered_addresses(&peer_id, vec![addr1.clone(), addr2.clone()])
+            .expect("add discovered addresses");
+    }
+    // announcement refreshes last seen time
+    assert!(peer_store
+        .add_discovered_address(&peer_id, addr1.clone())
+        .is_err());
+    assert_eq!(peer_store.evict_stale_addrs(), 1);
+    assert_eq!(peer_store.peer_addrs(&peer_id, 2), Some(vec![addr1]));
+}
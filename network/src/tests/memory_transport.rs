@@ -0,0 +1,175 @@
+use crate::memory_transport::{MemoryNetwork, MemorySocket, MemoryTransport, RETRANSMIT_TIMEOUT};
+use crate::network_group::MultiaddrExt;
+use crate::{
+    CKBProtocol, CKBProtocolContext, CKBProtocolHandler, Network, NetworkConfig, NetworkService,
+    PeerIndex, PeerStoreType, ToMultiaddr, TransportType,
+};
+use ckb_util::Mutex;
+use futures::{Future, Stream};
+use libp2p::core::Transport;
+use std::io::ErrorKind as IoErrorKind;
+use std::net::IpAddr;
+use std::sync::mpsc::{channel, Sender};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{read_exact, write_all};
+use tokio::runtime::Runtime;
+
+type Listener = <MemoryTransport as Transport>::Listener;
+
+// dial node "a" from node "b", return sockets of (a, b) and the listener of "a"
+fn connect(network: &Arc<MemoryNetwork>) -> (MemorySocket, MemorySocket, Listener) {
+    let listen_addr = "/ip4/0.0.0.0/tcp/30333".to_multiaddr().unwrap();
+    let (listener, listened_addr) = MemoryTransport::new(Arc::clone(network), "a")
+        .listen_on(listen_addr)
+        .unwrap();
+    assert_eq!(listened_addr, network.node_addr("a", 30333));
+
+    let (b_socket, _addr) = MemoryTransport::new(Arc::clone(network), "b")
+        .dial(listened_addr)
+        .unwrap()
+        .wait()
+        .unwrap();
+    let (upgrade, listener) = listener.into_future().wait().ok().unwrap();
+    let (a_socket, remote_addr) = upgrade.unwrap().wait().unwrap();
+    let remote_addr = remote_addr.wait().unwrap();
+    assert_eq!(
+        remote_addr.extract_ip_addr(),
+        Some(IpAddr::V4(network.node_ip("b")))
+    );
+    (a_socket, b_socket, listener)
+}
+
+#[test]
+fn test_connect_by_name() {
+    let network = Arc::new(MemoryNetwork::default());
+    let (a_socket, b_socket, _listener) = connect(&network);
+
+    let (b_socket, _) = write_all(b_socket, b"ping".to_vec()).wait().unwrap();
+    let (a_socket, buf) = read_exact(a_socket, [0u8; 4]).wait().unwrap();
+    assert_eq!(&buf, b"ping");
+    let (_a_socket, _) = write_all(a_socket, b"pong".to_vec()).wait().unwrap();
+    let (_b_socket, buf) = read_exact(b_socket, [0u8; 4]).wait().unwrap();
+    assert_eq!(&buf, b"pong");
+
+    // nobody listens on node "c"
+    let result = MemoryTransport::new(Arc::clone(&network), "b")
+        .dial(network.node_addr("c", 30333))
+        .unwrap()
+        .wait();
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_partition() {
+    let network = Arc::new(MemoryNetwork::default());
+    let (_a_socket, b_socket, _listener) = connect(&network);
+
+    network.partition("a", "b");
+    let err = write_all(b_socket, b"ping".to_vec()).wait().err().unwrap();
+    assert_eq!(err.kind(), IoErrorKind::ConnectionReset);
+    let result = MemoryTransport::new(Arc::clone(&network), "b")
+        .dial(network.node_addr("a", 30333))
+        .unwrap()
+        .wait();
+    assert!(result.is_err());
+
+    network.heal("a", "b");
+    let result = MemoryTransport::new(Arc::clone(&network), "b")
+        .dial(network.node_addr("a", 30333))
+        .unwrap()
+        .wait();
+    assert!(result.is_ok());
+}
+
+#[test]
+fn test_latency_and_drop_rate() {
+    let network = Arc::new(MemoryNetwork::default());
+    let (a_socket, b_socket, _listener) = connect(&network);
+    let latency = Duration::from_millis(50);
+    network.set_latency(latency);
+    network.set_drop_rate(1.0);
+
+    let mut runtime = Runtime::new().unwrap();
+    let start = Instant::now();
+    let future = write_all(b_socket, b"ping".to_vec())
+        .and_then(|(b_socket, _)| write_all(b_socket, b"pong".to_vec()))
+        .join(read_exact(a_socket, [0u8; 8]));
+    let (_, (_a_socket, buf)) = runtime.block_on(future).unwrap();
+    // dropped chunks are retransmitted in order
+    assert_eq!(&buf, b"pingpong");
+    assert!(start.elapsed() >= latency + RETRANSMIT_TIMEOUT);
+}
+
+// reports connected peers of the test protocol
+struct ConnectedHandler(Mutex<Sender<PeerIndex>>);
+
+impl CKBProtocolHandler for ConnectedHandler {
+    fn initialize(&self, _nc: Box<dyn CKBProtocolContext>) {}
+    fn received(&self, _nc: Box<dyn CKBProtocolContext>, _peer: PeerIndex, _data: &[u8]) {}
+    fn connected(&self, _nc: Box<dyn CKBProtocolContext>, peer: PeerIndex) {
+        let _ = self.0.lock().send(peer);
+    }
+    fn disconnected(&self, _nc: Box<dyn CKBProtocolContext>, _peer: PeerIndex) {}
+}
+
+fn memory_node_config(network: &Arc<MemoryNetwork>, name: &str) -> NetworkConfig {
+    let mut config = NetworkConfig::default();
+    config.generate_random_key().unwrap();
+    config.peer_store_type = PeerStoreType::Memory;
+    config.transport_type = TransportType::Memory {
+        network: Arc::clone(network),
+        node: name.to_owned(),
+    };
+    config
+}
+
+#[test]
+fn test_network_services_over_memory_transport() {
+    let network = Arc::new(MemoryNetwork::default());
+    let (connected_tx, connected_rx) = channel();
+    let protocols = || {
+        let handler = ConnectedHandler(Mutex::new(connected_tx.clone()));
+        vec![CKBProtocol::new(
+            "ckb".to_owned(),
+            Arc::new(handler) as Arc<_>,
+            *b"tst",
+            &[1][..],
+        )]
+    };
+
+    let config_a = memory_node_config(&network, "a");
+    let service_a = NetworkService::run_in_thread(&config_a, protocols()).unwrap();
+    let (url_a, _) = service_a.external_urls(1).remove(0);
+    assert!(url_a.starts_with(&network.node_addr("a", 30333).to_string()));
+
+    let mut config_b = memory_node_config(&network, "b");
+    config_b.bootnodes = vec![url_a];
+    let service_b = NetworkService::run_in_thread(&config_b, protocols()).unwrap();
+
+    // both nodes open the test protocol with each other
+    let timeout = Duration::from_secs(10);
+    assert!(connected_rx.recv_timeout(timeout).is_ok());
+    assert!(connected_rx.recv_timeout(timeout).is_ok());
+    assert_eq!(service_a.connected_peers().len(), 1);
+    assert_eq!(service_b.connected_peers().len(), 1);
+
+    service_b.close();
+    service_a.close();
+}
+
+#[test]
+fn test_memory_listen_address_error() {
+    let network = Arc::new(MemoryNetwork::default());
+    let mut config = memory_node_config(&network, "a");
+    // not an ip4 tcp address, which must not fall back to a real listen
+    config.listen_addresses = vec!["/ip6/::1/tcp/30333".to_multiaddr().unwrap()];
+    assert!(Network::build(&config, Vec::new()).is_err());
+
+    // the address is already taken by another node in the memory network
+    let config = memory_node_config(&network, "b");
+    let _listener = MemoryTransport::new(Arc::clone(&network), "b")
+        .listen_on("/ip4/0.0.0.0/tcp/30333".to_multiaddr().unwrap())
+        .unwrap();
+    assert!(Network::build(&config, Vec::new()).is_err());
+}
@@ -1,3 +1,8 @@
+mod addr_gossip;
+mod ban;
+mod memory_peer_store;
+mod memory_transport;
 mod peers_registry;
 #[cfg(test)]
 mod sqlite_peer_store;
+mod traffic_stats;
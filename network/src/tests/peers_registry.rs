@@ -1,11 +1,14 @@
 use crate::{
     peer_store::{Behaviour, PeerStore, SqlitePeerStore},
     peers_registry::{PeersRegistry, EVICTION_PROTECT_PEERS},
-    random_peer_id, ToMultiaddr,
+    random_peer_id,
+    whitelist::Whitelist,
+    NetworkConfig, ToMultiaddr,
 };
 use ckb_util::RwLock;
 use faketime::unix_time_as_millis;
 use std::sync::Arc;
+use std::time::Duration;
 
 fn new_peer_store() -> impl PeerStore {
     SqlitePeerStore::temp()
@@ -24,6 +27,7 @@ fn test_accept_inbound_peer_in_reserve_only_mode() {
         3,
         true,
         vec![reserved_peer.clone()],
+        Whitelist::default(),
     );
     assert!(peers_registry
         .accept_inbound_peer(random_peer_id().unwrap(), addr.clone())
@@ -45,6 +49,7 @@ fn test_accept_inbound_peer_until_full() {
         3,
         false,
         vec![reserved_peer.clone()],
+        Whitelist::default(),
     );
     peers_registry
         .accept_inbound_peer(random_peer_id().unwrap(), addr.clone())
@@ -89,6 +94,7 @@ fn test_accept_inbound_peer_eviction() {
         3,
         false,
         vec![reserved_peer.clone()],
+        Whitelist::default(),
     );
     for _ in 0..protected_peers_count {
         assert!(peers_registry
@@ -179,3 +185,153 @@ fn test_accept_inbound_peer_eviction() {
         .expect("accept");
     assert!(peers_registry.get(&evict_target).is_none());
 }
+
+#[test]
+fn test_outbound_peer_network_group_diversity() {
+    let peer_store: Arc<RwLock<dyn PeerStore>> = Arc::new(RwLock::new(new_peer_store()));
+    let reserved_peer = random_peer_id().unwrap();
+    let mut peers_registry = PeersRegistry::new(
+        Arc::clone(&peer_store),
+        3,
+        5,
+        false,
+        vec![reserved_peer.clone()],
+        Whitelist::default(),
+    );
+    peers_registry
+        .try_outbound_peer(
+            random_peer_id().unwrap(),
+            "/ip4/1.2.3.4".to_multiaddr().unwrap(),
+        )
+        .expect("connect");
+    // same /16 group
+    assert!(peers_registry
+        .try_outbound_peer(
+            random_peer_id().unwrap(),
+            "/ip4/1.2.200.1".to_multiaddr().unwrap(),
+        )
+        .is_err());
+    peers_registry
+        .try_outbound_peer(
+            random_peer_id().unwrap(),
+            "/ip4/1.3.3.4".to_multiaddr().unwrap(),
+        )
+        .expect("connect");
+    // reserved and local peers are not limited
+    peers_registry
+        .try_outbound_peer(reserved_peer, "/ip4/1.2.3.5".to_multiaddr().unwrap())
+        .expect("connect");
+    let local_addr = "/ip4/127.0.0.1".to_multiaddr().unwrap();
+    peers_registry
+        .try_outbound_peer(random_peer_id().unwrap(), local_addr.clone())
+        .expect("connect");
+    peers_registry
+        .try_outbound_peer(random_peer_id().unwrap(), local_addr)
+        .expect("connect");
+}
+
+#[test]
+fn test_anchor_peers() {
+    let peer_store: Arc<RwLock<dyn PeerStore>> = Arc::new(RwLock::new(new_peer_store()));
+    let mut peers_registry = PeersRegistry::new(
+        Arc::clone(&peer_store),
+        3,
+        3,
+        false,
+        Vec::new(),
+        Whitelist::default(),
+    );
+    let now = unix_time_as_millis();
+    let outbound_peers = (0..3)
+        .map(|i| {
+            let peer_id = random_peer_id().unwrap();
+            let addr = format!("/ip4/1.{}.0.1", i).to_multiaddr().unwrap();
+            peers_registry
+                .try_outbound_peer(peer_id.clone(), addr.clone())
+                .expect("connect");
+            peers_registry.get_mut(&peer_id).unwrap().connected_time = Some(now - i * 1000);
+            (peer_id, addr)
+        })
+        .collect::<Vec<_>>();
+    let inbound_peer = random_peer_id().unwrap();
+    peers_registry
+        .accept_inbound_peer(inbound_peer.clone(), "/ip4/2.0.0.1".to_multiaddr().unwrap())
+        .expect("accept");
+    peers_registry
+        .get_mut(&inbound_peer)
+        .unwrap()
+        .connected_time = Some(0);
+
+    // outbound peers with the longest connection time
+    let anchors = peers_registry.anchor_peers(2);
+    assert_eq!(
+        anchors,
+        vec![outbound_peers[2].clone(), outbound_peers[1].clone()]
+    );
+}
+
+#[test]
+fn test_write_and_read_anchors() {
+    let anchors_file = tempfile::NamedTempFile::new().expect("tempfile");
+    let config = NetworkConfig {
+        anchors_path: Some(anchors_file.path().to_string_lossy().into_owned()),
+        ..Default::default()
+    };
+    let peer_id = random_peer_id().unwrap();
+    let addr = "/ip4/1.0.0.1/tcp/8115".to_multiaddr().unwrap();
+    // connected address which already ends with the peer id
+    let addr_with_peer_id = format!("{}/p2p/{}", addr, peer_id.to_base58())
+        .to_multiaddr()
+        .unwrap();
+    config
+        .write_anchors(&[
+            (peer_id.clone(), addr.clone()),
+            (peer_id.clone(), addr_with_peer_id),
+        ])
+        .expect("write anchors");
+    assert_eq!(
+        config.read_anchors(),
+        vec![(peer_id.clone(), addr.clone()), (peer_id, addr)]
+    );
+}
+
+#[test]
+fn test_whitelisted_peers() {
+    let peer_store: Arc<RwLock<dyn PeerStore>> = Arc::new(RwLock::new(new_peer_store()));
+    let whitelisted_peer = random_peer_id().unwrap();
+    let whitelist = Whitelist::parse(&[whitelisted_peer.to_base58(), "192.168.0.0/16".to_owned()])
+        .expect("parse whitelist");
+    let mut peers_registry =
+        PeersRegistry::new(Arc::clone(&peer_store), 1, 3, false, Vec::new(), whitelist);
+    let addr = "/ip4/127.0.0.1".to_multiaddr().unwrap();
+    let subnet_addr = "/ip4/192.168.1.1".to_multiaddr().unwrap();
+    let inbound_peer = random_peer_id().unwrap();
+    peers_registry
+        .accept_inbound_peer(inbound_peer.clone(), addr.clone())
+        .expect("accept");
+
+    // whitelisted peers exceed the inbound limitation without evicting others
+    peers_registry
+        .accept_inbound_peer(whitelisted_peer.clone(), addr.clone())
+        .expect("accept");
+    let subnet_peer = random_peer_id().unwrap();
+    peers_registry
+        .accept_inbound_peer(subnet_peer.clone(), subnet_addr.clone())
+        .expect("accept");
+    assert!(peers_registry.get(&inbound_peer).is_some());
+    assert!(peers_registry.is_whitelisted(&whitelisted_peer));
+    assert!(peers_registry.is_whitelisted(&subnet_peer));
+    assert!(!peers_registry.is_whitelisted(&inbound_peer));
+    assert_eq!(peers_registry.connection_status().unreserved_inbound, 1);
+
+    // whitelisted peers are accepted even if they are banned
+    peers_registry.drop_peer(&subnet_peer);
+    peer_store.write().ban_subnet(
+        "192.168.0.0/16".parse().unwrap(),
+        Duration::from_secs(10),
+        "test".to_owned(),
+    );
+    peers_registry
+        .accept_inbound_peer(subnet_peer, subnet_addr)
+        .expect("accept");
+}
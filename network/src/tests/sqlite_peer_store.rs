@@ -1,10 +1,12 @@
 use crate::{
     peer_store::{
-        sqlite_peer_store::{PEER_NOT_SEEN_TIMEOUT_SECS, PEER_STORE_LIMIT},
-        Behaviour, PeerStore, SqlitePeerStore, Status,
+        sqlite::{self, ConnectionPoolExt},
+        Behaviour, IpSubnet, PeerStore, SqlitePeerStore, Status, ADDRS_PER_PEER_LIMIT,
+        ADDR_MAX_RETRIES, ADDR_NOT_SEEN_TIMEOUT_SECS, PEER_NOT_SEEN_TIMEOUT_SECS, PEER_STORE_LIMIT,
     },
     random_peer_id, Endpoint, ToMultiaddr,
 };
+use rusqlite::types::ToSql;
 use std::time::Duration;
 
 #[test]
@@ -51,6 +53,21 @@ fn test_report() {
     );
 }
 
+#[test]
+fn test_report_misbehaviour_until_banned() {
+    let mut peer_store: Box<dyn PeerStore> = Box::new(SqlitePeerStore::temp());
+    let peer_id = random_peer_id().unwrap();
+    let addr = "/ip4/127.0.0.1".to_multiaddr().unwrap();
+    peer_store.new_connected_peer(&peer_id, addr, Endpoint::Listener);
+    assert!(peer_store.report(&peer_id, Behaviour::InvalidBlock).is_ok());
+    assert!(!peer_store.is_banned(&peer_id));
+    assert!(peer_store
+        .report(&peer_id, Behaviour::InvalidBlock)
+        .is_banned());
+    assert!(peer_store.is_banned(&peer_id));
+    assert!(peer_store.report(&peer_id, Behaviour::Ping).is_banned());
+}
+
 #[test]
 fn test_update_status() {
     let mut peer_store: Box<dyn PeerStore> = Box::new(SqlitePeerStore::temp());
@@ -67,14 +84,125 @@ fn test_update_status() {
 fn test_ban_peer() {
     let mut peer_store: Box<dyn PeerStore> = Box::new(SqlitePeerStore::temp());
     let peer_id = random_peer_id().unwrap();
-    peer_store.ban_peer(&peer_id, Duration::from_secs(10));
+    peer_store.ban_peer(&peer_id, Duration::from_secs(10), "test".to_string());
     assert!(!peer_store.is_banned(&peer_id));
     let addr = "/ip4/127.0.0.1".to_multiaddr().unwrap();
     peer_store.new_connected_peer(&peer_id, addr, Endpoint::Listener);
-    peer_store.ban_peer(&peer_id, Duration::from_secs(10));
+    peer_store.ban_peer(&peer_id, Duration::from_secs(10), "test".to_string());
     assert!(peer_store.is_banned(&peer_id));
 }
 
+#[test]
+fn test_ban_subnet() {
+    let mut peer_store: Box<dyn PeerStore> = Box::new(SqlitePeerStore::temp());
+    let peer_id = random_peer_id().unwrap();
+    let addr = "/ip4/192.168.1.2".to_multiaddr().unwrap();
+    peer_store.new_connected_peer(&peer_id, addr, Endpoint::Listener);
+    let subnet = "192.168.0.0/16".parse::<IpSubnet>().unwrap();
+    peer_store.ban_subnet(subnet, Duration::from_secs(10), "test".to_string());
+    assert!(peer_store.is_banned(&peer_id));
+    let banned_list = peer_store.banned_list();
+    assert_eq!(banned_list.len(), 1);
+    assert_eq!(banned_list[0].subnet, subnet);
+    assert_eq!(banned_list[0].reason, "test");
+
+    assert!(peer_store.unban_subnet(&subnet));
+    assert!(!peer_store.unban_subnet(&subnet));
+    assert!(!peer_store.is_banned(&peer_id));
+
+    peer_store.ban_peer(&peer_id, Duration::from_secs(10), "test".to_string());
+    assert!(peer_store.unban_peer(&peer_id));
+    assert!(!peer_store.is_banned(&peer_id));
+
+    peer_store.ban_subnet(subnet, Duration::from_secs(10), "test".to_string());
+    peer_store.clear_banned();
+    assert!(peer_store.banned_list().is_empty());
+    assert!(!peer_store.is_banned(&peer_id));
+}
+
+#[test]
+fn test_migrate_baseline_ban_list() {
+    // a single connection, each connection opens its own temp database
+    let pool = sqlite::open_pool(sqlite::StorePath::File("".into()), 1);
+    let ban_time = faketime::unix_time().as_secs() + 100;
+    pool.fetch(|conn| {
+        let sql = format!(
+            r#"
+            CREATE TABLE ban_list (
+            id INTEGER PRIMARY KEY NOT NULL,
+            ip BINARY UNIQUE NOT NULL,
+            ban_time INTEGER NOT NULL
+            );
+            INSERT INTO ban_list (ip, ban_time) VALUES (x'c0a80102', {});
+            "#,
+            ban_time
+        );
+        conn.execute_batch(&sql).map_err(Into::into)
+    })
+    .expect("create baseline ban list");
+
+    let mut peer_store: Box<dyn PeerStore> = Box::new(SqlitePeerStore::new(pool));
+    let banned_list = peer_store.banned_list();
+    assert_eq!(banned_list.len(), 1);
+    assert_eq!(
+        banned_list[0].subnet,
+        "192.168.1.2/32".parse::<IpSubnet>().unwrap()
+    );
+    assert_eq!(banned_list[0].reason, "");
+
+    let subnet = "192.168.0.0/16".parse::<IpSubnet>().unwrap();
+    peer_store.ban_subnet(subnet, Duration::from_secs(10), "test".to_string());
+    assert_eq!(peer_store.banned_list().len(), 2);
+}
+
+#[test]
+fn test_migrate_baseline_peer_addr() {
+    let pool = sqlite::open_pool(sqlite::StorePath::File("".into()), 1);
+    let peer_id = random_peer_id().unwrap();
+    let addr = "/ip4/192.168.0.1".to_multiaddr().unwrap();
+    pool.fetch(|conn| {
+        conn.execute_batch(
+            r#"
+            CREATE TABLE peer_info (
+            id INTEGER PRIMARY KEY NOT NULL,
+            peer_id BINARY UNIQUE NOT NULL,
+            connected_addr BINARY NOT NULL,
+            network_group BINARY NOT NULL,
+            score INTEGER NOT NULL,
+            status INTEGER NOT NULL,
+            endpoint INTEGER NOT NULL,
+            ban_time INTEGER NOT NULL,
+            connected_time INTEGER NOT NULL
+            );
+            CREATE TABLE peer_addr (
+            id INTEGER PRIMARY KEY NOT NULL,
+            peer_info_id INTEGER NOT NULL,
+            addr BINARY NOT NULL
+            );
+            "#,
+        )?;
+        conn.execute(
+            "INSERT INTO peer_info (id, peer_id, connected_addr, network_group, score, status,
+             endpoint, ban_time, connected_time) VALUES (1, ?1, ?2, x'00', 0, 0, 0, 0, 0)",
+            &[&peer_id.as_bytes() as &ToSql, &addr.to_bytes()],
+        )?;
+        conn.execute(
+            "INSERT INTO peer_addr (peer_info_id, addr) VALUES (1, ?1)",
+            &[&addr.to_bytes()],
+        )?;
+        Ok(())
+    })
+    .expect("create baseline peer addr");
+
+    let mut peer_store: Box<dyn PeerStore> = Box::new(SqlitePeerStore::new(pool));
+    let addr_infos = peer_store.peer_addr_infos(&peer_id);
+    assert_eq!(addr_infos.len(), 1);
+    assert_eq!(addr_infos[0].addr, addr);
+    assert!(!addr_infos[0].tried);
+    assert_eq!(addr_infos[0].attempts, 0);
+    assert_eq!(peer_store.evict_stale_addrs(), 0);
+}
+
 #[test]
 fn test_bootnodes() {
     let mut peer_store: Box<dyn PeerStore> = Box::new(SqlitePeerStore::temp());
@@ -150,3 +278,81 @@ fn test_delete_peer_info() {
         peer_store.scoring_schema().peer_init_score()
     );
 }
+
+#[test]
+fn test_addrs_per_peer_limit() {
+    let mut peer_store: Box<dyn PeerStore> = Box::new(SqlitePeerStore::temp());
+    let peer_id = random_peer_id().unwrap();
+    let addrs = (0..=ADDRS_PER_PEER_LIMIT)
+        .map(|i| format!("/ip4/192.168.0.{}", i).to_multiaddr().unwrap())
+        .collect();
+    assert_eq!(
+        peer_store.add_discovered_addresses(&peer_id, addrs),
+        Ok(ADDRS_PER_PEER_LIMIT)
+    );
+    assert_eq!(
+        peer_store.peer_addr_infos(&peer_id).len(),
+        ADDRS_PER_PEER_LIMIT
+    );
+}
+
+#[test]
+fn test_tried_and_new_addrs() {
+    let mut peer_store: Box<dyn PeerStore> = Box::new(SqlitePeerStore::temp());
+    let peer_id = random_peer_id().unwrap();
+    let new_addr = "/ip4/192.168.0.1".to_multiaddr().unwrap();
+    let tried_addr = "/ip4/192.168.0.2".to_multiaddr().unwrap();
+    peer_store
+        .add_discovered_address(&peer_id, new_addr.clone())
+        .expect("add discovered address");
+    peer_store.add_tried_address(&peer_id, tried_addr.clone());
+    // the peer is in both buckets
+    let peers = peer_store.peers_to_attempt(2);
+    assert_eq!(peers.len(), 1);
+    assert!(peers[0].1 == new_addr || peers[0].1 == tried_addr);
+
+    // attempted addresses are not attempted again immediately
+    peer_store.mark_addr_attempted(&peer_id, &tried_addr);
+    assert_eq!(
+        peer_store.peers_to_attempt(1),
+        vec![(peer_id.clone(), new_addr.clone())]
+    );
+    for _ in 0..ADDR_MAX_RETRIES {
+        peer_store.mark_addr_attempted(&peer_id, &new_addr);
+    }
+    assert!(peer_store.peers_to_attempt(1).is_empty());
+
+    // new address is evicted after too many failures, tried address is kept
+    assert_eq!(peer_store.evict_stale_addrs(), 1);
+    let addr_infos = peer_store.peer_addr_infos(&peer_id);
+    assert_eq!(addr_infos.len(), 1);
+    assert_eq!(addr_infos[0].addr, tried_addr);
+    assert!(addr_infos[0].tried);
+    assert_eq!(addr_infos[0].attempts, 1);
+    peer_store.add_tried_address(&peer_id, tried_addr);
+    assert_eq!(peer_store.peer_addr_infos(&peer_id)[0].attempts, 0);
+}
+
+#[test]
+fn test_evict_not_seen_addrs() {
+    let mut peer_store: Box<dyn PeerStore> = Box::new(SqlitePeerStore::temp());
+    let peer_id = random_peer_id().unwrap();
+    let addr1 = "/ip4/192.168.0.1".to_multiaddr().unwrap();
+    let addr2 = "/ip4/192.168.0.2".to_multiaddr().unwrap();
+    {
+        let not_seen_time =
+            faketime::unix_time() - Duration::from_secs(u64::from(ADDR_NOT_SEEN_TIMEOUT_SECS + 1));
+        let faketime_file = faketime::millis_tempfile(not_seen_time.as_secs() * 1000)
+            .expect("create faketime file");
+        faketime::enable(&faketime_file);
+        peer_store
+            .add_discovered_addresses(&peer_id, vec![addr1.clone(), addr2.clone()])
+            .expect("add discovered addresses");
+    }
+    // announcement refreshes last seen time
+    assert!(peer_store
+        .add_discovered_address(&peer_id, addr1.clone())
+        .is_err());
+    assert_eq!(peer_store.evict_stale_addrs(), 1);
+    assert_eq!(peer_store.peer_addrs(&peer_id, 2), Some(vec![addr1]));
+}
@@ -0,0 +1,46 @@
+use crate::random_peer_id;
+use crate::traffic_stats::{sum_message_stats, MessageStats, TrafficStats};
+
+#[test]
+fn test_record_traffic_stats() {
+    let mut traffic_stats = TrafficStats::default();
+    let peer1 = random_peer_id().unwrap();
+    let peer2 = random_peer_id().unwrap();
+    traffic_stats.record_sent(&peer1, *b"syn", "GetHeaders", 10);
+    traffic_stats.record_sent(&peer1, *b"syn", "GetHeaders", 20);
+    traffic_stats.record_received(&peer1, *b"syn", "Headers", 100);
+    traffic_stats.record_received(&peer2, *b"rel", "CompactBlock", 50);
+
+    let protocols = traffic_stats.protocols();
+    assert_eq!(protocols.len(), 3);
+    assert_eq!(
+        protocols[&(*b"syn", "GetHeaders")],
+        MessageStats {
+            sent_messages: 2,
+            sent_bytes: 30,
+            received_messages: 0,
+            received_bytes: 0,
+        }
+    );
+
+    let peer1_stats = sum_message_stats(traffic_stats.peer(&peer1).unwrap());
+    assert_eq!(
+        peer1_stats,
+        MessageStats {
+            sent_messages: 2,
+            sent_bytes: 30,
+            received_messages: 1,
+            received_bytes: 100,
+        }
+    );
+    assert_eq!(peer1_stats.total_bytes(), 130);
+
+    // protocol stats are kept after peer is disconnected
+    traffic_stats.retain_peers(|peer_id| peer_id != &peer2);
+    assert!(traffic_stats.peer(&peer2).is_none());
+    assert_eq!(traffic_stats.peers().count(), 1);
+    assert_eq!(
+        traffic_stats.protocols()[&(*b"rel", "CompactBlock")].received_bytes,
+        50
+    );
+}
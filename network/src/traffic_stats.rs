@@ -0,0 +1,119 @@
+use crate::ProtocolId;
+use fnv::FnvHashMap;
+use libp2p::core::PeerId;
+
+// used when the protocol handler can't tell the message type
+pub const UNKNOWN_MESSAGE_TYPE: &str = "unknown";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MessageStats {
+    pub sent_messages: u64,
+    pub sent_bytes: u64,
+    pub received_messages: u64,
+    pub received_bytes: u64,
+}
+
+impl MessageStats {
+    pub fn total_bytes(&self) -> u64 {
+        self.sent_bytes.saturating_add(self.received_bytes)
+    }
+
+    fn add(&mut self, other: &MessageStats) {
+        self.sent_messages = self.sent_messages.saturating_add(other.sent_messages);
+        self.sent_bytes = self.sent_bytes.saturating_add(other.sent_bytes);
+        self.received_messages = self
+            .received_messages
+            .saturating_add(other.received_messages);
+        self.received_bytes = self.received_bytes.saturating_add(other.received_bytes);
+    }
+}
+
+// message stats grouped by protocol and message type
+pub type MessageStatsMap = FnvHashMap<(ProtocolId, &'static str), MessageStats>;
+
+pub fn sum_message_stats(stats: &MessageStatsMap) -> MessageStats {
+    stats
+        .values()
+        .fold(MessageStats::default(), |mut sum, stats| {
+            sum.add(stats);
+            sum
+        })
+}
+
+// Counts messages and bytes of ckb protocols in each direction.
+// Protocol stats are kept for the whole process lifetime,
+// peer stats are only kept while the peer is connected.
+#[derive(Debug, Clone, Default)]
+pub struct TrafficStats {
+    protocols: MessageStatsMap,
+    peers: FnvHashMap<PeerId, MessageStatsMap>,
+}
+
+impl TrafficStats {
+    pub fn record_sent(
+        &mut self,
+        peer_id: &PeerId,
+        protocol_id: ProtocolId,
+        message_type: &'static str,
+        bytes: usize,
+    ) {
+        let delta = MessageStats {
+            sent_messages: 1,
+            sent_bytes: bytes as u64,
+            ..Default::default()
+        };
+        self.record(peer_id, protocol_id, message_type, &delta);
+    }
+
+    pub fn record_received(
+        &mut self,
+        peer_id: &PeerId,
+        protocol_id: ProtocolId,
+        message_type: &'static str,
+        bytes: usize,
+    ) {
+        let delta = MessageStats {
+            received_messages: 1,
+            received_bytes: bytes as u64,
+            ..Default::default()
+        };
+        self.record(peer_id, protocol_id, message_type, &delta);
+    }
+
+    fn record(
+        &mut self,
+        peer_id: &PeerId,
+        protocol_id: ProtocolId,
+        message_type: &'static str,
+        delta: &MessageStats,
+    ) {
+        let key = (protocol_id, message_type);
+        self.protocols.entry(key).or_default().add(delta);
+        self.peers
+            .entry(peer_id.to_owned())
+            .or_default()
+            .entry(key)
+            .or_default()
+            .add(delta);
+    }
+
+    pub fn protocols(&self) -> &MessageStatsMap {
+        &self.protocols
+    }
+
+    pub fn peers(&self) -> impl Iterator<Item = (&PeerId, &MessageStatsMap)> {
+        self.peers.iter()
+    }
+
+    pub fn peer(&self, peer_id: &PeerId) -> Option<&MessageStatsMap> {
+        self.peers.get(peer_id)
+    }
+
+    // drop stats of peers which are no longer connected
+    pub fn retain_peers<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&PeerId) -> bool,
+    {
+        self.peers.retain(|peer_id, _| f(peer_id));
+    }
+}
@@ -0,0 +1,126 @@
+use crate::protocol::Protocol;
+use crate::protocol_service::ProtocolService;
+use crate::traffic_stats::sum_message_stats;
+use crate::transport::TransportOutput;
+use crate::Network;
+use crate::PeerId;
+use futures::future::{self, Future};
+use futures::Stream;
+use libp2p::core::Multiaddr;
+use libp2p::core::MuxedTransport;
+use libp2p::core::SwarmController;
+use log::{info, warn};
+use std::boxed::Box;
+use std::io::{Error as IoError, ErrorKind as IoErrorKind};
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::timer::Interval;
+
+// number of most chatty peers in the log
+const LOG_TOP_PEERS: usize = 5;
+
+pub struct TrafficStatsService {
+    pub log_interval: Duration,
+}
+
+impl TrafficStatsService {
+    fn log_traffic_stats(network: &Network) {
+        let traffic_stats = network.traffic_stats();
+        let mut protocols = traffic_stats.protocols().iter().collect::<Vec<_>>();
+        protocols.sort_by_key(|((protocol_id, message_type), _)| (*protocol_id, *message_type));
+        for ((protocol_id, message_type), stats) in protocols {
+            info!(
+                target: "network",
+                "traffic protocol {} message {}: sent {} msgs {} bytes, received {} msgs {} bytes",
+                String::from_utf8_lossy(protocol_id),
+                message_type,
+                stats.sent_messages,
+                stats.sent_bytes,
+                stats.received_messages,
+                stats.received_bytes
+            );
+        }
+
+        let mut peers = traffic_stats
+            .peers()
+            .map(|(peer_id, stats)| (peer_id, sum_message_stats(stats)))
+            .collect::<Vec<_>>();
+        peers.sort_by(|(_, a), (_, b)| b.total_bytes().cmp(&a.total_bytes()));
+        for (peer_id, stats) in peers.into_iter().take(LOG_TOP_PEERS) {
+            info!(
+                target: "network",
+                "traffic peer {:?}: sent {} msgs {} bytes, received {} msgs {} bytes",
+                peer_id,
+                stats.sent_messages,
+                stats.sent_bytes,
+                stats.received_messages,
+                stats.received_bytes
+            );
+        }
+    }
+}
+
+impl<T: Send + 'static> ProtocolService<T> for TrafficStatsService {
+    type Output = ();
+    fn convert_to_protocol(
+        _peer_id: Arc<PeerId>,
+        _addr: &Multiaddr,
+        _output: Self::Output,
+    ) -> Protocol<T> {
+        unreachable!()
+    }
+    fn handle(
+        &self,
+        _network: Arc<Network>,
+        _protocol: Protocol<T>,
+    ) -> Box<Future<Item = (), Error = IoError> + Send> {
+        unreachable!()
+    }
+
+    // Periodicly log traffic stats
+    fn start_protocol<SwarmTran, Tran, TranOut>(
+        &self,
+        network: Arc<Network>,
+        _swarm_controller: SwarmController<
+            SwarmTran,
+            Box<Future<Item = (), Error = IoError> + Send>,
+        >,
+        _transport: Tran,
+    ) -> Box<Future<Item = (), Error = IoError> + Send>
+    where
+        SwarmTran: MuxedTransport<Output = Protocol<T>> + Clone + Send + 'static,
+        SwarmTran::MultiaddrFuture: Send + 'static,
+        SwarmTran::Dial: Send,
+        SwarmTran::Listener: Send,
+        SwarmTran::ListenerUpgrade: Send,
+        SwarmTran::Incoming: Send,
+        SwarmTran::IncomingUpgrade: Send,
+        Tran: MuxedTransport<Output = TransportOutput<TranOut>> + Clone + Send + 'static,
+        Tran::MultiaddrFuture: Send + 'static,
+        Tran::Dial: Send,
+        Tran::Listener: Send,
+        Tran::ListenerUpgrade: Send,
+        Tran::Incoming: Send,
+        Tran::IncomingUpgrade: Send,
+        TranOut: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        let log_future = Interval::new(Instant::now() + self.log_interval, self.log_interval)
+            .map_err(|err| {
+                IoError::new(
+                    IoErrorKind::Other,
+                    format!("traffic stats service error {:?}", err),
+                )
+            })
+            .for_each(move |_| {
+                Self::log_traffic_stats(&network);
+                future::ok(())
+            })
+            .then(|err| {
+                warn!(target: "network", "Traffic stats service stopped, reason: {:?}", err);
+                err
+            });
+        Box::new(log_future) as Box<Future<Item = _, Error = _> + Send>
+    }
+}
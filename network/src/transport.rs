@@ -1,5 +1,8 @@
+use crate::memory_transport::MemoryTransport;
+use crate::network_config::TransportType;
 use libp2p::core::{transport::BoxedMuxed, upgrade, Multiaddr, PeerId, Transport};
 use libp2p::{self, mplex, secio, yamux, TransportTimeout};
+use std::sync::Arc;
 use std::time::Duration;
 use std::usize;
 use tokio::io::{AsyncRead, AsyncWrite};
@@ -7,12 +10,26 @@ use tokio::io::{AsyncRead, AsyncWrite};
 pub fn new_transport(
     local_private_key: secio::SecioKeyPair,
     timeout: Duration,
+    transport_type: &TransportType,
 ) -> BoxedMuxed<(PeerId, impl AsyncRead + AsyncWrite)> {
     let mut mplex_config = mplex::MplexConfig::new();
     mplex_config.max_buffer_len_behaviour(mplex::MaxBufferBehaviour::Block);
     mplex_config.max_buffer_len(usize::MAX);
 
-    let transport = libp2p::CommonTransport::new()
+    // memory transport takes all addresses when it's enabled, otherwise tcp is used,
+    // addresses the memory transport can't listen on are errors instead of tcp listens
+    let (memory_transport, tcp_transport) = match transport_type {
+        TransportType::Tcp => (
+            MemoryTransport::default(),
+            OptionalTransport(Some(libp2p::CommonTransport::new())),
+        ),
+        TransportType::Memory { network, node } => (
+            MemoryTransport::new(Arc::clone(network), node),
+            OptionalTransport(None),
+        ),
+    };
+    let transport = memory_transport
+        .or_transport(tcp_transport)
         .with_upgrade(secio::SecioConfig {
             key: local_private_key,
         })
@@ -29,6 +46,42 @@ pub fn new_transport(
     transport.boxed_muxed()
 }
 
+// Transport which rejects all addresses when disabled
+#[derive(Clone)]
+struct OptionalTransport<T>(Option<T>);
+
+impl<T: Transport> Transport for OptionalTransport<T> {
+    type Output = T::Output;
+    type MultiaddrFuture = T::MultiaddrFuture;
+    type Listener = T::Listener;
+    type ListenerUpgrade = T::ListenerUpgrade;
+    type Dial = T::Dial;
+
+    fn listen_on(self, addr: Multiaddr) -> Result<(Self::Listener, Multiaddr), (Self, Multiaddr)> {
+        match self.0 {
+            Some(inner) => inner
+                .listen_on(addr)
+                .map_err(|(inner, addr)| (OptionalTransport(Some(inner)), addr)),
+            None => Err((OptionalTransport(None), addr)),
+        }
+    }
+
+    fn dial(self, addr: Multiaddr) -> Result<Self::Dial, (Self, Multiaddr)> {
+        match self.0 {
+            Some(inner) => inner
+                .dial(addr)
+                .map_err(|(inner, addr)| (OptionalTransport(Some(inner)), addr)),
+            None => Err((OptionalTransport(None), addr)),
+        }
+    }
+
+    fn nat_traversal(&self, server: &Multiaddr, observed: &Multiaddr) -> Option<Multiaddr> {
+        self.0
+            .as_ref()
+            .and_then(|inner| inner.nat_traversal(server, observed))
+    }
+}
+
 pub struct TransportOutput<S> {
     pub socket: S,
     pub peer_id: PeerId,
@@ -0,0 +1,43 @@
+use crate::network_group::MultiaddrExt;
+use crate::peer_store::IpSubnet;
+use crate::{Error, ErrorKind, PeerId};
+use fnv::FnvHashSet;
+use libp2p::core::Multiaddr;
+
+// Whitelisted peers are never banned or evicted, skip rate limits,
+// always get transactions relayed and may exceed the inbound peers limit.
+#[derive(Debug, Clone, Default)]
+pub struct Whitelist {
+    peer_ids: FnvHashSet<PeerId>,
+    subnets: Vec<IpSubnet>,
+}
+
+impl Whitelist {
+    // parse entries like "QmXXX", "192.168.0.1" or "192.168.0.0/16"
+    pub fn parse(entries: &[String]) -> Result<Self, Error> {
+        let mut whitelist = Whitelist::default();
+        for entry in entries {
+            if let Ok(subnet) = entry.parse::<IpSubnet>() {
+                whitelist.subnets.push(subnet);
+            } else {
+                let peer_id = PeerId::from_base58(entry).map_err(|_| ErrorKind::ParseAddress)?;
+                whitelist.peer_ids.insert(peer_id);
+            }
+        }
+        Ok(whitelist)
+    }
+
+    pub fn contains_peer_id(&self, peer_id: &PeerId) -> bool {
+        self.peer_ids.contains(peer_id)
+    }
+
+    pub fn contains(&self, peer_id: &PeerId, addr: &Multiaddr) -> bool {
+        if self.contains_peer_id(peer_id) {
+            return true;
+        }
+        match addr.extract_ip_addr() {
+            Some(ip) => self.subnets.iter().any(|subnet| subnet.contains(ip)),
+            None => false,
+        }
+    }
+}
@@ -2,6 +2,7 @@
 
 use ckb_core::block::Block;
 use ckb_core::service::Request;
+use ckb_core::uncle::UncleBlock;
 use crossbeam_channel::{select, Receiver, Sender};
 use fnv::FnvHashMap;
 use log::{debug, trace, warn};
@@ -39,7 +40,7 @@ impl ForkBlocks {
 
 pub type MsgNewTransaction = ();
 pub type MsgNewTip = Arc<Block>;
-pub type MsgNewUncle = Arc<Block>;
+pub type MsgNewUncle = Arc<UncleBlock>;
 pub type MsgSwitchFork = Arc<ForkBlocks>;
 pub type NotifyRegister<M> = Sender<Request<(String, usize), Receiver<M>>>;
 
@@ -575,9 +575,16 @@ where
                 // TODO: Parallel
 
                 let mut txs_cache = self.shared.txs_verify_cache().write();
-                let cycles = self
-                    .verify_transaction(&rtx, &mut txs_cache)
-                    .map_err(PoolError::InvalidTx)?;
+                let cycles = match self.verify_transaction(&rtx, &mut txs_cache) {
+                    Ok(cycles) => cycles,
+                    Err(e) => {
+                        if self.config.trace_enable() {
+                            self.trace
+                                .invalid(&tx.hash(), format!("verify failed {:?}", e));
+                        }
+                        return Err(PoolError::InvalidTx(e));
+                    }
+                };
                 pe.cycles = Some(cycles);
             }
         }
@@ -12,6 +12,7 @@ pub(crate) enum Action {
     Timeout,
     AddOrphan,
     Committed,
+    Invalid,
 }
 
 #[derive(Clone, Eq, PartialEq, Serialize, Hash)]
@@ -81,6 +82,21 @@ impl TxTraceMap {
             ));
     }
 
+    /// Invalid transactions are not in the pool, so the entry is created
+    /// like `add_pending`, info contains the verification error, including
+    /// the debug output of the failed script.
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn invalid<S: ToString>(&mut self, hash: &H256, info: S) {
+        self.inner
+            .entry(hash.clone())
+            .or_insert_with(Vec::new)
+            .push(TxTrace::new(
+                Action::Invalid,
+                info.to_string(),
+                unix_time_as_millis(),
+            ));
+    }
+
     pub fn get(&self, hash: &H256) -> Option<&Vec<TxTrace>> {
         self.inner.get(hash)
     }
@@ -112,6 +128,7 @@ mod tests {
         map.add_orphan(&tx_hash, "add_orphan");
         map.timeout(&tx_hash, "timeout");
         map.committed(&tx_hash, "committed");
+        map.invalid(&tx_hash, "invalid");
 
         let traces = map.get(&tx_hash);
 
@@ -124,7 +141,8 @@ mod tests {
                 "{ action: AddCommit, info: add_commit, time: 9102 }, ",
                 "{ action: AddOrphan, info: add_orphan, time: 9102 }, ",
                 "{ action: Timeout, info: timeout, time: 9102 }, ",
-                "{ action: Committed, info: committed, time: 9102 }",
+                "{ action: Committed, info: committed, time: 9102 }, ",
+                "{ action: Invalid, info: invalid, time: 9102 }",
                 "])"
             ),
         );
@@ -4,12 +4,15 @@ use crate::protocol_generated::ckb::protocol::{
     CellOutput as FbsCellOutput, CellOutputBuilder, CompactBlock, CompactBlockBuilder,
     FilteredBlock, FilteredBlockBuilder, GetBlockProposalBuilder, GetBlockTransactionsBuilder,
     GetBlocks as FbsGetBlocks, GetBlocksBuilder, GetHeaders as FbsGetHeaders, GetHeadersBuilder,
+    GetRelayTransactionsBuilder, GetSnapshotChunkBuilder, GetUncleBlockBuilder,
     Header as FbsHeader, HeaderBuilder, Headers as FbsHeaders, HeadersBuilder,
     IndexTransactionBuilder, MerkleProofBuilder, OutPoint as FbsOutPoint, OutPointBuilder,
     ProposalShortId as FbsProposalShortId, RelayMessage, RelayMessageBuilder, RelayPayload,
-    Script as FbsScript, ScriptBuilder, SyncMessage, SyncMessageBuilder, SyncPayload,
-    Time as FbsTime, TimeBuilder, TimeMessage, TimeMessageBuilder, Transaction as FbsTransaction,
-    TransactionBuilder, UncleBlock as FbsUncleBlock, UncleBlockBuilder, H256 as FbsH256,
+    RelayTransactionHashesBuilder, Script as FbsScript, ScriptBuilder, SetCompactBlockModeBuilder,
+    SnapshotChunkBuilder, SyncMessage, SyncMessageBuilder, SyncPayload, Time as FbsTime,
+    TimeBuilder, TimeMessage, TimeMessageBuilder, Transaction as FbsTransaction,
+    TransactionBuilder, UncleAnnouncementBuilder, UncleBlock as FbsUncleBlock, UncleBlockBuilder,
+    H256 as FbsH256,
 };
 use crate::{short_transaction_id, short_transaction_id_keys};
 use ckb_core::block::Block;
@@ -356,6 +359,55 @@ impl<'a> SyncMessage<'a> {
         builder.add_payload(filtered_block.as_union_value());
         builder.finish()
     }
+
+    pub fn build_get_snapshot_chunk<'b>(
+        fbb: &mut FlatBufferBuilder<'b>,
+        block_hash: &H256,
+        index: u32,
+    ) -> WIPOffset<SyncMessage<'b>> {
+        let get_snapshot_chunk = {
+            let block_hash = block_hash.into();
+            let mut builder = GetSnapshotChunkBuilder::new(fbb);
+            builder.add_block_hash(&block_hash);
+            builder.add_index(index);
+            builder.finish()
+        };
+
+        let mut builder = SyncMessageBuilder::new(fbb);
+        builder.add_payload_type(SyncPayload::GetSnapshotChunk);
+        builder.add_payload(get_snapshot_chunk.as_union_value());
+        builder.finish()
+    }
+
+    pub fn build_snapshot_chunk<'b>(
+        fbb: &mut FlatBufferBuilder<'b>,
+        block_hash: &H256,
+        index: u32,
+        chunk_hashes: &[H256],
+        data: &[u8],
+    ) -> WIPOffset<SyncMessage<'b>> {
+        let snapshot_chunk = {
+            let block_hash = block_hash.into();
+            let chunk_hashes = fbb.create_vector(
+                &chunk_hashes
+                    .iter()
+                    .map(Into::into)
+                    .collect::<Vec<FbsH256>>(),
+            );
+            let data = fbb.create_vector(data);
+            let mut builder = SnapshotChunkBuilder::new(fbb);
+            builder.add_block_hash(&block_hash);
+            builder.add_index(index);
+            builder.add_chunk_hashes(chunk_hashes);
+            builder.add_data(data);
+            builder.finish()
+        };
+
+        let mut builder = SyncMessageBuilder::new(fbb);
+        builder.add_payload_type(SyncPayload::SnapshotChunk);
+        builder.add_payload(snapshot_chunk.as_union_value());
+        builder.finish()
+    }
 }
 
 impl<'a> FilteredBlock<'a> {
@@ -585,6 +637,103 @@ impl<'a> RelayMessage<'a> {
         builder.add_payload(block_proposal.as_union_value());
         builder.finish()
     }
+
+    pub fn build_relay_transaction_hashes<'b>(
+        fbb: &mut FlatBufferBuilder<'b>,
+        tx_hashes: &[H256],
+    ) -> WIPOffset<RelayMessage<'b>> {
+        let relay_transaction_hashes = {
+            let vec = tx_hashes.iter().map(Into::into).collect::<Vec<FbsH256>>();
+            let tx_hashes = fbb.create_vector(&vec);
+            let mut builder = RelayTransactionHashesBuilder::new(fbb);
+            builder.add_tx_hashes(tx_hashes);
+            builder.finish()
+        };
+
+        let mut builder = RelayMessageBuilder::new(fbb);
+        builder.add_payload_type(RelayPayload::RelayTransactionHashes);
+        builder.add_payload(relay_transaction_hashes.as_union_value());
+        builder.finish()
+    }
+
+    pub fn build_get_relay_transactions<'b>(
+        fbb: &mut FlatBufferBuilder<'b>,
+        tx_hashes: &[H256],
+    ) -> WIPOffset<RelayMessage<'b>> {
+        let get_relay_transactions = {
+            let vec = tx_hashes.iter().map(Into::into).collect::<Vec<FbsH256>>();
+            let tx_hashes = fbb.create_vector(&vec);
+            let mut builder = GetRelayTransactionsBuilder::new(fbb);
+            builder.add_tx_hashes(tx_hashes);
+            builder.finish()
+        };
+
+        let mut builder = RelayMessageBuilder::new(fbb);
+        builder.add_payload_type(RelayPayload::GetRelayTransactions);
+        builder.add_payload(get_relay_transactions.as_union_value());
+        builder.finish()
+    }
+
+    pub fn build_set_compact_block_mode<'b>(
+        fbb: &mut FlatBufferBuilder<'b>,
+        high_bandwidth: bool,
+    ) -> WIPOffset<RelayMessage<'b>> {
+        let set_compact_block_mode = {
+            let mut builder = SetCompactBlockModeBuilder::new(fbb);
+            builder.add_high_bandwidth(high_bandwidth);
+            builder.finish()
+        };
+
+        let mut builder = RelayMessageBuilder::new(fbb);
+        builder.add_payload_type(RelayPayload::SetCompactBlockMode);
+        builder.add_payload(set_compact_block_mode.as_union_value());
+        builder.finish()
+    }
+
+    pub fn build_uncle_announcement<'b>(
+        fbb: &mut FlatBufferBuilder<'b>,
+        block_hash: &H256,
+    ) -> WIPOffset<RelayMessage<'b>> {
+        let uncle_announcement = {
+            let block_hash = block_hash.into();
+            let mut builder = UncleAnnouncementBuilder::new(fbb);
+            builder.add_block_hash(&block_hash);
+            builder.finish()
+        };
+
+        let mut builder = RelayMessageBuilder::new(fbb);
+        builder.add_payload_type(RelayPayload::UncleAnnouncement);
+        builder.add_payload(uncle_announcement.as_union_value());
+        builder.finish()
+    }
+
+    pub fn build_get_uncle_block<'b>(
+        fbb: &mut FlatBufferBuilder<'b>,
+        block_hash: &H256,
+    ) -> WIPOffset<RelayMessage<'b>> {
+        let get_uncle_block = {
+            let block_hash = block_hash.into();
+            let mut builder = GetUncleBlockBuilder::new(fbb);
+            builder.add_block_hash(&block_hash);
+            builder.finish()
+        };
+
+        let mut builder = RelayMessageBuilder::new(fbb);
+        builder.add_payload_type(RelayPayload::GetUncleBlock);
+        builder.add_payload(get_uncle_block.as_union_value());
+        builder.finish()
+    }
+
+    pub fn build_uncle_block<'b>(
+        fbb: &mut FlatBufferBuilder<'b>,
+        uncle: &UncleBlock,
+    ) -> WIPOffset<RelayMessage<'b>> {
+        let fbs_uncle = FbsUncleBlock::build(fbb, uncle);
+        let mut builder = RelayMessageBuilder::new(fbb);
+        builder.add_payload_type(RelayPayload::UncleBlock);
+        builder.add_payload(fbs_uncle.as_union_value());
+        builder.finish()
+    }
 }
 
 impl<'a> TimeMessage<'a> {
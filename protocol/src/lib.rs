@@ -3,8 +3,10 @@ mod convert;
 #[rustfmt::skip]
 #[allow(clippy::all)]
 mod protocol_generated;
+mod verifier;
 
 pub use crate::protocol_generated::ckb::protocol::*;
+pub use crate::verifier::{verify_relay_message, verify_sync_message, verify_time_message};
 use byteorder::{ByteOrder, LittleEndian, WriteBytesExt};
 use hash::sha3_256;
 use numext_fixed_hash::H256;
@@ -37,11 +37,13 @@ pub enum SyncPayload {
   AddFilter = 6,
   ClearFilter = 7,
   FilteredBlock = 8,
+  GetSnapshotChunk = 9,
+  SnapshotChunk = 10,
 
 }
 
 const ENUM_MIN_SYNC_PAYLOAD: u8 = 0;
-const ENUM_MAX_SYNC_PAYLOAD: u8 = 8;
+const ENUM_MAX_SYNC_PAYLOAD: u8 = 10;
 
 impl<'a> flatbuffers::Follow<'a> for SyncPayload {
   type Inner = Self;
@@ -75,7 +77,7 @@ impl flatbuffers::Push for SyncPayload {
 }
 
 #[allow(non_camel_case_types)]
-const ENUM_VALUES_SYNC_PAYLOAD:[SyncPayload; 9] = [
+const ENUM_VALUES_SYNC_PAYLOAD:[SyncPayload; 11] = [
   SyncPayload::NONE,
   SyncPayload::GetHeaders,
   SyncPayload::Headers,
@@ -84,11 +86,13 @@ const ENUM_VALUES_SYNC_PAYLOAD:[SyncPayload; 9] = [
   SyncPayload::SetFilter,
   SyncPayload::AddFilter,
   SyncPayload::ClearFilter,
-  SyncPayload::FilteredBlock
+  SyncPayload::FilteredBlock,
+  SyncPayload::GetSnapshotChunk,
+  SyncPayload::SnapshotChunk
 ];
 
 #[allow(non_camel_case_types)]
-const ENUM_NAMES_SYNC_PAYLOAD:[&'static str; 9] = [
+const ENUM_NAMES_SYNC_PAYLOAD:[&'static str; 11] = [
     "NONE",
     "GetHeaders",
     "Headers",
@@ -97,7 +101,9 @@ const ENUM_NAMES_SYNC_PAYLOAD:[&'static str; 9] = [
     "SetFilter",
     "AddFilter",
     "ClearFilter",
-    "FilteredBlock"
+    "FilteredBlock",
+    "GetSnapshotChunk",
+    "SnapshotChunk"
 ];
 
 pub fn enum_name_sync_payload(e: SyncPayload) -> &'static str {
@@ -117,11 +123,17 @@ pub enum RelayPayload {
   BlockTransactions = 4,
   GetBlockProposal = 5,
   BlockProposal = 6,
+  RelayTransactionHashes = 7,
+  GetRelayTransactions = 8,
+  SetCompactBlockMode = 9,
+  UncleAnnouncement = 10,
+  GetUncleBlock = 11,
+  UncleBlock = 12,
 
 }
 
 const ENUM_MIN_RELAY_PAYLOAD: u8 = 0;
-const ENUM_MAX_RELAY_PAYLOAD: u8 = 6;
+const ENUM_MAX_RELAY_PAYLOAD: u8 = 12;
 
 impl<'a> flatbuffers::Follow<'a> for RelayPayload {
   type Inner = Self;
@@ -155,25 +167,37 @@ impl flatbuffers::Push for RelayPayload {
 }
 
 #[allow(non_camel_case_types)]
-const ENUM_VALUES_RELAY_PAYLOAD:[RelayPayload; 7] = [
+const ENUM_VALUES_RELAY_PAYLOAD:[RelayPayload; 13] = [
   RelayPayload::NONE,
   RelayPayload::CompactBlock,
   RelayPayload::Transaction,
   RelayPayload::GetBlockTransactions,
   RelayPayload::BlockTransactions,
   RelayPayload::GetBlockProposal,
-  RelayPayload::BlockProposal
+  RelayPayload::BlockProposal,
+  RelayPayload::RelayTransactionHashes,
+  RelayPayload::GetRelayTransactions,
+  RelayPayload::SetCompactBlockMode,
+  RelayPayload::UncleAnnouncement,
+  RelayPayload::GetUncleBlock,
+  RelayPayload::UncleBlock
 ];
 
 #[allow(non_camel_case_types)]
-const ENUM_NAMES_RELAY_PAYLOAD:[&'static str; 7] = [
+const ENUM_NAMES_RELAY_PAYLOAD:[&'static str; 13] = [
     "NONE",
     "CompactBlock",
     "Transaction",
     "GetBlockTransactions",
     "BlockTransactions",
     "GetBlockProposal",
-    "BlockProposal"
+    "BlockProposal",
+    "RelayTransactionHashes",
+    "GetRelayTransactions",
+    "SetCompactBlockMode",
+    "UncleAnnouncement",
+    "GetUncleBlock",
+    "UncleBlock"
 ];
 
 pub fn enum_name_relay_payload(e: RelayPayload) -> &'static str {
@@ -619,6 +643,26 @@ impl<'a> SyncMessage<'a> {
     }
   }
 
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn payload_as_get_snapshot_chunk(&'a self) -> Option<GetSnapshotChunk> {
+    if self.payload_type() == SyncPayload::GetSnapshotChunk {
+      self.payload().map(|u| GetSnapshotChunk::init_from_table(u))
+    } else {
+      None
+    }
+  }
+
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn payload_as_snapshot_chunk(&'a self) -> Option<SnapshotChunk> {
+    if self.payload_type() == SyncPayload::SnapshotChunk {
+      self.payload().map(|u| SnapshotChunk::init_from_table(u))
+    } else {
+      None
+    }
+  }
+
 }
 
 pub struct SyncMessageArgs {
@@ -2051,6 +2095,66 @@ impl<'a> RelayMessage<'a> {
     }
   }
 
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn payload_as_relay_transaction_hashes(&'a self) -> Option<RelayTransactionHashes> {
+    if self.payload_type() == RelayPayload::RelayTransactionHashes {
+      self.payload().map(|u| RelayTransactionHashes::init_from_table(u))
+    } else {
+      None
+    }
+  }
+
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn payload_as_get_relay_transactions(&'a self) -> Option<GetRelayTransactions> {
+    if self.payload_type() == RelayPayload::GetRelayTransactions {
+      self.payload().map(|u| GetRelayTransactions::init_from_table(u))
+    } else {
+      None
+    }
+  }
+
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn payload_as_set_compact_block_mode(&'a self) -> Option<SetCompactBlockMode> {
+    if self.payload_type() == RelayPayload::SetCompactBlockMode {
+      self.payload().map(|u| SetCompactBlockMode::init_from_table(u))
+    } else {
+      None
+    }
+  }
+
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn payload_as_uncle_announcement(&'a self) -> Option<UncleAnnouncement> {
+    if self.payload_type() == RelayPayload::UncleAnnouncement {
+      self.payload().map(|u| UncleAnnouncement::init_from_table(u))
+    } else {
+      None
+    }
+  }
+
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn payload_as_get_uncle_block(&'a self) -> Option<GetUncleBlock> {
+    if self.payload_type() == RelayPayload::GetUncleBlock {
+      self.payload().map(|u| GetUncleBlock::init_from_table(u))
+    } else {
+      None
+    }
+  }
+
+  #[inline]
+  #[allow(non_snake_case)]
+  pub fn payload_as_uncle_block(&'a self) -> Option<UncleBlock> {
+    if self.payload_type() == RelayPayload::UncleBlock {
+      self.payload().map(|u| UncleBlock::init_from_table(u))
+    } else {
+      None
+    }
+  }
+
 }
 
 pub struct RelayMessageArgs {
@@ -2658,6 +2762,386 @@ impl<'a: 'b, 'b> BlockProposalBuilder<'a, 'b> {
   }
 }
 
+pub enum RelayTransactionHashesOffset {}
+#[derive(Copy, Clone, Debug, PartialEq)]
+
+pub struct RelayTransactionHashes<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for RelayTransactionHashes<'a> {
+    type Inner = RelayTransactionHashes<'a>;
+    #[inline]
+    fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+        Self {
+            _tab: flatbuffers::Table { buf: buf, loc: loc },
+        }
+    }
+}
+
+impl<'a> RelayTransactionHashes<'a> {
+    #[inline]
+    pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+        RelayTransactionHashes {
+            _tab: table,
+        }
+    }
+    #[allow(unused_mut)]
+    pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
+        _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
+        args: &'args RelayTransactionHashesArgs<'args>) -> flatbuffers::WIPOffset<RelayTransactionHashes<'bldr>> {
+      let mut builder = RelayTransactionHashesBuilder::new(_fbb);
+      if let Some(x) = args.tx_hashes { builder.add_tx_hashes(x); }
+      builder.finish()
+    }
+
+    pub const VT_TX_HASHES: flatbuffers::VOffsetT = 4;
+
+  #[inline]
+  pub fn tx_hashes(&self) -> Option<&'a [H256]> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<H256>>>(RelayTransactionHashes::VT_TX_HASHES, None).map(|v| v.safe_slice() )
+  }
+}
+
+pub struct RelayTransactionHashesArgs<'a> {
+    pub tx_hashes: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a , H256>>>,
+}
+impl<'a> Default for RelayTransactionHashesArgs<'a> {
+    #[inline]
+    fn default() -> Self {
+        RelayTransactionHashesArgs {
+            tx_hashes: None,
+        }
+    }
+}
+pub struct RelayTransactionHashesBuilder<'a: 'b, 'b> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b> RelayTransactionHashesBuilder<'a, 'b> {
+  #[inline]
+  pub fn add_tx_hashes(&mut self, tx_hashes: flatbuffers::WIPOffset<flatbuffers::Vector<'b , H256>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(RelayTransactionHashes::VT_TX_HASHES, tx_hashes);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> RelayTransactionHashesBuilder<'a, 'b> {
+    let start = _fbb.start_table();
+    RelayTransactionHashesBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<RelayTransactionHashes<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
+pub enum GetRelayTransactionsOffset {}
+#[derive(Copy, Clone, Debug, PartialEq)]
+
+pub struct GetRelayTransactions<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for GetRelayTransactions<'a> {
+    type Inner = GetRelayTransactions<'a>;
+    #[inline]
+    fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+        Self {
+            _tab: flatbuffers::Table { buf: buf, loc: loc },
+        }
+    }
+}
+
+impl<'a> GetRelayTransactions<'a> {
+    #[inline]
+    pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+        GetRelayTransactions {
+            _tab: table,
+        }
+    }
+    #[allow(unused_mut)]
+    pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
+        _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
+        args: &'args GetRelayTransactionsArgs<'args>) -> flatbuffers::WIPOffset<GetRelayTransactions<'bldr>> {
+      let mut builder = GetRelayTransactionsBuilder::new(_fbb);
+      if let Some(x) = args.tx_hashes { builder.add_tx_hashes(x); }
+      builder.finish()
+    }
+
+    pub const VT_TX_HASHES: flatbuffers::VOffsetT = 4;
+
+  #[inline]
+  pub fn tx_hashes(&self) -> Option<&'a [H256]> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<H256>>>(GetRelayTransactions::VT_TX_HASHES, None).map(|v| v.safe_slice() )
+  }
+}
+
+pub struct GetRelayTransactionsArgs<'a> {
+    pub tx_hashes: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a , H256>>>,
+}
+impl<'a> Default for GetRelayTransactionsArgs<'a> {
+    #[inline]
+    fn default() -> Self {
+        GetRelayTransactionsArgs {
+            tx_hashes: None,
+        }
+    }
+}
+pub struct GetRelayTransactionsBuilder<'a: 'b, 'b> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b> GetRelayTransactionsBuilder<'a, 'b> {
+  #[inline]
+  pub fn add_tx_hashes(&mut self, tx_hashes: flatbuffers::WIPOffset<flatbuffers::Vector<'b , H256>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(GetRelayTransactions::VT_TX_HASHES, tx_hashes);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> GetRelayTransactionsBuilder<'a, 'b> {
+    let start = _fbb.start_table();
+    GetRelayTransactionsBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<GetRelayTransactions<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
+pub enum SetCompactBlockModeOffset {}
+#[derive(Copy, Clone, Debug, PartialEq)]
+
+pub struct SetCompactBlockMode<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for SetCompactBlockMode<'a> {
+    type Inner = SetCompactBlockMode<'a>;
+    #[inline]
+    fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+        Self {
+            _tab: flatbuffers::Table { buf: buf, loc: loc },
+        }
+    }
+}
+
+impl<'a> SetCompactBlockMode<'a> {
+    #[inline]
+    pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+        SetCompactBlockMode {
+            _tab: table,
+        }
+    }
+    #[allow(unused_mut)]
+    pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
+        _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
+        args: &'args SetCompactBlockModeArgs) -> flatbuffers::WIPOffset<SetCompactBlockMode<'bldr>> {
+      let mut builder = SetCompactBlockModeBuilder::new(_fbb);
+      builder.add_high_bandwidth(args.high_bandwidth);
+      builder.finish()
+    }
+
+    pub const VT_HIGH_BANDWIDTH: flatbuffers::VOffsetT = 4;
+
+  #[inline]
+  pub fn high_bandwidth(&self) -> bool {
+    self._tab.get::<bool>(SetCompactBlockMode::VT_HIGH_BANDWIDTH, Some(false)).unwrap()
+  }
+}
+
+pub struct SetCompactBlockModeArgs {
+    pub high_bandwidth: bool,
+}
+impl<'a> Default for SetCompactBlockModeArgs {
+    #[inline]
+    fn default() -> Self {
+        SetCompactBlockModeArgs {
+            high_bandwidth: false,
+        }
+    }
+}
+pub struct SetCompactBlockModeBuilder<'a: 'b, 'b> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b> SetCompactBlockModeBuilder<'a, 'b> {
+  #[inline]
+  pub fn add_high_bandwidth(&mut self, high_bandwidth: bool) {
+    self.fbb_.push_slot::<bool>(SetCompactBlockMode::VT_HIGH_BANDWIDTH, high_bandwidth, false);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> SetCompactBlockModeBuilder<'a, 'b> {
+    let start = _fbb.start_table();
+    SetCompactBlockModeBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<SetCompactBlockMode<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
+pub enum UncleAnnouncementOffset {}
+#[derive(Copy, Clone, Debug, PartialEq)]
+
+pub struct UncleAnnouncement<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for UncleAnnouncement<'a> {
+    type Inner = UncleAnnouncement<'a>;
+    #[inline]
+    fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+        Self {
+            _tab: flatbuffers::Table { buf: buf, loc: loc },
+        }
+    }
+}
+
+impl<'a> UncleAnnouncement<'a> {
+    #[inline]
+    pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+        UncleAnnouncement {
+            _tab: table,
+        }
+    }
+    #[allow(unused_mut)]
+    pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
+        _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
+        args: &'args UncleAnnouncementArgs<'args>) -> flatbuffers::WIPOffset<UncleAnnouncement<'bldr>> {
+      let mut builder = UncleAnnouncementBuilder::new(_fbb);
+      if let Some(x) = args.block_hash { builder.add_block_hash(x); }
+      builder.finish()
+    }
+
+    pub const VT_BLOCK_HASH: flatbuffers::VOffsetT = 4;
+
+  #[inline]
+  pub fn block_hash(&self) -> Option<&'a H256> {
+    self._tab.get::<H256>(UncleAnnouncement::VT_BLOCK_HASH, None)
+  }
+}
+
+pub struct UncleAnnouncementArgs<'a> {
+    pub block_hash: Option<&'a  H256>,
+}
+impl<'a> Default for UncleAnnouncementArgs<'a> {
+    #[inline]
+    fn default() -> Self {
+        UncleAnnouncementArgs {
+            block_hash: None,
+        }
+    }
+}
+pub struct UncleAnnouncementBuilder<'a: 'b, 'b> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b> UncleAnnouncementBuilder<'a, 'b> {
+  #[inline]
+  pub fn add_block_hash(&mut self, block_hash: &'b  H256) {
+    self.fbb_.push_slot_always::<&H256>(UncleAnnouncement::VT_BLOCK_HASH, block_hash);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> UncleAnnouncementBuilder<'a, 'b> {
+    let start = _fbb.start_table();
+    UncleAnnouncementBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<UncleAnnouncement<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
+pub enum GetUncleBlockOffset {}
+#[derive(Copy, Clone, Debug, PartialEq)]
+
+pub struct GetUncleBlock<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for GetUncleBlock<'a> {
+    type Inner = GetUncleBlock<'a>;
+    #[inline]
+    fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+        Self {
+            _tab: flatbuffers::Table { buf: buf, loc: loc },
+        }
+    }
+}
+
+impl<'a> GetUncleBlock<'a> {
+    #[inline]
+    pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+        GetUncleBlock {
+            _tab: table,
+        }
+    }
+    #[allow(unused_mut)]
+    pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
+        _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
+        args: &'args GetUncleBlockArgs<'args>) -> flatbuffers::WIPOffset<GetUncleBlock<'bldr>> {
+      let mut builder = GetUncleBlockBuilder::new(_fbb);
+      if let Some(x) = args.block_hash { builder.add_block_hash(x); }
+      builder.finish()
+    }
+
+    pub const VT_BLOCK_HASH: flatbuffers::VOffsetT = 4;
+
+  #[inline]
+  pub fn block_hash(&self) -> Option<&'a H256> {
+    self._tab.get::<H256>(GetUncleBlock::VT_BLOCK_HASH, None)
+  }
+}
+
+pub struct GetUncleBlockArgs<'a> {
+    pub block_hash: Option<&'a  H256>,
+}
+impl<'a> Default for GetUncleBlockArgs<'a> {
+    #[inline]
+    fn default() -> Self {
+        GetUncleBlockArgs {
+            block_hash: None,
+        }
+    }
+}
+pub struct GetUncleBlockBuilder<'a: 'b, 'b> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b> GetUncleBlockBuilder<'a, 'b> {
+  #[inline]
+  pub fn add_block_hash(&mut self, block_hash: &'b  H256) {
+    self.fbb_.push_slot_always::<&H256>(GetUncleBlock::VT_BLOCK_HASH, block_hash);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> GetUncleBlockBuilder<'a, 'b> {
+    let start = _fbb.start_table();
+    GetUncleBlockBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<GetUncleBlock<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
 pub enum SetFilterOffset {}
 #[derive(Copy, Clone, Debug, PartialEq)]
 
@@ -3085,6 +3569,206 @@ impl<'a: 'b, 'b> MerkleProofBuilder<'a, 'b> {
   }
 }
 
+pub enum GetSnapshotChunkOffset {}
+#[derive(Copy, Clone, Debug, PartialEq)]
+
+pub struct GetSnapshotChunk<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for GetSnapshotChunk<'a> {
+    type Inner = GetSnapshotChunk<'a>;
+    #[inline]
+    fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+        Self {
+            _tab: flatbuffers::Table { buf: buf, loc: loc },
+        }
+    }
+}
+
+impl<'a> GetSnapshotChunk<'a> {
+    #[inline]
+    pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+        GetSnapshotChunk {
+            _tab: table,
+        }
+    }
+    #[allow(unused_mut)]
+    pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
+        _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
+        args: &'args GetSnapshotChunkArgs<'args>) -> flatbuffers::WIPOffset<GetSnapshotChunk<'bldr>> {
+      let mut builder = GetSnapshotChunkBuilder::new(_fbb);
+      builder.add_index(args.index);
+      if let Some(x) = args.block_hash { builder.add_block_hash(x); }
+      builder.finish()
+    }
+
+    pub const VT_BLOCK_HASH: flatbuffers::VOffsetT = 4;
+    pub const VT_INDEX: flatbuffers::VOffsetT = 6;
+
+  #[inline]
+  pub fn block_hash(&self) -> Option<&'a H256> {
+    self._tab.get::<H256>(GetSnapshotChunk::VT_BLOCK_HASH, None)
+  }
+  #[inline]
+  pub fn index(&self) -> u32 {
+    self._tab.get::<u32>(GetSnapshotChunk::VT_INDEX, Some(0)).unwrap()
+  }
+}
+
+pub struct GetSnapshotChunkArgs<'a> {
+    pub block_hash: Option<&'a  H256>,
+    pub index: u32,
+}
+impl<'a> Default for GetSnapshotChunkArgs<'a> {
+    #[inline]
+    fn default() -> Self {
+        GetSnapshotChunkArgs {
+            block_hash: None,
+            index: 0,
+        }
+    }
+}
+pub struct GetSnapshotChunkBuilder<'a: 'b, 'b> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b> GetSnapshotChunkBuilder<'a, 'b> {
+  #[inline]
+  pub fn add_block_hash(&mut self, block_hash: &'b  H256) {
+    self.fbb_.push_slot_always::<&H256>(GetSnapshotChunk::VT_BLOCK_HASH, block_hash);
+  }
+  #[inline]
+  pub fn add_index(&mut self, index: u32) {
+    self.fbb_.push_slot::<u32>(GetSnapshotChunk::VT_INDEX, index, 0);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> GetSnapshotChunkBuilder<'a, 'b> {
+    let start = _fbb.start_table();
+    GetSnapshotChunkBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<GetSnapshotChunk<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
+pub enum SnapshotChunkOffset {}
+#[derive(Copy, Clone, Debug, PartialEq)]
+
+pub struct SnapshotChunk<'a> {
+  pub _tab: flatbuffers::Table<'a>,
+}
+
+impl<'a> flatbuffers::Follow<'a> for SnapshotChunk<'a> {
+    type Inner = SnapshotChunk<'a>;
+    #[inline]
+    fn follow(buf: &'a [u8], loc: usize) -> Self::Inner {
+        Self {
+            _tab: flatbuffers::Table { buf: buf, loc: loc },
+        }
+    }
+}
+
+impl<'a> SnapshotChunk<'a> {
+    #[inline]
+    pub fn init_from_table(table: flatbuffers::Table<'a>) -> Self {
+        SnapshotChunk {
+            _tab: table,
+        }
+    }
+    #[allow(unused_mut)]
+    pub fn create<'bldr: 'args, 'args: 'mut_bldr, 'mut_bldr>(
+        _fbb: &'mut_bldr mut flatbuffers::FlatBufferBuilder<'bldr>,
+        args: &'args SnapshotChunkArgs<'args>) -> flatbuffers::WIPOffset<SnapshotChunk<'bldr>> {
+      let mut builder = SnapshotChunkBuilder::new(_fbb);
+      if let Some(x) = args.data { builder.add_data(x); }
+      if let Some(x) = args.chunk_hashes { builder.add_chunk_hashes(x); }
+      builder.add_index(args.index);
+      if let Some(x) = args.block_hash { builder.add_block_hash(x); }
+      builder.finish()
+    }
+
+    pub const VT_BLOCK_HASH: flatbuffers::VOffsetT = 4;
+    pub const VT_INDEX: flatbuffers::VOffsetT = 6;
+    pub const VT_CHUNK_HASHES: flatbuffers::VOffsetT = 8;
+    pub const VT_DATA: flatbuffers::VOffsetT = 10;
+
+  #[inline]
+  pub fn block_hash(&self) -> Option<&'a H256> {
+    self._tab.get::<H256>(SnapshotChunk::VT_BLOCK_HASH, None)
+  }
+  #[inline]
+  pub fn index(&self) -> u32 {
+    self._tab.get::<u32>(SnapshotChunk::VT_INDEX, Some(0)).unwrap()
+  }
+  #[inline]
+  pub fn chunk_hashes(&self) -> Option<&'a [H256]> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<H256>>>(SnapshotChunk::VT_CHUNK_HASHES, None).map(|v| v.safe_slice() )
+  }
+  #[inline]
+  pub fn data(&self) -> Option<&'a [u8]> {
+    self._tab.get::<flatbuffers::ForwardsUOffset<flatbuffers::Vector<'a, u8>>>(SnapshotChunk::VT_DATA, None).map(|v| v.safe_slice())
+  }
+}
+
+pub struct SnapshotChunkArgs<'a> {
+    pub block_hash: Option<&'a  H256>,
+    pub index: u32,
+    pub chunk_hashes: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a , H256>>>,
+    pub data: Option<flatbuffers::WIPOffset<flatbuffers::Vector<'a ,  u8>>>,
+}
+impl<'a> Default for SnapshotChunkArgs<'a> {
+    #[inline]
+    fn default() -> Self {
+        SnapshotChunkArgs {
+            block_hash: None,
+            index: 0,
+            chunk_hashes: None,
+            data: None,
+        }
+    }
+}
+pub struct SnapshotChunkBuilder<'a: 'b, 'b> {
+  fbb_: &'b mut flatbuffers::FlatBufferBuilder<'a>,
+  start_: flatbuffers::WIPOffset<flatbuffers::TableUnfinishedWIPOffset>,
+}
+impl<'a: 'b, 'b> SnapshotChunkBuilder<'a, 'b> {
+  #[inline]
+  pub fn add_block_hash(&mut self, block_hash: &'b  H256) {
+    self.fbb_.push_slot_always::<&H256>(SnapshotChunk::VT_BLOCK_HASH, block_hash);
+  }
+  #[inline]
+  pub fn add_index(&mut self, index: u32) {
+    self.fbb_.push_slot::<u32>(SnapshotChunk::VT_INDEX, index, 0);
+  }
+  #[inline]
+  pub fn add_chunk_hashes(&mut self, chunk_hashes: flatbuffers::WIPOffset<flatbuffers::Vector<'b , H256>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(SnapshotChunk::VT_CHUNK_HASHES, chunk_hashes);
+  }
+  #[inline]
+  pub fn add_data(&mut self, data: flatbuffers::WIPOffset<flatbuffers::Vector<'b , u8>>) {
+    self.fbb_.push_slot_always::<flatbuffers::WIPOffset<_>>(SnapshotChunk::VT_DATA, data);
+  }
+  #[inline]
+  pub fn new(_fbb: &'b mut flatbuffers::FlatBufferBuilder<'a>) -> SnapshotChunkBuilder<'a, 'b> {
+    let start = _fbb.start_table();
+    SnapshotChunkBuilder {
+      fbb_: _fbb,
+      start_: start,
+    }
+  }
+  #[inline]
+  pub fn finish(self) -> flatbuffers::WIPOffset<SnapshotChunk<'a>> {
+    let o = self.fbb_.end_table(self.start_);
+    flatbuffers::WIPOffset::new(o.value())
+  }
+}
+
 pub enum TimeMessageOffset {}
 #[derive(Copy, Clone, Debug, PartialEq)]
 
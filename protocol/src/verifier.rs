@@ -0,0 +1,499 @@
+// flatbuffers 0.5 has no verifier, its generated accessors trust every offset
+// and length in the buffer. Messages from peers must be checked here before
+// `get_root`, otherwise a malformed message reads out of bounds.
+//
+// Field slots and types below must follow protocol.fbs.
+
+const H256_SIZE: usize = 32;
+const PROPOSAL_SHORT_ID_SIZE: usize = 10;
+const SYNC_PAYLOAD_MAX: u8 = 10;
+const RELAY_PAYLOAD_MAX: u8 = 12;
+
+type Verified = Option<()>;
+
+struct Verifier<'a> {
+    buf: &'a [u8],
+    // Offsets may point at the same table many times, the budget bounds
+    // the tables visited to what a buffer of this size could really hold.
+    budget: usize,
+}
+
+#[derive(Clone, Copy)]
+struct Table {
+    pos: usize,
+    vtable: usize,
+    vtable_size: usize,
+    table_size: usize,
+}
+
+impl<'a> Verifier<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Verifier {
+            buf,
+            budget: buf.len() / 4 + 1,
+        }
+    }
+
+    fn check_range(&self, pos: usize, size: usize) -> Verified {
+        let end = pos.checked_add(size)?;
+        if end <= self.buf.len() {
+            Some(())
+        } else {
+            None
+        }
+    }
+
+    fn read_u16(&self, pos: usize) -> Option<usize> {
+        self.check_range(pos, 2)?;
+        Some(u16::from_le_bytes([self.buf[pos], self.buf[pos + 1]]) as usize)
+    }
+
+    fn read_u32(&self, pos: usize) -> Option<u32> {
+        self.check_range(pos, 4)?;
+        let mut bytes = [0u8; 4];
+        bytes.copy_from_slice(&self.buf[pos..pos + 4]);
+        Some(u32::from_le_bytes(bytes))
+    }
+
+    fn follow(&self, pos: usize) -> Option<usize> {
+        pos.checked_add(self.read_u32(pos)? as usize)
+    }
+
+    fn table(&mut self, pos: usize) -> Option<Table> {
+        self.budget = self.budget.checked_sub(1)?;
+        let soffset = i64::from(self.read_u32(pos)? as i32);
+        let vtable = pos as i64 - soffset;
+        if vtable < 0 {
+            return None;
+        }
+        let vtable = vtable as usize;
+        let vtable_size = self.read_u16(vtable)?;
+        let table_size = self.read_u16(vtable + 2)?;
+        if vtable_size < 4 || vtable_size % 2 != 0 || table_size < 4 {
+            return None;
+        }
+        self.check_range(vtable, vtable_size)?;
+        self.check_range(pos, table_size)?;
+        Some(Table {
+            pos,
+            vtable,
+            vtable_size,
+            table_size,
+        })
+    }
+
+    // position of the field at `slot`, None when the field is absent
+    fn field(&self, table: Table, slot: usize, size: usize) -> Option<Option<usize>> {
+        if slot + 2 > table.vtable_size {
+            return Some(None);
+        }
+        let offset = self.read_u16(table.vtable + slot)?;
+        if offset == 0 {
+            return Some(None);
+        }
+        if offset.checked_add(size)? > table.table_size {
+            return None;
+        }
+        Some(Some(table.pos + offset))
+    }
+
+    fn scalar(&self, table: Table, slot: usize, size: usize) -> Verified {
+        self.field(table, slot, size).map(|_| ())
+    }
+
+    fn bool(&self, table: Table, slot: usize) -> Verified {
+        match self.field(table, slot, 1)? {
+            Some(pos) if self.buf[pos] > 1 => None,
+            _ => Some(()),
+        }
+    }
+
+    fn table_field(
+        &mut self,
+        table: Table,
+        slot: usize,
+        verify: fn(&mut Self, usize) -> Verified,
+    ) -> Verified {
+        match self.field(table, slot, 4)? {
+            Some(pos) => {
+                let target = self.follow(pos)?;
+                verify(self, target)
+            }
+            None => Some(()),
+        }
+    }
+
+    // Vector of scalars or structs, returns the position of its length prefix
+    fn vector(&self, table: Table, slot: usize, elem_size: usize) -> Option<Option<usize>> {
+        match self.field(table, slot, 4)? {
+            Some(pos) => {
+                let vector = self.follow(pos)?;
+                let len = self.read_u32(vector)? as usize;
+                self.check_range(vector + 4, len.checked_mul(elem_size)?)?;
+                Some(Some(vector))
+            }
+            None => Some(None),
+        }
+    }
+
+    fn table_vector(
+        &mut self,
+        table: Table,
+        slot: usize,
+        verify: fn(&mut Self, usize) -> Verified,
+    ) -> Verified {
+        if let Some(vector) = self.vector(table, slot, 4)? {
+            let len = self.read_u32(vector)? as usize;
+            for i in 0..len {
+                let target = self.follow(vector + 4 + i * 4)?;
+                verify(self, target)?;
+            }
+        }
+        Some(())
+    }
+
+    fn union(
+        &mut self,
+        pos: usize,
+        max_type: u8,
+        verify: fn(&mut Self, u8, usize) -> Verified,
+    ) -> Verified {
+        let table = self.table(pos)?;
+        let payload_type = match self.field(table, 4, 1)? {
+            Some(pos) => self.buf[pos],
+            None => 0,
+        };
+        if payload_type > max_type {
+            return None;
+        }
+        match self.field(table, 6, 4)? {
+            Some(pos) if payload_type != 0 => {
+                let target = self.follow(pos)?;
+                verify(self, payload_type, target)
+            }
+            _ => Some(()),
+        }
+    }
+
+    fn root(&mut self, verify: fn(&mut Self, usize) -> Verified) -> Verified {
+        let pos = self.follow(0)?;
+        verify(self, pos)
+    }
+
+    fn sync_message(&mut self, pos: usize) -> Verified {
+        self.union(
+            pos,
+            SYNC_PAYLOAD_MAX,
+            |v, payload_type, pos| match payload_type {
+                1 => v.get_headers(pos),
+                2 => v.headers(pos),
+                3 => v.get_blocks(pos),
+                4 => v.block(pos),
+                5 => v.set_filter(pos),
+                6 => v.add_filter(pos),
+                7 => v.table(pos).map(|_| ()),
+                8 => v.filtered_block(pos),
+                9 => v.get_snapshot_chunk(pos),
+                10 => v.snapshot_chunk(pos),
+                _ => None,
+            },
+        )
+    }
+
+    fn relay_message(&mut self, pos: usize) -> Verified {
+        self.union(
+            pos,
+            RELAY_PAYLOAD_MAX,
+            |v, payload_type, pos| match payload_type {
+                1 => v.compact_block(pos),
+                2 => v.transaction(pos),
+                3 => v.get_block_transactions(pos),
+                4 => v.block_transactions(pos),
+                5 => v.get_block_proposal(pos),
+                6 => v.block_proposal(pos),
+                7 | 8 => v.tx_hashes(pos),
+                9 => v.set_compact_block_mode(pos),
+                10 | 11 => v.block_hash(pos),
+                12 => v.uncle_block(pos),
+                _ => None,
+            },
+        )
+    }
+
+    fn time_message(&mut self, pos: usize) -> Verified {
+        let table = self.table(pos)?;
+        self.table_field(table, 4, |v, pos| {
+            let table = v.table(pos)?;
+            v.scalar(table, 4, 8)
+        })
+    }
+
+    fn bytes(&mut self, pos: usize) -> Verified {
+        let table = self.table(pos)?;
+        self.vector(table, 4, 1).map(|_| ())
+    }
+
+    fn get_headers(&mut self, pos: usize) -> Verified {
+        let table = self.table(pos)?;
+        self.scalar(table, 4, 4)?;
+        self.vector(table, 6, H256_SIZE)?;
+        self.scalar(table, 8, H256_SIZE)
+    }
+
+    fn headers(&mut self, pos: usize) -> Verified {
+        let table = self.table(pos)?;
+        self.table_vector(table, 4, Self::header)
+    }
+
+    fn get_blocks(&mut self, pos: usize) -> Verified {
+        let table = self.table(pos)?;
+        self.vector(table, 4, H256_SIZE).map(|_| ())
+    }
+
+    fn header(&mut self, pos: usize) -> Verified {
+        let table = self.table(pos)?;
+        self.scalar(table, 4, 4)?;
+        self.scalar(table, 6, H256_SIZE)?;
+        self.scalar(table, 8, 8)?;
+        self.scalar(table, 10, 8)?;
+        self.scalar(table, 12, H256_SIZE)?;
+        self.scalar(table, 14, H256_SIZE)?;
+        self.table_field(table, 16, Self::bytes)?;
+        self.scalar(table, 18, 8)?;
+        self.table_field(table, 20, Self::bytes)?;
+        self.scalar(table, 22, H256_SIZE)?;
+        self.scalar(table, 24, H256_SIZE)?;
+        self.scalar(table, 26, 4)
+    }
+
+    fn block(&mut self, pos: usize) -> Verified {
+        let table = self.table(pos)?;
+        self.table_field(table, 4, Self::header)?;
+        self.table_vector(table, 6, Self::uncle_block)?;
+        self.table_vector(table, 8, Self::transaction)?;
+        self.vector(table, 10, PROPOSAL_SHORT_ID_SIZE).map(|_| ())
+    }
+
+    fn uncle_block(&mut self, pos: usize) -> Verified {
+        let table = self.table(pos)?;
+        self.table_field(table, 4, Self::header)?;
+        self.table_field(table, 6, Self::transaction)?;
+        self.vector(table, 8, PROPOSAL_SHORT_ID_SIZE).map(|_| ())
+    }
+
+    fn transaction(&mut self, pos: usize) -> Verified {
+        let table = self.table(pos)?;
+        self.scalar(table, 4, 4)?;
+        self.table_vector(table, 6, Self::out_point)?;
+        self.table_vector(table, 8, Self::cell_input)?;
+        self.table_vector(table, 10, Self::cell_output)
+    }
+
+    fn out_point(&mut self, pos: usize) -> Verified {
+        let table = self.table(pos)?;
+        self.scalar(table, 4, H256_SIZE)?;
+        self.scalar(table, 6, 4)
+    }
+
+    fn cell_input(&mut self, pos: usize) -> Verified {
+        let table = self.table(pos)?;
+        self.scalar(table, 4, H256_SIZE)?;
+        self.scalar(table, 6, 4)?;
+        self.table_field(table, 8, Self::script)
+    }
+
+    fn cell_output(&mut self, pos: usize) -> Verified {
+        let table = self.table(pos)?;
+        self.scalar(table, 4, 8)?;
+        self.table_field(table, 6, Self::bytes)?;
+        self.scalar(table, 8, H256_SIZE)?;
+        self.table_field(table, 10, Self::script)
+    }
+
+    fn script(&mut self, pos: usize) -> Verified {
+        let table = self.table(pos)?;
+        self.scalar(table, 4, 1)?;
+        self.table_vector(table, 6, Self::bytes)?;
+        self.table_field(table, 8, Self::bytes)?;
+        self.scalar(table, 10, H256_SIZE)?;
+        self.table_vector(table, 12, Self::bytes)
+    }
+
+    fn compact_block(&mut self, pos: usize) -> Verified {
+        let table = self.table(pos)?;
+        self.table_field(table, 4, Self::header)?;
+        self.scalar(table, 6, 8)?;
+        self.table_vector(table, 8, Self::bytes)?;
+        self.table_vector(table, 10, Self::index_transaction)?;
+        self.table_vector(table, 12, Self::uncle_block)?;
+        self.vector(table, 14, PROPOSAL_SHORT_ID_SIZE).map(|_| ())
+    }
+
+    fn index_transaction(&mut self, pos: usize) -> Verified {
+        let table = self.table(pos)?;
+        self.scalar(table, 4, 4)?;
+        self.table_field(table, 6, Self::transaction)
+    }
+
+    fn get_block_transactions(&mut self, pos: usize) -> Verified {
+        let table = self.table(pos)?;
+        self.scalar(table, 4, H256_SIZE)?;
+        self.vector(table, 6, 4).map(|_| ())
+    }
+
+    fn block_transactions(&mut self, pos: usize) -> Verified {
+        let table = self.table(pos)?;
+        self.scalar(table, 4, H256_SIZE)?;
+        self.table_vector(table, 6, Self::transaction)
+    }
+
+    fn get_block_proposal(&mut self, pos: usize) -> Verified {
+        let table = self.table(pos)?;
+        self.scalar(table, 4, 8)?;
+        self.vector(table, 6, PROPOSAL_SHORT_ID_SIZE).map(|_| ())
+    }
+
+    fn block_proposal(&mut self, pos: usize) -> Verified {
+        let table = self.table(pos)?;
+        self.table_vector(table, 4, Self::transaction)
+    }
+
+    // RelayTransactionHashes and GetRelayTransactions
+    fn tx_hashes(&mut self, pos: usize) -> Verified {
+        let table = self.table(pos)?;
+        self.vector(table, 4, H256_SIZE).map(|_| ())
+    }
+
+    fn set_compact_block_mode(&mut self, pos: usize) -> Verified {
+        let table = self.table(pos)?;
+        self.bool(table, 4)
+    }
+
+    // UncleAnnouncement and GetUncleBlock
+    fn block_hash(&mut self, pos: usize) -> Verified {
+        let table = self.table(pos)?;
+        self.scalar(table, 4, H256_SIZE)
+    }
+
+    fn set_filter(&mut self, pos: usize) -> Verified {
+        let table = self.table(pos)?;
+        self.vector(table, 4, 1)?;
+        self.scalar(table, 6, 1)?;
+        self.scalar(table, 8, 4)
+    }
+
+    fn add_filter(&mut self, pos: usize) -> Verified {
+        let table = self.table(pos)?;
+        self.vector(table, 4, 1).map(|_| ())
+    }
+
+    fn filtered_block(&mut self, pos: usize) -> Verified {
+        let table = self.table(pos)?;
+        self.table_field(table, 4, Self::header)?;
+        self.table_vector(table, 6, Self::transaction)?;
+        self.table_field(table, 8, |v, pos| {
+            let table = v.table(pos)?;
+            v.vector(table, 4, 4)?;
+            v.vector(table, 6, H256_SIZE).map(|_| ())
+        })
+    }
+
+    fn get_snapshot_chunk(&mut self, pos: usize) -> Verified {
+        let table = self.table(pos)?;
+        self.scalar(table, 4, H256_SIZE)?;
+        self.scalar(table, 6, 4)
+    }
+
+    fn snapshot_chunk(&mut self, pos: usize) -> Verified {
+        let table = self.table(pos)?;
+        self.scalar(table, 4, H256_SIZE)?;
+        self.scalar(table, 6, 4)?;
+        self.vector(table, 8, H256_SIZE)?;
+        self.vector(table, 10, 1).map(|_| ())
+    }
+}
+
+/// Checks `data` is a well formed `SyncMessage` before it is read by `get_root`
+pub fn verify_sync_message(data: &[u8]) -> bool {
+    Verifier::new(data).root(Verifier::sync_message).is_some()
+}
+
+/// Checks `data` is a well formed `RelayMessage` before it is read by `get_root`
+pub fn verify_relay_message(data: &[u8]) -> bool {
+    Verifier::new(data).root(Verifier::relay_message).is_some()
+}
+
+/// Checks `data` is a well formed `TimeMessage` before it is read by `get_root`
+pub fn verify_time_message(data: &[u8]) -> bool {
+    Verifier::new(data).root(Verifier::time_message).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RelayMessage, SyncMessage};
+    use ckb_core::block::BlockBuilder;
+    use ckb_core::header::HeaderBuilder;
+    use ckb_core::transaction::{CellInput, CellOutput, OutPoint, TransactionBuilder};
+    use flatbuffers::FlatBufferBuilder;
+    use numext_fixed_hash::H256;
+
+    fn block_message() -> Vec<u8> {
+        let transaction = TransactionBuilder::default()
+            .input(CellInput::new(OutPoint::null(), Default::default()))
+            .output(CellOutput::new(100, vec![1, 2, 3], H256::zero(), None))
+            .build();
+        let block = BlockBuilder::default()
+            .header(HeaderBuilder::default().number(1).build())
+            .commit_transaction(transaction)
+            .build();
+        let fbb = &mut FlatBufferBuilder::new();
+        let message = SyncMessage::build_block(fbb, &block);
+        fbb.finish(message, None);
+        fbb.finished_data().to_vec()
+    }
+
+    #[test]
+    fn test_verify_well_formed_messages() {
+        assert!(verify_sync_message(&block_message()));
+
+        let fbb = &mut FlatBufferBuilder::new();
+        let message = RelayMessage::build_get_uncle_block(fbb, &H256::zero());
+        fbb.finish(message, None);
+        assert!(verify_relay_message(fbb.finished_data()));
+    }
+
+    #[test]
+    fn test_verify_truncated_message() {
+        let data = block_message();
+        for len in &[0, 4, data.len() / 2] {
+            assert!(!verify_sync_message(&data[..*len]));
+        }
+    }
+
+    #[test]
+    fn test_verify_out_of_bounds_vector() {
+        let mut data = block_message();
+        // the output data [1, 2, 3] is the only vector holding these bytes
+        let pos = data
+            .windows(7)
+            .position(|w| w == [3, 0, 0, 0, 1, 2, 3])
+            .expect("data vector");
+        data[pos..pos + 4].copy_from_slice(&u32::max_value().to_le_bytes());
+        assert!(!verify_sync_message(&data));
+    }
+
+    #[test]
+    fn test_verify_unknown_payload_type() {
+        assert!(!verify_sync_message(&[]));
+        let mut data = block_message();
+        // vtable of the root table: size, table size, payload type offset
+        let root = u32::from_le_bytes([data[0], data[1], data[2], data[3]]) as usize;
+        let soffset =
+            i32::from_le_bytes([data[root], data[root + 1], data[root + 2], data[root + 3]]);
+        let vtable = (root as i64 - i64::from(soffset)) as usize;
+        let type_offset = u16::from_le_bytes([data[vtable + 4], data[vtable + 5]]) as usize;
+        data[root + type_offset] = SYNC_PAYLOAD_MAX + 1;
+        assert!(!verify_sync_message(&data));
+    }
+}
@@ -1,24 +1,105 @@
 use build_info::{get_version, Version};
-use ckb_network::NetworkService;
-use jsonrpc_core::Result;
+use ckb_network::peer_store::IpSubnet;
+use ckb_network::{AddrComponent, MessageStatsMap, NetworkService, PeerId, ToMultiaddr};
+use ckb_shared::index::ChainIndex;
+use ckb_shared::net_time::NetTime;
+use ckb_sync::Synchronizer;
+use jsonrpc_core::{Error, Result};
 use jsonrpc_derive::rpc;
-use jsonrpc_types::{LocalNode, NodeAddress};
+use jsonrpc_types::{
+    BannedAddress, LocalNode, MessageTraffic, NetworkTime, NodeAddress, Peer, PeerBestKnownHeader,
+    PeerIdentifyInfo, PeerInflightBlocks, PeerTraffic, SyncState, TrafficStats,
+};
 use std::sync::Arc;
+use std::time::Duration;
 
 const MAX_ADDRS: usize = 50;
+const DEFAULT_BAN_TIME: u64 = 24 * 60 * 60 * 1000; // 24 hours
 
 #[rpc]
 pub trait NetworkRpc {
     // curl -d '{"id": 2, "jsonrpc": "2.0", "method":"local_node_info","params": []}' -H 'content-type:application/json' 'http://localhost:8114'
     #[rpc(name = "local_node_info")]
     fn local_node_info(&self) -> Result<LocalNode>;
+
+    // curl -d '{"id": 2, "jsonrpc": "2.0", "method":"get_peers","params": []}' -H 'content-type:application/json' 'http://localhost:8114'
+    #[rpc(name = "get_peers")]
+    fn get_peers(&self) -> Result<Vec<Peer>>;
+
+    // curl -d '{"id": 2, "jsonrpc": "2.0", "method":"set_ban","params": ["192.168.0.0/16", "insert", 86400000, "spam"]}' -H 'content-type:application/json' 'http://localhost:8114'
+    #[rpc(name = "set_ban")]
+    fn set_ban(
+        &self,
+        address: String,
+        command: String,
+        ban_time: Option<u64>,
+        reason: Option<String>,
+    ) -> Result<()>;
+
+    // curl -d '{"id": 2, "jsonrpc": "2.0", "method":"list_banned","params": []}' -H 'content-type:application/json' 'http://localhost:8114'
+    #[rpc(name = "list_banned")]
+    fn list_banned(&self) -> Result<Vec<BannedAddress>>;
+
+    // curl -d '{"id": 2, "jsonrpc": "2.0", "method":"clear_banned","params": []}' -H 'content-type:application/json' 'http://localhost:8114'
+    #[rpc(name = "clear_banned")]
+    fn clear_banned(&self) -> Result<()>;
+
+    // curl -d '{"id": 2, "jsonrpc": "2.0", "method":"get_traffic_stats","params": []}' -H 'content-type:application/json' 'http://localhost:8114'
+    #[rpc(name = "get_traffic_stats")]
+    fn get_traffic_stats(&self) -> Result<TrafficStats>;
+
+    // curl -d '{"id": 2, "jsonrpc": "2.0", "method":"sync_state","params": []}' -H 'content-type:application/json' 'http://localhost:8114'
+    #[rpc(name = "sync_state")]
+    fn sync_state(&self) -> Result<SyncState>;
+
+    // curl -d '{"id": 2, "jsonrpc": "2.0", "method":"get_network_time","params": []}' -H 'content-type:application/json' 'http://localhost:8114'
+    #[rpc(name = "get_network_time")]
+    fn get_network_time(&self) -> Result<NetworkTime>;
 }
 
-pub(crate) struct NetworkRpcImpl {
+pub(crate) struct NetworkRpcImpl<CI: ChainIndex> {
     pub network: Arc<NetworkService>,
+    pub synchronizer: Synchronizer<CI>,
+    pub net_time: NetTime,
+}
+
+enum BanTarget {
+    Subnet(IpSubnet),
+    Peer(PeerId),
 }
 
-impl NetworkRpc for NetworkRpcImpl {
+// address is an IP, an IP subnet such as "192.168.0.0/16", or a peer id
+fn parse_ban_target(address: &str) -> Option<BanTarget> {
+    if let Ok(subnet) = address.parse::<IpSubnet>() {
+        return Some(BanTarget::Subnet(subnet));
+    }
+    let mut addr = format!("/p2p/{}", address).to_multiaddr().ok()?;
+    match addr.pop() {
+        Some(AddrComponent::P2P(key)) => PeerId::from_bytes(key.into_bytes())
+            .ok()
+            .map(BanTarget::Peer),
+        _ => None,
+    }
+}
+
+fn message_traffic(stats: &MessageStatsMap) -> Vec<MessageTraffic> {
+    let mut messages = stats
+        .iter()
+        .map(|((protocol_id, message_type), stats)| MessageTraffic {
+            protocol_id: String::from_utf8_lossy(protocol_id).into_owned(),
+            message_type: message_type.to_string(),
+            sent_messages: stats.sent_messages,
+            sent_bytes: stats.sent_bytes,
+            received_messages: stats.received_messages,
+            received_bytes: stats.received_bytes,
+        })
+        .collect::<Vec<_>>();
+    messages
+        .sort_by(|a, b| (&a.protocol_id, &a.message_type).cmp(&(&b.protocol_id, &b.message_type)));
+    messages
+}
+
+impl<CI: ChainIndex + 'static> NetworkRpc for NetworkRpcImpl<CI> {
     fn local_node_info(&self) -> Result<LocalNode> {
         Ok(LocalNode {
             version: get_version!().to_string(),
@@ -31,4 +112,151 @@ impl NetworkRpc for NetworkRpcImpl {
                 .collect(),
         })
     }
+
+    fn get_peers(&self) -> Result<Vec<Peer>> {
+        Ok(self
+            .network
+            .connected_peers()
+            .into_iter()
+            .map(|connected_peer| {
+                let peer = connected_peer.peer;
+                Peer {
+                    node_id: peer.peer_id.to_base58(),
+                    address: peer.connected_addr.to_string(),
+                    is_outbound: peer.is_outbound(),
+                    network_group: connected_peer.network_group,
+                    score: connected_peer.score,
+                    identify_info: peer.identify_info.map(|info| PeerIdentifyInfo {
+                        client_version: info.client_version,
+                        protocol_version: info.protocol_version,
+                        supported_protocols: info.supported_protocols,
+                    }),
+                    ping: peer.ping,
+                    connected_time: peer.connected_time,
+                    best_known_header: self
+                        .synchronizer
+                        .peers
+                        .best_known_header(connected_peer.peer_index)
+                        .map(|header| PeerBestKnownHeader {
+                            number: header.number(),
+                            hash: header.hash(),
+                            total_difficulty: header.total_difficulty().clone(),
+                        }),
+                }
+            })
+            .collect())
+    }
+
+    fn set_ban(
+        &self,
+        address: String,
+        command: String,
+        ban_time: Option<u64>,
+        reason: Option<String>,
+    ) -> Result<()> {
+        let target = parse_ban_target(&address)
+            .ok_or_else(|| Error::invalid_params(format!("invalid address: {}", address)))?;
+        let timeout = Duration::from_millis(ban_time.unwrap_or(DEFAULT_BAN_TIME));
+        let reason = reason.unwrap_or_default();
+        match (command.as_str(), target) {
+            ("insert", BanTarget::Subnet(subnet)) => {
+                self.network.ban_subnet(subnet, timeout, reason);
+                Ok(())
+            }
+            ("insert", BanTarget::Peer(peer_id)) => {
+                self.network.ban_peer(&peer_id, timeout, reason);
+                if self.network.is_banned(&peer_id) {
+                    Ok(())
+                } else {
+                    Err(Error::invalid_params("unknown peer address"))
+                }
+            }
+            ("delete", BanTarget::Subnet(subnet)) => {
+                if self.network.unban_subnet(&subnet) {
+                    Ok(())
+                } else {
+                    Err(Error::invalid_params("address is not banned"))
+                }
+            }
+            ("delete", BanTarget::Peer(peer_id)) => {
+                if self.network.unban_peer(&peer_id) {
+                    Ok(())
+                } else {
+                    Err(Error::invalid_params("peer is not banned"))
+                }
+            }
+            _ => Err(Error::invalid_params(
+                "command should be \"insert\" or \"delete\"",
+            )),
+        }
+    }
+
+    fn list_banned(&self) -> Result<Vec<BannedAddress>> {
+        Ok(self
+            .network
+            .banned_list()
+            .into_iter()
+            .map(|record| BannedAddress {
+                address: record.subnet.to_string(),
+                ban_until: record.ban_until.as_secs() * 1000
+                    + u64::from(record.ban_until.subsec_millis()),
+                reason: record.reason,
+            })
+            .collect())
+    }
+
+    fn clear_banned(&self) -> Result<()> {
+        self.network.clear_banned();
+        Ok(())
+    }
+
+    fn get_traffic_stats(&self) -> Result<TrafficStats> {
+        let traffic_stats = self.network.traffic_stats();
+        Ok(TrafficStats {
+            protocols: message_traffic(traffic_stats.protocols()),
+            peers: traffic_stats
+                .peers()
+                .map(|(peer_id, stats)| PeerTraffic {
+                    node_id: peer_id.to_base58(),
+                    messages: message_traffic(stats),
+                })
+                .collect(),
+        })
+    }
+
+    fn sync_state(&self) -> Result<SyncState> {
+        let sync_state = self.synchronizer.sync_state();
+        let connected_peers = self.network.connected_peers();
+        let inflight_blocks = sync_state
+            .blocks_inflight
+            .iter()
+            .filter_map(|(peer_index, count)| {
+                connected_peers
+                    .iter()
+                    .find(|connected_peer| connected_peer.peer_index == *peer_index)
+                    .map(|connected_peer| PeerInflightBlocks {
+                        node_id: connected_peer.peer.peer_id.to_base58(),
+                        count: *count as u64,
+                    })
+            })
+            .collect();
+        Ok(SyncState {
+            is_initial_block_download: sync_state.is_initial_block_download,
+            best_known_header_number: sync_state.best_known_header.number(),
+            best_known_header_hash: sync_state.best_known_header.hash(),
+            tip_number: sync_state.tip_number,
+            remaining_blocks: sync_state.remaining_blocks(),
+            download_rate: sync_state.download_rate as u64,
+            inflight_blocks,
+            orphan_blocks: sync_state.orphan_blocks as u64,
+        })
+    }
+
+    fn get_network_time(&self) -> Result<NetworkTime> {
+        Ok(NetworkTime {
+            network_time: self.net_time.now(),
+            offset: self.net_time.offset(),
+            samples: self.net_time.samples(),
+        })
+    }
 }
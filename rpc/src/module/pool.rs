@@ -1,10 +1,9 @@
 use ckb_core::transaction::Transaction as CoreTransaction;
 use ckb_network::NetworkService;
-use ckb_pool::txs_pool::TransactionPoolController;
-use ckb_protocol::RelayMessage;
-use ckb_sync::RELAY_PROTOCOL_ID;
-use flatbuffers::FlatBufferBuilder;
-use jsonrpc_core::Result;
+use ckb_pool::txs_pool::{PoolError, TransactionPoolController};
+use ckb_shared::index::ChainIndex;
+use ckb_sync::{Relayer, RELAY_PROTOCOL_ID};
+use jsonrpc_core::{Error, Result};
 use jsonrpc_derive::rpc;
 use jsonrpc_types::Transaction;
 use log::debug;
@@ -18,27 +17,31 @@ pub trait PoolRpc {
     fn send_transaction(&self, _tx: Transaction) -> Result<H256>;
 }
 
-pub(crate) struct PoolRpcImpl {
+pub(crate) struct PoolRpcImpl<CI: ChainIndex> {
     pub network: Arc<NetworkService>,
     pub tx_pool: TransactionPoolController,
+    pub relayer: Relayer<CI>,
 }
 
-impl PoolRpc for PoolRpcImpl {
+impl<CI: ChainIndex + 'static> PoolRpc for PoolRpcImpl<CI> {
     fn send_transaction(&self, tx: Transaction) -> Result<H256> {
         let tx: CoreTransaction = tx.into();
         let tx_hash = tx.hash().clone();
         let pool_result = self.tx_pool.add_transaction(tx.clone());
         debug!(target: "rpc", "send_transaction add to pool result: {:?}", pool_result);
+        // Verification error contains the debug output of failed scripts
+        if let Err(PoolError::InvalidTx(e)) = pool_result {
+            return Err(Error::invalid_params(format!(
+                "invalid transaction: {:?}",
+                e
+            )));
+        }
 
-        let fbb = &mut FlatBufferBuilder::new();
-        let message = RelayMessage::build_transaction(fbb, &tx);
-        fbb.finish(message, None);
-
+        // announced with random per-peer delays and rebroadcast until it leaves the pool,
+        // pushing it to all peers at once would reveal this node as its origin
         self.network.with_protocol_context(RELAY_PROTOCOL_ID, |nc| {
-            for peer in nc.connected_peers() {
-                debug!(target: "rpc", "relay transaction {} to peer#{}", tx_hash, peer);
-                let _ = nc.send(peer, fbb.finished_data().to_vec());
-            }
+            debug!(target: "rpc", "relay local transaction {}", tx_hash);
+            self.relayer.relay_local_tx(nc, tx);
         });
         Ok(tx_hash)
     }
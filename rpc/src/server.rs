@@ -10,6 +10,7 @@ use ckb_pool::txs_pool::TransactionPoolController;
 use ckb_pow::Clicker;
 use ckb_shared::index::ChainIndex;
 use ckb_shared::shared::Shared;
+use ckb_sync::{Relayer, Synchronizer};
 use jsonrpc_core::IoHandler;
 use jsonrpc_http_server::{Server, ServerBuilder};
 use jsonrpc_server_utils::cors::AccessControlAllowOrigin;
@@ -28,12 +29,15 @@ impl RpcServer {
         tx_pool: TransactionPoolController,
         chain: ChainController,
         block_assembler: BlockAssemblerController,
+        synchronizer: Synchronizer<CI>,
+        relayer: Relayer<CI>,
         test_engine: Option<Arc<Clicker>>,
     ) -> RpcServer
     where
         CI: ChainIndex,
     {
         let mut io = IoHandler::new();
+        let net_time = shared.net_time().clone();
 
         if config.chain_enable() {
             io.extend_with(
@@ -49,6 +53,7 @@ impl RpcServer {
                 PoolRpcImpl {
                     network: Arc::clone(&network),
                     tx_pool: tx_pool.clone(),
+                    relayer,
                 }
                 .to_delegate(),
             );
@@ -70,6 +75,8 @@ impl RpcServer {
             io.extend_with(
                 NetworkRpcImpl {
                     network: Arc::clone(&network),
+                    synchronizer,
+                    net_time,
                 }
                 .to_delegate(),
             );
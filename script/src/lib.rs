@@ -1,17 +1,25 @@
 mod cost_model;
 mod syscalls;
+mod type_id;
 mod verify;
+mod vm_config;
 
 use ckb_vm::Error as VMInternalError;
 
 pub use crate::verify::TransactionScriptsVerifier;
 
-#[derive(Debug, PartialEq, Clone, Copy, Eq)]
+#[derive(Debug, PartialEq, Clone, Eq)]
 pub enum ScriptError {
     NoScript,
     InvalidReferenceIndex,
     ArgumentError,
-    ValidationFailure(u8),
+    /// Script exits with a non-zero code, debug output of the script run is
+    /// attached so callers can see the script's own diagnostics.
+    ValidationFailure(u8, Vec<String>),
     VMError(VMInternalError),
     ExceededMaximumCycles,
+    InvalidTypeId,
+    /// The referenced type hash matches more than one dep cell
+    AmbiguousReference,
+    UnsupportedVersion(u8),
 }
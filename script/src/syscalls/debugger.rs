@@ -1,14 +1,19 @@
 use crate::syscalls::DEBUG_PRINT_SYSCALL_NUMBER;
 use ckb_vm::{CoreMachine, Error as VMError, Memory, Register, Syscalls, A0, A7};
 use log::debug;
+use std::cell::RefCell;
+use std::rc::Rc;
 
+// Debug messages are captured into `output`, which is shared with the caller
+// so they can be attached to the result of the script run.
 pub struct Debugger<'a> {
     prefix: &'a str,
+    output: Rc<RefCell<Vec<String>>>,
 }
 
 impl<'a> Debugger<'a> {
-    pub fn new(prefix: &'a str) -> Debugger<'a> {
-        Debugger { prefix }
+    pub fn new(prefix: &'a str, output: Rc<RefCell<Vec<String>>>) -> Debugger<'a> {
+        Debugger { prefix, output }
     }
 }
 
@@ -38,6 +43,7 @@ impl<'a, R: Register, M: Memory> Syscalls<R, M> for Debugger<'a> {
         machine.add_cycles((buffer.len() as u64 + 1) * 10);
         let s = String::from_utf8(buffer).map_err(|_| VMError::ParseError)?;
         debug!(target: "script", "{} DEBUG OUTPUT: {}", self.prefix, s);
+        self.output.borrow_mut().push(s);
         Ok(true)
     }
 }
@@ -0,0 +1,43 @@
+use crate::syscalls::{utils::store_data, ITEM_MISSING, LOAD_SCRIPT_GROUP_SYSCALL_NUMBER, SUCCESS};
+use ckb_vm::{CoreMachine, Error as VMError, Memory, Register, Syscalls, A0, A3, A7};
+
+// Since script version 1, inputs sharing the same lock, or outputs sharing the
+// same type, form a script group, the script only runs once for the whole
+// group. This syscall loads the index(in transaction inputs or outputs) of the
+// group member at A3, as a little endian u64.
+#[derive(Debug)]
+pub struct LoadScriptGroup<'a> {
+    indices: &'a [usize],
+}
+
+impl<'a> LoadScriptGroup<'a> {
+    pub fn new(indices: &'a [usize]) -> LoadScriptGroup<'a> {
+        LoadScriptGroup { indices }
+    }
+}
+
+impl<'a, R: Register, M: Memory> Syscalls<R, M> for LoadScriptGroup<'a> {
+    fn initialize(&mut self, _machine: &mut CoreMachine<R, M>) -> Result<(), VMError> {
+        Ok(())
+    }
+
+    fn ecall(&mut self, machine: &mut CoreMachine<R, M>) -> Result<bool, VMError> {
+        if machine.registers()[A7].to_u64() != LOAD_SCRIPT_GROUP_SYSCALL_NUMBER {
+            return Ok(false);
+        }
+        machine.add_cycles(10);
+
+        let index = machine.registers()[A3].to_usize();
+        let member = match self.indices.get(index) {
+            Some(member) => *member,
+            None => {
+                machine.registers_mut()[A0] = R::from_u8(ITEM_MISSING);
+                return Ok(true);
+            }
+        };
+
+        store_data(machine, &(member as u64).to_le_bytes())?;
+        machine.registers_mut()[A0] = R::from_u8(SUCCESS);
+        Ok(true)
+    }
+}
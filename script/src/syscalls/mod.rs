@@ -3,6 +3,7 @@ mod debugger;
 mod load_cell;
 mod load_cell_by_field;
 mod load_input_by_field;
+mod load_script_group;
 mod load_tx;
 mod utils;
 
@@ -11,6 +12,7 @@ pub use self::debugger::Debugger;
 pub use self::load_cell::LoadCell;
 pub use self::load_cell_by_field::LoadCellByField;
 pub use self::load_input_by_field::LoadInputByField;
+pub use self::load_script_group::LoadScriptGroup;
 pub use self::load_tx::LoadTx;
 
 use ckb_vm::Error;
@@ -22,6 +24,7 @@ pub const LOAD_TX_SYSCALL_NUMBER: u64 = 2049;
 pub const LOAD_CELL_SYSCALL_NUMBER: u64 = 2053;
 pub const LOAD_CELL_BY_FIELD_SYSCALL_NUMBER: u64 = 2054;
 pub const LOAD_INPUT_BY_FIELD_SYSCALL_NUMBER: u64 = 2055;
+pub const LOAD_SCRIPT_GROUP_SYSCALL_NUMBER: u64 = 2056;
 pub const DEBUG_PRINT_SYSCALL_NUMBER: u64 = 2177;
 
 #[derive(Debug, PartialEq, Clone, Copy, Eq)]
@@ -898,4 +901,29 @@ mod tests {
             _test_load_dep_cell_data_hash(data)?;
         }
     }
+
+    #[test]
+    fn test_load_script_group() {
+        let mut machine = DefaultCoreMachine::<u64, SparseMemory>::default();
+        let size_addr = 0;
+        let addr = 100;
+
+        machine.registers_mut()[A0] = addr; // addr
+        machine.registers_mut()[A1] = size_addr; // size_addr
+        machine.registers_mut()[A2] = 0; // offset
+        machine.registers_mut()[A3] = 1; // index in group
+        machine.registers_mut()[A7] = LOAD_SCRIPT_GROUP_SYSCALL_NUMBER; // syscall number
+
+        assert!(machine.memory_mut().store64(size_addr as usize, 8).is_ok());
+
+        let indices = vec![0, 3, 5];
+        let mut load_script_group = LoadScriptGroup::new(&indices);
+        assert!(load_script_group.ecall(&mut machine).is_ok());
+        assert_eq!(machine.registers()[A0], u64::from(SUCCESS));
+        assert_eq!(machine.memory_mut().load64(addr as usize), Ok(3));
+
+        machine.registers_mut()[A3] = 3; // out of group
+        assert!(load_script_group.ecall(&mut machine).is_ok());
+        assert_eq!(machine.registers()[A0], u64::from(ITEM_MISSING));
+    }
 }
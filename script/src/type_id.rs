@@ -0,0 +1,65 @@
+use crate::ScriptError;
+use ckb_core::script::Script;
+use ckb_core::transaction::{CellInput, CellOutput};
+use fnv::FnvHashMap;
+use numext_fixed_hash::H256;
+
+// Type id is verified natively instead of running in CKB VM, see
+// `ckb_core::script::TYPE_ID_REFERENCE` for the rules.
+pub struct TypeIdVerifier<'a, 'b> {
+    inputs: &'b [&'a CellInput],
+    input_cells: &'b [&'a CellOutput],
+    outputs: &'b [&'a CellOutput],
+}
+
+impl<'a, 'b> TypeIdVerifier<'a, 'b> {
+    pub fn new(
+        inputs: &'b [&'a CellInput],
+        input_cells: &'b [&'a CellOutput],
+        outputs: &'b [&'a CellOutput],
+    ) -> Self {
+        TypeIdVerifier {
+            inputs,
+            input_cells,
+            outputs,
+        }
+    }
+
+    // Collects type id scripts by type hash, together with the cell index
+    fn collect_type_ids(
+        cells: &[&'a CellOutput],
+    ) -> Result<FnvHashMap<H256, (usize, &'a Script)>, ScriptError> {
+        let mut type_ids = FnvHashMap::default();
+        for (index, cell) in cells.iter().cloned().enumerate() {
+            if let Some(ref type_) = cell.type_ {
+                if type_.is_type_id() {
+                    if type_.signed_args.len() != 1 || type_.signed_args[0].len() != 32 {
+                        return Err(ScriptError::InvalidTypeId);
+                    }
+                    if type_ids.insert(type_.type_hash(), (index, type_)).is_some() {
+                        return Err(ScriptError::InvalidTypeId);
+                    }
+                }
+            }
+        }
+        Ok(type_ids)
+    }
+
+    pub fn verify(&self) -> Result<(), ScriptError> {
+        let input_type_ids = Self::collect_type_ids(self.input_cells)?;
+        let output_type_ids = Self::collect_type_ids(self.outputs)?;
+
+        for (hash, (index, type_)) in output_type_ids {
+            if input_type_ids.contains_key(&hash) {
+                // Cell upgrade, the identity is carried over from the input
+                continue;
+            }
+            let first_input = self.inputs.first().ok_or(ScriptError::InvalidTypeId)?;
+            let expected = Script::type_id_args(&first_input.previous_output, index as u64);
+            if expected.as_bytes() != &type_.signed_args[0][..] {
+                return Err(ScriptError::InvalidTypeId);
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,6 +1,9 @@
 use crate::{
-    cost_model::instruction_cycles,
-    syscalls::{build_tx, Debugger, LoadCell, LoadCellByField, LoadInputByField, LoadTx},
+    syscalls::{
+        build_tx, Debugger, LoadCell, LoadCellByField, LoadInputByField, LoadScriptGroup, LoadTx,
+    },
+    type_id::TypeIdVerifier,
+    vm_config::{vm_config, Syscall},
     ScriptError,
 };
 use ckb_core::cell::ResolvedTransaction;
@@ -13,20 +16,54 @@ use flatbuffers::{get_root, FlatBufferBuilder};
 use fnv::FnvHashMap;
 use log::info;
 use numext_fixed_hash::H256;
+use std::cell::RefCell;
+use std::rc::Rc;
 
 // This struct leverages CKB VM to verify transaction inputs.
 // FlatBufferBuilder owned Vec<u8> that grows as needed, in the
 // future, we might refactor this to share buffer to achive zero-copy
 pub struct TransactionScriptsVerifier<'a> {
     dep_cell_index: FnvHashMap<H256, &'a CellOutput>,
+    // None when more than one dep cell has the type hash
+    dep_cell_type_index: FnvHashMap<H256, Option<&'a CellOutput>>,
     inputs: Vec<&'a CellInput>,
     outputs: Vec<&'a CellOutput>,
     tx_builder: FlatBufferBuilder<'a>,
     input_cells: Vec<&'a CellOutput>,
     dep_cells: Vec<&'a CellOutput>,
+    // Indices of inputs grouped by lock, and outputs grouped by type, each
+    // script only runs once per group. Only scripts whose version enables
+    // grouping share a group, every other script gets its own one.
+    lock_groups: Vec<Vec<usize>>,
+    type_groups: Vec<Vec<usize>>,
     hash: H256,
 }
 
+// Groups indices by identical script, groups are ordered by their first
+// appearance so verification order is deterministic.
+fn group_scripts<'a, I>(scripts: I) -> Vec<Vec<usize>>
+where
+    I: Iterator<Item = (usize, &'a Script)>,
+{
+    let mut positions: FnvHashMap<&'a Script, usize> = FnvHashMap::default();
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    for (index, script) in scripts {
+        let groupable = vm_config(script.version)
+            .map(|config| config.group_scripts)
+            .unwrap_or(false);
+        if !groupable {
+            groups.push(vec![index]);
+            continue;
+        }
+        let position = *positions.entry(script).or_insert_with(|| {
+            groups.push(Vec::new());
+            groups.len() - 1
+        });
+        groups[position].push(index);
+    }
+    groups
+}
+
 impl<'a> TransactionScriptsVerifier<'a> {
     pub fn new(rtx: &'a ResolvedTransaction) -> TransactionScriptsVerifier<'a> {
         let dep_cells: Vec<&'a CellOutput> = rtx
@@ -44,9 +81,37 @@ impl<'a> TransactionScriptsVerifier<'a> {
                 (hash, *cell)
             })
             .collect();
+        // Dep cells can also be referenced by the type hash of their type
+        // scripts, so scripts can survive the upgrade of a type id cell.
+        let mut dep_cell_type_index: FnvHashMap<H256, Option<&'a CellOutput>> =
+            FnvHashMap::default();
+        for cell in &dep_cells {
+            if let Some(ref type_) = cell.type_ {
+                dep_cell_type_index
+                    .entry(type_.type_hash())
+                    .and_modify(|entry| *entry = None)
+                    .or_insert_with(|| Some(*cell));
+            }
+        }
 
-        let inputs = rtx.transaction.inputs().iter().collect();
-        let outputs = rtx.transaction.outputs().iter().collect();
+        let inputs: Vec<&'a CellInput> = rtx.transaction.inputs().iter().collect();
+        let outputs: Vec<&'a CellOutput> = rtx.transaction.outputs().iter().collect();
+
+        let lock_groups = group_scripts(
+            inputs
+                .iter()
+                .enumerate()
+                .map(|(index, input)| (index, &input.unlock)),
+        );
+        // Type id is verified natively, see TypeIdVerifier
+        let type_groups =
+            group_scripts(outputs.iter().enumerate().filter_map(|(index, output)| {
+                output
+                    .type_
+                    .as_ref()
+                    .filter(|type_| !type_.is_type_id())
+                    .map(|type_| (index, type_))
+            }));
 
         let input_cells = rtx
             .input_cells
@@ -63,11 +128,14 @@ impl<'a> TransactionScriptsVerifier<'a> {
 
         TransactionScriptsVerifier {
             dep_cell_index,
+            dep_cell_type_index,
             inputs,
             tx_builder,
             outputs,
             input_cells,
             dep_cells,
+            lock_groups,
+            type_groups,
             hash: rtx.transaction.hash().clone(),
         }
     }
@@ -98,6 +166,10 @@ impl<'a> TransactionScriptsVerifier<'a> {
         LoadInputByField::new(&self.inputs, current_input)
     }
 
+    fn build_load_script_group<'b>(&self, group: &'b [usize]) -> LoadScriptGroup<'b> {
+        LoadScriptGroup::new(group)
+    }
+
     // Script struct might contain references to external cells, this
     // method exacts the referenced script if any. It also fills signed args
     // so we don't need to do a second time of memory copy
@@ -111,7 +183,15 @@ impl<'a> TransactionScriptsVerifier<'a> {
             return Ok(data);
         }
         if let Some(ref hash) = script.reference {
-            return match self.dep_cell_index.get(hash) {
+            let cell = match self.dep_cell_index.get(hash) {
+                Some(cell) => Some(cell),
+                None => match self.dep_cell_type_index.get(hash) {
+                    Some(Some(cell)) => Some(cell),
+                    Some(None) => return Err(ScriptError::AmbiguousReference),
+                    None => None,
+                },
+            };
+            return match cell {
                 Some(ref cell_output) => {
                     let fbs_script = get_root::<FbsScript>(&cell_output.data);
                     // This way we can avoid copying the actual script binary one more
@@ -145,22 +225,42 @@ impl<'a> TransactionScriptsVerifier<'a> {
         prefix: &str,
         current_cell: &'a CellOutput,
         current_input: Option<&'a CellInput>,
+        group: &[usize],
         max_cycles: Cycle,
     ) -> Result<Cycle, ScriptError> {
+        let config = vm_config(script.version)?;
+        let debug_output = Rc::new(RefCell::new(Vec::new()));
         let mut args = vec![b"verify".to_vec()];
         self.extract_script(script, &mut args)
             .and_then(|script_binary| {
                 args.extend_from_slice(&script.args.as_slice());
 
                 let mut machine = DefaultMachine::<u64, SparseMemory>::new_with_cost_model(
-                    Box::new(instruction_cycles),
+                    Box::new(config.cost_model),
                     max_cycles,
                 );
-                machine.add_syscall_module(Box::new(self.build_load_tx()));
-                machine.add_syscall_module(Box::new(self.build_load_cell(current_cell)));
-                machine.add_syscall_module(Box::new(self.build_load_cell_by_field(current_cell)));
-                machine.add_syscall_module(Box::new(self.build_load_input_by_field(current_input)));
-                machine.add_syscall_module(Box::new(Debugger::new(prefix)));
+                for syscall in config.syscalls {
+                    match syscall {
+                        Syscall::LoadTx => {
+                            machine.add_syscall_module(Box::new(self.build_load_tx()))
+                        }
+                        Syscall::LoadCell => {
+                            machine.add_syscall_module(Box::new(self.build_load_cell(current_cell)))
+                        }
+                        Syscall::LoadCellByField => machine.add_syscall_module(Box::new(
+                            self.build_load_cell_by_field(current_cell),
+                        )),
+                        Syscall::LoadInputByField => machine.add_syscall_module(Box::new(
+                            self.build_load_input_by_field(current_input),
+                        )),
+                        Syscall::LoadScriptGroup => machine
+                            .add_syscall_module(Box::new(self.build_load_script_group(group))),
+                        Syscall::Debug => machine.add_syscall_module(Box::new(Debugger::new(
+                            prefix,
+                            Rc::clone(&debug_output),
+                        ))),
+                    }
+                }
                 machine
                     .run(script_binary, &args)
                     .map_err(ScriptError::VMError)
@@ -168,17 +268,29 @@ impl<'a> TransactionScriptsVerifier<'a> {
                         if code == 0 {
                             Ok(machine.cycles())
                         } else {
-                            Err(ScriptError::ValidationFailure(code))
+                            Err(ScriptError::ValidationFailure(
+                                code,
+                                debug_output.replace(Vec::new()),
+                            ))
                         }
                     })
             })
     }
 
     pub fn verify(&self, max_cycles: Cycle) -> Result<Cycle, ScriptError> {
+        TypeIdVerifier::new(&self.inputs, &self.input_cells, &self.outputs)
+            .verify()
+            .map_err(|e| {
+                info!(target: "script", "Error validating type id of transaction {}: {:?}", self.hash, e);
+                e
+            })?;
         let mut cycles = 0;
-        for (i, input) in self.inputs.iter().enumerate() {
+        for group in &self.lock_groups {
+            // The first input of the group is considered as current input
+            let i = group[0];
+            let input = self.inputs[i];
             let prefix = format!("Transaction {}, input {}", self.hash, i);
-            let cycle = self.verify_script(&input.unlock, &prefix, self.input_cells[i], Some(input), max_cycles - cycles).map_err(|e| {
+            let cycle = self.verify_script(&input.unlock, &prefix, self.input_cells[i], Some(input), group, max_cycles - cycles).map_err(|e| {
                 info!(target: "script", "Error validating input {} of transaction {}: {:?}", i, self.hash, e);
                 e
             })?;
@@ -190,21 +302,23 @@ impl<'a> TransactionScriptsVerifier<'a> {
             }
             cycles = current_cycles;
         }
-        for (i, output) in self.outputs.iter().enumerate() {
-            if let Some(ref type_) = output.type_ {
-                let prefix = format!("Transaction {}, output {}", self.hash, i);
-                let cycle = self.verify_script(type_, &prefix, output, None, max_cycles - cycles).map_err(|e| {
-                    info!(target: "script", "Error validating output {} of transaction {}: {:?}", i, self.hash, e);
-                    e
-                })?;
-                let current_cycles = cycles
-                    .checked_add(cycle)
-                    .ok_or(ScriptError::ExceededMaximumCycles)?;
-                if current_cycles > max_cycles {
-                    return Err(ScriptError::ExceededMaximumCycles);
-                }
-                cycles = current_cycles;
+        for group in &self.type_groups {
+            // The first output of the group is considered as current cell
+            let i = group[0];
+            let output = self.outputs[i];
+            let type_ = output.type_.as_ref().expect("grouped by type");
+            let prefix = format!("Transaction {}, output {}", self.hash, i);
+            let cycle = self.verify_script(type_, &prefix, output, None, group, max_cycles - cycles).map_err(|e| {
+                info!(target: "script", "Error validating output {} of transaction {}: {:?}", i, self.hash, e);
+                e
+            })?;
+            let current_cycles = cycles
+                .checked_add(cycle)
+                .ok_or(ScriptError::ExceededMaximumCycles)?;
+            if current_cycles > max_cycles {
+                return Err(ScriptError::ExceededMaximumCycles);
             }
+            cycles = current_cycles;
         }
         Ok(cycles)
     }
@@ -217,7 +331,7 @@ mod tests {
     use ckb_core::script::Script;
     use ckb_core::transaction::{CellInput, CellOutput, OutPoint, TransactionBuilder};
     use ckb_core::Capacity;
-    use crypto::secp::Generator;
+    use crypto::secp::{Generator, MultisigConfig, Privkey};
     use faster_hex::hex_encode;
     use hash::sha3_256;
     use numext_fixed_hash::H256;
@@ -228,6 +342,11 @@ mod tests {
     fn open_cell_verify() -> File {
         File::open(Path::new(env!("CARGO_MANIFEST_DIR")).join("../script/testdata/verify")).unwrap()
     }
+    // Built from testdata/multisig.rs by testdata/build-multisig.sh
+    fn open_cell_multisig() -> File {
+        File::open(Path::new(env!("CARGO_MANIFEST_DIR")).join("../script/testdata/multisig"))
+            .unwrap()
+    }
     fn open_cell_always_success() -> File {
         File::open(
             Path::new(env!("CARGO_MANIFEST_DIR"))
@@ -373,6 +492,74 @@ mod tests {
         assert!(verifier.verify(100_000_000).is_err());
     }
 
+    fn verify_multisig(
+        config: &MultisigConfig,
+        signers: &[&Privkey],
+        signed_message: &[Vec<u8>],
+        message: &[Vec<u8>],
+    ) -> Result<Cycle, ScriptError> {
+        let mut file = open_cell_multisig();
+        let mut buffer = Vec::new();
+        file.read_to_end(&mut buffer).unwrap();
+
+        let hash1 = sha3_256(signed_message.concat());
+        let hash2 = sha3_256(hash1);
+        let signatures = signers
+            .iter()
+            .map(|privkey| privkey.sign_recoverable(&hash2.into()).unwrap())
+            .collect::<Vec<_>>();
+        // signatures in signers order, the script expects them in pubkey order
+        let mut args = signatures
+            .iter()
+            .map(|signature| {
+                let signature_der = signature.serialize_der();
+                let mut hex_signature = vec![0; signature_der.len() * 2];
+                hex_encode(&signature_der, &mut hex_signature).expect("hex signature");
+                hex_signature
+            })
+            .collect::<Vec<_>>();
+        args.extend_from_slice(message);
+
+        let script = Script::new(0, args, None, Some(buffer), config.signed_args());
+        let input = CellInput::new(OutPoint::null(), script);
+        let transaction = TransactionBuilder::default().input(input).build();
+        let dummy_cell = CellOutput::new(100, vec![], H256::default(), None);
+        let rtx = ResolvedTransaction {
+            transaction,
+            dep_cells: vec![],
+            input_cells: vec![CellStatus::Live(dummy_cell)],
+        };
+
+        TransactionScriptsVerifier::new(&rtx).verify(100_000_000)
+    }
+
+    #[test]
+    fn check_multisig() {
+        let keys = (0..3)
+            .map(|_| Generator::new().random_keypair().unwrap())
+            .collect::<Vec<_>>();
+        let config =
+            MultisigConfig::new(2, keys.iter().map(|(_, pubkey)| pubkey.clone()).collect())
+                .unwrap();
+        let message = vec![b"foo".to_vec(), b"bar".to_vec()];
+
+        // 2 of 3 in pubkey order
+        assert!(verify_multisig(&config, &[&keys[0].0, &keys[2].0], &message, &message).is_ok());
+        assert!(verify_multisig(&config, &[&keys[1].0, &keys[2].0], &message, &message).is_ok());
+        // out of pubkey order
+        assert!(verify_multisig(&config, &[&keys[2].0, &keys[0].0], &message, &message).is_err());
+        // a pubkey can only be used once
+        assert!(verify_multisig(&config, &[&keys[0].0, &keys[0].0], &message, &message).is_err());
+        // not enough signatures
+        assert!(verify_multisig(&config, &[&keys[0].0], &message, &message).is_err());
+        // signatures of another message
+        let other = vec![b"foo".to_vec(), b"baz".to_vec()];
+        assert!(verify_multisig(&config, &[&keys[0].0, &keys[1].0], &other, &message).is_err());
+        // signer outside of the pubkeys
+        let (outsider, _) = Generator::new().random_keypair().unwrap();
+        assert!(verify_multisig(&config, &[&keys[0].0, &outsider], &message, &message).is_err());
+    }
+
     #[test]
     fn check_valid_dep_reference() {
         let mut file = open_cell_verify();
@@ -586,4 +773,277 @@ mod tests {
 
         assert!(verifier.verify(100_000_000).is_err());
     }
+
+    #[test]
+    fn check_type_id_creation() {
+        let input = CellInput::new(
+            OutPoint::new(H256::from_trimmed_hex_str("123").unwrap(), 0),
+            create_always_success_script(),
+        );
+        let type_id = Script::type_id(&input.previous_output, 0);
+        let output = CellOutput::new(0, Vec::new(), H256::zero(), Some(type_id));
+
+        let transaction = TransactionBuilder::default()
+            .input(input)
+            .output(output)
+            .build();
+
+        let dummy_cell = CellOutput::new(100, vec![], H256::default(), None);
+
+        let rtx = ResolvedTransaction {
+            transaction,
+            dep_cells: vec![],
+            input_cells: vec![CellStatus::Live(dummy_cell)],
+        };
+
+        let verifier = TransactionScriptsVerifier::new(&rtx);
+
+        assert!(verifier.verify(100_000_000).is_ok());
+    }
+
+    #[test]
+    fn check_invalid_type_id_creation() {
+        let input = CellInput::new(
+            OutPoint::new(H256::from_trimmed_hex_str("123").unwrap(), 0),
+            create_always_success_script(),
+        );
+        // type id of another output index
+        let type_id = Script::type_id(&input.previous_output, 1);
+        let output = CellOutput::new(0, Vec::new(), H256::zero(), Some(type_id));
+
+        let transaction = TransactionBuilder::default()
+            .input(input)
+            .output(output)
+            .build();
+
+        let dummy_cell = CellOutput::new(100, vec![], H256::default(), None);
+
+        let rtx = ResolvedTransaction {
+            transaction,
+            dep_cells: vec![],
+            input_cells: vec![CellStatus::Live(dummy_cell)],
+        };
+
+        let verifier = TransactionScriptsVerifier::new(&rtx);
+
+        assert_eq!(
+            verifier.verify(100_000_000),
+            Err(ScriptError::InvalidTypeId)
+        );
+    }
+
+    #[test]
+    fn check_type_id_upgrade() {
+        let type_id = Script::type_id(&OutPoint::new(H256::zero(), 0), 0);
+        let input = CellInput::new(
+            OutPoint::new(H256::from_trimmed_hex_str("123").unwrap(), 0),
+            create_always_success_script(),
+        );
+        let input_cell = CellOutput::new(100, vec![1], H256::default(), Some(type_id.clone()));
+        let output = CellOutput::new(100, vec![2], H256::zero(), Some(type_id.clone()));
+
+        let transaction = TransactionBuilder::default()
+            .input(input.clone())
+            .output(output.clone())
+            .build();
+
+        let rtx = ResolvedTransaction {
+            transaction,
+            dep_cells: vec![],
+            input_cells: vec![CellStatus::Live(input_cell.clone())],
+        };
+
+        assert!(TransactionScriptsVerifier::new(&rtx)
+            .verify(100_000_000)
+            .is_ok());
+
+        // A type id can't be duplicated
+        let transaction = TransactionBuilder::default()
+            .input(input)
+            .output(output.clone())
+            .output(output)
+            .build();
+
+        let rtx = ResolvedTransaction {
+            transaction,
+            dep_cells: vec![],
+            input_cells: vec![CellStatus::Live(input_cell)],
+        };
+
+        assert_eq!(
+            TransactionScriptsVerifier::new(&rtx).verify(100_000_000),
+            Err(ScriptError::InvalidTypeId)
+        );
+    }
+
+    #[test]
+    fn check_valid_dep_type_reference() {
+        let mut builder = FlatBufferBuilder::new();
+        let offset = FbsScript::build(&mut builder, &create_always_success_script());
+        builder.finish(offset, None);
+        let buffer = builder.finished_data().to_vec();
+
+        let type_id = Script::type_id(&OutPoint::new(H256::zero(), 0), 0);
+        let dep_out_point = OutPoint::new(H256::from_trimmed_hex_str("123").unwrap(), 8);
+        let dep_cell = CellOutput::new(
+            buffer.len() as Capacity,
+            buffer,
+            H256::zero(),
+            Some(type_id.clone()),
+        );
+
+        let script = Script::new(0, vec![], Some(type_id.type_hash()), None, vec![]);
+        let input = CellInput::new(OutPoint::null(), script);
+
+        let transaction = TransactionBuilder::default()
+            .input(input)
+            .dep(dep_out_point)
+            .build();
+
+        let dummy_cell = CellOutput::new(100, vec![], H256::default(), None);
+
+        let rtx = ResolvedTransaction {
+            transaction,
+            dep_cells: vec![CellStatus::Live(dep_cell)],
+            input_cells: vec![CellStatus::Live(dummy_cell)],
+        };
+
+        let verifier = TransactionScriptsVerifier::new(&rtx);
+
+        assert!(verifier.verify(100_000_000).is_ok());
+    }
+
+    #[test]
+    fn check_ambiguous_dep_type_reference() {
+        let mut builder = FlatBufferBuilder::new();
+        let offset = FbsScript::build(&mut builder, &create_always_success_script());
+        builder.finish(offset, None);
+        let buffer = builder.finished_data().to_vec();
+
+        // a non type id script can be shared by several cells
+        let type_ = Script::new(0, vec![], None, Some(vec![1]), vec![]);
+        let dep_cell = CellOutput::new(
+            buffer.len() as Capacity,
+            buffer,
+            H256::zero(),
+            Some(type_.clone()),
+        );
+        let other_dep_cell = CellOutput::new(100, vec![], H256::zero(), Some(type_.clone()));
+
+        let script = Script::new(0, vec![], Some(type_.type_hash()), None, vec![]);
+        let input = CellInput::new(OutPoint::null(), script);
+
+        let transaction = TransactionBuilder::default()
+            .input(input)
+            .dep(OutPoint::new(H256::from_trimmed_hex_str("123").unwrap(), 8))
+            .dep(OutPoint::new(H256::from_trimmed_hex_str("123").unwrap(), 9))
+            .build();
+
+        let dummy_cell = CellOutput::new(100, vec![], H256::default(), None);
+
+        let rtx = ResolvedTransaction {
+            transaction,
+            dep_cells: vec![CellStatus::Live(dep_cell), CellStatus::Live(other_dep_cell)],
+            input_cells: vec![CellStatus::Live(dummy_cell)],
+        };
+
+        let verifier = TransactionScriptsVerifier::new(&rtx);
+
+        assert_eq!(
+            verifier.verify(100_000_000),
+            Err(ScriptError::AmbiguousReference)
+        );
+    }
+
+    #[test]
+    fn check_unsupported_version() {
+        let mut script = create_always_success_script();
+        script.version = 2;
+        let input = CellInput::new(OutPoint::null(), script);
+
+        let transaction = TransactionBuilder::default().input(input).build();
+
+        let dummy_cell = CellOutput::new(100, vec![], H256::default(), None);
+
+        let rtx = ResolvedTransaction {
+            transaction,
+            dep_cells: vec![],
+            input_cells: vec![CellStatus::Live(dummy_cell)],
+        };
+
+        let verifier = TransactionScriptsVerifier::new(&rtx);
+
+        assert_eq!(
+            verifier.verify(100_000_000),
+            Err(ScriptError::UnsupportedVersion(2))
+        );
+    }
+
+    #[test]
+    fn check_inputs_not_grouped_in_version_0() {
+        let script = create_always_success_script();
+        let input1 = CellInput::new(OutPoint::new(H256::zero(), 0), script.clone());
+        let input2 = CellInput::new(OutPoint::new(H256::zero(), 1), script);
+        let dummy_cell = CellOutput::new(100, vec![], H256::default(), None);
+
+        let transaction = TransactionBuilder::default()
+            .input(input1)
+            .input(input2)
+            .build();
+        let rtx = ResolvedTransaction {
+            transaction,
+            dep_cells: vec![],
+            input_cells: vec![
+                CellStatus::Live(dummy_cell.clone()),
+                CellStatus::Live(dummy_cell),
+            ],
+        };
+        let verifier = TransactionScriptsVerifier::new(&rtx);
+        assert_eq!(verifier.lock_groups, vec![vec![0], vec![1]]);
+        assert!(verifier.verify(100_000_000).is_ok());
+    }
+
+    #[test]
+    fn check_inputs_grouped_by_lock() {
+        let mut script = create_always_success_script();
+        script.version = 1;
+        let mut other_script = script.clone();
+        other_script.args = vec![b"other".to_vec()];
+        let input1 = CellInput::new(OutPoint::new(H256::zero(), 0), script.clone());
+        let input2 = CellInput::new(OutPoint::new(H256::zero(), 1), other_script);
+        let input3 = CellInput::new(OutPoint::new(H256::zero(), 2), script);
+        let dummy_cell = CellOutput::new(100, vec![], H256::default(), None);
+
+        let single_cycles = |input: &CellInput| {
+            let transaction = TransactionBuilder::default().input(input.clone()).build();
+            let rtx = ResolvedTransaction {
+                transaction,
+                dep_cells: vec![],
+                input_cells: vec![CellStatus::Live(dummy_cell.clone())],
+            };
+            TransactionScriptsVerifier::new(&rtx)
+                .verify(100_000_000)
+                .unwrap()
+        };
+        let expected_cycles = single_cycles(&input1) + single_cycles(&input2);
+
+        let transaction = TransactionBuilder::default()
+            .input(input1)
+            .input(input2)
+            .input(input3)
+            .build();
+        let rtx = ResolvedTransaction {
+            transaction,
+            dep_cells: vec![],
+            input_cells: vec![
+                CellStatus::Live(dummy_cell.clone()),
+                CellStatus::Live(dummy_cell.clone()),
+                CellStatus::Live(dummy_cell),
+            ],
+        };
+        let verifier = TransactionScriptsVerifier::new(&rtx);
+        // scripts differing only in args are different groups
+        assert_eq!(verifier.lock_groups, vec![vec![0, 2], vec![1]]);
+        assert_eq!(verifier.verify(100_000_000), Ok(expected_cycles));
+    }
 }
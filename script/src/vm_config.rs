@@ -0,0 +1,82 @@
+use crate::cost_model::instruction_cycles;
+use crate::ScriptError;
+use ckb_vm::Instruction;
+
+#[derive(Debug, PartialEq, Clone, Copy, Eq)]
+pub enum Syscall {
+    LoadTx,
+    LoadCell,
+    LoadCellByField,
+    LoadInputByField,
+    LoadScriptGroup,
+    Debug,
+}
+
+/// VM settings selected by `Script::version`. Once a version is released its
+/// config must never change, new cost models or syscalls go to a new version
+/// so existing cells keep the same behavior.
+/// Note the instruction set is fixed to RV64IMC by CKB VM for now, it will be
+/// part of the config once CKB VM supports configurable ISA extensions.
+pub struct VmConfig {
+    pub version: u8,
+    pub cost_model: fn(&Instruction) -> u64,
+    pub syscalls: &'static [Syscall],
+    // Whether inputs(outputs) sharing an identical lock(type) script run the
+    // script only once for the whole group, see LoadScriptGroup
+    pub group_scripts: bool,
+}
+
+static VM_CONFIGS: [VmConfig; 2] = [
+    VmConfig {
+        version: 0,
+        cost_model: instruction_cycles,
+        syscalls: &[
+            Syscall::LoadTx,
+            Syscall::LoadCell,
+            Syscall::LoadCellByField,
+            Syscall::LoadInputByField,
+            Syscall::Debug,
+        ],
+        group_scripts: false,
+    },
+    VmConfig {
+        version: 1,
+        cost_model: instruction_cycles,
+        syscalls: &[
+            Syscall::LoadTx,
+            Syscall::LoadCell,
+            Syscall::LoadCellByField,
+            Syscall::LoadInputByField,
+            Syscall::LoadScriptGroup,
+            Syscall::Debug,
+        ],
+        group_scripts: true,
+    },
+];
+
+pub fn vm_config(version: u8) -> Result<&'static VmConfig, ScriptError> {
+    VM_CONFIGS
+        .get(version as usize)
+        .ok_or(ScriptError::UnsupportedVersion(version))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vm_config_versions() {
+        for (version, config) in VM_CONFIGS.iter().enumerate() {
+            assert_eq!(config.version as usize, version);
+        }
+        assert!(vm_config(0).is_ok());
+        assert!(!vm_config(0)
+            .unwrap()
+            .syscalls
+            .contains(&Syscall::LoadScriptGroup));
+        assert_eq!(
+            vm_config(VM_CONFIGS.len() as u8).err(),
+            Some(ScriptError::UnsupportedVersion(VM_CONFIGS.len() as u8))
+        );
+    }
+}
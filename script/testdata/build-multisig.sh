@@ -0,0 +1,12 @@
+#!/bin/sh
+# Builds testdata/multisig from multisig.rs, which needs a nightly toolchain
+# but no prebuilt RISC-V target libraries as the program is `no_core`.
+#
+# Copy the result to nodes_template/spec/cells/multisig after rebuilding.
+set -e
+
+cd "$(dirname "$0")"
+rustc +nightly --edition 2018 --target riscv64imac-unknown-none-elf \
+    --crate-type bin -C panic=abort -C opt-level=2 -C overflow-checks=off \
+    -C linker=rust-lld -C linker-flavor=ld.lld -C strip=symbols \
+    -o multisig multisig.rs
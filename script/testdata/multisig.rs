@@ -0,0 +1,1124 @@
+// M-of-N secp256k1 multisig lock script, build it with build-multisig.sh.
+//
+// Arguments are listed in the following order:
+// 0. Program name, ignored here, only preserved for compatibility reason
+// 1. Threshold M in hex format, a single byte
+// 2. Number of pubkeys N in hex format, a single byte
+// 3. N pubkeys in hex format, compressed or uncompressed
+// 4. M DER signatures in hex format, a maximum of 256 bytes each
+// 5. Current script hash in hex format, see verify.c for details
+// 6. Other additional parameters that might be included.
+//
+// Threshold, pubkeys are expected to come from signed_args, while signatures
+// and the remaining parameters come from args.
+//
+// Like verify.c, this program runs double sha3 on all arguments after the
+// signatures and uses the result as the message. Signatures must be provided
+// in the same order as the pubkeys they are signed with, each pubkey can be
+// used at most once, the same rule as Bitcoin's OP_CHECKMULTISIG. Only low-S
+// signatures are accepted, like secp256k1_ecdsa_verify. It returns 0 if M valid
+// signatures are found, and a non-zero value otherwise. An empty hex argument is
+// an error.
+//
+// Note all hex values passed in as arguments must have lower case letters for
+// deterministic behavior.
+//
+// The program is built with `no_core`, so no RISC-V build of core is needed,
+// the few language items it uses are declared below.
+
+#![feature(no_core, lang_items, rustc_attrs, decl_macro, auto_traits)]
+#![allow(internal_features)]
+#![no_core]
+#![no_std]
+#![no_main]
+
+// ---- minimal language items ----
+
+#[lang = "pointee_sized"]
+pub trait PointeeSized {}
+
+#[lang = "meta_sized"]
+pub trait MetaSized: PointeeSized {}
+
+#[lang = "sized"]
+pub trait Sized: MetaSized {}
+
+#[lang = "copy"]
+pub trait Copy {}
+
+impl<T: Copy, const LEN: usize> Copy for [T; LEN] {}
+
+#[lang = "freeze"]
+pub unsafe auto trait Freeze {}
+
+#[lang = "unpin"]
+pub auto trait Unpin {}
+
+#[lang = "unsafe_unpin"]
+pub unsafe auto trait UnsafeUnpin {}
+
+#[lang = "legacy_receiver"]
+pub trait LegacyReceiver {}
+
+impl<T: PointeeSized> LegacyReceiver for &T {}
+
+impl<T: PointeeSized> LegacyReceiver for &mut T {}
+
+#[lang = "index"]
+pub trait Index<Idx> {
+    type Output: ?Sized;
+    fn index(&self, index: Idx) -> &Self::Output;
+}
+
+impl<T, const LEN: usize> Index<usize> for [T; LEN] {
+    type Output = T;
+    fn index(&self, index: usize) -> &T {
+        &self[index]
+    }
+}
+
+#[lang = "index_mut"]
+pub trait IndexMut<Idx>: Index<Idx> {
+    fn index_mut(&mut self, index: Idx) -> &mut Self::Output;
+}
+
+impl<T, const LEN: usize> IndexMut<usize> for [T; LEN] {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        &mut self[index]
+    }
+}
+
+#[lang = "deref"]
+pub trait Deref {
+    #[lang = "deref_target"]
+    type Target: ?Sized;
+    fn deref(&self) -> &Self::Target;
+}
+
+impl<T: ?Sized> Deref for &T {
+    type Target = T;
+    fn deref(&self) -> &T {
+        *self
+    }
+}
+
+impl<T: ?Sized> Deref for &mut T {
+    type Target = T;
+    fn deref(&self) -> &T {
+        *self
+    }
+}
+
+#[lang = "deref_mut"]
+pub trait DerefMut: Deref {
+    fn deref_mut(&mut self) -> &mut Self::Target;
+}
+
+impl<T: ?Sized> DerefMut for &mut T {
+    fn deref_mut(&mut self) -> &mut T {
+        *self
+    }
+}
+
+#[lang = "eq"]
+pub trait PartialEq<Rhs = Self> {
+    fn eq(&self, other: &Rhs) -> bool;
+    fn ne(&self, other: &Rhs) -> bool;
+}
+
+#[lang = "partial_ord"]
+pub trait PartialOrd<Rhs = Self>: PartialEq<Rhs> {
+    fn lt(&self, other: &Rhs) -> bool;
+    fn le(&self, other: &Rhs) -> bool;
+    fn gt(&self, other: &Rhs) -> bool;
+    fn ge(&self, other: &Rhs) -> bool;
+}
+
+#[lang = "not"]
+pub trait Not {
+    type Output;
+    fn not(self) -> Self::Output;
+}
+
+macro_rules! binary_op {
+    ($lang:tt, $trait:ident, $method:ident, $op:tt, $($t:ty)*) => {
+        #[lang = $lang]
+        pub trait $trait<Rhs = Self> {
+            type Output;
+            fn $method(self, rhs: Rhs) -> Self::Output;
+        }
+        $(
+            impl $trait for $t {
+                type Output = $t;
+                fn $method(self, rhs: $t) -> $t {
+                    self $op rhs
+                }
+            }
+        )*
+    };
+}
+
+binary_op!("add", Add, add, +, u8 u64 u128 usize);
+binary_op!("sub", Sub, sub, -, u8 u64 u128 usize);
+binary_op!("mul", Mul, mul, *, u64 u128 usize);
+binary_op!("bitand", BitAnd, bitand, &, u8 u64 u128 usize);
+binary_op!("bitor", BitOr, bitor, |, u8 u64 u128 usize);
+binary_op!("bitxor", BitXor, bitxor, ^, u8 u64);
+
+#[lang = "shl"]
+pub trait Shl<Rhs> {
+    type Output;
+    fn shl(self, rhs: Rhs) -> Self::Output;
+}
+
+#[lang = "shr"]
+pub trait Shr<Rhs> {
+    type Output;
+    fn shr(self, rhs: Rhs) -> Self::Output;
+}
+
+macro_rules! shift_op {
+    ($($t:ty)*) => {
+        $(
+            impl Shl<u64> for $t {
+                type Output = $t;
+                fn shl(self, rhs: u64) -> $t {
+                    self << rhs
+                }
+            }
+            impl Shr<u64> for $t {
+                type Output = $t;
+                fn shr(self, rhs: u64) -> $t {
+                    self >> rhs
+                }
+            }
+        )*
+    };
+}
+
+shift_op!(u64 u128 usize);
+
+macro_rules! compare_op {
+    ($($t:ty)*) => {
+        $(
+            impl Copy for $t {}
+            impl PartialEq for $t {
+                fn eq(&self, other: &$t) -> bool {
+                    *self == *other
+                }
+                fn ne(&self, other: &$t) -> bool {
+                    *self != *other
+                }
+            }
+            impl PartialOrd for $t {
+                fn lt(&self, other: &$t) -> bool {
+                    *self < *other
+                }
+                fn le(&self, other: &$t) -> bool {
+                    *self <= *other
+                }
+                fn gt(&self, other: &$t) -> bool {
+                    *self > *other
+                }
+                fn ge(&self, other: &$t) -> bool {
+                    *self >= *other
+                }
+            }
+        )*
+    };
+}
+
+compare_op!(u8 u64 u128 usize);
+
+impl Copy for bool {}
+
+impl PartialEq for bool {
+    fn eq(&self, other: &bool) -> bool {
+        *self == *other
+    }
+    fn ne(&self, other: &bool) -> bool {
+        *self != *other
+    }
+}
+
+impl Not for bool {
+    type Output = bool;
+    fn not(self) -> bool {
+        !self
+    }
+}
+
+impl Not for u64 {
+    type Output = u64;
+    fn not(self) -> u64 {
+        !self
+    }
+}
+
+impl<T: PointeeSized> Copy for *const T {}
+
+#[lang = "panic_location"]
+#[allow(dead_code)]
+pub struct Location<'a> {
+    file: &'a str,
+    line: u32,
+    col: u32,
+}
+
+#[lang = "panic_bounds_check"]
+#[track_caller]
+fn panic_bounds_check(_index: usize, _len: usize) -> ! {
+    unsafe { exit(ERROR_INTERNAL) }
+}
+
+#[rustc_builtin_macro]
+macro global_asm("assembly template", $(operands,)* $(options($(option),*))?) {}
+
+// The VM starts with argc at sp, followed by the argv pointers. LLVM may emit
+// calls to memcpy and memset, they are written in assembly so they can't be
+// compiled back into calls to themselves.
+global_asm!(
+    "
+    .section .text._start
+    .globl _start
+_start:
+    ld a0, 0(sp)
+    addi a1, sp, 8
+    andi sp, sp, -16
+    call main
+    li a7, 93
+    ecall
+
+    .section .text.memcpy
+    .globl memcpy
+memcpy:
+    mv t0, a0
+1:
+    beqz a2, 2f
+    lbu t1, 0(a1)
+    sb t1, 0(t0)
+    addi a1, a1, 1
+    addi t0, t0, 1
+    addi a2, a2, -1
+    j 1b
+2:
+    ret
+
+    .section .text.memset
+    .globl memset
+memset:
+    mv t0, a0
+1:
+    beqz a2, 2f
+    sb a1, 0(t0)
+    addi t0, t0, 1
+    addi a2, a2, -1
+    j 1b
+2:
+    ret
+
+    .section .text.exit
+    .globl exit
+exit:
+    li a7, 93
+    ecall
+"
+);
+
+extern "C" {
+    fn exit(code: u64) -> !;
+}
+
+// ---- exit codes ----
+
+const ERROR_ARGUMENTS: u64 = 6;
+const ERROR_PUBKEY: u64 = 1;
+const ERROR_VERIFY: u64 = 2;
+const ERROR_SIGNATURE: u64 = 3;
+const ERROR_INTERNAL: u64 = 4;
+const ERROR_CONFIG: u64 = 5;
+
+const MAX_PUBKEYS: usize = 16;
+
+// ---- 256-bit arithmetic, little-endian 64-bit limbs ----
+
+type U256 = [u64; 4];
+
+fn adc(a: u64, b: u64, carry: u64) -> (u64, u64) {
+    let t = a as u128 + b as u128 + carry as u128;
+    (t as u64, (t >> 64) as u64)
+}
+
+fn sbb(a: u64, b: u64, borrow: u64) -> (u64, u64) {
+    let t = (a as u128) - (b as u128) - (borrow as u128);
+    (t as u64, (t >> 127) as u64)
+}
+
+// a + b * c + carry, which never overflows 128 bits
+fn mac(a: u64, b: u64, c: u64, carry: u64) -> (u64, u64) {
+    let t = a as u128 + (b as u128) * (c as u128) + carry as u128;
+    (t as u64, (t >> 64) as u64)
+}
+
+fn add(a: &U256, b: &U256) -> (U256, u64) {
+    let (r0, c) = adc(a[0], b[0], 0);
+    let (r1, c) = adc(a[1], b[1], c);
+    let (r2, c) = adc(a[2], b[2], c);
+    let (r3, c) = adc(a[3], b[3], c);
+    ([r0, r1, r2, r3], c)
+}
+
+fn sub(a: &U256, b: &U256) -> (U256, u64) {
+    let (r0, c) = sbb(a[0], b[0], 0);
+    let (r1, c) = sbb(a[1], b[1], c);
+    let (r2, c) = sbb(a[2], b[2], c);
+    let (r3, c) = sbb(a[3], b[3], c);
+    ([r0, r1, r2, r3], c)
+}
+
+fn is_zero(a: &U256) -> bool {
+    (a[0] | a[1] | a[2] | a[3]) == 0
+}
+
+fn equal(a: &U256, b: &U256) -> bool {
+    a[0] == b[0] && a[1] == b[1] && a[2] == b[2] && a[3] == b[3]
+}
+
+fn less_than(a: &U256, b: &U256) -> bool {
+    sub(a, b).1 == 1
+}
+
+fn bit(a: &U256, index: usize) -> bool {
+    (a[index >> 6] >> (index & 63) as u64) & 1 == 1
+}
+
+fn from_be_bytes(bytes: &[u8; 32]) -> U256 {
+    let mut limbs = [0u64; 4];
+    let mut i = 0;
+    while i < 32 {
+        let limb = 3 - (i >> 3);
+        limbs[limb] = (limbs[limb] << 8) | bytes[i] as u64;
+        i = i + 1;
+    }
+    limbs
+}
+
+// ---- Montgomery arithmetic modulo p or n ----
+
+struct Modulus {
+    m: U256,
+    // -m^-1 mod 2^64
+    minv: u64,
+    // 2^512 mod m
+    r2: U256,
+}
+
+const P: Modulus = Modulus {
+    m: [
+        0xfffffffefffffc2f,
+        0xffffffffffffffff,
+        0xffffffffffffffff,
+        0xffffffffffffffff,
+    ],
+    minv: 0xd838091dd2253531,
+    r2: [0x000007a2000e90a1, 0x0000000000000001, 0, 0],
+};
+
+const N: Modulus = Modulus {
+    m: [
+        0xbfd25e8cd0364141,
+        0xbaaedce6af48a03b,
+        0xfffffffffffffffe,
+        0xffffffffffffffff,
+    ],
+    minv: 0x4b0dff665588b13f,
+    r2: [
+        0x896cf21467d7d140,
+        0x741496c20e7cf878,
+        0xe697f5e45bcd07c6,
+        0x9d671cd581c69bc5,
+    ],
+};
+
+const P_MINUS_2: U256 = [
+    0xfffffffefffffc2d,
+    0xffffffffffffffff,
+    0xffffffffffffffff,
+    0xffffffffffffffff,
+];
+// square roots are a^((p + 1) / 4) as p = 3 mod 4
+const P_PLUS_1_DIV_4: U256 = [
+    0xffffffffbfffff0c,
+    0xffffffffffffffff,
+    0xffffffffffffffff,
+    0x3fffffffffffffff,
+];
+const N_MINUS_2: U256 = [
+    0xbfd25e8cd036413f,
+    0xbaaedce6af48a03b,
+    0xfffffffffffffffe,
+    0xffffffffffffffff,
+];
+const N_HALF: U256 = [
+    0xdfe92f46681b20a0,
+    0x5d576e7357a4501d,
+    0xffffffffffffffff,
+    0x7fffffffffffffff,
+];
+const GX: U256 = [
+    0x59f2815b16f81798,
+    0x029bfcdb2dce28d9,
+    0x55a06295ce870b07,
+    0x79be667ef9dcbbac,
+];
+const GY: U256 = [
+    0x9c47d08ffb10d4b8,
+    0xfd17b448a6855419,
+    0x5da4fbfc0e1108a8,
+    0x483ada7726a3c465,
+];
+const ONE: U256 = [1, 0, 0, 0];
+const SEVEN: U256 = [7, 0, 0, 0];
+
+// a * b / 2^256 mod m, for a, b < m
+fn mont_mul(a: &U256, b: &U256, modulus: &Modulus) -> U256 {
+    let m = &modulus.m;
+    let mut t = [0u64; 6];
+    let mut i = 0;
+    while i < 4 {
+        let mut carry = 0;
+        let mut j = 0;
+        while j < 4 {
+            let (lo, hi) = mac(t[j], a[i], b[j], carry);
+            t[j] = lo;
+            carry = hi;
+            j = j + 1;
+        }
+        let (lo, hi) = adc(t[4], carry, 0);
+        t[4] = lo;
+        t[5] = hi;
+
+        let u = t[0] * modulus.minv;
+        let (_, mut carry) = mac(t[0], u, m[0], 0);
+        let mut j = 1;
+        while j < 4 {
+            let (lo, hi) = mac(t[j], u, m[j], carry);
+            t[j - 1] = lo;
+            carry = hi;
+            j = j + 1;
+        }
+        let (lo, hi) = adc(t[4], carry, 0);
+        t[3] = lo;
+        t[4] = t[5] + hi;
+        i = i + 1;
+    }
+    let result = [t[0], t[1], t[2], t[3]];
+    let (reduced, borrow) = sub(&result, m);
+    if t[4] != 0 || borrow == 0 {
+        reduced
+    } else {
+        result
+    }
+}
+
+fn to_mont(a: &U256, modulus: &Modulus) -> U256 {
+    mont_mul(a, &modulus.r2, modulus)
+}
+
+fn from_mont(a: &U256, modulus: &Modulus) -> U256 {
+    mont_mul(a, &ONE, modulus)
+}
+
+fn add_mod(a: &U256, b: &U256, modulus: &Modulus) -> U256 {
+    let (sum, carry) = add(a, b);
+    let (reduced, borrow) = sub(&sum, &modulus.m);
+    if carry == 1 || borrow == 0 {
+        reduced
+    } else {
+        sum
+    }
+}
+
+fn sub_mod(a: &U256, b: &U256, modulus: &Modulus) -> U256 {
+    let (difference, borrow) = sub(a, b);
+    if borrow == 1 {
+        add(&difference, &modulus.m).0
+    } else {
+        difference
+    }
+}
+
+// a^e of a Montgomery form a, the result is in Montgomery form
+fn pow(a: &U256, e: &U256, modulus: &Modulus) -> U256 {
+    let mut result = to_mont(&ONE, modulus);
+    let mut i = 256;
+    while i > 0 {
+        i = i - 1;
+        result = mont_mul(&result, &result, modulus);
+        if bit(e, i) {
+            result = mont_mul(&result, a, modulus);
+        }
+    }
+    result
+}
+
+// ---- secp256k1 points in Jacobian coordinates, Montgomery form modulo p ----
+
+struct Point {
+    x: U256,
+    y: U256,
+    // zero for the point at infinity
+    z: U256,
+}
+
+impl Copy for Point {}
+
+fn fmul(a: &U256, b: &U256) -> U256 {
+    mont_mul(a, b, &P)
+}
+
+fn fadd(a: &U256, b: &U256) -> U256 {
+    add_mod(a, b, &P)
+}
+
+fn fsub(a: &U256, b: &U256) -> U256 {
+    sub_mod(a, b, &P)
+}
+
+fn infinity() -> Point {
+    Point {
+        x: [0; 4],
+        y: [0; 4],
+        z: [0; 4],
+    }
+}
+
+fn double(p: &Point) -> Point {
+    if is_zero(&p.z) {
+        return *p;
+    }
+    let a = fmul(&p.x, &p.x);
+    let b = fmul(&p.y, &p.y);
+    let c = fmul(&b, &b);
+    let xb = fadd(&p.x, &b);
+    let d = fsub(&fsub(&fmul(&xb, &xb), &a), &c);
+    let d = fadd(&d, &d);
+    let e = fadd(&fadd(&a, &a), &a);
+    let f = fmul(&e, &e);
+    let x = fsub(&fsub(&f, &d), &d);
+    let c2 = fadd(&c, &c);
+    let c4 = fadd(&c2, &c2);
+    let c8 = fadd(&c4, &c4);
+    let y = fsub(&fmul(&e, &fsub(&d, &x)), &c8);
+    let yz = fmul(&p.y, &p.z);
+    let z = fadd(&yz, &yz);
+    Point { x, y, z }
+}
+
+fn add_points(p: &Point, q: &Point) -> Point {
+    if is_zero(&p.z) {
+        return *q;
+    }
+    if is_zero(&q.z) {
+        return *p;
+    }
+    let z1z1 = fmul(&p.z, &p.z);
+    let z2z2 = fmul(&q.z, &q.z);
+    let u1 = fmul(&p.x, &z2z2);
+    let u2 = fmul(&q.x, &z1z1);
+    let s1 = fmul(&fmul(&p.y, &q.z), &z2z2);
+    let s2 = fmul(&fmul(&q.y, &p.z), &z1z1);
+    let h = fsub(&u2, &u1);
+    let r = fsub(&s2, &s1);
+    if is_zero(&h) {
+        if is_zero(&r) {
+            return double(p);
+        }
+        return infinity();
+    }
+    let hh = fmul(&h, &h);
+    let hhh = fmul(&h, &hh);
+    let v = fmul(&u1, &hh);
+    let x = fsub(&fsub(&fsub(&fmul(&r, &r), &hhh), &v), &v);
+    let y = fsub(&fmul(&r, &fsub(&v, &x)), &fmul(&s1, &hhh));
+    let z = fmul(&fmul(&p.z, &q.z), &h);
+    Point { x, y, z }
+}
+
+// u1 * G + u2 * q with Shamir's trick
+fn mul_add(u1: &U256, u2: &U256, q: &Point) -> Point {
+    let g = Point {
+        x: to_mont(&GX, &P),
+        y: to_mont(&GY, &P),
+        z: to_mont(&ONE, &P),
+    };
+    let gq = add_points(&g, q);
+    let mut result = infinity();
+    let mut i = 256;
+    while i > 0 {
+        i = i - 1;
+        result = double(&result);
+        let b1 = bit(u1, i);
+        let b2 = bit(u2, i);
+        if b1 && b2 {
+            result = add_points(&result, &gq);
+        } else if b1 {
+            result = add_points(&result, &g);
+        } else if b2 {
+            result = add_points(&result, q);
+        }
+    }
+    result
+}
+
+fn is_on_curve(x: &U256, y: &U256) -> bool {
+    let x3 = fmul(&fmul(x, x), x);
+    let rhs = fadd(&x3, &to_mont(&SEVEN, &P));
+    equal(&fmul(y, y), &rhs)
+}
+
+fn is_odd(a: &U256) -> bool {
+    from_mont(a, &P)[0] & 1 == 1
+}
+
+// Like secp256k1_ec_pubkey_parse, accepts compressed, uncompressed and hybrid keys
+fn parse_pubkey(bytes: &[u8; 256], len: usize, pubkey: &mut Point) -> bool {
+    let mut x_bytes = [0u8; 32];
+    let mut i = 0;
+    while i < 32 {
+        x_bytes[i] = bytes[1 + i];
+        i = i + 1;
+    }
+    let x = from_be_bytes(&x_bytes);
+    if !less_than(&x, &P.m) {
+        return false;
+    }
+    let x = to_mont(&x, &P);
+    let prefix = bytes[0];
+    let y = if len == 33 && (prefix == 2 || prefix == 3) {
+        let x3 = fmul(&fmul(&x, &x), &x);
+        let y2 = fadd(&x3, &to_mont(&SEVEN, &P));
+        let y = pow(&y2, &P_PLUS_1_DIV_4, &P);
+        if is_odd(&y) != (prefix == 3) {
+            fsub(&[0; 4], &y)
+        } else {
+            y
+        }
+    } else if len == 65 && (prefix == 4 || prefix == 6 || prefix == 7) {
+        let mut y_bytes = [0u8; 32];
+        let mut i = 0;
+        while i < 32 {
+            y_bytes[i] = bytes[33 + i];
+            i = i + 1;
+        }
+        let y = from_be_bytes(&y_bytes);
+        if !less_than(&y, &P.m) {
+            return false;
+        }
+        let y = to_mont(&y, &P);
+        if prefix != 4 && is_odd(&y) != (prefix == 7) {
+            return false;
+        }
+        y
+    } else {
+        return false;
+    };
+    if !is_on_curve(&x, &y) {
+        return false;
+    }
+    *pubkey = Point {
+        x,
+        y,
+        z: to_mont(&ONE, &P),
+    };
+    true
+}
+
+// ---- DER signatures, the same rules as secp256k1_ecdsa_signature_parse_der ----
+
+// Stores the length in len, returns false for invalid encodings
+fn der_read_len(sig: &[u8; 256], pos: &mut usize, end: usize, len: &mut usize) -> bool {
+    if *pos >= end {
+        return false;
+    }
+    let b1 = sig[*pos];
+    *pos = *pos + 1;
+    if b1 == 0xff {
+        return false;
+    }
+    if b1 & 0x80 == 0 {
+        *len = b1 as usize;
+        return true;
+    }
+    if b1 == 0x80 {
+        return false;
+    }
+    let mut lenleft = (b1 & 0x7f) as usize;
+    if lenleft > end - *pos {
+        return false;
+    }
+    if sig[*pos] == 0 {
+        return false;
+    }
+    if lenleft > 8 {
+        return false;
+    }
+    let mut ret = 0usize;
+    while lenleft > 0 {
+        ret = (ret << 8) | sig[*pos] as usize;
+        if ret + lenleft > end - *pos {
+            return false;
+        }
+        *pos = *pos + 1;
+        lenleft = lenleft - 1;
+    }
+    if ret < 128 {
+        return false;
+    }
+    *len = ret;
+    true
+}
+
+// An integer which doesn't fit a scalar is parsed as zero and fails verification
+fn der_parse_integer(sig: &[u8; 256], pos: &mut usize, end: usize, value: &mut U256) -> bool {
+    if *pos == end || sig[*pos] != 0x02 {
+        return false;
+    }
+    *pos = *pos + 1;
+    let mut rlen = 0;
+    if !der_read_len(sig, pos, end, &mut rlen) {
+        return false;
+    }
+    if rlen == 0 || rlen > end - *pos {
+        return false;
+    }
+    if sig[*pos] == 0x00 && rlen > 1 && sig[*pos + 1] & 0x80 == 0x00 {
+        return false;
+    }
+    if sig[*pos] == 0xff && rlen > 1 && sig[*pos + 1] & 0x80 == 0x80 {
+        return false;
+    }
+    let mut overflow = sig[*pos] & 0x80 == 0x80;
+    while rlen > 0 && sig[*pos] == 0 {
+        *pos = *pos + 1;
+        rlen = rlen - 1;
+    }
+    if rlen > 32 {
+        overflow = true;
+    }
+    *value = [0; 4];
+    if !overflow {
+        let mut bytes = [0u8; 32];
+        let mut i = 0;
+        while i < rlen {
+            bytes[32 - rlen + i] = sig[*pos + i];
+            i = i + 1;
+        }
+        let scalar = from_be_bytes(&bytes);
+        if less_than(&scalar, &N.m) {
+            *value = scalar;
+        }
+    }
+    *pos = *pos + rlen;
+    true
+}
+
+fn parse_signature(sig: &[u8; 256], len: usize, r: &mut U256, s: &mut U256) -> bool {
+    let mut pos = 0;
+    if len == 0 || sig[0] != 0x30 {
+        return false;
+    }
+    pos = pos + 1;
+    let mut rlen = 0;
+    if !der_read_len(sig, &mut pos, len, &mut rlen) {
+        return false;
+    }
+    if rlen != len - pos {
+        return false;
+    }
+    if !der_parse_integer(sig, &mut pos, len, r) {
+        return false;
+    }
+    if !der_parse_integer(sig, &mut pos, len, s) {
+        return false;
+    }
+    pos == len
+}
+
+// ---- ECDSA ----
+
+fn verify(r: &U256, s: &U256, message: &U256, pubkey: &Point) -> bool {
+    if is_zero(r) || is_zero(s) || less_than(&N_HALF, s) {
+        return false;
+    }
+    let s_inv = pow(&to_mont(s, &N), &N_MINUS_2, &N);
+    let u1 = mont_mul(message, &s_inv, &N);
+    let u2 = mont_mul(r, &s_inv, &N);
+    let point = mul_add(&u1, &u2, pubkey);
+    if is_zero(&point.z) {
+        return false;
+    }
+    let z_inv = pow(&point.z, &P_MINUS_2, &P);
+    let x = from_mont(&fmul(&point.x, &fmul(&z_inv, &z_inv)), &P);
+    // x mod n == r, where x < p < 2n
+    let (x_minus_n, borrow) = sub(&x, &N.m);
+    equal(&x, r) || (borrow == 0 && equal(&x_minus_n, r))
+}
+
+// ---- SHA3-256 ----
+
+const KECCAK_ROUND_CONSTANTS: [u64; 24] = [
+    0x0000000000000001,
+    0x0000000000008082,
+    0x800000000000808a,
+    0x8000000080008000,
+    0x000000000000808b,
+    0x0000000080000001,
+    0x8000000080008081,
+    0x8000000000008009,
+    0x000000000000008a,
+    0x0000000000000088,
+    0x0000000080008009,
+    0x000000008000000a,
+    0x000000008000808b,
+    0x800000000000008b,
+    0x8000000000008089,
+    0x8000000000008003,
+    0x8000000000008002,
+    0x8000000000000080,
+    0x000000000000800a,
+    0x800000008000000a,
+    0x8000000080008081,
+    0x8000000000008080,
+    0x0000000080000001,
+    0x8000000080008008,
+];
+const KECCAK_ROTATIONS: [u64; 24] = [
+    1, 3, 6, 10, 15, 21, 28, 36, 45, 55, 2, 14, 27, 41, 56, 8, 25, 43, 62, 18, 39, 61, 20, 44,
+];
+const KECCAK_PI_LANES: [usize; 24] = [
+    10, 7, 11, 17, 18, 3, 5, 16, 8, 21, 24, 4, 15, 23, 19, 13, 12, 2, 20, 14, 22, 9, 6, 1,
+];
+// i mod 5 for i < 10
+const MOD5: [usize; 10] = [0, 1, 2, 3, 4, 0, 1, 2, 3, 4];
+const SHA3_RATE: usize = 136;
+
+fn rotl(x: u64, n: u64) -> u64 {
+    (x << n) | (x >> (64 - n))
+}
+
+fn keccakf(st: &mut [u64; 25]) {
+    let mut bc = [0u64; 5];
+    let mut round = 0;
+    while round < 24 {
+        let mut i = 0;
+        while i < 5 {
+            bc[i] = st[i] ^ st[i + 5] ^ st[i + 10] ^ st[i + 15] ^ st[i + 20];
+            i = i + 1;
+        }
+        let mut i = 0;
+        while i < 5 {
+            let t = bc[MOD5[i + 4]] ^ rotl(bc[MOD5[i + 1]], 1);
+            let mut j = 0;
+            while j < 25 {
+                st[j + i] = st[j + i] ^ t;
+                j = j + 5;
+            }
+            i = i + 1;
+        }
+
+        let mut t = st[1];
+        let mut i = 0;
+        while i < 24 {
+            let j = KECCAK_PI_LANES[i];
+            let lane = st[j];
+            st[j] = rotl(t, KECCAK_ROTATIONS[i]);
+            t = lane;
+            i = i + 1;
+        }
+
+        let mut j = 0;
+        while j < 25 {
+            let mut i = 0;
+            while i < 5 {
+                bc[i] = st[j + i];
+                i = i + 1;
+            }
+            let mut i = 0;
+            while i < 5 {
+                st[j + i] = st[j + i] ^ (!bc[MOD5[i + 1]] & bc[MOD5[i + 2]]);
+                i = i + 1;
+            }
+            j = j + 5;
+        }
+
+        st[0] = st[0] ^ KECCAK_ROUND_CONSTANTS[round];
+        round = round + 1;
+    }
+}
+
+struct Sha3 {
+    st: [u64; 25],
+    pt: usize,
+}
+
+fn sha3_init() -> Sha3 {
+    Sha3 { st: [0; 25], pt: 0 }
+}
+
+fn sha3_update_byte(ctx: &mut Sha3, byte: u8) {
+    ctx.st[ctx.pt >> 3] = ctx.st[ctx.pt >> 3] ^ ((byte as u64) << ((ctx.pt & 7) * 8) as u64);
+    ctx.pt = ctx.pt + 1;
+    if ctx.pt == SHA3_RATE {
+        keccakf(&mut ctx.st);
+        ctx.pt = 0;
+    }
+}
+
+fn sha3_final(ctx: &mut Sha3, hash: &mut [u8; 32]) {
+    sha3_update_byte_xor(ctx, ctx.pt, 0x06);
+    sha3_update_byte_xor(ctx, SHA3_RATE - 1, 0x80);
+    keccakf(&mut ctx.st);
+    let mut i = 0;
+    while i < 32 {
+        hash[i] = (ctx.st[i >> 3] >> ((i & 7) * 8) as u64) as u8;
+        i = i + 1;
+    }
+}
+
+fn sha3_update_byte_xor(ctx: &mut Sha3, pt: usize, byte: u8) {
+    ctx.st[pt >> 3] = ctx.st[pt >> 3] ^ ((byte as u64) << ((pt & 7) * 8) as u64);
+}
+
+// ---- arguments ----
+
+unsafe fn arg(argv: *const *const u8, index: usize) -> *const u8 {
+    *((argv as usize + index * 8) as *const *const u8)
+}
+
+unsafe fn byte_at(s: *const u8, index: usize) -> u8 {
+    *((s as usize + index) as *const u8)
+}
+
+fn char_to_int(ch: u8) -> u8 {
+    if ch >= b'0' && ch <= b'9' {
+        return ch - b'0';
+    }
+    if ch >= b'a' && ch <= b'f' {
+        return ch - b'a' + 10;
+    }
+    0xff
+}
+
+// Decodes at most buf_len bytes, returns 0 for empty or invalid hex
+unsafe fn hex_to_bin(buf: &mut [u8; 256], buf_len: usize, hex: *const u8) -> usize {
+    let mut i = 0;
+    while i < buf_len && byte_at(hex, i * 2) != 0 && byte_at(hex, i * 2 + 1) != 0 {
+        let a = char_to_int(byte_at(hex, i * 2));
+        let b = char_to_int(byte_at(hex, i * 2 + 1));
+        if a == 0xff || b == 0xff {
+            return 0;
+        }
+        buf[i] = ((a as u64) << 4 | b as u64) as u8;
+        i = i + 1;
+    }
+    if i == buf_len && byte_at(hex, i * 2) != 0 {
+        return 0;
+    }
+    i
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn main(argc: u64, argv: *const *const u8) -> u64 {
+    let argc = argc as usize;
+    let mut buf = [0u8; 256];
+
+    if argc < 4 {
+        return ERROR_ARGUMENTS;
+    }
+    if hex_to_bin(&mut buf, 1, arg(argv, 1)) == 0 {
+        return ERROR_ARGUMENTS;
+    }
+    let threshold = buf[0] as usize;
+    if hex_to_bin(&mut buf, 1, arg(argv, 2)) == 0 {
+        return ERROR_ARGUMENTS;
+    }
+    let pubkeys_count = buf[0] as usize;
+
+    if threshold == 0 || pubkeys_count > MAX_PUBKEYS || threshold > pubkeys_count {
+        return ERROR_CONFIG;
+    }
+    if argc < 3 + pubkeys_count + threshold + 1 {
+        return ERROR_ARGUMENTS;
+    }
+
+    let mut pubkeys = [infinity(); MAX_PUBKEYS];
+    let mut i = 0;
+    while i < pubkeys_count {
+        let len = hex_to_bin(&mut buf, 65, arg(argv, 3 + i));
+        if len == 0 {
+            return ERROR_ARGUMENTS;
+        }
+        if !parse_pubkey(&buf, len, &mut pubkeys[i]) {
+            return ERROR_PUBKEY;
+        }
+        i = i + 1;
+    }
+
+    let message_start = 3 + pubkeys_count + threshold;
+    let mut ctx = sha3_init();
+    let mut i = message_start;
+    while i < argc {
+        let s = arg(argv, i);
+        let mut j = 0;
+        while byte_at(s, j) != 0 {
+            sha3_update_byte(&mut ctx, byte_at(s, j));
+            j = j + 1;
+        }
+        i = i + 1;
+    }
+    let mut hash = [0u8; 32];
+    sha3_final(&mut ctx, &mut hash);
+    let mut ctx = sha3_init();
+    let mut i = 0;
+    while i < 32 {
+        sha3_update_byte(&mut ctx, hash[i]);
+        i = i + 1;
+    }
+    sha3_final(&mut ctx, &mut hash);
+
+    // the message as a scalar, reduced modulo n
+    let message = from_be_bytes(&hash);
+    let (reduced, borrow) = sub(&message, &N.m);
+    let message = if borrow == 0 { reduced } else { message };
+
+    let mut pubkey_index = 0;
+    let mut i = 0;
+    while i < threshold {
+        let len = hex_to_bin(&mut buf, 256, arg(argv, 3 + pubkeys_count + i));
+        if len == 0 {
+            return ERROR_ARGUMENTS;
+        }
+        let mut r = [0u64; 4];
+        let mut s = [0u64; 4];
+        if !parse_signature(&buf, len, &mut r, &mut s) {
+            return ERROR_SIGNATURE;
+        }
+
+        let mut matched = false;
+        while pubkey_index < pubkeys_count {
+            let valid = verify(&r, &s, &message, &pubkeys[pubkey_index]);
+            pubkey_index = pubkey_index + 1;
+            if valid {
+                matched = true;
+                break;
+            }
+        }
+        if !matched {
+            return ERROR_VERIFY;
+        }
+        i = i + 1;
+    }
+
+    0
+}
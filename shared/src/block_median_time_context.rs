@@ -1,8 +1,13 @@
+use faketime::unix_time_as_millis;
 use numext_fixed_hash::H256;
 pub trait BlockMedianTimeContext {
     fn block_count(&self) -> u32;
     fn timestamp(&self, hash: &H256) -> Option<u64>;
     fn parent_hash(&self, hash: &H256) -> Option<H256>;
+    // current time that block timestamps are checked against
+    fn now(&self) -> u64 {
+        unix_time_as_millis()
+    }
     fn block_median_time(&self, hash: &H256) -> Option<u64> {
         let count = self.block_count() as usize;
         let mut block_times = Vec::with_capacity(count);
@@ -84,4 +84,9 @@ where
         }
         self.db.partial_read(col, key, range)
     }
+
+    // writes go through to the db, it has all the entries
+    fn read_all(&self, col: Col) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        self.db.read_all(col)
+    }
 }
@@ -5,6 +5,10 @@ pub enum SharedError {
     InvalidInput,
     InvalidOutput,
     InvalidTransaction,
+    // a snapshot can only be imported into a chain at genesis
+    InvalidSnapshot,
+    // blocks up to an imported snapshot are not stored
+    BelowSnapshot,
     DB(DBError),
 }
 
@@ -1,16 +1,21 @@
 use crate::flat_serializer::serialized_addresses;
+use crate::snapshot::SnapshotTransaction;
 use crate::store::{ChainKVStore, ChainStore};
-use crate::{COLUMN_BLOCK_BODY, COLUMN_INDEX, COLUMN_META, COLUMN_TRANSACTION_ADDR};
+use crate::{
+    COLUMN_BLOCK_BODY, COLUMN_INDEX, COLUMN_META, COLUMN_SNAPSHOT_TRANSACTION,
+    COLUMN_TRANSACTION_ADDR,
+};
 use bincode::{deserialize, serialize};
 use ckb_core::block::Block;
 use ckb_core::extras::{BlockExt, TransactionAddress};
 use ckb_core::header::{BlockNumber, Header};
-use ckb_core::transaction::{Transaction, TransactionBuilder};
+use ckb_core::transaction::{CellOutput, Transaction, TransactionBuilder};
 use ckb_db::batch::Batch;
 use ckb_db::kvdb::KeyValueDB;
 use numext_fixed_hash::H256;
 
 const META_TIP_HEADER_KEY: &[u8] = b"TIP_HEADER";
+const META_SNAPSHOT_NUMBER_KEY: &[u8] = b"SNAPSHOT_NUMBER";
 
 // maintain chain index, extend chainstore
 pub trait ChainIndex: ChainStore {
@@ -20,6 +25,12 @@ pub trait ChainIndex: ChainStore {
     fn get_tip_header(&self) -> Option<Header>;
     fn get_transaction(&self, h: &H256) -> Option<Transaction>;
     fn get_transaction_address(&self, hash: &H256) -> Option<TransactionAddress>;
+    /// Outputs of a transaction, also found for the live transactions of an imported snapshot
+    /// whose blocks are not stored.
+    fn get_transaction_outputs(&self, h: &H256) -> Option<Vec<CellOutput>>;
+    /// Number of the imported snapshot block, blocks up to it have no bodies stored.
+    fn get_snapshot_number(&self) -> Option<BlockNumber>;
+    fn get_snapshot_transactions(&self) -> Vec<SnapshotTransaction>;
 
     fn insert_block_hash(&self, batch: &mut Batch, number: BlockNumber, hash: &H256);
     fn delete_block_hash(&self, batch: &mut Batch, number: BlockNumber);
@@ -28,6 +39,12 @@ pub trait ChainIndex: ChainStore {
     fn insert_tip_header(&self, batch: &mut Batch, h: &Header);
     fn insert_transaction_address(&self, batch: &mut Batch, block_hash: &H256, txs: &[Transaction]);
     fn delete_transaction_address(&self, batch: &mut Batch, txs: &[Transaction]);
+    fn insert_snapshot(
+        &self,
+        batch: &mut Batch,
+        number: BlockNumber,
+        transactions: &[SnapshotTransaction],
+    );
 }
 
 impl<T: 'static + KeyValueDB> ChainIndex for ChainKVStore<T> {
@@ -101,6 +118,33 @@ impl<T: 'static + KeyValueDB> ChainIndex for ChainKVStore<T> {
             .map(|raw| deserialize(&raw[..]).unwrap())
     }
 
+    fn get_transaction_outputs(&self, h: &H256) -> Option<Vec<CellOutput>> {
+        self.get_transaction(h)
+            .map(|tx| tx.outputs().to_vec())
+            .or_else(|| {
+                self.get(COLUMN_SNAPSHOT_TRANSACTION, h.as_bytes())
+                    .map(|raw| {
+                        deserialize::<SnapshotTransaction>(&raw[..])
+                            .unwrap()
+                            .outputs
+                    })
+            })
+    }
+
+    fn get_snapshot_number(&self) -> Option<BlockNumber> {
+        self.get(COLUMN_META, META_SNAPSHOT_NUMBER_KEY)
+            .map(|raw| deserialize(&raw[..]).unwrap())
+    }
+
+    fn get_snapshot_transactions(&self) -> Vec<SnapshotTransaction> {
+        self.db
+            .read_all(COLUMN_SNAPSHOT_TRANSACTION)
+            .expect("db operation should be ok")
+            .into_iter()
+            .map(|(_, raw)| deserialize(&raw[..]).unwrap())
+            .collect()
+    }
+
     fn insert_tip_header(&self, batch: &mut Batch, h: &Header) {
         batch.insert(COLUMN_META, META_TIP_HEADER_KEY.to_vec(), h.hash().to_vec());
     }
@@ -149,6 +193,26 @@ impl<T: 'static + KeyValueDB> ChainIndex for ChainKVStore<T> {
     fn delete_block_number(&self, batch: &mut Batch, hash: &H256) {
         batch.delete(COLUMN_INDEX, hash.to_vec());
     }
+
+    fn insert_snapshot(
+        &self,
+        batch: &mut Batch,
+        number: BlockNumber,
+        transactions: &[SnapshotTransaction],
+    ) {
+        batch.insert(
+            COLUMN_META,
+            META_SNAPSHOT_NUMBER_KEY.to_vec(),
+            serialize(&number).unwrap(),
+        );
+        for tx in transactions {
+            batch.insert(
+                COLUMN_SNAPSHOT_TRANSACTION,
+                tx.hash.to_vec(),
+                serialize(tx).unwrap(),
+            );
+        }
+    }
 }
 
 #[cfg(test)]
@@ -12,7 +12,9 @@ pub mod cachedb;
 pub mod error;
 mod flat_serializer;
 pub mod index;
+pub mod net_time;
 pub mod shared;
+pub mod snapshot;
 pub mod store;
 #[cfg(test)]
 mod tests;
@@ -21,7 +23,7 @@ pub mod txo_set;
 use ckb_db::batch::Col;
 
 // REMEMBER to update the const defined in util/avl/src/lib.rs as well
-pub const COLUMNS: u32 = 12;
+pub const COLUMNS: u32 = 14;
 pub const COLUMN_INDEX: Col = Some(0);
 pub const COLUMN_BLOCK_HEADER: Col = Some(1);
 pub const COLUMN_BLOCK_BODY: Col = Some(2);
@@ -32,3 +34,5 @@ pub const COLUMN_EXT: Col = Some(7);
 pub const COLUMN_BLOCK_TRANSACTION_ADDRESSES: Col = Some(9);
 pub const COLUMN_BLOCK_TRANSACTION_IDS: Col = Some(10);
 pub const COLUMN_BLOCK_PROPOSAL_IDS: Col = Some(11);
+pub const COLUMN_UNCLE_CANDIDATE: Col = Some(12);
+pub const COLUMN_SNAPSHOT_TRANSACTION: Col = Some(13);
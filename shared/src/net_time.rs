@@ -0,0 +1,59 @@
+use ckb_util::RwLock;
+use faketime::unix_time_as_millis;
+use std::sync::Arc;
+
+// Local clock adjusted by the median time offset of outbound peers.
+// `offset` is the local time minus the network time, so network time is `local - offset`.
+#[derive(Clone, Default, Debug)]
+pub struct NetTime {
+    inner: Arc<RwLock<NetTimeState>>,
+}
+
+#[derive(Clone, Default, Debug)]
+struct NetTimeState {
+    offset: i64,
+    samples: Vec<i64>,
+}
+
+impl NetTime {
+    pub fn now(&self) -> u64 {
+        adjust(unix_time_as_millis(), self.offset())
+    }
+
+    pub fn offset(&self) -> i64 {
+        self.inner.read().offset
+    }
+
+    // offsets of the collected samples, oldest first
+    pub fn samples(&self) -> Vec<i64> {
+        self.inner.read().samples.clone()
+    }
+
+    pub fn update(&self, offset: i64, samples: Vec<i64>) {
+        let mut state = self.inner.write();
+        state.offset = offset;
+        state.samples = samples;
+    }
+}
+
+fn adjust(local: u64, offset: i64) -> u64 {
+    (i128::from(local) - i128::from(offset)).max(0) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adjust_by_offset() {
+        assert_eq!(adjust(1_000, 0), 1_000);
+        assert_eq!(adjust(1_000, 300), 700);
+        assert_eq!(adjust(1_000, -300), 1_300);
+        assert_eq!(adjust(1_000, 2_000), 0);
+
+        let net_time = NetTime::default();
+        net_time.update(-300, vec![-200, -300, -400]);
+        assert_eq!(net_time.clone().offset(), -300);
+        assert_eq!(net_time.samples(), vec![-200, -300, -400]);
+    }
+}
@@ -2,6 +2,8 @@ use crate::block_median_time_context::BlockMedianTimeContext;
 use crate::cachedb::CacheDB;
 use crate::error::SharedError;
 use crate::index::ChainIndex;
+use crate::net_time::NetTime;
+use crate::snapshot;
 use crate::store::ChainKVStore;
 use crate::txo_set::{TxoSet, TxoSetDiff};
 use crate::{COLUMNS, COLUMN_BLOCK_HEADER};
@@ -79,6 +81,7 @@ pub struct Shared<CI> {
     chain_state: Arc<RwLock<ChainState>>,
     txs_verify_cache: Arc<RwLock<Option<LruCache<H256, Cycle>>>>,
     consensus: Arc<Consensus>,
+    net_time: NetTime,
 }
 
 // https://github.com/rust-lang/rust/issues/40754
@@ -89,6 +92,7 @@ impl<CI: ChainIndex> ::std::clone::Clone for Shared<CI> {
             chain_state: Arc::clone(&self.chain_state),
             txs_verify_cache: Arc::clone(&self.txs_verify_cache),
             consensus: Arc::clone(&self.consensus),
+            net_time: self.net_time.clone(),
         }
     }
 }
@@ -131,6 +135,7 @@ impl<CI: ChainIndex> Shared<CI> {
             chain_state,
             txs_verify_cache,
             consensus: Arc::new(consensus),
+            net_time: NetTime::default(),
         }
     }
 
@@ -142,14 +147,25 @@ impl<CI: ChainIndex> Shared<CI> {
         &self.store
     }
 
+    pub fn net_time(&self) -> &NetTime {
+        &self.net_time
+    }
+
     pub fn txs_verify_cache(&self) -> &RwLock<Option<LruCache<H256, Cycle>>> {
         &self.txs_verify_cache
     }
 
     pub fn init_txo_set(store: &CI, number: u64) -> TxoSet {
-        let mut txo_set = TxoSet::new();
+        // blocks up to an imported snapshot are not stored, start from its cell set
+        let (mut txo_set, start) = match store.get_snapshot_number() {
+            Some(snapshot_number) if snapshot_number <= number => (
+                snapshot::txo_set(&store.get_snapshot_transactions()),
+                snapshot_number + 1,
+            ),
+            _ => (TxoSet::new(), 0),
+        };
 
-        for n in 0..=number {
+        for n in start..=number {
             let hash = store.get_block_hash(n).unwrap();
             for tx in store.get_block_body(&hash).unwrap() {
                 let inputs = tx.input_pts();
@@ -183,11 +199,11 @@ impl<CI: ChainIndex> CellProvider for Shared<CI> {
             if f {
                 CellStatus::Dead
             } else {
-                let transaction = self
+                let outputs = self
                     .store
-                    .get_transaction(&out_point.hash)
+                    .get_transaction_outputs(&out_point.hash)
                     .expect("transaction must exist");
-                CellStatus::Live(transaction.outputs()[index].clone())
+                CellStatus::Live(outputs[index].clone())
             }
         } else {
             CellStatus::Unknown
@@ -357,11 +373,11 @@ impl<CI: ChainIndex> ChainProvider for Shared<CI> {
         let mut fee = 0;
         for input in transaction.inputs() {
             let previous_output = &input.previous_output;
-            match self.get_transaction(&previous_output.hash) {
-                Some(previous_transaction) => {
+            match self.store.get_transaction_outputs(&previous_output.hash) {
+                Some(previous_outputs) => {
                     let index = previous_output.index as usize;
-                    if index < previous_transaction.outputs().len() {
-                        fee += previous_transaction.outputs()[index].capacity;
+                    if index < previous_outputs.len() {
+                        fee += previous_outputs[index].capacity;
                     } else {
                         return Err(SharedError::InvalidInput);
                     }
@@ -443,6 +459,9 @@ impl<CI: ChainIndex> BlockMedianTimeContext for Shared<CI> {
         self.block_header(hash)
             .map(|header| header.parent_hash().to_owned())
     }
+    fn now(&self) -> u64 {
+        self.net_time.now()
+    }
 }
 
 pub struct SharedBuilder<DB: KeyValueDB> {
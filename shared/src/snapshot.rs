@@ -0,0 +1,235 @@
+use crate::index::ChainIndex;
+use crate::shared::Shared;
+use crate::txo_set::TxoSet;
+use bincode::{deserialize, serialize};
+use ckb_core::block::Block;
+use ckb_core::extras::BlockExt;
+use ckb_core::header::{BlockNumber, Header};
+use ckb_core::transaction::CellOutput;
+use ckb_core::transaction_meta::TransactionMeta;
+use ckb_merkle_tree::merkle_root;
+use hash::sha3_256;
+use numext_fixed_hash::H256;
+use serde_derive::{Deserialize, Serialize};
+
+// Number of transactions serialized into one snapshot chunk
+pub const SNAPSHOT_CHUNK_SIZE: usize = 1024;
+
+#[derive(Debug, PartialEq, Clone, Eq)]
+pub enum SnapshotError {
+    // the chunk hashes do not match the trusted snapshot root
+    RootMismatch,
+    // the chunk does not match its hash
+    ChunkMismatch(usize),
+    MalformedChunk(usize),
+}
+
+// A transaction which still has live outputs at the snapshot block,
+// together with its spent bitmap and the outputs themselves.
+#[derive(Debug, PartialEq, Clone, Eq, Serialize, Deserialize)]
+pub struct SnapshotTransaction {
+    pub hash: H256,
+    pub meta: TransactionMeta,
+    pub outputs: Vec<CellOutput>,
+}
+
+// A verified snapshot ready to be imported into a chain at genesis: the headers from block 1 to
+// the snapshot block with their extras, the full blocks the verifiers need before the
+// snapshot block, and the live transactions at it.
+#[derive(Debug, Clone)]
+pub struct SnapshotImport {
+    pub headers: Vec<(Header, BlockExt)>,
+    pub blocks: Vec<Block>,
+    pub transactions: Vec<SnapshotTransaction>,
+}
+
+// Live cell set at a block, split into chunks which can be served
+// and verified one by one against the snapshot root.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    block_hash: H256,
+    number: BlockNumber,
+    chunks: Vec<Vec<u8>>,
+    chunk_hashes: Vec<H256>,
+    root: H256,
+}
+
+impl Snapshot {
+    pub fn build<CI: ChainIndex>(store: &CI, number: BlockNumber) -> Option<Snapshot> {
+        let block_hash = store.get_block_hash(number)?;
+        // the cell set below an imported snapshot is unknown
+        if store
+            .get_snapshot_number()
+            .map_or(false, |base| number < base)
+        {
+            return None;
+        }
+        let txo_set = Shared::init_txo_set(store, number);
+        let mut transactions = Vec::with_capacity(txo_set.inner.len());
+        for (hash, meta) in txo_set.inner {
+            if meta.is_fully_spent() {
+                continue;
+            }
+            let outputs = store.get_transaction_outputs(&hash)?;
+            transactions.push(SnapshotTransaction {
+                hash,
+                meta,
+                outputs,
+            });
+        }
+        Some(Snapshot::from_transactions(
+            block_hash,
+            number,
+            transactions,
+        ))
+    }
+
+    pub fn from_transactions(
+        block_hash: H256,
+        number: BlockNumber,
+        mut transactions: Vec<SnapshotTransaction>,
+    ) -> Snapshot {
+        // sorted, so every node builds the same chunks for the same cell set
+        transactions.sort_by(|a, b| a.hash.cmp(&b.hash));
+        let chunks = transactions
+            .chunks(SNAPSHOT_CHUNK_SIZE)
+            .map(|chunk| serialize(chunk).expect("serialize snapshot chunk"))
+            .collect::<Vec<_>>();
+        let chunk_hashes = chunks
+            .iter()
+            .map(|chunk| chunk_hash(chunk))
+            .collect::<Vec<_>>();
+        let root = snapshot_root(&chunk_hashes);
+        Snapshot {
+            block_hash,
+            number,
+            chunks,
+            chunk_hashes,
+            root,
+        }
+    }
+
+    pub fn block_hash(&self) -> &H256 {
+        &self.block_hash
+    }
+
+    pub fn number(&self) -> BlockNumber {
+        self.number
+    }
+
+    pub fn root(&self) -> &H256 {
+        &self.root
+    }
+
+    pub fn chunks_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn chunk(&self, index: usize) -> Option<&[u8]> {
+        self.chunks.get(index).map(|chunk| chunk.as_slice())
+    }
+
+    pub fn chunk_hashes(&self) -> &[H256] {
+        &self.chunk_hashes
+    }
+}
+
+pub fn chunk_hash(chunk: &[u8]) -> H256 {
+    sha3_256(chunk).into()
+}
+
+// The root commits to the hash of every chunk, so once the chunk hashes
+// are checked against it, each chunk can be verified as soon as it arrives.
+pub fn snapshot_root(chunk_hashes: &[H256]) -> H256 {
+    merkle_root(chunk_hashes)
+}
+
+pub fn verify_chunk_hashes(
+    chunk_hashes: &[H256],
+    expected_root: &H256,
+) -> Result<(), SnapshotError> {
+    if chunk_hashes.is_empty() || &snapshot_root(chunk_hashes) != expected_root {
+        return Err(SnapshotError::RootMismatch);
+    }
+    Ok(())
+}
+
+// Verify a downloaded chunk against the verified chunk hashes and decode it,
+// untrusted data is never decoded.
+pub fn verify_chunk(
+    chunk_hashes: &[H256],
+    index: usize,
+    chunk: &[u8],
+) -> Result<Vec<SnapshotTransaction>, SnapshotError> {
+    if chunk_hashes.get(index) != Some(&chunk_hash(chunk)) {
+        return Err(SnapshotError::ChunkMismatch(index));
+    }
+    deserialize(chunk).map_err(|_| SnapshotError::MalformedChunk(index))
+}
+
+pub fn txo_set(transactions: &[SnapshotTransaction]) -> TxoSet {
+    let mut txo_set = TxoSet::new();
+    for tx in transactions {
+        txo_set.inner.insert(tx.hash.clone(), tx.meta.clone());
+    }
+    txo_set
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transactions(count: usize) -> Vec<SnapshotTransaction> {
+        (0..count)
+            .map(|i| {
+                let mut bytes = [0u8; 32];
+                bytes[..8].copy_from_slice(&(i as u64).to_le_bytes());
+                let mut meta = TransactionMeta::new(2);
+                meta.set_spent(0);
+                SnapshotTransaction {
+                    hash: H256::from_slice(&bytes).unwrap(),
+                    meta,
+                    outputs: vec![CellOutput::new(i as u64, Vec::new(), H256::zero(), None); 2],
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_snapshot_roundtrip() {
+        let snapshot =
+            Snapshot::from_transactions(H256::zero(), 10, transactions(SNAPSHOT_CHUNK_SIZE + 1));
+        assert_eq!(snapshot.chunks_count(), 2);
+
+        let chunk_hashes = snapshot.chunk_hashes();
+        assert_eq!(verify_chunk_hashes(chunk_hashes, snapshot.root()), Ok(()));
+        let mut verified = Vec::new();
+        for index in 0..snapshot.chunks_count() {
+            let chunk = snapshot.chunk(index).unwrap();
+            verified.extend(verify_chunk(chunk_hashes, index, chunk).unwrap());
+        }
+        let mut expected = transactions(SNAPSHOT_CHUNK_SIZE + 1);
+        expected.sort_by(|a, b| a.hash.cmp(&b.hash));
+        assert_eq!(verified, expected);
+        assert_eq!(txo_set(&verified).inner.len(), SNAPSHOT_CHUNK_SIZE + 1);
+    }
+
+    #[test]
+    fn test_reject_tampered_snapshot() {
+        let snapshot = Snapshot::from_transactions(H256::zero(), 10, transactions(3));
+        let mut chunk = snapshot.chunk(0).unwrap().to_vec();
+        let last = chunk.len() - 1;
+        chunk[last] ^= 1;
+        assert_eq!(
+            verify_chunk(snapshot.chunk_hashes(), 0, &chunk),
+            Err(SnapshotError::ChunkMismatch(0))
+        );
+
+        let mut chunk_hashes = snapshot.chunk_hashes().to_vec();
+        chunk_hashes[0] = chunk_hash(&chunk);
+        assert_eq!(
+            verify_chunk_hashes(&chunk_hashes, snapshot.root()),
+            Err(SnapshotError::RootMismatch)
+        );
+    }
+}
@@ -3,7 +3,7 @@ use crate::flat_serializer::{serialize as flat_serialize, Address};
 use crate::{
     COLUMN_BLOCK_BODY, COLUMN_BLOCK_HEADER, COLUMN_BLOCK_PROPOSAL_IDS,
     COLUMN_BLOCK_TRANSACTION_ADDRESSES, COLUMN_BLOCK_TRANSACTION_IDS, COLUMN_BLOCK_UNCLE,
-    COLUMN_EXT,
+    COLUMN_EXT, COLUMN_UNCLE_CANDIDATE,
 };
 use bincode::{deserialize, serialize};
 use ckb_core::block::{Block, BlockBuilder};
@@ -54,7 +54,14 @@ pub trait ChainStore: Sync + Send {
     fn get_block_uncles(&self, block_hash: &H256) -> Option<Vec<UncleBlock>>;
     fn get_block_ext(&self, block_hash: &H256) -> Option<BlockExt>;
     fn insert_block(&self, batch: &mut Batch, b: &Block);
+    /// Only headers are stored for the blocks below an imported snapshot.
+    fn insert_header(&self, batch: &mut Batch, header: &Header);
     fn insert_block_ext(&self, batch: &mut Batch, block_hash: &H256, ext: &BlockExt);
+    /// Uncles relayed by peers are kept apart from blocks, we may never see their bodies.
+    fn get_uncle_candidate(&self, hash: &H256) -> Option<UncleBlock>;
+    fn get_uncle_candidates(&self) -> Vec<UncleBlock>;
+    fn insert_uncle_candidate(&self, batch: &mut Batch, uncle: &UncleBlock);
+    fn delete_uncle_candidate(&self, batch: &mut Batch, hash: &H256);
     fn save_with_batch<F: FnOnce(&mut Batch) -> Result<(), SharedError>>(
         &self,
         f: F,
@@ -101,23 +108,23 @@ impl<'a, T: ChainStore> Iterator for ChainStoreHeaderIterator<'a, T> {
 impl<T: 'static + KeyValueDB> ChainStore for ChainKVStore<T> {
     // TODO error log
     fn get_block(&self, h: &H256) -> Option<Block> {
-        self.get_header(h).map(|header| {
-            let commit_transactions = self
-                .get_block_body(h)
-                .expect("block transactions must be stored");
-            let uncles = self
-                .get_block_uncles(h)
-                .expect("block uncles must be stored");
-            let proposal_transactions = self
-                .get_block_proposal_txs_ids(h)
-                .expect("block proposal_ids must be stored");
+        let header = self.get_header(h)?;
+        // None for blocks below an imported snapshot, only their headers are stored
+        let commit_transactions = self.get_block_body(h)?;
+        let uncles = self
+            .get_block_uncles(h)
+            .expect("block uncles must be stored");
+        let proposal_transactions = self
+            .get_block_proposal_txs_ids(h)
+            .expect("block proposal_ids must be stored");
+        Some(
             BlockBuilder::default()
                 .header(header)
                 .uncles(uncles)
                 .commit_transactions(commit_transactions)
                 .proposal_transactions(proposal_transactions)
-                .build()
-        })
+                .build(),
+        )
     }
 
     fn get_header(&self, h: &H256) -> Option<Header> {
@@ -209,9 +216,43 @@ impl<T: 'static + KeyValueDB> ChainStore for ChainKVStore<T> {
         );
     }
 
+    fn insert_header(&self, batch: &mut Batch, header: &Header) {
+        batch.insert(
+            COLUMN_BLOCK_HEADER,
+            header.hash().to_vec(),
+            serialize(header).expect("serializing header should be ok"),
+        );
+    }
+
     fn insert_block_ext(&self, batch: &mut Batch, block_hash: &H256, ext: &BlockExt) {
         batch.insert(COLUMN_EXT, block_hash.to_vec(), serialize(&ext).unwrap());
     }
+
+    fn get_uncle_candidate(&self, hash: &H256) -> Option<UncleBlock> {
+        self.get(COLUMN_UNCLE_CANDIDATE, hash.as_bytes())
+            .map(|raw| deserialize(&raw[..]).unwrap())
+    }
+
+    fn get_uncle_candidates(&self) -> Vec<UncleBlock> {
+        self.db
+            .read_all(COLUMN_UNCLE_CANDIDATE)
+            .expect("db operation should be ok")
+            .into_iter()
+            .map(|(_, raw)| deserialize(&raw[..]).unwrap())
+            .collect()
+    }
+
+    fn insert_uncle_candidate(&self, batch: &mut Batch, uncle: &UncleBlock) {
+        batch.insert(
+            COLUMN_UNCLE_CANDIDATE,
+            uncle.header().hash().to_vec(),
+            serialize(uncle).expect("serializing uncle should be ok"),
+        );
+    }
+
+    fn delete_uncle_candidate(&self, batch: &mut Batch, hash: &H256) {
+        batch.delete(COLUMN_UNCLE_CANDIDATE, hash.to_vec());
+    }
 }
 
 #[cfg(test)]
@@ -290,4 +331,34 @@ mod tests {
         assert!(ret.is_ok());
         assert_eq!(ext, store.get_block_ext(&hash).unwrap());
     }
+
+    #[test]
+    fn save_get_and_delete_uncle_candidate() {
+        let db = setup_db("save_get_and_delete_uncle_candidate", COLUMNS);
+        let store = ChainKVStore::new(db);
+        let block = BlockBuilder::default()
+            .commit_transaction(TransactionBuilder::default().build())
+            .build();
+        let uncle: UncleBlock = block.into();
+
+        let hash = uncle.header().hash();
+        assert!(store.get_uncle_candidate(&hash).is_none());
+        let ret = store.save_with_batch(|batch| {
+            store.insert_uncle_candidate(batch, &uncle);
+            Ok(())
+        });
+        assert!(ret.is_ok());
+        assert_eq!(uncle, store.get_uncle_candidate(&hash).unwrap());
+        assert_eq!(vec![uncle.clone()], store.get_uncle_candidates());
+        // candidates are not blocks
+        assert!(store.get_header(&hash).is_none());
+
+        let ret = store.save_with_batch(|batch| {
+            store.delete_uncle_candidate(batch, &hash);
+            Ok(())
+        });
+        assert!(ret.is_ok());
+        assert!(store.get_uncle_candidate(&hash).is_none());
+        assert!(store.get_uncle_candidates().is_empty());
+    }
 }
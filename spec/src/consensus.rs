@@ -3,7 +3,9 @@ use ckb_core::header::HeaderBuilder;
 use ckb_core::transaction::Capacity;
 use ckb_core::{BlockNumber, Cycle, Version};
 use ckb_pow::{Pow, PowEngine};
+use numext_fixed_hash::H256;
 use numext_fixed_uint::U256;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 
 pub const DEFAULT_BLOCK_REWARD: Capacity = 5_000;
@@ -49,6 +51,11 @@ pub struct Consensus {
     pub max_block_bytes: u64,
     // block version number supported
     pub block_version: Version,
+    // Known block hashes at given numbers, headers conflicting with them are rejected
+    // and scripts of blocks up to the last checkpoint are not executed.
+    pub checkpoints: BTreeMap<BlockNumber, H256>,
+    // Trusted live cell set snapshot roots, keyed by checkpoint block hash
+    pub snapshot_roots: HashMap<H256, H256>,
 }
 
 // genesis difficulty should not be zero
@@ -74,6 +81,8 @@ impl Default for Consensus {
             max_block_cycles: MAX_BLOCK_CYCLES,
             max_block_bytes: MAX_BLOCK_BYTES,
             block_version: BLOCK_VERSION,
+            checkpoints: BTreeMap::new(),
+            snapshot_roots: HashMap::new(),
         }
     }
 }
@@ -104,6 +113,16 @@ impl Consensus {
         self
     }
 
+    pub fn set_checkpoints(mut self, checkpoints: BTreeMap<BlockNumber, H256>) -> Self {
+        self.checkpoints = checkpoints;
+        self
+    }
+
+    pub fn set_snapshot_roots(mut self, snapshot_roots: HashMap<H256, H256>) -> Self {
+        self.snapshot_roots = snapshot_roots;
+        self
+    }
+
     pub fn genesis_block(&self) -> &Block {
         &self.genesis_block
     }
@@ -155,4 +174,19 @@ impl Consensus {
     pub fn block_version(&self) -> Version {
         self.block_version
     }
+
+    pub fn checkpoint(&self, number: BlockNumber) -> Option<&H256> {
+        self.checkpoints.get(&number)
+    }
+
+    pub fn last_checkpoint(&self) -> Option<(BlockNumber, &H256)> {
+        self.checkpoints
+            .iter()
+            .next_back()
+            .map(|(number, hash)| (*number, hash))
+    }
+
+    pub fn snapshot_root(&self, block_hash: &H256) -> Option<&H256> {
+        self.snapshot_roots.get(block_hash)
+    }
 }
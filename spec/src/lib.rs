@@ -10,12 +10,13 @@ use ckb_core::block::BlockBuilder;
 use ckb_core::header::HeaderBuilder;
 use ckb_core::script::Script;
 use ckb_core::transaction::{CellOutput, Transaction, TransactionBuilder};
-use ckb_core::{Capacity, Cycle};
+use ckb_core::{BlockNumber, Capacity, Cycle};
 use ckb_pow::{Pow, PowEngine};
 use ckb_protocol::Script as FbsScript;
 use flatbuffers::FlatBufferBuilder;
 use numext_fixed_hash::H256;
 use numext_fixed_uint::U256;
+use occupied_capacity::OccupiedCapacity;
 use serde_derive::Deserialize;
 use std::error::Error;
 use std::fs::File;
@@ -32,6 +33,17 @@ pub struct ChainSpec {
     pub params: Params,
     pub system_cells: Vec<SystemCell>,
     pub pow: Pow,
+    #[serde(default)]
+    pub checkpoints: Vec<Checkpoint>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize)]
+pub struct Checkpoint {
+    pub number: BlockNumber,
+    pub hash: H256,
+    // root of the live cell set snapshot at this block, see `ckb_shared::snapshot`
+    #[serde(default)]
+    pub snapshot_root: Option<H256>,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize)]
@@ -62,11 +74,15 @@ pub struct Genesis {
 #[derive(Clone, PartialEq, Eq, Debug, Deserialize)]
 pub struct SystemCell {
     pub path: PathBuf,
+    // Lock hash of the system cell, the owner of the lock can upgrade the
+    // system cell. Default lock(zero hash) can never be unlocked.
+    #[serde(default)]
+    pub lock: H256,
 }
 
 fn build_system_cell_transaction(cells: &[SystemCell]) -> Result<Transaction, Box<Error>> {
     let mut outputs = Vec::new();
-    for system_cell in cells {
+    for (index, system_cell) in cells.iter().enumerate() {
         let mut file = File::open(&system_cell.path)?;
         let mut data = Vec::new();
         file.read_to_end(&mut data)?;
@@ -77,14 +93,15 @@ fn build_system_cell_transaction(cells: &[SystemCell]) -> Result<Transaction, Bo
         builder.finish(offset, None);
         let script_data = builder.finished_data().to_vec();
 
-        // TODO: we should either provide a valid type hash so we can
-        // update system cell, or we can update this when P2SH is moved into VM.
-        let output = CellOutput::new(
-            script_data.len() as Capacity,
+        // System cells use type id, scripts referencing a system cell by its
+        // type hash keep working after the system cell gets upgraded.
+        let mut output = CellOutput::new(
+            0,
             script_data,
-            H256::default(),
-            None,
+            system_cell.lock.clone(),
+            Some(Script::genesis_type_id(index as u64)),
         );
+        output.capacity = output.occupied_capacity() as Capacity;
         outputs.push(output);
     }
 
@@ -99,6 +116,12 @@ impl ChainSpec {
         Ok(spec)
     }
 
+    /// Type hash of the type id script of the system cell at `index`, scripts
+    /// should reference system cells by this hash to survive upgrades.
+    pub fn system_cell_type_hash(&self, index: usize) -> H256 {
+        Script::genesis_type_id(index as u64).type_hash()
+    }
+
     pub fn pow_engine(&self) -> Arc<dyn PowEngine> {
         self.pow.engine()
     }
@@ -127,7 +150,24 @@ impl ChainSpec {
             .set_genesis_block(genesis_block)
             .set_initial_block_reward(self.params.initial_block_reward)
             .set_max_block_cycles(self.params.max_block_cycles)
-            .set_pow(self.pow.clone());
+            .set_pow(self.pow.clone())
+            .set_checkpoints(
+                self.checkpoints
+                    .iter()
+                    .map(|checkpoint| (checkpoint.number, checkpoint.hash.clone()))
+                    .collect(),
+            )
+            .set_snapshot_roots(
+                self.checkpoints
+                    .iter()
+                    .filter_map(|checkpoint| {
+                        checkpoint
+                            .snapshot_root
+                            .clone()
+                            .map(|root| (checkpoint.hash.clone(), root))
+                    })
+                    .collect(),
+            );
 
         Ok(consensus)
     }
@@ -95,4 +95,67 @@ fn cli() -> App<'static, 'static> {
                 .about("Generate lock script type hash using the first system cell, which by default is always_success"),
         )
         .subcommand(SubCommand::with_name("keygen").about("Generate new key"))
+        .subcommand(
+            SubCommand::with_name("multisig_hash")
+                .arg(arg_config_with_help(CKB_CONFIG_HELP))
+                .arg(arg_threshold())
+                .arg(arg_pubkeys())
+                .about("Generate M-of-N multisig lock script type hash using the multisig system cell"),
+        )
+        .subcommand(
+            SubCommand::with_name("multisig_sign")
+                .arg(arg_config_with_help(CKB_CONFIG_HELP))
+                .arg(arg_threshold())
+                .arg(arg_pubkeys())
+                .arg(arg_partial_tx())
+                .arg(
+                    Arg::with_name("privkey")
+                        .long("privkey")
+                        .value_name("PRIVKEY")
+                        .required(true)
+                        .takes_value(true)
+                        .help("Specify the hex encoded private key to sign with."),
+                )
+                .about("Add signatures to the multisig inputs of a partially signed transaction"),
+        )
+        .subcommand(
+            SubCommand::with_name("multisig_assemble")
+                .arg(arg_config_with_help(CKB_CONFIG_HELP))
+                .arg(arg_threshold())
+                .arg(arg_pubkeys())
+                .arg(arg_partial_tx())
+                .about("Assemble the final transaction once enough signatures are collected"),
+        )
+}
+
+fn arg_threshold() -> Arg<'static, 'static> {
+    Arg::with_name("threshold")
+        .short("m")
+        .long("threshold")
+        .value_name("M")
+        .required(true)
+        .takes_value(true)
+        .help("Specify the number of signatures required to unlock.")
+}
+
+fn arg_pubkeys() -> Arg<'static, 'static> {
+    Arg::with_name("pubkey")
+        .short("p")
+        .long("pubkey")
+        .value_name("PUBKEY")
+        .required(true)
+        .takes_value(true)
+        .multiple(true)
+        .number_of_values(1)
+        .help("Specify a hex encoded pubkey, repeat it for each of the N pubkeys. Order matters.")
+}
+
+fn arg_partial_tx() -> Arg<'static, 'static> {
+    Arg::with_name("tx")
+        .short("t")
+        .long("tx")
+        .value_name("PATH")
+        .required(true)
+        .takes_value(true)
+        .help("Specify the partially signed transaction file.")
 }
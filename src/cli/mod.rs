@@ -2,10 +2,12 @@ mod args;
 mod export;
 mod import;
 mod miner;
+mod multisig;
 mod run_impl;
 
 pub use self::args::get_matches;
 pub use self::export::export;
 pub use self::import::import;
 pub use self::miner::miner;
+pub use self::multisig::{multisig_assemble, multisig_hash, multisig_sign};
 pub use self::run_impl::{keygen, run, type_hash};
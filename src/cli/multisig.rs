@@ -0,0 +1,176 @@
+use crate::Setup;
+use ckb_core::script::Script;
+use clap::{value_t, ArgMatches};
+use crypto::secp::{MultisigConfig, Privkey, Pubkey, Signature};
+use faster_hex::hex_decode;
+use hash::sha3_256;
+use jsonrpc_types::Transaction;
+use numext_fixed_hash::{H256, H520};
+use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::fs::File;
+use std::process::exit;
+
+const MULTISIG_CELL_NAME: &str = "multisig";
+
+/// Transaction passed around multisig participants. The unlock args of the
+/// multisig inputs only contain the signed message parts, signatures are
+/// collected separately by input index until `multisig_assemble`.
+#[derive(Serialize, Deserialize)]
+struct PartialTransaction {
+    transaction: Transaction,
+    #[serde(default)]
+    signatures: BTreeMap<usize, Vec<H520>>,
+}
+
+fn exit_with<E: ::std::fmt::Debug>(msg: &str, e: E) -> ! {
+    eprintln!("{}, cause err: {:?}", msg, e);
+    exit(1)
+}
+
+fn parse_hex(value: &str) -> Vec<u8> {
+    let value = value.trim_start_matches("0x");
+    let mut buffer = vec![0; value.len() / 2];
+    hex_decode(value.as_bytes(), &mut buffer)
+        .unwrap_or_else(|e| exit_with(&format!("Invalid hex {}", value), e));
+    buffer
+}
+
+fn multisig_config(matches: &ArgMatches) -> MultisigConfig {
+    let threshold = value_t!(matches.value_of("threshold"), u8).unwrap_or_else(|e| e.exit());
+    let pubkeys = matches
+        .values_of("pubkey")
+        .expect("required arg")
+        .map(|pubkey| {
+            Pubkey::from_slice(&parse_hex(pubkey))
+                .unwrap_or_else(|e| exit_with(&format!("Invalid pubkey {}", pubkey), e))
+        })
+        .collect();
+    MultisigConfig::new(threshold, pubkeys)
+        .unwrap_or_else(|e| exit_with("Invalid multisig config", e))
+}
+
+fn multisig_lock(setup: &Setup, config: &MultisigConfig) -> Script {
+    let index = setup
+        .chain_spec
+        .system_cells
+        .iter()
+        .position(|cell| cell.path.file_name() == Some(OsStr::new(MULTISIG_CELL_NAME)))
+        .unwrap_or_else(|| {
+            eprintln!(
+                "No multisig system cell found in chain spec, \
+                 add nodes_template/spec/cells/multisig to system_cells"
+            );
+            exit(1)
+        });
+    let consensus = setup.chain_spec.to_consensus().unwrap();
+    let system_cell_tx = &consensus.genesis_block().commit_transactions()[0];
+    let data_hash = system_cell_tx.outputs()[index].data_hash();
+
+    Script::new(0, vec![], Some(data_hash), None, config.signed_args())
+}
+
+// Same as the message calculated by the multisig script: double sha3 on
+// all arguments following the signatures.
+fn message(args: &[Vec<u8>]) -> H256 {
+    let mut bytes = vec![];
+    for arg in args {
+        bytes.extend_from_slice(arg);
+    }
+    sha3_256(sha3_256(bytes)).into()
+}
+
+fn read_partial_transaction(matches: &ArgMatches) -> PartialTransaction {
+    let path = value_t!(matches.value_of("tx"), String).unwrap_or_else(|e| e.exit());
+    let file = File::open(&path).unwrap_or_else(|e| exit_with(&format!("Open {}", path), e));
+    serde_json::from_reader(file)
+        .unwrap_or_else(|e| exit_with(&format!("Invalid transaction file {}", path), e))
+}
+
+// Indices and signed messages of the inputs locked by the multisig lock
+fn multisig_inputs(partial: &PartialTransaction, lock: &Script) -> Vec<(usize, H256)> {
+    let inputs: Vec<(usize, H256)> = partial
+        .transaction
+        .inputs
+        .iter()
+        .enumerate()
+        .filter_map(|(index, input)| {
+            let unlock: Script = input.unlock.clone().into();
+            if unlock.reference == lock.reference && unlock.signed_args == lock.signed_args {
+                Some((index, message(&unlock.args)))
+            } else {
+                None
+            }
+        })
+        .collect();
+    if inputs.is_empty() {
+        eprintln!("No input is locked by the multisig lock");
+        exit(1);
+    }
+    inputs
+}
+
+pub fn multisig_hash(setup: &Setup, matches: &ArgMatches) {
+    let config = multisig_config(matches);
+    let lock = multisig_lock(setup, &config);
+    println!("{:#x}", lock.type_hash());
+}
+
+pub fn multisig_sign(setup: &Setup, matches: &ArgMatches) {
+    let config = multisig_config(matches);
+    let lock = multisig_lock(setup, &config);
+    let privkey = value_t!(matches.value_of("privkey"), String).unwrap_or_else(|e| e.exit());
+    let privkey: Privkey = privkey
+        .trim_start_matches("0x")
+        .parse()
+        .unwrap_or_else(|e| exit_with("Invalid privkey", e));
+
+    let mut partial = read_partial_transaction(matches);
+    for (index, message) in multisig_inputs(&partial, &lock) {
+        let signature = privkey
+            .sign_recoverable(&message)
+            .unwrap_or_else(|e| exit_with("Sign failed", e));
+        // reject signatures from keys outside the multisig config early
+        config
+            .sort_signatures(&message, &[signature.clone()])
+            .unwrap_or_else(|e| exit_with("Invalid signer", e));
+        partial
+            .signatures
+            .entry(index)
+            .or_insert_with(Vec::new)
+            .push(signature.into());
+    }
+
+    println!("{}", serde_json::to_string_pretty(&partial).unwrap());
+}
+
+pub fn multisig_assemble(setup: &Setup, matches: &ArgMatches) {
+    let config = multisig_config(matches);
+    let lock = multisig_lock(setup, &config);
+
+    let mut partial = read_partial_transaction(matches);
+    for (index, message) in multisig_inputs(&partial, &lock) {
+        let signatures: Vec<Signature> = partial
+            .signatures
+            .get(&index)
+            .map(|signatures| signatures.iter().cloned().map(Into::into).collect())
+            .unwrap_or_default();
+        let signature_args = config
+            .signature_args(&message, &signatures)
+            .unwrap_or_else(|e| exit_with(&format!("Input {}", index), e));
+
+        let unlock = &mut partial.transaction.inputs[index].unlock;
+        let message_args = unlock.args.split_off(0);
+        unlock.args = signature_args
+            .into_iter()
+            .map(jsonrpc_types::Bytes::new)
+            .chain(message_args)
+            .collect();
+    }
+
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&partial.transaction).unwrap()
+    );
+}
@@ -51,14 +51,21 @@ pub fn run(setup: Setup) {
         setup.configs.sync,
     ));
 
+    let sync_peers = synchronizer.peers();
+    let rpc_synchronizer = Synchronizer::clone(&synchronizer);
+
     let relayer = Arc::new(Relayer::new(
         chain_controller.clone(),
         shared.clone(),
         tx_pool_controller.clone(),
-        synchronizer.peers(),
+        notify.clone(),
+        Arc::clone(&sync_peers),
+        Arc::clone(&synchronizer.config),
     ));
 
-    let net_time_checker = Arc::new(NetTimeProtocol::default());
+    let rpc_relayer = Relayer::clone(&relayer);
+
+    let net_time_checker = Arc::new(NetTimeProtocol::new(shared.net_time().clone()));
 
     let network_config = NetworkConfig::from(setup.configs.network);
     let protocol_base_name = "ckb";
@@ -95,6 +102,8 @@ pub fn run(setup: Setup) {
         tx_pool_controller,
         chain_controller,
         block_assembler_controller,
+        rpc_synchronizer,
+        rpc_relayer,
     );
 
     wait_for_exit();
@@ -133,6 +142,8 @@ fn setup_rpc<CI: ChainIndex + 'static>(
     tx_pool: TransactionPoolController,
     chain: ChainController,
     agent: BlockAssemblerController,
+    synchronizer: Synchronizer<CI>,
+    relayer: Relayer<CI>,
 ) -> RpcServer {
     use ckb_pow::Clicker;
 
@@ -142,7 +153,17 @@ fn setup_rpc<CI: ChainIndex + 'static>(
         .downcast_ref::<Clicker>()
         .map(|pow| Arc::new(pow.clone()));
 
-    RpcServer::new(config, network, shared, tx_pool, chain, agent, pow)
+    RpcServer::new(
+        config,
+        network,
+        shared,
+        tx_pool,
+        chain,
+        agent,
+        synchronizer,
+        relayer,
+        pow,
+    )
 }
 
 pub fn type_hash(setup: &Setup) {
@@ -16,6 +16,15 @@ fn main() {
         ("cli", Some(cli_matches)) => match cli_matches.subcommand() {
             ("type_hash", Some(type_hash_matches)) => cli::type_hash(&setup(&type_hash_matches)),
             ("keygen", _) => cli::keygen(),
+            ("multisig_hash", Some(multisig_matches)) => {
+                cli::multisig_hash(&setup(&multisig_matches), &multisig_matches)
+            }
+            ("multisig_sign", Some(multisig_matches)) => {
+                cli::multisig_sign(&setup(&multisig_matches), &multisig_matches)
+            }
+            ("multisig_assemble", Some(multisig_matches)) => {
+                cli::multisig_assemble(&setup(&multisig_matches), &multisig_matches)
+            }
             _ => unreachable!(),
         },
         ("run", Some(run_matches)) => {
@@ -198,6 +198,23 @@ pub mod test {
         );
     }
 
+    #[test]
+    fn test_load_unknown_peer_store() {
+        let tmp_dir = tempfile::Builder::new()
+            .prefix("test_load_unknown_peer_store")
+            .tempdir()
+            .unwrap();
+
+        let test_conifg = r#"{
+            "network": {
+                "peer_store": "Rocksdb"
+            }
+        }"#;
+        let config_path = tmp_dir.path().join("config.json");
+        write_file(&config_path, test_conifg);
+        assert!(override_default_config_file(&config_path).is_err());
+    }
+
     #[test]
     fn test_load_db_config() {
         let tmp_dir = tempfile::Builder::new()
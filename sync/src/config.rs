@@ -1,14 +1,67 @@
 use serde_derive::Deserialize;
+use std::collections::HashMap;
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct Config {
+    // limits of the orphan block pool, the oldest orphans are evicted when exceeded
     pub orphan_block_limit: usize,
+    #[serde(default = "default_orphan_block_size_limit")]
+    pub orphan_block_size_limit: usize,
+    #[serde(default = "default_orphan_block_limit_per_peer")]
+    pub orphan_block_limit_per_peer: usize,
+    // per peer limits keyed by message type, e.g. "GetBlocks"
+    #[serde(default = "default_rate_limits")]
+    pub rate_limits: HashMap<String, RateLimit>,
+    // send transactions submitted to this node to one outbound peer first,
+    // and to all peers only after a random delay
+    #[serde(default)]
+    pub stem_local_txs: bool,
+}
+
+// Token bucket, a peer can send `burst` messages at once,
+// then the bucket is refilled by `per_second` messages every second.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub struct RateLimit {
+    pub burst: u32,
+    pub per_second: u32,
+}
+
+impl RateLimit {
+    pub fn new(burst: u32, per_second: u32) -> Self {
+        RateLimit { burst, per_second }
+    }
+}
+
+fn default_orphan_block_size_limit() -> usize {
+    64 * 1024 * 1024 // 64mb
+}
+
+fn default_orphan_block_limit_per_peer() -> usize {
+    256
+}
+
+fn default_rate_limits() -> HashMap<String, RateLimit> {
+    // only the messages which make us read from disk are limited
+    vec![
+        ("GetHeaders", RateLimit::new(20, 10)),
+        ("GetBlocks", RateLimit::new(32, 16)),
+        ("GetBlockTransactions", RateLimit::new(20, 10)),
+        ("GetBlockProposal", RateLimit::new(20, 10)),
+        ("GetSnapshotChunk", RateLimit::new(16, 8)),
+    ]
+    .into_iter()
+    .map(|(message_type, limit)| (message_type.to_owned(), limit))
+    .collect()
 }
 
 impl Config {
     pub fn default() -> Self {
         Config {
             orphan_block_limit: 1024,
+            orphan_block_size_limit: default_orphan_block_size_limit(),
+            orphan_block_limit_per_peer: default_orphan_block_limit_per_peer(),
+            rate_limits: default_rate_limits(),
+            stem_local_txs: false,
         }
     }
 }
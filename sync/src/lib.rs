@@ -5,6 +5,7 @@
 
 mod config;
 mod net_time_checker;
+mod rate_limiter;
 mod relayer;
 mod synchronizer;
 mod types;
@@ -12,10 +13,12 @@ mod types;
 #[cfg(test)]
 mod tests;
 
-pub use crate::config::Config;
-pub use crate::net_time_checker::NetTimeProtocol;
+pub use crate::config::{Config, RateLimit};
+pub use crate::net_time_checker::{NetTimeChecker, NetTimeProtocol};
+pub use crate::rate_limiter::RateLimiter;
 pub use crate::relayer::Relayer;
 pub use crate::synchronizer::Synchronizer;
+pub use crate::types::{Peers, SyncState};
 
 use ckb_network::ProtocolId;
 
@@ -24,7 +27,10 @@ pub const MAX_INVENTORY_LEN: usize = 50_000;
 pub const MAX_SCHEDULED_LEN: usize = 4 * 1024;
 pub const MAX_BLOCKS_TO_ANNOUNCE: usize = 8;
 pub const MAX_UNCONNECTING_HEADERS: usize = 10;
-pub const MAX_BLOCKS_IN_TRANSIT_PER_PEER: usize = 16;
+// The inflight limit of a peer starts at INITIAL and adapts to its download throughput
+pub const INITIAL_BLOCKS_IN_TRANSIT_PER_PEER: usize = 16;
+pub const MIN_BLOCKS_IN_TRANSIT_PER_PEER: usize = 2;
+pub const MAX_BLOCKS_IN_TRANSIT_PER_PEER: usize = 128;
 pub const MAX_TIP_AGE: u64 = 60 * 60 * 1000;
 pub const STALE_RELAY_AGE_LIMIT: u64 = 30 * 24 * 60 * 60 * 1000;
 pub const BLOCK_DOWNLOAD_WINDOW: u64 = 1024;
@@ -48,3 +54,47 @@ pub const EVICTION_HEADERS_RESPONSE_TIME: u64 = 120 * 1000; // 2 minutes
 pub const MAX_LOCATOR_SIZE: usize = 101;
 
 pub const BLOCK_DOWNLOAD_TIMEOUT: u64 = 30 * 1000; // 30s
+
+// A peer should be able to deliver its inflight blocks in about this time
+pub const BLOCKS_IN_TRANSIT_TARGET_TIME: u64 = 4 * 1000; // 4s
+
+// Time a peer may hold back the download window before its block is requested elsewhere
+pub const BLOCK_STALLING_TIMEOUT: u64 = 2 * 1000; // 2s
+
+// Peers stalling the download this many times in a row are disconnected
+pub const MAX_BLOCK_STALLS: u32 = 3;
+
+// Orphan blocks whose parent doesn't arrive in this time are dropped
+pub const ORPHAN_BLOCK_EXPIRY: u64 = 10 * 60 * 1000; // 10 minutes
+
+// The download rate is the number of blocks received during this window
+pub const DOWNLOAD_RATE_WINDOW: u64 = 60 * 1000; // 1 minute
+
+// Snapshot chunks requested at once, a chunk not received in time is requested again
+pub const MAX_SNAPSHOT_CHUNKS_IN_TRANSIT: usize = 16;
+
+pub const SNAPSHOT_CHUNK_TIMEOUT: u64 = 30 * 1000; // 30s
+
+// Fast sync falls back to downloading all blocks if the snapshot doesn't make progress
+pub const SNAPSHOT_DOWNLOAD_TIMEOUT: u64 = 5 * 60 * 1000; // 5 minutes
+
+// A peer sending a chunk which does not match the trusted snapshot root is banned
+pub const BAD_SNAPSHOT_CHUNK_BAN_TIME: u64 = 24 * 60 * 60 * 1000; // 1 day
+
+pub const MAX_RELAY_TX_HASHES_PER_BATCH: usize = 1_000;
+pub const MAX_KNOWN_TXS_PER_PEER: usize = 10_000;
+// Transactions are announced to each peer after a random delay of this average
+pub const TX_ANNOUNCE_AVERAGE_DELAY: u64 = 1000; // 1s
+
+pub const MAX_TX_ANNOUNCE_DELAY: u64 = 10 * TX_ANNOUNCE_AVERAGE_DELAY;
+
+// Transactions submitted to this node are announced again until they leave the pool
+pub const LOCAL_TX_REBROADCAST_INTERVAL: u64 = 10 * 60 * 1000; // 10 minutes
+
+// Average time a local transaction is only known to the stem peer before it is broadcast
+pub const LOCAL_TX_STEM_AVERAGE_DELAY: u64 = 10 * 1000; // 10s
+pub const TX_REQUEST_TIMEOUT: u64 = 30 * 1000; // 30s
+pub const UNCLE_REQUEST_TIMEOUT: u64 = 30 * 1000; // 30s
+
+// Number of peers we ask to relay compact blocks in high-bandwidth mode
+pub const MAX_HIGH_BANDWIDTH_PEERS: usize = 3;
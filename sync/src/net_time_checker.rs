@@ -1,21 +1,26 @@
-use ckb_network::{CKBProtocolContext, CKBProtocolHandler, PeerIndex};
-use ckb_protocol::TimeMessage;
+use ckb_network::{peer_store::Behaviour, CKBProtocolContext, CKBProtocolHandler, PeerIndex};
+use ckb_protocol::{verify_time_message, TimeMessage};
+use ckb_shared::net_time::NetTime;
 use ckb_util::RwLock;
+use ckb_verification::ALLOWED_FUTURE_BLOCKTIME;
 use flatbuffers::{get_root, FlatBufferBuilder};
 use log::{debug, warn};
 use std::collections::VecDeque;
 
 const TOLERANT_OFFSET: u64 = 7_200_000;
+// Blocks of nodes with adjusted clocks must still be accepted by nodes that didn't adjust,
+// so the adjustment stays well within the allowed future block time.
+const MAX_ADJUSTED_OFFSET: i64 = (ALLOWED_FUTURE_BLOCKTIME / 2) as i64;
 const MIN_SAMPLES: usize = 5;
 const MAX_SAMPLES: usize = 11;
 
-/// Collect and check time offset samples
+/// Collect and check time offset samples, one from each peer
 pub struct NetTimeChecker {
     /// Local clock should has less offset than this value.
     tolerant_offset: u64,
     max_samples: usize,
     min_samples: usize,
-    samples: VecDeque<i64>,
+    samples: VecDeque<(PeerIndex, i64)>,
 }
 
 impl NetTimeChecker {
@@ -28,18 +33,28 @@ impl NetTimeChecker {
         }
     }
 
-    pub fn add_sample(&mut self, offset: i64) {
-        self.samples.push_back(offset);
+    // a new sample of a peer replaces its previous one
+    pub fn add_sample(&mut self, peer: PeerIndex, offset: i64) {
+        self.remove_sample(peer);
+        self.samples.push_back((peer, offset));
         if self.samples.len() > self.max_samples {
             self.samples.pop_front();
         }
     }
 
-    fn median_offset(&self) -> Option<i64> {
+    pub fn remove_sample(&mut self, peer: PeerIndex) {
+        self.samples.retain(|(sample_peer, _)| *sample_peer != peer);
+    }
+
+    pub fn samples(&self) -> Vec<i64> {
+        self.samples.iter().map(|(_, offset)| *offset).collect()
+    }
+
+    pub fn median_offset(&self) -> Option<i64> {
         if self.samples.is_empty() || self.samples.len() < self.min_samples {
             return None;
         }
-        let mut samples = self.samples.iter().cloned().collect::<Vec<_>>();
+        let mut samples = self.samples();
         samples.sort();
         if samples.len() % 2 == 0 {
             // samples is even
@@ -69,22 +84,52 @@ impl Default for NetTimeChecker {
     }
 }
 
-/// Collect time offset samples from network peers and send notify to user if offset is too large
-pub struct NetTimeProtocol(RwLock<NetTimeChecker>);
+/// Collect time offset samples from network peers, adjust the shared network time by their median
+/// and send notify to user if offset is too large
+pub struct NetTimeProtocol {
+    checker: RwLock<NetTimeChecker>,
+    net_time: NetTime,
+}
 
 impl NetTimeProtocol {
-    pub fn new(min_samples: usize, max_samples: usize, tolerant_offset: u64) -> Self {
-        NetTimeProtocol(RwLock::new(NetTimeChecker::new(
-            min_samples,
-            max_samples,
-            tolerant_offset,
-        )))
+    pub fn new(net_time: NetTime) -> Self {
+        NetTimeProtocol::with_checker(NetTimeChecker::default(), net_time)
     }
-}
 
-impl Default for NetTimeProtocol {
-    fn default() -> Self {
-        NetTimeProtocol(RwLock::new(NetTimeChecker::default()))
+    pub fn with_checker(checker: NetTimeChecker, net_time: NetTime) -> Self {
+        NetTimeProtocol {
+            checker: RwLock::new(checker),
+            net_time,
+        }
+    }
+
+    fn add_sample(&self, peer: PeerIndex, offset: i64) {
+        let mut net_time_checker = self.checker.write();
+        debug!(target: "network", "peer={} net time offset sample {}ms", peer, offset);
+        net_time_checker.add_sample(peer, offset);
+        self.update_net_time(&net_time_checker);
+    }
+
+    fn remove_sample(&self, peer: PeerIndex) {
+        let mut net_time_checker = self.checker.write();
+        net_time_checker.remove_sample(peer);
+        self.update_net_time(&net_time_checker);
+    }
+
+    fn update_net_time(&self, net_time_checker: &NetTimeChecker) {
+        // the local clock is kept when the network disagrees too much,
+        // peers shouldn't be able to move our time that far
+        let adjusted_offset = match net_time_checker.check() {
+            Ok(()) => net_time_checker.median_offset().map_or(0, |offset| {
+                offset.max(-MAX_ADJUSTED_OFFSET).min(MAX_ADJUSTED_OFFSET)
+            }),
+            Err(offset) => {
+                warn!(target: "network", "Please check your computer's local clock({}ms offset from network peers), If your clock is wrong, it may cause unexpected errors.", offset);
+                0
+            }
+        };
+        self.net_time
+            .update(adjusted_offset, net_time_checker.samples());
     }
 }
 
@@ -93,6 +138,10 @@ impl CKBProtocolHandler for NetTimeProtocol {
     fn received(&self, nc: Box<CKBProtocolContext>, peer: PeerIndex, data: &[u8]) {
         // collect time sample from outbound peer
         if nc.session_info(peer).map(|s| s.peer.is_outbound()) == Some(true) {
+            if !verify_time_message(data) {
+                nc.report(peer, Behaviour::MalformedMessage);
+                return;
+            }
             let now: u64 = faketime::unix_time_as_millis();
             let timestamp: u64 = match get_root::<TimeMessage>(&data)
                 .payload()
@@ -102,12 +151,7 @@ impl CKBProtocolHandler for NetTimeProtocol {
                 None => return,
             };
             let offset: i64 = (i128::from(now) - i128::from(timestamp)) as i64;
-            let mut net_time_checker = self.0.write();
-            debug!(target: "network", "new net time offset sample {}ms", offset);
-            net_time_checker.add_sample(offset);
-            if let Err(offset) = net_time_checker.check() {
-                warn!(target: "network", "Please check your computer's local clock({}ms offset from network peers), If your clock is wrong, it may cause unexpected errors.", offset);
-            }
+            self.add_sample(peer, offset);
         }
     }
 
@@ -121,7 +165,9 @@ impl CKBProtocolHandler for NetTimeProtocol {
             let _ = nc.send(peer, fbb.finished_data().to_vec());
         }
     }
-    fn disconnected(&self, _nc: Box<CKBProtocolContext>, _peer: PeerIndex) {}
+    fn disconnected(&self, _nc: Box<CKBProtocolContext>, peer: PeerIndex) {
+        self.remove_sample(peer);
+    }
 }
 
 #[cfg(test)]
@@ -134,28 +180,96 @@ mod tests {
         // zero samples
         assert!(ntc.check().is_ok());
         // 1 sample
-        ntc.add_sample(TOLERANT_OFFSET as i64 + 1);
+        ntc.add_sample(0, TOLERANT_OFFSET as i64 + 1);
         assert!(ntc.check().is_ok());
         // 3 samples
-        ntc.add_sample(TOLERANT_OFFSET as i64 + 2);
-        ntc.add_sample(TOLERANT_OFFSET as i64 + 3);
+        ntc.add_sample(1, TOLERANT_OFFSET as i64 + 2);
+        ntc.add_sample(2, TOLERANT_OFFSET as i64 + 3);
         assert_eq!(ntc.check().unwrap_err(), TOLERANT_OFFSET as i64 + 2);
         // 4 samples
-        ntc.add_sample(1);
+        ntc.add_sample(3, 1);
         assert_eq!(ntc.check().unwrap_err(), TOLERANT_OFFSET as i64 + 1);
         // 5 samples
-        ntc.add_sample(2);
+        ntc.add_sample(4, 2);
         assert_eq!(ntc.check().unwrap_err(), TOLERANT_OFFSET as i64 + 1);
         // 5 samples within tolerant offset
-        ntc.add_sample(3);
-        ntc.add_sample(4);
-        ntc.add_sample(5);
+        ntc.add_sample(5, 3);
+        ntc.add_sample(6, 4);
+        ntc.add_sample(7, 5);
         assert!(ntc.check().is_ok());
         // 5 samples negative offset
-        ntc.add_sample(-(TOLERANT_OFFSET as i64) - 1);
-        ntc.add_sample(-(TOLERANT_OFFSET as i64) - 2);
+        ntc.add_sample(8, -(TOLERANT_OFFSET as i64) - 1);
+        ntc.add_sample(9, -(TOLERANT_OFFSET as i64) - 2);
         assert!(ntc.check().is_ok());
-        ntc.add_sample(-(TOLERANT_OFFSET as i64) - 3);
+        ntc.add_sample(10, -(TOLERANT_OFFSET as i64) - 3);
         assert_eq!(ntc.check().unwrap_err(), -(TOLERANT_OFFSET as i64) - 1);
     }
+
+    #[test]
+    fn test_adjust_net_time() {
+        let net_time = NetTime::default();
+        let protocol = NetTimeProtocol::with_checker(
+            NetTimeChecker::new(3, 5, TOLERANT_OFFSET),
+            net_time.clone(),
+        );
+        // not enough samples
+        protocol.add_sample(0, 100);
+        protocol.add_sample(1, 300);
+        assert_eq!(net_time.offset(), 0);
+        assert_eq!(net_time.samples(), vec![100, 300]);
+        protocol.add_sample(2, 200);
+        assert_eq!(net_time.offset(), 200);
+        // local clock is kept when the offset is over tolerant offset
+        protocol.add_sample(3, TOLERANT_OFFSET as i64 + 1);
+        protocol.add_sample(4, TOLERANT_OFFSET as i64 + 2);
+        assert_eq!(net_time.offset(), 300);
+        protocol.add_sample(5, TOLERANT_OFFSET as i64 + 3);
+        assert_eq!(net_time.offset(), 0);
+        assert_eq!(net_time.samples().len(), 5);
+    }
+
+    #[test]
+    fn test_one_sample_per_peer() {
+        let net_time = NetTime::default();
+        let protocol = NetTimeProtocol::with_checker(
+            NetTimeChecker::new(3, 5, TOLERANT_OFFSET),
+            net_time.clone(),
+        );
+        // a peer sending again replaces its sample, it can't fill the samples alone
+        for offset in 1..=5 {
+            protocol.add_sample(0, offset * 1_000);
+        }
+        assert_eq!(net_time.samples(), vec![5_000]);
+        assert_eq!(net_time.offset(), 0);
+
+        protocol.add_sample(1, 100);
+        protocol.add_sample(2, 200);
+        assert_eq!(net_time.samples(), vec![5_000, 100, 200]);
+        assert_eq!(net_time.offset(), 200);
+        protocol.add_sample(0, 300);
+        assert_eq!(net_time.samples(), vec![100, 200, 300]);
+        assert_eq!(net_time.offset(), 200);
+
+        // samples of disconnected peers are removed
+        protocol.remove_sample(2);
+        assert_eq!(net_time.samples(), vec![100, 300]);
+        assert_eq!(net_time.offset(), 0);
+    }
+
+    #[test]
+    fn test_clamp_adjusted_offset() {
+        let net_time = NetTime::default();
+        let protocol = NetTimeProtocol::with_checker(
+            NetTimeChecker::new(3, 5, TOLERANT_OFFSET),
+            net_time.clone(),
+        );
+        for peer in 0..3 {
+            protocol.add_sample(peer, 60_000);
+        }
+        assert_eq!(net_time.offset(), MAX_ADJUSTED_OFFSET);
+        for peer in 0..3 {
+            protocol.add_sample(peer, -60_000);
+        }
+        assert_eq!(net_time.offset(), -MAX_ADJUSTED_OFFSET);
+    }
 }
@@ -0,0 +1,123 @@
+use crate::config::RateLimit;
+use ckb_network::PeerIndex;
+use ckb_util::Mutex;
+use faketime::unix_time_as_millis;
+use fnv::FnvHashMap;
+use std::collections::HashMap;
+
+// tokens are counted in thousandths to refill by milliseconds without floats
+const TOKEN: u64 = 1000;
+
+#[derive(Debug, Clone)]
+struct TokenBucket {
+    limit: RateLimit,
+    tokens: u64,
+    last_refill: u64,
+}
+
+impl TokenBucket {
+    fn new(limit: RateLimit, now: u64) -> Self {
+        TokenBucket {
+            limit,
+            tokens: u64::from(limit.burst) * TOKEN,
+            last_refill: now,
+        }
+    }
+
+    fn try_acquire(&mut self, now: u64) -> bool {
+        let elapsed = now.saturating_sub(self.last_refill);
+        let capacity = u64::from(self.limit.burst) * TOKEN;
+        self.tokens = self
+            .tokens
+            .saturating_add(elapsed.saturating_mul(u64::from(self.limit.per_second)))
+            .min(capacity);
+        self.last_refill = now;
+        if self.tokens >= TOKEN {
+            self.tokens -= TOKEN;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+// Per peer and per message type rate limiter,
+// message types without a configured limit are always allowed.
+#[derive(Default)]
+pub struct RateLimiter {
+    limits: HashMap<String, RateLimit>,
+    buckets: Mutex<FnvHashMap<(PeerIndex, &'static str), TokenBucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(limits: HashMap<String, RateLimit>) -> Self {
+        RateLimiter {
+            limits,
+            buckets: Mutex::new(FnvHashMap::default()),
+        }
+    }
+
+    // return false if the peer exceeds the limit of message type
+    pub fn check(&self, peer: PeerIndex, message_type: &'static str) -> bool {
+        self.check_at(peer, message_type, unix_time_as_millis())
+    }
+
+    fn check_at(&self, peer: PeerIndex, message_type: &'static str, now: u64) -> bool {
+        let limit = match self.limits.get(message_type) {
+            Some(limit) => *limit,
+            None => return true,
+        };
+        self.buckets
+            .lock()
+            .entry((peer, message_type))
+            .or_insert_with(|| TokenBucket::new(limit, now))
+            .try_acquire(now)
+    }
+
+    pub fn disconnected(&self, peer: PeerIndex) {
+        self.buckets
+            .lock()
+            .retain(|(bucket_peer, _), _| *bucket_peer != peer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter() -> RateLimiter {
+        let mut limits = HashMap::new();
+        limits.insert("GetBlocks".to_owned(), RateLimit::new(2, 1));
+        RateLimiter::new(limits)
+    }
+
+    #[test]
+    fn test_burst_then_refill() {
+        let limiter = limiter();
+        assert!(limiter.check_at(0, "GetBlocks", 0));
+        assert!(limiter.check_at(0, "GetBlocks", 0));
+        assert!(!limiter.check_at(0, "GetBlocks", 0));
+        assert!(!limiter.check_at(0, "GetBlocks", 999));
+        assert!(limiter.check_at(0, "GetBlocks", 1000));
+        assert!(!limiter.check_at(0, "GetBlocks", 1000));
+        // refill never exceeds burst
+        assert!(limiter.check_at(0, "GetBlocks", 60_000));
+        assert!(limiter.check_at(0, "GetBlocks", 60_000));
+        assert!(!limiter.check_at(0, "GetBlocks", 60_000));
+    }
+
+    #[test]
+    fn test_limit_per_peer_and_message_type() {
+        let limiter = limiter();
+        assert!(limiter.check_at(0, "GetBlocks", 0));
+        assert!(limiter.check_at(0, "GetBlocks", 0));
+        assert!(!limiter.check_at(0, "GetBlocks", 0));
+        assert!(limiter.check_at(1, "GetBlocks", 0));
+        for _ in 0..10 {
+            assert!(limiter.check_at(0, "Block", 0));
+        }
+
+        limiter.disconnected(0);
+        assert!(limiter.check_at(0, "GetBlocks", 0));
+    }
+}
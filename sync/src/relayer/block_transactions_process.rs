@@ -1,5 +1,6 @@
 use crate::relayer::Relayer;
 use ckb_core::transaction::Transaction;
+use ckb_network::peer_store::Behaviour;
 use ckb_network::CKBProtocolContext;
 use ckb_network::PeerIndex;
 use ckb_protocol::{BlockTransactions, FlatbuffersVectorIterator};
@@ -45,9 +46,12 @@ where
                     .map(Into::into)
                     .collect();
 
-            if let Ok(block) = self.relayer.reconstruct_block(&compact_block, transactions) {
-                self.relayer
-                    .accept_block(self.nc, self.peer, &Arc::new(block));
+            match self.relayer.reconstruct_block(&compact_block, transactions) {
+                Ok(block) => self
+                    .relayer
+                    .accept_block(self.nc, self.peer, &Arc::new(block)),
+                // peer didn't send all the transactions we asked for
+                Err(_) => self.nc.report(self.peer, Behaviour::InvalidCompactBlock),
             }
         }
     }
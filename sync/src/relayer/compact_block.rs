@@ -46,3 +46,19 @@ impl<'a> From<ckb_protocol::CompactBlock<'a>> for CompactBlock {
         }
     }
 }
+
+impl CompactBlock {
+    // Prefilled transactions must be sorted by index, and the indexes must be inside the block,
+    // otherwise the block can't be reconstructed.
+    pub fn has_valid_prefilled_indexes(&self) -> bool {
+        let txs_len = self.prefilled_transactions.len() + self.short_ids.len();
+        let mut next_index = 0;
+        for pt in &self.prefilled_transactions {
+            if pt.index < next_index || pt.index >= txs_len {
+                return false;
+            }
+            next_index = pt.index + 1;
+        }
+        true
+    }
+}
@@ -1,18 +1,20 @@
 use super::compact_block::CompactBlock;
 use crate::relayer::Relayer;
-use ckb_network::{CKBProtocolContext, PeerIndex};
+use ckb_network::{peer_store::Behaviour, CKBProtocolContext, PeerIndex};
 use ckb_protocol::{CompactBlock as FbsCompactBlock, RelayMessage};
 use ckb_shared::block_median_time_context::BlockMedianTimeContext;
 use ckb_shared::index::ChainIndex;
 use ckb_shared::shared::ChainProvider;
 use ckb_util::RwLockUpgradableReadGuard;
-use ckb_verification::{HeaderResolverWrapper, HeaderVerifier, Verifier};
+use ckb_verification::{Error as VerifyError, HeaderResolverWrapper, HeaderVerifier, Verifier};
 use flatbuffers::FlatBufferBuilder;
 use numext_fixed_hash::H256;
 use std::sync::Arc;
 
 pub struct CompactBlockProcess<'a, CI: ChainIndex + 'a> {
     message: &'a FbsCompactBlock<'a>,
+    // the raw relay message, forwarded unchanged to high-bandwidth peers
+    data: &'a [u8],
     relayer: &'a Relayer<CI>,
     peer: PeerIndex,
     nc: &'a CKBProtocolContext,
@@ -24,12 +26,14 @@ where
 {
     pub fn new(
         message: &'a FbsCompactBlock,
+        data: &'a [u8],
         relayer: &'a Relayer<CI>,
         peer: PeerIndex,
         nc: &'a CKBProtocolContext,
     ) -> Self {
         CompactBlockProcess {
             message,
+            data,
             nc,
             relayer,
             peer,
@@ -38,6 +42,10 @@ where
 
     pub fn execute(self) {
         let compact_block: CompactBlock = (*self.message).into();
+        if !compact_block.has_valid_prefilled_indexes() {
+            self.nc.report(self.peer, Behaviour::InvalidCompactBlock);
+            return;
+        }
         let block_hash = compact_block.header.hash();
         let pending_compact_blocks = self.relayer.state.pending_compact_blocks.upgradable_read();
         if pending_compact_blocks.get(&block_hash).is_none()
@@ -52,7 +60,17 @@ where
                 Arc::clone(&self.relayer.shared.consensus().pow_engine()),
             );
 
-            if header_verifier.verify(&resolver).is_ok() {
+            let verify_result = header_verifier.verify(&resolver);
+            if let Err(ref err) = verify_result {
+                // the parent may be unknown to us yet, that is not the peer's fault
+                match err {
+                    VerifyError::UnknownParent(_) => {}
+                    _ => self.nc.report(self.peer, Behaviour::InvalidHeader),
+                }
+            }
+            if verify_result.is_ok() {
+                self.relayer
+                    .relay_compact_block_early(self.nc, self.peer, self.data);
                 self.relayer
                     .request_proposal_txs(self.nc, self.peer, &compact_block);
 
@@ -138,4 +156,7 @@ where
                     .map(|header| header.parent_hash().to_owned())
             })
     }
+    fn now(&self) -> u64 {
+        self.relayer.shared.net_time().now()
+    }
 }
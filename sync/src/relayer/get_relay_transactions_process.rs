@@ -0,0 +1,68 @@
+use crate::relayer::Relayer;
+use crate::MAX_RELAY_TX_HASHES_PER_BATCH;
+use ckb_core::transaction::ProposalShortId;
+use ckb_network::{peer_store::Behaviour, CKBProtocolContext, PeerIndex};
+use ckb_protocol::{GetRelayTransactions, RelayMessage};
+use ckb_shared::index::ChainIndex;
+use flatbuffers::FlatBufferBuilder;
+use log::debug;
+use numext_fixed_hash::H256;
+
+pub struct GetRelayTransactionsProcess<'a, CI: ChainIndex + 'a> {
+    message: &'a GetRelayTransactions<'a>,
+    relayer: &'a Relayer<CI>,
+    peer: PeerIndex,
+    nc: &'a CKBProtocolContext,
+}
+
+impl<'a, CI> GetRelayTransactionsProcess<'a, CI>
+where
+    CI: ChainIndex + 'static,
+{
+    pub fn new(
+        message: &'a GetRelayTransactions,
+        relayer: &'a Relayer<CI>,
+        peer: PeerIndex,
+        nc: &'a CKBProtocolContext,
+    ) -> Self {
+        GetRelayTransactionsProcess {
+            message,
+            nc,
+            peer,
+            relayer,
+        }
+    }
+
+    pub fn execute(self) {
+        let tx_hashes = match self.message.tx_hashes() {
+            Some(tx_hashes) if tx_hashes.len() <= MAX_RELAY_TX_HASHES_PER_BATCH => tx_hashes,
+            _ => {
+                self.nc.report(self.peer, Behaviour::MalformedMessage);
+                return;
+            }
+        };
+
+        for fbs_h256 in tx_hashes {
+            let tx_hash: H256 = fbs_h256.into();
+            debug!(target: "relay", "peer={} get_relay_transaction {:?}", self.peer, tx_hash);
+            if !self.relayer.is_announced(self.peer, &tx_hash) {
+                debug!(target: "relay", "tx {:?} was not announced to peer={}", tx_hash, self.peer);
+                continue;
+            }
+            // transactions which are no longer in the pool are silently skipped
+            let tx = match self
+                .relayer
+                .tx_pool
+                .get_transaction(ProposalShortId::from_h256(&tx_hash))
+            {
+                Some(entry) if entry.transaction.hash() == tx_hash => entry.transaction,
+                _ => continue,
+            };
+
+            let fbb = &mut FlatBufferBuilder::new();
+            let message = RelayMessage::build_transaction(fbb, &tx);
+            fbb.finish(message, None);
+            let _ = self.nc.send(self.peer, fbb.finished_data().to_vec());
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use crate::relayer::Relayer;
+use ckb_network::{peer_store::Behaviour, CKBProtocolContext, PeerIndex};
+use ckb_protocol::{GetUncleBlock, RelayMessage};
+use ckb_shared::index::ChainIndex;
+use flatbuffers::FlatBufferBuilder;
+use log::debug;
+use numext_fixed_hash::H256;
+
+pub struct GetUncleBlockProcess<'a, CI: ChainIndex + 'a> {
+    message: &'a GetUncleBlock<'a>,
+    relayer: &'a Relayer<CI>,
+    peer: PeerIndex,
+    nc: &'a CKBProtocolContext,
+}
+
+impl<'a, CI> GetUncleBlockProcess<'a, CI>
+where
+    CI: ChainIndex + 'static,
+{
+    pub fn new(
+        message: &'a GetUncleBlock,
+        relayer: &'a Relayer<CI>,
+        peer: PeerIndex,
+        nc: &'a CKBProtocolContext,
+    ) -> Self {
+        GetUncleBlockProcess {
+            message,
+            nc,
+            peer,
+            relayer,
+        }
+    }
+
+    pub fn execute(self) {
+        let block_hash: H256 = match self.message.block_hash() {
+            Some(block_hash) => block_hash.into(),
+            None => {
+                self.nc.report(self.peer, Behaviour::MalformedMessage);
+                return;
+            }
+        };
+        debug!(target: "relay", "peer={} get_uncle_block {:?}", self.peer, block_hash);
+        // unknown uncles are silently skipped
+        if let Some(uncle) = self.relayer.get_uncle(&block_hash) {
+            let fbb = &mut FlatBufferBuilder::new();
+            let message = RelayMessage::build_uncle_block(fbb, &uncle);
+            fbb.finish(message, None);
+
+            let _ = self.nc.send(self.peer, fbb.finished_data().to_vec());
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use crate::{LOCAL_TX_REBROADCAST_INTERVAL, LOCAL_TX_STEM_AVERAGE_DELAY};
+use ckb_core::transaction::Transaction;
+use fnv::FnvHashMap;
+use numext_fixed_hash::H256;
+use rand::distributions::Exp;
+use rand::{thread_rng, Rng};
+use std::time::{Duration, Instant};
+
+struct LocalTx {
+    transaction: Transaction,
+    // None once the transaction is broadcast to all peers
+    stem_until: Option<Instant>,
+    rebroadcast_at: Instant,
+}
+
+// Transactions submitted to this node. They are broadcast after the stem phase,
+// when only one outbound peer knows them, and then rebroadcast periodically.
+#[derive(Default)]
+pub struct LocalTxs {
+    txs: FnvHashMap<H256, LocalTx>,
+}
+
+impl LocalTxs {
+    // without a stem phase the transaction is broadcast by the next `take_due` call
+    pub fn insert(&mut self, transaction: Transaction, stem: bool, now: Instant) {
+        let stem_until = if stem { now + random_stem_delay() } else { now };
+        self.txs.insert(
+            transaction.hash(),
+            LocalTx {
+                transaction,
+                stem_until: Some(stem_until),
+                rebroadcast_at: stem_until,
+            },
+        );
+    }
+
+    pub fn remove(&mut self, hash: &H256) {
+        self.txs.remove(hash);
+    }
+
+    // Transactions to broadcast to all peers, paired with whether they were broadcast before
+    pub fn take_due(&mut self, now: Instant) -> Vec<(Transaction, bool)> {
+        let interval = Duration::from_millis(LOCAL_TX_REBROADCAST_INTERVAL);
+        self.txs
+            .values_mut()
+            .filter(|local_tx| local_tx.rebroadcast_at <= now)
+            .map(|local_tx| {
+                let is_rebroadcast = local_tx.stem_until.take().is_none();
+                local_tx.rebroadcast_at = now + interval;
+                (local_tx.transaction.clone(), is_rebroadcast)
+            })
+            .collect()
+    }
+}
+
+// exponentially distributed, capped at 3 times the average
+fn random_stem_delay() -> Duration {
+    let delay = thread_rng().sample(Exp::new(1.0 / LOCAL_TX_STEM_AVERAGE_DELAY as f64));
+    Duration::from_millis((delay as u64).min(3 * LOCAL_TX_STEM_AVERAGE_DELAY))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ckb_core::transaction::{CellOutput, TransactionBuilder};
+
+    fn tx(capacity: u64) -> Transaction {
+        TransactionBuilder::default()
+            .output(CellOutput::new(capacity, Vec::new(), H256::zero(), None))
+            .build()
+    }
+
+    #[test]
+    fn test_broadcast_then_rebroadcast() {
+        let now = Instant::now();
+        let mut local_txs = LocalTxs::default();
+        local_txs.insert(tx(1), false, now);
+
+        assert_eq!(local_txs.take_due(now), vec![(tx(1), false)]);
+        assert!(local_txs.take_due(now).is_empty());
+
+        let now = now + Duration::from_millis(LOCAL_TX_REBROADCAST_INTERVAL);
+        assert_eq!(local_txs.take_due(now), vec![(tx(1), true)]);
+
+        local_txs.remove(&tx(1).hash());
+        let now = now + Duration::from_millis(LOCAL_TX_REBROADCAST_INTERVAL);
+        assert!(local_txs.take_due(now).is_empty());
+    }
+
+    #[test]
+    fn test_broadcast_after_stem_phase() {
+        let now = Instant::now();
+        let mut local_txs = LocalTxs::default();
+        local_txs.insert(tx(1), true, now);
+
+        let stem_end = now + Duration::from_millis(3 * LOCAL_TX_STEM_AVERAGE_DELAY);
+        assert_eq!(local_txs.take_due(stem_end), vec![(tx(1), false)]);
+        assert!(local_txs.take_due(stem_end).is_empty());
+    }
+}
@@ -6,7 +6,16 @@ pub mod compact_block;
 mod compact_block_process;
 mod get_block_proposal_process;
 mod get_block_transactions_process;
+mod get_relay_transactions_process;
+mod get_uncle_block_process;
+mod local_txs;
+mod relay_transaction_hashes_process;
+mod set_compact_block_mode_process;
 mod transaction_process;
+mod tx_inventory;
+mod tx_requests;
+mod uncle_announcement_process;
+mod uncle_block_process;
 
 use self::block_proposal_process::BlockProposalProcess;
 use self::block_transactions_process::BlockTransactionsProcess;
@@ -14,35 +23,64 @@ use self::compact_block::CompactBlock;
 use self::compact_block_process::CompactBlockProcess;
 use self::get_block_proposal_process::GetBlockProposalProcess;
 use self::get_block_transactions_process::GetBlockTransactionsProcess;
+use self::get_relay_transactions_process::GetRelayTransactionsProcess;
+use self::get_uncle_block_process::GetUncleBlockProcess;
+use self::local_txs::LocalTxs;
+use self::relay_transaction_hashes_process::RelayTransactionHashesProcess;
+use self::set_compact_block_mode_process::SetCompactBlockModeProcess;
 use self::transaction_process::TransactionProcess;
+use self::tx_inventory::TxInventory;
+use self::tx_requests::TxRequests;
+use self::uncle_announcement_process::UncleAnnouncementProcess;
+use self::uncle_block_process::UncleBlockProcess;
+use crate::config::Config;
+use crate::rate_limiter::RateLimiter;
+use crate::synchronizer::is_outbound;
 use crate::types::Peers;
+use crate::MAX_HIGH_BANDWIDTH_PEERS;
 use ckb_chain::chain::ChainController;
+use ckb_chain::error::ProcessBlockError;
 use ckb_core::block::{Block, BlockBuilder};
 use ckb_core::transaction::{ProposalShortId, Transaction};
-use ckb_network::{CKBProtocolContext, CKBProtocolHandler, PeerIndex, TimerToken};
+use ckb_core::uncle::UncleBlock;
+use ckb_network::{
+    peer_store::Behaviour, CKBProtocolContext, CKBProtocolHandler, PeerIndex, TimerToken,
+    UNKNOWN_MESSAGE_TYPE,
+};
+use ckb_notify::NotifyController;
 use ckb_pool::txs_pool::TransactionPoolController;
-use ckb_protocol::{short_transaction_id, short_transaction_id_keys, RelayMessage, RelayPayload};
+use ckb_protocol::{
+    enum_name_relay_payload, short_transaction_id, short_transaction_id_keys, verify_relay_message,
+    RelayMessage, RelayPayload,
+};
 use ckb_shared::index::ChainIndex;
 use ckb_shared::shared::{ChainProvider, Shared};
+use ckb_shared::store::ChainStore;
 use ckb_util::{Mutex, RwLock};
 use flatbuffers::{get_root, FlatBufferBuilder};
 use fnv::{FnvHashMap, FnvHashSet};
 use log::{debug, info};
 use numext_fixed_hash::H256;
-use std::collections::HashSet;
+use rand::seq::SliceRandom;
+use rand::thread_rng;
+use std::collections::{HashSet, VecDeque};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 pub const TX_PROPOSAL_TOKEN: TimerToken = 0;
+pub const TX_ANNOUNCE_TOKEN: TimerToken = 1;
 
 #[derive(Clone)]
 pub struct Relayer<CI: ChainIndex> {
     chain: ChainController,
     shared: Shared<CI>,
     tx_pool: TransactionPoolController,
+    notify: NotifyController,
     state: Arc<RelayState>,
     // TODO refactor shared Peers struct with Synchronizer
     peers: Arc<Peers>,
+    rate_limiter: Arc<RateLimiter>,
+    config: Arc<Config>,
 }
 
 impl<CI> Relayer<CI>
@@ -53,21 +91,40 @@ where
         chain: ChainController,
         shared: Shared<CI>,
         tx_pool: TransactionPoolController,
+        notify: NotifyController,
         peers: Arc<Peers>,
+        config: Arc<Config>,
     ) -> Self {
+        // separate from the synchronizer's so disconnecting one protocol
+        // doesn't reset the other's buckets
+        let rate_limiter = Arc::new(RateLimiter::new(config.rate_limits.clone()));
         Relayer {
             chain,
             shared,
             tx_pool,
+            notify,
             state: Arc::new(RelayState::default()),
             peers,
+            rate_limiter,
+            config,
         }
     }
 
-    fn process(&self, nc: &CKBProtocolContext, peer: PeerIndex, message: RelayMessage) {
+    fn process(
+        &self,
+        nc: &CKBProtocolContext,
+        peer: PeerIndex,
+        message: RelayMessage,
+        data: &[u8],
+    ) {
+        if message.payload().is_none() {
+            nc.report(peer, Behaviour::MalformedMessage);
+            return;
+        }
         match message.payload_type() {
             RelayPayload::CompactBlock => CompactBlockProcess::new(
                 &message.payload_as_compact_block().unwrap(),
+                data,
                 self,
                 peer,
                 nc,
@@ -102,6 +159,44 @@ where
                 BlockProposalProcess::new(&message.payload_as_block_proposal().unwrap(), self)
                     .execute()
             }
+            RelayPayload::RelayTransactionHashes => RelayTransactionHashesProcess::new(
+                &message.payload_as_relay_transaction_hashes().unwrap(),
+                self,
+                peer,
+                nc,
+            )
+            .execute(),
+            RelayPayload::GetRelayTransactions => GetRelayTransactionsProcess::new(
+                &message.payload_as_get_relay_transactions().unwrap(),
+                self,
+                peer,
+                nc,
+            )
+            .execute(),
+            RelayPayload::SetCompactBlockMode => SetCompactBlockModeProcess::new(
+                &message.payload_as_set_compact_block_mode().unwrap(),
+                self,
+                peer,
+            )
+            .execute(),
+            RelayPayload::UncleAnnouncement => UncleAnnouncementProcess::new(
+                &message.payload_as_uncle_announcement().unwrap(),
+                self,
+                peer,
+                nc,
+            )
+            .execute(),
+            RelayPayload::GetUncleBlock => GetUncleBlockProcess::new(
+                &message.payload_as_get_uncle_block().unwrap(),
+                self,
+                peer,
+                nc,
+            )
+            .execute(),
+            RelayPayload::UncleBlock => {
+                UncleBlockProcess::new(&message.payload_as_uncle_block().unwrap(), self, peer, nc)
+                    .execute()
+            }
             RelayPayload::NONE => {}
         }
     }
@@ -137,20 +232,122 @@ where
     pub fn accept_block(&self, nc: &CKBProtocolContext, peer: PeerIndex, block: &Arc<Block>) {
         let ret = self.chain.process_block(Arc::clone(&block));
         if ret.is_ok() {
+            let block_hash = block.header().hash();
+            if self.shared.chain_state().read().tip_hash() != block_hash {
+                // stale but valid, miners may still include it as an uncle
+                self.announce_uncle(nc, peer, &block_hash);
+                return;
+            }
+            self.select_high_bandwidth_provider(nc, peer);
             let fbb = &mut FlatBufferBuilder::new();
             let message = RelayMessage::build_compact_block(fbb, block, &HashSet::new());
             fbb.finish(message, None);
 
+            // high-bandwidth peers already got the compact block before validation
+            let high_bandwidth_peers = self.state.high_bandwidth_peers.lock();
             for peer_id in nc.connected_peers() {
-                if peer_id != peer {
+                if peer_id != peer && !high_bandwidth_peers.contains(&peer_id) {
                     let _ = nc.send(peer_id, fbb.finished_data().to_vec());
                 }
             }
         } else {
             debug!(target: "relay", "accept_block verify error {:?}", ret);
+            // blocks here are all reconstructed from compact blocks,
+            // high-bandwidth providers forward blocks before validation and are not to blame,
+            // but they are no longer asked to
+            if let Err(ProcessBlockError::Verification(_)) = ret {
+                if !self.drop_high_bandwidth_provider(nc, peer) {
+                    nc.report(peer, Behaviour::InvalidCompactBlock);
+                }
+            }
+        }
+    }
+
+    pub fn announce_uncle(&self, nc: &CKBProtocolContext, peer: PeerIndex, block_hash: &H256) {
+        let fbb = &mut FlatBufferBuilder::new();
+        let message = RelayMessage::build_uncle_announcement(fbb, block_hash);
+        fbb.finish(message, None);
+
+        for peer_id in nc.connected_peers() {
+            if peer_id != peer {
+                let _ = nc.send(peer_id, fbb.finished_data().to_vec());
+            }
+        }
+    }
+
+    // uncles are served from stale blocks we stored and from relayed uncle candidates
+    pub fn get_uncle(&self, hash: &H256) -> Option<UncleBlock> {
+        self.shared
+            .block(hash)
+            .filter(|block| !block.commit_transactions().is_empty())
+            .map(Into::into)
+            .or_else(|| self.shared.store().get_uncle_candidate(hash))
+    }
+
+    pub fn is_known_uncle(&self, hash: &H256) -> bool {
+        self.shared.block_header(hash).is_some()
+            || self.shared.store().get_uncle_candidate(hash).is_some()
+    }
+
+    // forward the compact block as received, right after its header and pow are verified
+    pub fn relay_compact_block_early(&self, nc: &CKBProtocolContext, peer: PeerIndex, data: &[u8]) {
+        for peer_id in self.state.high_bandwidth_peers.lock().iter() {
+            if *peer_id != peer {
+                let _ = nc.send(*peer_id, data.to_vec());
+            }
+        }
+    }
+
+    // Peers which deliver new valid blocks first are asked to send compact blocks in
+    // high-bandwidth mode, only the latest MAX_HIGH_BANDWIDTH_PEERS of them are kept.
+    pub fn select_high_bandwidth_provider(&self, nc: &CKBProtocolContext, peer: PeerIndex) {
+        let mut providers = self.state.high_bandwidth_providers.lock();
+        if let Some(index) = providers.iter().position(|provider| *provider == peer) {
+            providers.remove(index);
+            providers.push_back(peer);
+            return;
+        }
+        providers.push_back(peer);
+        self.send_compact_block_mode(nc, peer, true);
+        if providers.len() > MAX_HIGH_BANDWIDTH_PEERS {
+            if let Some(provider) = providers.pop_front() {
+                self.send_compact_block_mode(nc, provider, false);
+            }
+        }
+    }
+
+    // returns whether the peer was a high-bandwidth provider
+    fn drop_high_bandwidth_provider(&self, nc: &CKBProtocolContext, peer: PeerIndex) -> bool {
+        let mut providers = self.state.high_bandwidth_providers.lock();
+        match providers.iter().position(|provider| *provider == peer) {
+            Some(index) => {
+                providers.remove(index);
+                self.send_compact_block_mode(nc, peer, false);
+                true
+            }
+            None => false,
         }
     }
 
+    fn send_compact_block_mode(
+        &self,
+        nc: &CKBProtocolContext,
+        peer: PeerIndex,
+        high_bandwidth: bool,
+    ) {
+        debug!(
+            target: "relay",
+            "peer={} set_compact_block_mode high_bandwidth={}",
+            peer,
+            high_bandwidth
+        );
+        let fbb = &mut FlatBufferBuilder::new();
+        let message = RelayMessage::build_set_compact_block_mode(fbb, high_bandwidth);
+        fbb.finish(message, None);
+
+        let _ = nc.send(peer, fbb.finished_data().to_vec());
+    }
+
     pub fn reconstruct_block(
         &self,
         compact_block: &CompactBlock,
@@ -245,6 +442,138 @@ where
         }
     }
 
+    pub fn mark_known_txs(&self, peer: PeerIndex, tx_hashes: &[H256]) {
+        let mut tx_inventories = self.state.tx_inventories.lock();
+        let tx_inventory = tx_inventories.entry(peer).or_default();
+        for tx_hash in tx_hashes {
+            tx_inventory.mark_known(tx_hash.clone());
+        }
+    }
+
+    pub fn is_announced(&self, peer: PeerIndex, tx_hash: &H256) -> bool {
+        self.state
+            .tx_inventories
+            .lock()
+            .get(&peer)
+            .map_or(false, |tx_inventory| tx_inventory.is_announced(tx_hash))
+    }
+
+    // queue the transaction hash, it is sent with the next batch of announcements to the peer
+    pub fn announce_tx(&self, peer: PeerIndex, tx_hash: H256) {
+        self.state
+            .tx_inventories
+            .lock()
+            .entry(peer)
+            .or_default()
+            .queue_announcement(tx_hash, Instant::now());
+    }
+
+    // whitelisted peers get transactions regardless of their filters
+    pub fn wants_tx(&self, nc: &CKBProtocolContext, peer: PeerIndex, tx: &Transaction) -> bool {
+        nc.is_whitelisted(peer)
+            || self
+                .peers
+                .transaction_filters
+                .read()
+                .get(&peer)
+                .map_or(true, |filter| filter.contains(tx))
+    }
+
+    // Transactions submitted to this node are announced like relayed ones, so they are not
+    // sent to all peers at once. In stem mode one random outbound peer is told first.
+    pub fn relay_local_tx(&self, nc: &CKBProtocolContext, tx: Transaction) {
+        let stem_peer = if self.config.stem_local_txs {
+            let outbound_peers = nc
+                .connected_peers()
+                .into_iter()
+                .filter(|peer| {
+                    is_outbound(nc, *peer) == Some(true) && self.wants_tx(nc, *peer, &tx)
+                })
+                .collect::<Vec<_>>();
+            outbound_peers.choose(&mut thread_rng()).cloned()
+        } else {
+            None
+        };
+        if let Some(peer) = stem_peer {
+            debug!(target: "relay", "stem local transaction {:?} to peer={}", tx.hash(), peer);
+            self.announce_tx(peer, tx.hash());
+        }
+        self.state
+            .local_txs
+            .lock()
+            .insert(tx, stem_peer.is_some(), Instant::now());
+    }
+
+    fn broadcast_local_txs(&self, nc: &CKBProtocolContext, now: Instant) {
+        let mut due_txs = self.state.local_txs.lock().take_due(now);
+        if due_txs.is_empty() {
+            return;
+        }
+        // committed or dropped transactions are no longer ours to relay
+        due_txs.retain(|(tx, _)| {
+            let in_pool = self
+                .tx_pool
+                .contains_key(ProposalShortId::from_h256(&tx.hash()));
+            if !in_pool {
+                self.state.local_txs.lock().remove(&tx.hash());
+            }
+            in_pool
+        });
+
+        let peers = nc.connected_peers();
+        let mut tx_inventories = self.state.tx_inventories.lock();
+        for (tx, is_rebroadcast) in due_txs {
+            for peer in peers.iter().filter(|peer| self.wants_tx(nc, **peer, &tx)) {
+                let tx_inventory = tx_inventories.entry(*peer).or_default();
+                if is_rebroadcast {
+                    tx_inventory.queue_rebroadcast(tx.hash(), now);
+                } else {
+                    tx_inventory.queue_announcement(tx.hash(), now);
+                }
+            }
+        }
+    }
+
+    fn send_tx_announcements(&self, nc: &CKBProtocolContext) {
+        let now = Instant::now();
+        self.broadcast_local_txs(nc, now);
+        let announcements = self
+            .state
+            .tx_inventories
+            .lock()
+            .iter_mut()
+            .map(|(peer, tx_inventory)| (*peer, tx_inventory.take_due_announcements(now)))
+            .filter(|(_peer, tx_hashes)| !tx_hashes.is_empty())
+            .collect::<Vec<_>>();
+
+        for (peer, tx_hashes) in announcements {
+            let fbb = &mut FlatBufferBuilder::new();
+            let message = RelayMessage::build_relay_transaction_hashes(fbb, &tx_hashes);
+            fbb.finish(message, None);
+
+            let _ = nc.send(peer, fbb.finished_data().to_vec());
+        }
+
+        // request timed out transactions from the next peer which announced them
+        let mut retries = FnvHashMap::<PeerIndex, Vec<H256>>::default();
+        for (peer, tx_hash) in self.state.tx_requests.lock().retry_timed_out(now) {
+            if !self
+                .tx_pool
+                .contains_key(ProposalShortId::from_h256(&tx_hash))
+            {
+                retries.entry(peer).or_default().push(tx_hash);
+            }
+        }
+        for (peer, tx_hashes) in retries {
+            debug!(target: "relay", "peer={} get_relay_transactions {}", peer, tx_hashes.len());
+            let fbb = &mut FlatBufferBuilder::new();
+            let message = RelayMessage::build_get_relay_transactions(fbb, &tx_hashes);
+            fbb.finish(message, None);
+
+            let _ = nc.send(peer, fbb.finished_data().to_vec());
+        }
+    }
+
     pub fn get_block(&self, hash: &H256) -> Option<Block> {
         self.shared.block(hash)
     }
@@ -254,19 +583,50 @@ where
     }
 }
 
+// Name of the payload of a verified message
+fn relay_message_type(data: &[u8]) -> &'static str {
+    let payload_type = get_root::<RelayMessage>(data).payload_type();
+    // payload type sent by remote peer may be out of range
+    if payload_type as u8 > RelayPayload::UncleBlock as u8 {
+        UNKNOWN_MESSAGE_TYPE
+    } else {
+        enum_name_relay_payload(payload_type)
+    }
+}
+
 impl<CI> CKBProtocolHandler for Relayer<CI>
 where
     CI: ChainIndex + 'static,
 {
     fn initialize(&self, nc: Box<CKBProtocolContext>) {
         let _ = nc.register_timer(TX_PROPOSAL_TOKEN, Duration::from_millis(100));
+        let _ = nc.register_timer(TX_ANNOUNCE_TOKEN, Duration::from_millis(100));
     }
 
     fn received(&self, nc: Box<CKBProtocolContext>, peer: PeerIndex, data: &[u8]) {
-        // TODO use flatbuffers verifier
+        if !verify_relay_message(data) {
+            debug!(target: "relay", "peer={} sends malformed message", peer);
+            nc.report(peer, Behaviour::MalformedMessage);
+            return;
+        }
         let msg = get_root::<RelayMessage>(data);
         debug!(target: "relay", "msg {:?}", msg.payload_type());
-        self.process(nc.as_ref(), peer, msg);
+        let message_type = relay_message_type(data);
+        if !nc.is_whitelisted(peer) && !self.rate_limiter.check(peer, message_type) {
+            debug!(target: "relay", "peer={} exceeds rate limit of {}", peer, message_type);
+            nc.report(peer, Behaviour::TooManyMessages);
+            return;
+        }
+        self.process(nc.as_ref(), peer, msg, data);
+    }
+
+    // called by the network before `received`, so data isn't verified yet
+    fn message_type(&self, data: &[u8]) -> &'static str {
+        if verify_relay_message(data) {
+            relay_message_type(data)
+        } else {
+            UNKNOWN_MESSAGE_TYPE
+        }
     }
 
     fn connected(&self, _nc: Box<CKBProtocolContext>, peer: PeerIndex) {
@@ -276,12 +636,20 @@ where
 
     fn disconnected(&self, _nc: Box<CKBProtocolContext>, peer: PeerIndex) {
         info!(target: "relay", "peer={} RelayProtocol.disconnected", peer);
-        // TODO
+        self.rate_limiter.disconnected(peer);
+        self.state.tx_inventories.lock().remove(&peer);
+        self.state.tx_requests.lock().disconnected(peer);
+        self.state.high_bandwidth_peers.lock().remove(&peer);
+        self.state
+            .high_bandwidth_providers
+            .lock()
+            .retain(|provider| *provider != peer);
     }
 
     fn timer_triggered(&self, nc: Box<CKBProtocolContext>, token: TimerToken) {
         match token as usize {
             TX_PROPOSAL_TOKEN => self.prune_tx_proposal_request(nc.as_ref()),
+            TX_ANNOUNCE_TOKEN => self.send_tx_announcements(nc.as_ref()),
             _ => unreachable!(),
         }
     }
@@ -292,4 +660,15 @@ pub struct RelayState {
     pub pending_compact_blocks: RwLock<FnvHashMap<H256, CompactBlock>>,
     pub inflight_proposals: Mutex<FnvHashSet<ProposalShortId>>,
     pub pending_proposals_request: Mutex<FnvHashMap<ProposalShortId, FnvHashSet<PeerIndex>>>,
+    pub tx_inventories: Mutex<FnvHashMap<PeerIndex, TxInventory>>,
+    // requested transactions and the peers which announced them
+    pub tx_requests: Mutex<TxRequests>,
+    // peers which asked us to forward compact blocks before full validation
+    pub high_bandwidth_peers: Mutex<FnvHashSet<PeerIndex>>,
+    // peers we asked to forward compact blocks before full validation, oldest first
+    pub high_bandwidth_providers: Mutex<VecDeque<PeerIndex>>,
+    // requested uncles and the time they were requested
+    pub inflight_uncles: Mutex<FnvHashMap<H256, Instant>>,
+    // transactions submitted to this node, relayed until they leave the pool
+    pub local_txs: Mutex<LocalTxs>,
 }
@@ -0,0 +1,84 @@
+use crate::relayer::Relayer;
+use crate::MAX_RELAY_TX_HASHES_PER_BATCH;
+use ckb_core::transaction::ProposalShortId;
+use ckb_network::{peer_store::Behaviour, CKBProtocolContext, PeerIndex};
+use ckb_protocol::{RelayMessage, RelayTransactionHashes};
+use ckb_shared::index::ChainIndex;
+use flatbuffers::FlatBufferBuilder;
+use log::debug;
+use numext_fixed_hash::H256;
+use std::time::Instant;
+
+pub struct RelayTransactionHashesProcess<'a, CI: ChainIndex + 'a> {
+    message: &'a RelayTransactionHashes<'a>,
+    relayer: &'a Relayer<CI>,
+    peer: PeerIndex,
+    nc: &'a CKBProtocolContext,
+}
+
+impl<'a, CI> RelayTransactionHashesProcess<'a, CI>
+where
+    CI: ChainIndex + 'static,
+{
+    pub fn new(
+        message: &'a RelayTransactionHashes,
+        relayer: &'a Relayer<CI>,
+        peer: PeerIndex,
+        nc: &'a CKBProtocolContext,
+    ) -> Self {
+        RelayTransactionHashesProcess {
+            message,
+            nc,
+            peer,
+            relayer,
+        }
+    }
+
+    pub fn execute(self) {
+        let tx_hashes = match self.message.tx_hashes() {
+            Some(tx_hashes) if tx_hashes.len() <= MAX_RELAY_TX_HASHES_PER_BATCH => {
+                tx_hashes.iter().map(Into::into).collect::<Vec<H256>>()
+            }
+            _ => {
+                self.nc.report(self.peer, Behaviour::MalformedMessage);
+                return;
+            }
+        };
+        self.relayer.mark_known_txs(self.peer, &tx_hashes);
+
+        let unknown_tx_hashes = tx_hashes
+            .into_iter()
+            .filter(|hash| {
+                !self
+                    .relayer
+                    .tx_pool
+                    .contains_key(ProposalShortId::from_h256(hash))
+            })
+            .collect::<Vec<_>>();
+        // request each transaction from only one peer at a time,
+        // the other announcers are asked in turn if the request times out
+        let missing_tx_hashes = {
+            let now = Instant::now();
+            let mut tx_requests = self.relayer.state.tx_requests.lock();
+            unknown_tx_hashes
+                .into_iter()
+                .filter(|hash| tx_requests.announced(hash.clone(), self.peer, now))
+                .collect::<Vec<_>>()
+        };
+        if missing_tx_hashes.is_empty() {
+            return;
+        }
+
+        debug!(
+            target: "relay",
+            "peer={} get_relay_transactions {}",
+            self.peer,
+            missing_tx_hashes.len()
+        );
+        let fbb = &mut FlatBufferBuilder::new();
+        let message = RelayMessage::build_get_relay_transactions(fbb, &missing_tx_hashes);
+        fbb.finish(message, None);
+
+        let _ = self.nc.send(self.peer, fbb.finished_data().to_vec());
+    }
+}
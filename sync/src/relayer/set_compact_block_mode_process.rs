@@ -0,0 +1,39 @@
+use crate::relayer::Relayer;
+use ckb_network::PeerIndex;
+use ckb_protocol::SetCompactBlockMode;
+use ckb_shared::index::ChainIndex;
+use log::debug;
+
+pub struct SetCompactBlockModeProcess<'a, CI: ChainIndex + 'a> {
+    message: &'a SetCompactBlockMode<'a>,
+    relayer: &'a Relayer<CI>,
+    peer: PeerIndex,
+}
+
+impl<'a, CI> SetCompactBlockModeProcess<'a, CI>
+where
+    CI: ChainIndex + 'static,
+{
+    pub fn new(
+        message: &'a SetCompactBlockMode,
+        relayer: &'a Relayer<CI>,
+        peer: PeerIndex,
+    ) -> Self {
+        SetCompactBlockModeProcess {
+            message,
+            relayer,
+            peer,
+        }
+    }
+
+    pub fn execute(self) {
+        let high_bandwidth = self.message.high_bandwidth();
+        debug!(target: "relay", "peer={} high_bandwidth={}", self.peer, high_bandwidth);
+        let mut high_bandwidth_peers = self.relayer.state.high_bandwidth_peers.lock();
+        if high_bandwidth {
+            high_bandwidth_peers.insert(self.peer);
+        } else {
+            high_bandwidth_peers.remove(&self.peer);
+        }
+    }
+}
@@ -1,9 +1,8 @@
 use crate::relayer::Relayer;
 use ckb_core::transaction::Transaction;
 use ckb_network::{CKBProtocolContext, PeerIndex};
-use ckb_protocol::{RelayMessage, Transaction as FbsTransaction};
+use ckb_protocol::Transaction as FbsTransaction;
 use ckb_shared::index::ChainIndex;
-use flatbuffers::FlatBufferBuilder;
 
 pub struct TransactionProcess<'a, CI: ChainIndex + 'a> {
     message: &'a FbsTransaction<'a>,
@@ -32,22 +31,15 @@ where
 
     pub fn execute(self) {
         let tx: Transaction = (*self.message).into();
+        let tx_hash = tx.hash();
+        self.relayer.state.tx_requests.lock().received(&tx_hash);
+        self.relayer.mark_known_txs(self.peer, &[tx_hash.clone()]);
         if self.relayer.tx_pool.add_transaction(tx.clone()).is_ok() {
-            let fbb = &mut FlatBufferBuilder::new();
-            let message = RelayMessage::build_transaction(fbb, &tx);
-            fbb.finish(message, None);
-
+            // announce the hash instead of pushing the full transaction,
+            // peers request it only if they don't have it yet
             for peer_id in self.nc.connected_peers() {
-                if peer_id != self.peer
-                    && self
-                        .relayer
-                        .peers()
-                        .transaction_filters
-                        .read()
-                        .get(&peer_id)
-                        .map_or(true, |filter| filter.contains(&tx))
-                {
-                    let _ = self.nc.send(peer_id, fbb.finished_data().to_vec());
+                if peer_id != self.peer && self.relayer.wants_tx(self.nc, peer_id, &tx) {
+                    self.relayer.announce_tx(peer_id, tx_hash.clone());
                 }
             }
         }
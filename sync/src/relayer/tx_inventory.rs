@@ -0,0 +1,215 @@
+use crate::{
+    MAX_KNOWN_TXS_PER_PEER, MAX_RELAY_TX_HASHES_PER_BATCH, MAX_TX_ANNOUNCE_DELAY,
+    TX_ANNOUNCE_AVERAGE_DELAY,
+};
+use fnv::FnvHashSet;
+use numext_fixed_hash::H256;
+use rand::distributions::Exp;
+use rand::{thread_rng, Rng};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+// the oldest hashes are forgotten once MAX_KNOWN_TXS_PER_PEER is reached
+#[derive(Debug, Default)]
+struct HashSetWithLimit {
+    hashes: FnvHashSet<H256>,
+    order: VecDeque<H256>,
+}
+
+impl HashSetWithLimit {
+    fn insert(&mut self, hash: H256) -> bool {
+        if !self.hashes.insert(hash.clone()) {
+            return false;
+        }
+        if self.order.len() >= MAX_KNOWN_TXS_PER_PEER {
+            if let Some(oldest) = self.order.pop_front() {
+                self.hashes.remove(&oldest);
+            }
+        }
+        self.order.push_back(hash);
+        true
+    }
+
+    fn contains(&self, hash: &H256) -> bool {
+        self.hashes.contains(hash)
+    }
+}
+
+// Transaction inventory of a peer, the hashes the peer is known to have,
+// the hashes waiting to be announced to it and the hashes already announced.
+#[derive(Debug, Default)]
+pub struct TxInventory {
+    known: HashSetWithLimit,
+    announced: HashSetWithLimit,
+    pending: Vec<H256>,
+    next_announce_at: Option<Instant>,
+}
+
+impl TxInventory {
+    pub fn mark_known(&mut self, hash: H256) -> bool {
+        self.known.insert(hash)
+    }
+
+    pub fn is_known(&self, hash: &H256) -> bool {
+        self.known.contains(hash)
+    }
+
+    // the peer may only request transactions it was told about
+    pub fn is_announced(&self, hash: &H256) -> bool {
+        self.announced.contains(hash)
+    }
+
+    // Announcements are batched and sent after a random delay,
+    // so the origin of a transaction is harder to tell from the announce timing.
+    pub fn queue_announcement(&mut self, hash: H256, now: Instant) {
+        if self.mark_known(hash.clone()) {
+            self.pending.push(hash);
+            if self.next_announce_at.is_none() {
+                self.next_announce_at = Some(now + random_announce_delay());
+            }
+        }
+    }
+
+    // queue the hash even if the peer is known to have it, used to rebroadcast local transactions
+    pub fn queue_rebroadcast(&mut self, hash: H256, now: Instant) {
+        if !self.mark_known(hash.clone()) && self.pending.contains(&hash) {
+            return;
+        }
+        self.pending.push(hash);
+        if self.next_announce_at.is_none() {
+            self.next_announce_at = Some(now + random_announce_delay());
+        }
+    }
+
+    pub fn take_due_announcements(&mut self, now: Instant) -> Vec<H256> {
+        match self.next_announce_at {
+            Some(announce_at) if announce_at <= now => {}
+            _ => return Vec::new(),
+        }
+        let count = self.pending.len().min(MAX_RELAY_TX_HASHES_PER_BATCH);
+        let batch = self.pending.drain(..count).collect::<Vec<_>>();
+        for hash in &batch {
+            self.announced.insert(hash.clone());
+        }
+        self.next_announce_at = if self.pending.is_empty() {
+            None
+        } else {
+            Some(now + random_announce_delay())
+        };
+        batch
+    }
+}
+
+// Exponentially distributed with mean TX_ANNOUNCE_AVERAGE_DELAY, so the announcements to
+// each peer form a Poisson process. Capped, the long tail would hold back whole batches.
+fn random_announce_delay() -> Duration {
+    let delay = thread_rng().sample(Exp::new(1.0 / TX_ANNOUNCE_AVERAGE_DELAY as f64));
+    Duration::from_millis((delay as u64).min(MAX_TX_ANNOUNCE_DELAY))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn max_delay() -> Duration {
+        Duration::from_millis(MAX_TX_ANNOUNCE_DELAY)
+    }
+
+    fn hash(i: usize) -> H256 {
+        let mut bytes = [0u8; 32];
+        bytes[..8].copy_from_slice(&(i as u64).to_le_bytes());
+        H256::from_slice(&bytes).unwrap()
+    }
+
+    #[test]
+    fn test_announce_unknown_txs_once() {
+        let now = Instant::now();
+        let mut inventory = TxInventory::default();
+        inventory.mark_known(hash(0));
+        inventory.queue_announcement(hash(0), now);
+        inventory.queue_announcement(hash(1), now);
+        inventory.queue_announcement(hash(1), now);
+        inventory.queue_announcement(hash(2), now);
+
+        assert_eq!(
+            inventory.take_due_announcements(now + max_delay()),
+            vec![hash(1), hash(2)]
+        );
+        assert!(inventory
+            .take_due_announcements(now + max_delay())
+            .is_empty());
+        inventory.queue_announcement(hash(2), now);
+        assert!(inventory
+            .take_due_announcements(now + max_delay())
+            .is_empty());
+    }
+
+    #[test]
+    fn test_track_announced_txs() {
+        let now = Instant::now();
+        let mut inventory = TxInventory::default();
+        inventory.mark_known(hash(0));
+        inventory.queue_announcement(hash(1), now);
+        assert!(!inventory.is_announced(&hash(0)));
+        assert!(!inventory.is_announced(&hash(1)));
+
+        inventory.take_due_announcements(now + max_delay());
+        assert!(!inventory.is_announced(&hash(0)));
+        assert!(inventory.is_announced(&hash(1)));
+    }
+
+    #[test]
+    fn test_announce_in_batches() {
+        let now = Instant::now();
+        let mut inventory = TxInventory::default();
+        for i in 0..=MAX_RELAY_TX_HASHES_PER_BATCH {
+            inventory.queue_announcement(hash(i), now);
+        }
+
+        let now = now + max_delay();
+        assert_eq!(
+            inventory.take_due_announcements(now).len(),
+            MAX_RELAY_TX_HASHES_PER_BATCH
+        );
+        assert_eq!(
+            inventory.take_due_announcements(now + max_delay()),
+            vec![hash(MAX_RELAY_TX_HASHES_PER_BATCH)]
+        );
+    }
+
+    #[test]
+    fn test_rebroadcast_known_txs() {
+        let now = Instant::now();
+        let mut inventory = TxInventory::default();
+        inventory.mark_known(hash(0));
+        inventory.queue_rebroadcast(hash(0), now);
+        inventory.queue_rebroadcast(hash(0), now);
+        inventory.queue_rebroadcast(hash(1), now);
+        assert_eq!(
+            inventory.take_due_announcements(now + max_delay()),
+            vec![hash(0), hash(1)]
+        );
+    }
+
+    #[test]
+    fn test_random_announce_delay() {
+        let delays = (0..1000)
+            .map(|_| random_announce_delay())
+            .collect::<Vec<_>>();
+        assert!(delays.iter().all(|delay| *delay <= max_delay()));
+        let average = delays.iter().sum::<Duration>() / delays.len() as u32;
+        assert!(average > Duration::from_millis(TX_ANNOUNCE_AVERAGE_DELAY / 2));
+        assert!(average < Duration::from_millis(TX_ANNOUNCE_AVERAGE_DELAY * 2));
+    }
+
+    #[test]
+    fn test_forget_oldest_known_txs() {
+        let mut inventory = TxInventory::default();
+        for i in 0..=MAX_KNOWN_TXS_PER_PEER {
+            inventory.mark_known(hash(i));
+        }
+        assert!(!inventory.is_known(&hash(0)));
+        assert!(inventory.is_known(&hash(1)));
+        assert!(inventory.is_known(&hash(MAX_KNOWN_TXS_PER_PEER)));
+    }
+}
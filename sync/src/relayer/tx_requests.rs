@@ -0,0 +1,143 @@
+use crate::TX_REQUEST_TIMEOUT;
+use ckb_network::PeerIndex;
+use fnv::FnvHashMap;
+use numext_fixed_hash::H256;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+struct TxRequest {
+    // the peer the transaction is requested from and when
+    peer: PeerIndex,
+    requested_at: Instant,
+    // other peers which announced the transaction, asked in order if the request times out
+    announcers: VecDeque<PeerIndex>,
+}
+
+// Announced transactions we are waiting for. Each transaction is requested from one
+// announcer at a time, a peer which doesn't deliver only delays it until the timeout.
+#[derive(Default)]
+pub struct TxRequests {
+    requests: FnvHashMap<H256, TxRequest>,
+}
+
+impl TxRequests {
+    // returns whether the transaction should be requested from the peer now
+    pub fn announced(&mut self, hash: H256, peer: PeerIndex, now: Instant) -> bool {
+        match self.requests.get_mut(&hash) {
+            Some(request) => {
+                if request.peer != peer && !request.announcers.contains(&peer) {
+                    request.announcers.push_back(peer);
+                }
+                false
+            }
+            None => {
+                self.requests.insert(
+                    hash,
+                    TxRequest {
+                        peer,
+                        requested_at: now,
+                        announcers: VecDeque::new(),
+                    },
+                );
+                true
+            }
+        }
+    }
+
+    pub fn received(&mut self, hash: &H256) {
+        self.requests.remove(hash);
+    }
+
+    pub fn disconnected(&mut self, peer: PeerIndex) {
+        for request in self.requests.values_mut() {
+            request.announcers.retain(|announcer| *announcer != peer);
+        }
+    }
+
+    // Timed out requests are moved to the next announcer, returned as (peer, hash) pairs.
+    // Transactions without announcers left are forgotten.
+    pub fn retry_timed_out(&mut self, now: Instant) -> Vec<(PeerIndex, H256)> {
+        let timeout = Duration::from_millis(TX_REQUEST_TIMEOUT);
+        let mut retries = Vec::new();
+        self.requests.retain(|hash, request| {
+            if now.duration_since(request.requested_at) < timeout {
+                return true;
+            }
+            match request.announcers.pop_front() {
+                Some(peer) => {
+                    request.peer = peer;
+                    request.requested_at = now;
+                    retries.push((peer, hash.clone()));
+                    true
+                }
+                None => false,
+            }
+        });
+        retries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timeout() -> Duration {
+        Duration::from_millis(TX_REQUEST_TIMEOUT)
+    }
+
+    fn hash(i: u8) -> H256 {
+        let mut bytes = [0u8; 32];
+        bytes[0] = i;
+        H256::from_slice(&bytes).unwrap()
+    }
+
+    #[test]
+    fn test_request_from_one_announcer() {
+        let now = Instant::now();
+        let mut requests = TxRequests::default();
+        assert!(requests.announced(hash(1), 1, now));
+        assert!(!requests.announced(hash(1), 2, now));
+        assert!(!requests.announced(hash(1), 1, now));
+        assert!(requests.announced(hash(2), 2, now));
+        assert!(requests.retry_timed_out(now).is_empty());
+    }
+
+    #[test]
+    fn test_request_from_next_announcer_on_timeout() {
+        let now = Instant::now();
+        let mut requests = TxRequests::default();
+        // peer 1 announces first but withholds the transaction
+        requests.announced(hash(1), 1, now);
+        requests.announced(hash(1), 2, now);
+        requests.announced(hash(1), 3, now);
+
+        let now = now + timeout();
+        assert_eq!(requests.retry_timed_out(now), vec![(2, hash(1))]);
+        assert!(requests.retry_timed_out(now).is_empty());
+        assert_eq!(
+            requests.retry_timed_out(now + timeout()),
+            vec![(3, hash(1))]
+        );
+        // nobody else announced it
+        assert!(requests.retry_timed_out(now + timeout() * 2).is_empty());
+        assert!(requests.announced(hash(1), 1, now + timeout() * 2));
+    }
+
+    #[test]
+    fn test_received_and_disconnected() {
+        let now = Instant::now();
+        let mut requests = TxRequests::default();
+        requests.announced(hash(1), 1, now);
+        requests.announced(hash(1), 2, now);
+        requests.announced(hash(2), 1, now);
+        requests.announced(hash(2), 2, now);
+        requests.announced(hash(2), 3, now);
+
+        requests.received(&hash(1));
+        requests.disconnected(2);
+        assert_eq!(
+            requests.retry_timed_out(now + timeout()),
+            vec![(3, hash(2))]
+        );
+    }
+}
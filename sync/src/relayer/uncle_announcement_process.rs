@@ -0,0 +1,65 @@
+use crate::relayer::Relayer;
+use crate::UNCLE_REQUEST_TIMEOUT;
+use ckb_network::{peer_store::Behaviour, CKBProtocolContext, PeerIndex};
+use ckb_protocol::{RelayMessage, UncleAnnouncement};
+use ckb_shared::index::ChainIndex;
+use flatbuffers::FlatBufferBuilder;
+use log::debug;
+use numext_fixed_hash::H256;
+use std::time::{Duration, Instant};
+
+pub struct UncleAnnouncementProcess<'a, CI: ChainIndex + 'a> {
+    message: &'a UncleAnnouncement<'a>,
+    relayer: &'a Relayer<CI>,
+    peer: PeerIndex,
+    nc: &'a CKBProtocolContext,
+}
+
+impl<'a, CI> UncleAnnouncementProcess<'a, CI>
+where
+    CI: ChainIndex + 'static,
+{
+    pub fn new(
+        message: &'a UncleAnnouncement,
+        relayer: &'a Relayer<CI>,
+        peer: PeerIndex,
+        nc: &'a CKBProtocolContext,
+    ) -> Self {
+        UncleAnnouncementProcess {
+            message,
+            nc,
+            peer,
+            relayer,
+        }
+    }
+
+    pub fn execute(self) {
+        let block_hash: H256 = match self.message.block_hash() {
+            Some(block_hash) => block_hash.into(),
+            None => {
+                self.nc.report(self.peer, Behaviour::MalformedMessage);
+                return;
+            }
+        };
+        if self.relayer.is_known_uncle(&block_hash) {
+            return;
+        }
+        // request each uncle from only one peer until the request times out
+        {
+            let timeout = Duration::from_millis(UNCLE_REQUEST_TIMEOUT);
+            let mut inflight_uncles = self.relayer.state.inflight_uncles.lock();
+            inflight_uncles.retain(|_, requested_at| requested_at.elapsed() < timeout);
+            if inflight_uncles.contains_key(&block_hash) {
+                return;
+            }
+            inflight_uncles.insert(block_hash.clone(), Instant::now());
+        }
+
+        debug!(target: "relay", "peer={} get_uncle_block {:?}", self.peer, block_hash);
+        let fbb = &mut FlatBufferBuilder::new();
+        let message = RelayMessage::build_get_uncle_block(fbb, &block_hash);
+        fbb.finish(message, None);
+
+        let _ = self.nc.send(self.peer, fbb.finished_data().to_vec());
+    }
+}
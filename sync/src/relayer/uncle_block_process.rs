@@ -0,0 +1,99 @@
+use crate::relayer::Relayer;
+use ckb_core::uncle::UncleBlock;
+use ckb_network::{peer_store::Behaviour, CKBProtocolContext, PeerIndex};
+use ckb_protocol::UncleBlock as FbsUncleBlock;
+use ckb_shared::index::ChainIndex;
+use ckb_shared::shared::ChainProvider;
+use ckb_shared::store::ChainStore;
+use ckb_verification::{
+    verify_uncle_proposals, Error as VerifyError, HeaderResolverWrapper, HeaderVerifier, Verifier,
+};
+use log::debug;
+use std::sync::Arc;
+
+pub struct UncleBlockProcess<'a, CI: ChainIndex + 'a> {
+    message: &'a FbsUncleBlock<'a>,
+    relayer: &'a Relayer<CI>,
+    peer: PeerIndex,
+    nc: &'a CKBProtocolContext,
+}
+
+impl<'a, CI> UncleBlockProcess<'a, CI>
+where
+    CI: ChainIndex + 'static,
+{
+    pub fn new(
+        message: &'a FbsUncleBlock,
+        relayer: &'a Relayer<CI>,
+        peer: PeerIndex,
+        nc: &'a CKBProtocolContext,
+    ) -> Self {
+        UncleBlockProcess {
+            message,
+            nc,
+            peer,
+            relayer,
+        }
+    }
+
+    pub fn execute(self) {
+        let uncle: UncleBlock = (*self.message).into();
+        let block_hash = uncle.header().hash();
+        debug!(target: "relay", "peer={} uncle_block {:?}", self.peer, block_hash);
+        // only the uncles we asked for are accepted
+        if self
+            .relayer
+            .state
+            .inflight_uncles
+            .lock()
+            .remove(&block_hash)
+            .is_none()
+            || self.relayer.is_known_uncle(&block_hash)
+        {
+            return;
+        }
+
+        let shared = &self.relayer.shared;
+        let resolver = HeaderResolverWrapper::new(uncle.header(), shared.clone());
+        let header_verifier =
+            HeaderVerifier::new(shared.clone(), Arc::clone(&shared.consensus().pow_engine()));
+        if let Err(err) = header_verifier.verify(&resolver) {
+            debug!(target: "relay", "uncle_block verify error {:?}", err);
+            match err {
+                // we can't tell whether an uncle of an unknown fork is valid
+                VerifyError::UnknownParent(_) => {}
+                _ => self.nc.report(self.peer, Behaviour::InvalidHeader),
+            }
+            return;
+        }
+        if !uncle.cellbase().is_cellbase()
+            || uncle.header().cellbase_id() != &uncle.cellbase().hash()
+        {
+            self.nc.report(self.peer, Behaviour::InvalidBlock);
+            return;
+        }
+        if let Err(err) = verify_uncle_proposals(&uncle) {
+            debug!(target: "relay", "uncle_block verify error {:?}", err);
+            self.nc.report(self.peer, Behaviour::InvalidBlock);
+            return;
+        }
+        // too old to be included by the next block
+        let tip_number = shared.chain_state().read().tip_number();
+        if uncle.header().number() + shared.consensus().max_uncles_age() as u64 <= tip_number {
+            return;
+        }
+
+        let store = shared.store();
+        if store
+            .save_with_batch(|batch| {
+                store.insert_uncle_candidate(batch, &uncle);
+                Ok(())
+            })
+            .is_err()
+        {
+            return;
+        }
+        self.relayer.notify.notify_new_uncle(Arc::new(uncle));
+        self.relayer.announce_uncle(self.nc, self.peer, &block_hash);
+    }
+}
@@ -1,9 +1,6 @@
 use crate::synchronizer::{BlockStatus, Synchronizer};
 use crate::types::HeaderView;
-use crate::{
-    BLOCK_DOWNLOAD_TIMEOUT, BLOCK_DOWNLOAD_WINDOW, MAX_BLOCKS_IN_TRANSIT_PER_PEER,
-    PER_FETCH_BLOCK_LIMIT,
-};
+use crate::{BLOCK_DOWNLOAD_WINDOW, PER_FETCH_BLOCK_LIMIT};
 use ckb_core::header::Header;
 use ckb_network::PeerIndex;
 use ckb_shared::index::ChainIndex;
@@ -47,13 +44,13 @@ where
             .entry(self.peer)
             .or_insert_with(Default::default);
 
-        if inflight.timestamp < unix_time_as_millis().saturating_sub(BLOCK_DOWNLOAD_TIMEOUT) {
+        if inflight.is_timeout(unix_time_as_millis()) {
             debug!(target: "sync", "[block downloader] inflight block download timeout");
-            inflight.clear();
+            inflight.on_timeout();
         }
 
         // current peer block blocks_inflight reach limit
-        if MAX_BLOCKS_IN_TRANSIT_PER_PEER.saturating_sub(inflight.len()) == 0 {
+        if inflight.capacity() == 0 {
             debug!(target: "sync", "[block downloader] inflight count reach limit");
             true
         } else {
@@ -181,12 +178,20 @@ where
 
         let mut n_height = fixed_last_common_header.number();
         let mut v_fetch = Vec::with_capacity(PER_FETCH_BLOCK_LIMIT);
+        // the first block of the window which is downloading from some peer
+        let mut waiting_for: Option<(PeerIndex, H256)> = None;
 
         {
             let mut guard = self.synchronizer.peers.blocks_inflight.write();
-            let inflight = guard.get_mut(&self.peer).expect("inflight already init");
+            let capacity = cmp::min(
+                PER_FETCH_BLOCK_LIMIT,
+                guard
+                    .get(&self.peer)
+                    .expect("inflight already init")
+                    .capacity(),
+            );
 
-            while n_height < max_height && v_fetch.len() < PER_FETCH_BLOCK_LIMIT {
+            while n_height < max_height && v_fetch.len() < capacity {
                 n_height += 1;
                 let to_fetch = try_option!(self
                     .synchronizer
@@ -194,15 +199,42 @@ where
                 let to_fetch_hash = to_fetch.hash();
 
                 let block_status = self.synchronizer.get_block_status(&to_fetch_hash);
-                if block_status == BlockStatus::VALID_MASK
-                    && inflight.insert(to_fetch_hash.clone().clone())
+                if block_status != BlockStatus::VALID_MASK {
+                    continue;
+                }
+
+                // each block is downloaded from only one peer at a time
+                let downloader = guard
+                    .iter()
+                    .find(|(_, inflight)| inflight.contains(&to_fetch_hash))
+                    .map(|(peer, _)| *peer);
+                if let Some(downloader) = downloader {
+                    if waiting_for.is_none() {
+                        waiting_for = Some((downloader, to_fetch_hash));
+                    }
+                    continue;
+                }
+
+                let inflight = guard.get_mut(&self.peer).expect("inflight already init");
+                if !inflight.has_stalled_on(&to_fetch_hash)
+                    && inflight.insert(to_fetch_hash.clone())
                 {
                     debug!(
                         target: "sync", "[Synchronizer] inflight insert {:?}------------{:?}",
                         to_fetch.number(),
                         to_fetch_hash
                     );
-                    v_fetch.push(to_fetch_hash.clone());
+                    v_fetch.push(to_fetch_hash);
+                }
+            }
+
+            // This peer has room for more blocks but the window is exhausted,
+            // the peer holding the first block of the window is stalling the download.
+            if v_fetch.is_empty() && n_height > window_end {
+                if let Some((staller, hash)) = waiting_for {
+                    if let Some(inflight) = guard.get_mut(&staller) {
+                        inflight.mark_stalling(hash, unix_time_as_millis());
+                    }
                 }
             }
         }
@@ -1,59 +1,200 @@
+use crate::ORPHAN_BLOCK_EXPIRY;
 use ckb_core::block::Block;
+use ckb_network::PeerIndex;
+use ckb_protocol::Block as FbsBlock;
 use ckb_util::RwLock;
+use faketime::unix_time_as_millis;
+use flatbuffers::FlatBufferBuilder;
 use fnv::{FnvHashMap, FnvHashSet};
+use log::debug;
 use numext_fixed_hash::H256;
-use std::collections::hash_map::Entry;
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, VecDeque};
 
 pub type ParentHash = H256;
 
+struct OrphanBlock {
+    block: Block,
+    // None once the peer disconnects, the index may be given to another peer
+    peer: Option<PeerIndex>,
+    size: usize,
+    inserted_at: u64,
+    seq: u64,
+}
+
 #[derive(Default)]
+struct InnerPool {
+    blocks: FnvHashMap<H256, OrphanBlock>,
+    parents: FnvHashMap<ParentHash, FnvHashSet<H256>>,
+    // insertion order, the oldest orphans are evicted first
+    order: BTreeMap<u64, H256>,
+    next_seq: u64,
+    peer_counts: FnvHashMap<PeerIndex, usize>,
+    total_size: usize,
+}
+
+impl InnerPool {
+    fn remove(&mut self, hash: &H256) -> Option<OrphanBlock> {
+        let orphan = self.blocks.remove(hash)?;
+        let parent_hash = orphan.block.header().parent_hash();
+        if let Some(siblings) = self.parents.get_mut(parent_hash) {
+            siblings.remove(hash);
+            if siblings.is_empty() {
+                self.parents.remove(parent_hash);
+            }
+        }
+        self.order.remove(&orphan.seq);
+        if let Some(peer) = orphan.peer {
+            if let Some(count) = self.peer_counts.get_mut(&peer) {
+                *count -= 1;
+                if *count == 0 {
+                    self.peer_counts.remove(&peer);
+                }
+            }
+        }
+        self.total_size -= orphan.size;
+        Some(orphan)
+    }
+
+    fn oldest(&self) -> Option<(&H256, &OrphanBlock)> {
+        let hash = self.order.values().next()?;
+        self.blocks.get(hash).map(|orphan| (hash, orphan))
+    }
+}
+
+// Blocks whose parent is unknown yet, bounded by count, bytes and per peer count.
+// The oldest orphans are evicted to make room and orphans expire after ORPHAN_BLOCK_EXPIRY.
 pub struct OrphanBlockPool {
-    blocks: RwLock<FnvHashMap<ParentHash, FnvHashSet<Block>>>,
+    inner: RwLock<InnerPool>,
+    max_blocks: usize,
+    max_size: usize,
+    max_blocks_per_peer: usize,
 }
 
 impl OrphanBlockPool {
-    pub fn with_capacity(capacity: usize) -> Self {
+    pub fn new(max_blocks: usize, max_size: usize, max_blocks_per_peer: usize) -> Self {
         OrphanBlockPool {
-            blocks: RwLock::new(FnvHashMap::with_capacity_and_hasher(
-                capacity,
-                Default::default(),
-            )),
+            inner: RwLock::new(InnerPool::default()),
+            max_blocks,
+            max_size,
+            max_blocks_per_peer,
         }
     }
 
     /// Insert orphaned block, for which we have already requested its parent block
-    pub fn insert(&self, block: Block) {
-        self.blocks
-            .write()
+    pub fn insert(&self, peer: PeerIndex, block: Block) {
+        let hash = block.header().hash();
+        let size = serialized_size(&block);
+        let mut inner = self.inner.write();
+        if inner.blocks.contains_key(&hash) {
+            return;
+        }
+        if inner.peer_counts.get(&peer).cloned().unwrap_or(0) >= self.max_blocks_per_peer {
+            debug!(target: "sync", "peer={} exceeds the orphan block quota", peer);
+            return;
+        }
+
+        let seq = inner.next_seq;
+        inner.next_seq += 1;
+        inner
+            .parents
             .entry(block.header().parent_hash().clone())
             .or_insert_with(FnvHashSet::default)
-            .insert(block);
+            .insert(hash.clone());
+        inner.order.insert(seq, hash.clone());
+        *inner.peer_counts.entry(peer).or_insert(0) += 1;
+        inner.total_size += size;
+        inner.blocks.insert(
+            hash,
+            OrphanBlock {
+                block,
+                peer: Some(peer),
+                size,
+                inserted_at: unix_time_as_millis(),
+                seq,
+            },
+        );
+
+        while inner.blocks.len() > self.max_blocks || inner.total_size > self.max_size {
+            let oldest = match inner.oldest() {
+                Some((hash, _)) => hash.clone(),
+                None => break,
+            };
+            debug!(target: "sync", "evict orphan block {:?}", oldest);
+            inner.remove(&oldest);
+        }
     }
 
     pub fn remove_blocks_by_parent(&self, hash: &H256) -> VecDeque<Block> {
-        let mut guard = self.blocks.write();
+        let mut inner = self.inner.write();
         let mut queue: VecDeque<H256> = VecDeque::new();
         queue.push_back(hash.clone());
 
         let mut removed: VecDeque<Block> = VecDeque::new();
         while let Some(parent_hash) = queue.pop_front() {
-            if let Entry::Occupied(entry) = guard.entry(parent_hash) {
-                let (_, orphaned) = entry.remove_entry();
-                queue.extend(orphaned.iter().map(|b| b.header().hash().clone()));
-                removed.extend(orphaned.into_iter());
+            let children = match inner.parents.get(&parent_hash) {
+                Some(children) => children.iter().cloned().collect::<Vec<_>>(),
+                None => continue,
+            };
+            for child in children {
+                if let Some(orphan) = inner.remove(&child) {
+                    queue.push_back(child);
+                    removed.push_back(orphan.block);
+                }
             }
         }
         removed
     }
 
+    // Returns the number of expired orphans
+    pub fn expire(&self, now: u64) -> usize {
+        let mut inner = self.inner.write();
+        let mut expired = 0;
+        while let Some((hash, orphan)) = inner.oldest() {
+            if orphan.inserted_at.saturating_add(ORPHAN_BLOCK_EXPIRY) >= now {
+                break;
+            }
+            let hash = hash.clone();
+            inner.remove(&hash);
+            expired += 1;
+        }
+        expired
+    }
+
+    // The orphans are kept, but no longer count against the quota of the peer index
+    pub fn disconnected(&self, peer: PeerIndex) {
+        let mut inner = self.inner.write();
+        if inner.peer_counts.remove(&peer).is_none() {
+            return;
+        }
+        for orphan in inner.blocks.values_mut() {
+            if orphan.peer == Some(peer) {
+                orphan.peer = None;
+            }
+        }
+    }
+
+    pub fn contains(&self, hash: &H256) -> bool {
+        self.inner.read().blocks.contains_key(hash)
+    }
+
     pub fn len(&self) -> usize {
-        self.blocks.read().len()
+        self.inner.read().blocks.len()
     }
 
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
+
+    pub fn total_size(&self) -> usize {
+        self.inner.read().total_size
+    }
+}
+
+fn serialized_size(block: &Block) -> usize {
+    let fbb = &mut FlatBufferBuilder::new();
+    let message = FbsBlock::build(fbb, block);
+    fbb.finish(message, None);
+    fbb.finished_data().len()
 }
 
 #[cfg(test)]
@@ -83,17 +224,103 @@ mod tests {
         let block_number = 200;
         let mut blocks: Vec<Block> = Vec::new();
         let mut parent = consensus.genesis_block().header().clone();
-        let pool = OrphanBlockPool::with_capacity(200);
+        let pool = OrphanBlockPool::new(200, usize::max_value(), 200);
         for _ in 1..block_number {
             let new_block = gen_block(&parent);
             blocks.push(new_block.clone());
-            pool.insert(new_block.clone());
+            pool.insert(0, new_block.clone());
             parent = new_block.header().clone();
         }
 
         let orphan = pool.remove_blocks_by_parent(&consensus.genesis_block().header().hash());
         let orphan: HashSet<Block> = HashSet::from_iter(orphan.into_iter());
         let block: HashSet<Block> = HashSet::from_iter(blocks.into_iter());
-        assert_eq!(orphan, block)
+        assert_eq!(orphan, block);
+        assert!(pool.is_empty());
+        assert_eq!(pool.total_size(), 0);
+    }
+
+    fn gen_chain(len: usize) -> Vec<Block> {
+        let mut parent = Consensus::default().genesis_block().header().clone();
+        (0..len)
+            .map(|_| {
+                let block = gen_block(&parent);
+                parent = block.header().clone();
+                block
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_evict_oldest_orphans() {
+        let blocks = gen_chain(4);
+        let pool = OrphanBlockPool::new(3, usize::max_value(), 10);
+        for block in &blocks {
+            pool.insert(0, block.clone());
+        }
+        assert_eq!(pool.len(), 3);
+        assert!(!pool.contains(&blocks[0].header().hash()));
+        assert!(pool.contains(&blocks[3].header().hash()));
+
+        let size = pool.total_size() / 3;
+        let pool = OrphanBlockPool::new(10, size * 2, 10);
+        for block in &blocks {
+            pool.insert(0, block.clone());
+        }
+        assert_eq!(pool.len(), 2);
+        assert!(pool.contains(&blocks[2].header().hash()));
+        assert!(pool.contains(&blocks[3].header().hash()));
+    }
+
+    #[test]
+    fn test_orphan_quota_per_peer() {
+        let blocks = gen_chain(4);
+        let pool = OrphanBlockPool::new(10, usize::max_value(), 2);
+        for block in &blocks[..3] {
+            pool.insert(1, block.clone());
+        }
+        pool.insert(2, blocks[3].clone());
+        assert_eq!(pool.len(), 3);
+        assert!(!pool.contains(&blocks[2].header().hash()));
+        assert!(pool.contains(&blocks[3].header().hash()));
+    }
+
+    #[test]
+    fn test_reset_orphan_quota_on_disconnect() {
+        let blocks = gen_chain(5);
+        let pool = OrphanBlockPool::new(10, usize::max_value(), 2);
+        pool.insert(1, blocks[3].clone());
+        pool.insert(1, blocks[4].clone());
+        pool.disconnected(1);
+
+        // a new peer with the same index gets the full quota
+        pool.insert(1, blocks[0].clone());
+        pool.insert(1, blocks[1].clone());
+        pool.insert(1, blocks[2].clone());
+        assert_eq!(pool.len(), 4);
+        assert!(!pool.contains(&blocks[2].header().hash()));
+
+        // removing the orphans of the disconnected peer doesn't free the new peer's quota
+        assert_eq!(
+            pool.remove_blocks_by_parent(&blocks[2].header().hash())
+                .len(),
+            2
+        );
+        pool.insert(1, blocks[2].clone());
+        assert!(!pool.contains(&blocks[2].header().hash()));
+    }
+
+    #[test]
+    fn test_expire_orphans() {
+        let blocks = gen_chain(2);
+        let pool = OrphanBlockPool::new(10, usize::max_value(), 10);
+        pool.insert(0, blocks[1].clone());
+        let now = unix_time_as_millis();
+        assert_eq!(pool.expire(now), 0);
+        assert_eq!(pool.expire(now + ORPHAN_BLOCK_EXPIRY + 1), 1);
+        assert!(pool.is_empty());
+        assert!(pool
+            .remove_blocks_by_parent(&blocks[0].header().hash())
+            .is_empty());
     }
 }
@@ -1,6 +1,7 @@
 use crate::synchronizer::Synchronizer;
+use ckb_chain::error::ProcessBlockError;
 use ckb_core::block::Block;
-use ckb_network::{CKBProtocolContext, PeerIndex};
+use ckb_network::{peer_store::Behaviour, CKBProtocolContext, PeerIndex};
 use ckb_protocol::Block as PBlock;
 use ckb_shared::index::ChainIndex;
 use log::debug;
@@ -9,6 +10,7 @@ pub struct BlockProcess<'a, CI: ChainIndex + 'a> {
     message: &'a PBlock<'a>,
     synchronizer: &'a Synchronizer<CI>,
     peer: PeerIndex,
+    nc: &'a CKBProtocolContext,
 }
 
 impl<'a, CI> BlockProcess<'a, CI>
@@ -19,12 +21,13 @@ where
         message: &'a PBlock,
         synchronizer: &'a Synchronizer<CI>,
         peer: PeerIndex,
-        _nc: &'a CKBProtocolContext,
+        nc: &'a CKBProtocolContext,
     ) -> Self {
         BlockProcess {
             message,
             synchronizer,
             peer,
+            nc,
         }
     }
 
@@ -33,6 +36,17 @@ where
         debug!(target: "sync", "BlockProcess received block {} {:?}", block.header().number(), block.header().hash());
 
         self.synchronizer.peers.block_received(self.peer, &block);
-        self.synchronizer.process_new_block(self.peer, block);
+        if self
+            .synchronizer
+            .snapshot_block_received(self.nc, self.peer, &block)
+        {
+            return;
+        }
+        if let Err(ProcessBlockError::Verification(err)) =
+            self.synchronizer.process_new_block(self.peer, block)
+        {
+            debug!(target: "sync", "BlockProcess received invalid block {:?}", err);
+            self.nc.report(self.peer, Behaviour::InvalidBlock);
+        }
     }
 }
@@ -1,7 +1,7 @@
 use crate::synchronizer::{BlockStatus, Synchronizer};
 use crate::MAX_HEADERS_LEN;
 use ckb_core::header::Header;
-use ckb_network::{CKBProtocolContext, PeerIndex};
+use ckb_network::{peer_store::Behaviour, CKBProtocolContext, PeerIndex};
 use ckb_protocol::{FlatbuffersVectorIterator, Headers};
 use ckb_shared::block_median_time_context::BlockMedianTimeContext;
 use ckb_shared::index::ChainIndex;
@@ -83,6 +83,9 @@ impl<'a, CI: ChainIndex + 'a> BlockMedianTimeContext for VerifierResolver<'a, CI
                     .map(|header| header.parent_hash().to_owned())
             })
     }
+    fn now(&self) -> u64 {
+        self.synchronizer.shared.net_time().now()
+    }
 }
 
 impl<'a, CI: ChainIndex> HeaderResolver for VerifierResolver<'a, CI> {
@@ -206,11 +209,21 @@ where
         acceptor.accept()
     }
 
+    fn report_invalid(&self, result: &ValidationResult) {
+        match result.error {
+            Some(ValidationError::Verify(VerifyError::UnknownParent(_))) => {
+                self.nc.report(self.peer, Behaviour::UnconnectedHeaders)
+            }
+            _ if result.misbehavior > 0 => self.nc.report(self.peer, Behaviour::InvalidHeader),
+            _ => {}
+        }
+    }
+
     pub fn execute(self) {
         debug!(target: "sync", "HeadersProcess begin");
 
         if self.is_oversize() {
-            self.synchronizer.peers.misbehavior(self.peer, 20);
+            self.nc.report(self.peer, Behaviour::MalformedMessage);
             debug!(target: "sync", "HeadersProcess is_oversize");
             return;
         }
@@ -225,18 +238,14 @@ where
             .collect::<Vec<Header>>();
 
         if !self.is_continuous(&headers) {
-            self.synchronizer.peers.misbehavior(self.peer, 20);
+            self.nc.report(self.peer, Behaviour::UnconnectedHeaders);
             debug!(target: "sync", "HeadersProcess is not continuous");
             return;
         }
 
         let result = self.accept_first(&headers[0]);
         if !result.is_valid() {
-            if result.misbehavior > 0 {
-                self.synchronizer
-                    .peers
-                    .misbehavior(self.peer, result.misbehavior);
-            }
+            self.report_invalid(&result);
             debug!(target: "sync", "\n\nHeadersProcess accept_first is_valid {:?} headers = {:?}\n\n", result, headers[0]);
             return;
         }
@@ -253,11 +262,7 @@ where
                 let result = acceptor.accept();
 
                 if !result.is_valid() {
-                    if result.misbehavior > 0 {
-                        self.synchronizer
-                            .peers
-                            .misbehavior(self.peer, result.misbehavior);
-                    }
+                    self.report_invalid(&result);
                     debug!(target: "sync", "HeadersProcess accept is invalid {:?}", result);
                     return;
                 }
@@ -358,6 +363,33 @@ where
         Ok(())
     }
 
+    // Headers conflicting with a checkpoint, or forking off the chain below the
+    // last checkpoint once we know it, are rejected.
+    pub fn checkpoint_check(&self, state: &mut ValidationResult) -> Result<(), ()> {
+        let consensus = self.synchronizer.consensus();
+        let number = self.header.number();
+        let hash = self.header.hash();
+
+        if let Some(checkpoint) = consensus.checkpoint(number) {
+            if checkpoint != &hash {
+                state.dos(Some(ValidationError::Checkpoint), 100);
+                return Err(());
+            }
+        }
+
+        if let Some((last_number, last_hash)) = consensus.last_checkpoint() {
+            if number < last_number {
+                if let Some(ancestor) = self.synchronizer.get_ancestor(last_hash, number) {
+                    if ancestor.hash() != hash {
+                        state.dos(Some(ValidationError::Checkpoint), 100);
+                        return Err(());
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
     pub fn non_contextual_check(&self, state: &mut ValidationResult) -> Result<(), ()> {
         self.verifier.verify(&self.resolver).map_err(|error| match error {
             VerifyError::Pow(e) => {
@@ -401,6 +433,13 @@ where
             return result;
         }
 
+        if self.checkpoint_check(&mut result).is_err() {
+            debug!(target: "sync", "HeadersProcess accept {:?} checkpoint", self.header.number());
+            self.synchronizer
+                .insert_block_status(self.header.hash().clone(), BlockStatus::FAILED_MASK);
+            return result;
+        }
+
         if self.non_contextual_check(&mut result).is_err() {
             debug!(target: "sync", "HeadersProcess accept {:?} non_contextual", self.header.number());
             self.synchronizer
@@ -441,6 +480,7 @@ pub enum ValidationError {
     FailedMask,
     Version,
     InvalidParent,
+    Checkpoint,
 }
 
 #[derive(Debug, Clone, Default)]
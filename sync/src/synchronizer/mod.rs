@@ -5,6 +5,7 @@ mod filter_process;
 mod get_blocks_process;
 mod get_headers_process;
 mod headers_process;
+mod snapshot_process;
 
 use self::block_fetcher::BlockFetcher;
 use self::block_pool::OrphanBlockPool;
@@ -13,33 +14,45 @@ use self::filter_process::{AddFilterProcess, ClearFilterProcess, SetFilterProces
 use self::get_blocks_process::GetBlocksProcess;
 use self::get_headers_process::GetHeadersProcess;
 use self::headers_process::HeadersProcess;
+use self::snapshot_process::{GetSnapshotChunkProcess, SnapshotChunkProcess};
 use crate::config::Config;
-use crate::types::{HeaderView, Peers};
+use crate::rate_limiter::RateLimiter;
+use crate::types::{HeaderView, Peers, SnapshotDownload, SyncState};
 use crate::{
     CHAIN_SYNC_TIMEOUT, EVICTION_HEADERS_RESPONSE_TIME, HEADERS_DOWNLOAD_TIMEOUT_BASE,
     HEADERS_DOWNLOAD_TIMEOUT_PER_HEADER, MAX_HEADERS_LEN,
-    MAX_OUTBOUND_PEERS_TO_PROTECT_FROM_DISCONNECT, MAX_TIP_AGE, POW_SPACE,
+    MAX_OUTBOUND_PEERS_TO_PROTECT_FROM_DISCONNECT, MAX_SNAPSHOT_CHUNKS_IN_TRANSIT, MAX_TIP_AGE,
+    POW_SPACE,
 };
 use bitflags::bitflags;
 use ckb_chain::chain::ChainController;
 use ckb_chain::error::ProcessBlockError;
 use ckb_chain_spec::consensus::Consensus;
 use ckb_core::block::Block;
+use ckb_core::extras::BlockExt;
 use ckb_core::header::{BlockNumber, Header};
-use ckb_network::{CKBProtocolContext, CKBProtocolHandler, PeerIndex, Severity, TimerToken};
-use ckb_protocol::{SyncMessage, SyncPayload};
+use ckb_network::{
+    peer_store::Behaviour, CKBProtocolContext, CKBProtocolHandler, PeerIndex, Severity, TimerToken,
+    UNKNOWN_MESSAGE_TYPE,
+};
+use ckb_protocol::{enum_name_sync_payload, verify_sync_message, SyncMessage, SyncPayload};
+use ckb_shared::error::SharedError;
 use ckb_shared::index::ChainIndex;
 use ckb_shared::shared::{ChainProvider, Shared};
-use ckb_util::{try_option, RwLock, RwLockUpgradableReadGuard};
+use ckb_shared::snapshot::{Snapshot, SnapshotImport};
+use ckb_util::{try_option, Mutex, RwLock, RwLockUpgradableReadGuard};
 use faketime::unix_time_as_millis;
 use flatbuffers::{get_root, FlatBufferBuilder};
-use log::{debug, info, warn};
+use log::{debug, error, info, warn};
 use numext_fixed_hash::H256;
+use rand::seq::SliceRandom;
+use rand::thread_rng;
 use std::cmp;
 use std::collections::HashMap;
-use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::Ordering;
+use std::sync::atomic::{AtomicBool, AtomicUsize};
 use std::sync::Arc;
+use std::thread;
 use std::time::Duration;
 
 pub const SEND_GET_HEADERS_TOKEN: TimerToken = 0;
@@ -80,6 +93,18 @@ pub struct Synchronizer<CI: ChainIndex> {
     pub config: Arc<Config>,
     pub orphan_block_pool: Arc<OrphanBlockPool>,
     pub outbound_peers_with_protect: Arc<AtomicUsize>,
+    pub rate_limiter: Arc<RateLimiter>,
+    // Snapshots of main chain checkpoints with a trusted root, built in the background
+    // and served to peers in chunks. None if the built root does not match the chain
+    // spec, the snapshot is not built again then.
+    pub snapshots: Arc<RwLock<HashMap<H256, Option<Arc<Snapshot>>>>>,
+    pub building_snapshots: Arc<AtomicBool>,
+    pub snapshot_download: Arc<Mutex<Option<SnapshotDownload>>>,
+    // Set once the chain is past fast sync, blocks are only downloaded from then on
+    pub fast_sync_done: Arc<AtomicBool>,
+    // Hashes of the last checkpoint block and its ancestors indexed by number,
+    // collected from the headers once the checkpoint header is received
+    pub checkpoint_chain: Arc<RwLock<Option<Vec<H256>>>>,
 }
 
 // https://github.com/rust-lang/rust/issues/40754
@@ -96,17 +121,26 @@ impl<CI: ChainIndex> ::std::clone::Clone for Synchronizer<CI> {
             config: Arc::clone(&self.config),
             orphan_block_pool: Arc::clone(&self.orphan_block_pool),
             outbound_peers_with_protect: Arc::clone(&self.outbound_peers_with_protect),
+            rate_limiter: Arc::clone(&self.rate_limiter),
+            snapshots: Arc::clone(&self.snapshots),
+            building_snapshots: Arc::clone(&self.building_snapshots),
+            snapshot_download: Arc::clone(&self.snapshot_download),
+            fast_sync_done: Arc::clone(&self.fast_sync_done),
+            checkpoint_chain: Arc::clone(&self.checkpoint_chain),
         }
     }
 }
 
-fn is_outbound(nc: &CKBProtocolContext, peer: PeerIndex) -> Option<bool> {
+pub(crate) fn is_outbound(nc: &CKBProtocolContext, peer: PeerIndex) -> Option<bool> {
     nc.session_info(peer)
         .map(|session_info| session_info.peer.is_outbound())
 }
 
 impl<CI: ChainIndex> Synchronizer<CI> {
     pub fn new(chain: ChainController, shared: Shared<CI>, config: Config) -> Synchronizer<CI> {
+        // fast sync only applies to a chain at genesis
+        let fast_sync_done = shared.consensus().snapshot_roots.is_empty()
+            || shared.chain_state().read().tip_number() > 0;
         let (total_difficulty, header, total_uncles_count) = {
             let chain_state = shared.chain_state().read();
             let block_ext = shared
@@ -119,23 +153,38 @@ impl<CI: ChainIndex> Synchronizer<CI> {
             )
         };
         let best_known_header = HeaderView::new(header, total_difficulty, total_uncles_count);
-        let orphan_block_limit = config.orphan_block_limit;
+        let orphan_block_pool = OrphanBlockPool::new(
+            config.orphan_block_limit,
+            config.orphan_block_size_limit,
+            config.orphan_block_limit_per_peer,
+        );
+        let rate_limiter = RateLimiter::new(config.rate_limits.clone());
 
         Synchronizer {
             config: Arc::new(config),
             chain,
             shared,
             peers: Arc::new(Peers::default()),
-            orphan_block_pool: Arc::new(OrphanBlockPool::with_capacity(orphan_block_limit)),
+            orphan_block_pool: Arc::new(orphan_block_pool),
             best_known_header: Arc::new(RwLock::new(best_known_header)),
             status_map: Arc::new(RwLock::new(HashMap::new())),
             header_map: Arc::new(RwLock::new(HashMap::new())),
             n_sync: Arc::new(AtomicUsize::new(0)),
             outbound_peers_with_protect: Arc::new(AtomicUsize::new(0)),
+            rate_limiter: Arc::new(rate_limiter),
+            snapshots: Arc::new(RwLock::new(HashMap::new())),
+            building_snapshots: Arc::new(AtomicBool::new(false)),
+            snapshot_download: Arc::new(Mutex::new(None)),
+            fast_sync_done: Arc::new(AtomicBool::new(fast_sync_done)),
+            checkpoint_chain: Arc::new(RwLock::new(None)),
         }
     }
 
     fn process(&self, nc: &CKBProtocolContext, peer: PeerIndex, message: SyncMessage) {
+        if message.payload().is_none() {
+            nc.report(peer, Behaviour::MalformedMessage);
+            return;
+        }
         match message.payload_type() {
             SyncPayload::GetHeaders => {
                 GetHeadersProcess::new(&message.payload_as_get_headers().unwrap(), self, peer, nc)
@@ -162,6 +211,20 @@ impl<CI: ChainIndex> Synchronizer<CI> {
             }
             SyncPayload::ClearFilter => ClearFilterProcess::new(self, peer).execute(),
             SyncPayload::FilteredBlock => {} // ignore, should not receive FilteredBlock in full node mode
+            SyncPayload::GetSnapshotChunk => GetSnapshotChunkProcess::new(
+                &message.payload_as_get_snapshot_chunk().unwrap(),
+                self,
+                peer,
+                nc,
+            )
+            .execute(),
+            SyncPayload::SnapshotChunk => SnapshotChunkProcess::new(
+                &message.payload_as_snapshot_chunk().unwrap(),
+                self,
+                peer,
+                nc,
+            )
+            .execute(),
             SyncPayload::NONE => {}
         }
     }
@@ -194,6 +257,294 @@ impl<CI: ChainIndex> Synchronizer<CI> {
         self.best_known_header.read().clone()
     }
 
+    pub fn sync_state(&self) -> SyncState {
+        let mut blocks_inflight = self
+            .peers
+            .blocks_inflight
+            .read()
+            .iter()
+            .map(|(peer, inflight)| (*peer, inflight.len()))
+            .collect::<Vec<_>>();
+        blocks_inflight.sort();
+        SyncState {
+            is_initial_block_download: self.is_initial_block_download(),
+            best_known_header: self.best_known_header(),
+            tip_number: self.shared.chain_state().read().tip_number(),
+            download_rate: self
+                .peers
+                .download_rate
+                .lock()
+                .blocks_in_window(unix_time_as_millis()),
+            blocks_inflight,
+            orphan_blocks: self.orphan_block_pool.len(),
+        }
+    }
+
+    // Fast sync: a chain at genesis downloads the live cell set at the highest checkpoint
+    // with a trusted snapshot root, instead of all blocks up to it, once the checkpoint
+    // header is received. Blocks are not downloaded until the snapshot is imported or
+    // fast sync is given up.
+    pub fn start_fast_sync(&self, nc: &CKBProtocolContext) {
+        if self.fast_sync_done.load(Ordering::Acquire) || self.snapshot_download.lock().is_some() {
+            return;
+        }
+        let target = self
+            .consensus()
+            .snapshot_roots
+            .iter()
+            .filter_map(|(block_hash, root)| {
+                self.get_header_view(block_hash)
+                    .filter(|view| view.number() > 0)
+                    .map(|view| (view.number(), block_hash.clone(), root.clone()))
+            })
+            .max_by_key(|(number, _, _)| *number);
+        let (number, block_hash, root) = match target {
+            Some(target) => target,
+            None => {
+                // the headers are synced and none of them is a snapshot block
+                let best_known_header = self.best_known_header();
+                if unix_time_as_millis().saturating_sub(best_known_header.inner().timestamp())
+                    <= MAX_TIP_AGE
+                {
+                    info!(target: "sync", "no snapshot on the best chain, fast sync skipped");
+                    self.fast_sync_done.store(true, Ordering::Release);
+                }
+                return;
+            }
+        };
+        let block_hashes = match self.snapshot_block_hashes(&block_hash, number) {
+            Some(block_hashes) => block_hashes,
+            None => return,
+        };
+        info!(target: "sync", "fast sync from the snapshot of block {} {:?}", number, block_hash);
+        *self.snapshot_download.lock() = Some(SnapshotDownload::new(
+            block_hash,
+            root,
+            block_hashes,
+            unix_time_as_millis(),
+        ));
+        self.request_snapshot_chunks(nc);
+    }
+
+    // The snapshot block and the ancestors the block verifier looks into when verifying
+    // the blocks after it, they are downloaded in full
+    fn snapshot_block_hashes(&self, block_hash: &H256, number: BlockNumber) -> Option<Vec<H256>> {
+        let consensus = self.consensus();
+        let count = cmp::min(
+            cmp::max(
+                consensus.max_uncles_age() as BlockNumber,
+                consensus.transaction_propagation_timeout,
+            ),
+            number,
+        );
+        let mut block_hashes = Vec::with_capacity(count as usize);
+        let mut hash = block_hash.clone();
+        for _ in 0..count {
+            let parent_hash = self.get_header(&hash)?.parent_hash().clone();
+            block_hashes.push(hash);
+            hash = parent_hash;
+        }
+        block_hashes.reverse();
+        Some(block_hashes)
+    }
+
+    // Spread the missing chunks over the connected peers and request the missing blocks,
+    // fast sync is given up if the download stalls.
+    pub fn request_snapshot_chunks(&self, nc: &CKBProtocolContext) {
+        let peers = self.peers.state.read().keys().cloned().collect::<Vec<_>>();
+        let now = unix_time_as_millis();
+        let mut download = self.snapshot_download.lock();
+        if download
+            .as_ref()
+            .map_or(false, |download| download.is_stalled(now))
+        {
+            let block_hash = download.take().map(|download| download.block_hash);
+            warn!(
+                target: "sync",
+                "snapshot download of block {:?} stalled, fast sync given up",
+                block_hash
+            );
+            self.fast_sync_done.store(true, Ordering::Release);
+            return;
+        }
+        let (block_hash, requests, blocks) = match *download {
+            Some(ref mut download) => (
+                download.block_hash.clone(),
+                download.chunks_to_request(&peers, now, MAX_SNAPSHOT_CHUNKS_IN_TRANSIT),
+                if peers.is_empty() {
+                    Vec::new()
+                } else {
+                    download.blocks_to_request(now)
+                },
+            ),
+            None => return,
+        };
+        drop(download);
+        for (peer, index) in requests {
+            debug!(target: "sync", "peer={} get_snapshot_chunk {}", peer, index);
+            let fbb = &mut FlatBufferBuilder::new();
+            let message = SyncMessage::build_get_snapshot_chunk(fbb, &block_hash, index as u32);
+            fbb.finish(message, None);
+            let _ = nc.send(peer, fbb.finished_data().to_vec());
+        }
+        if let Some(peer) = peers.choose(&mut thread_rng()) {
+            if !blocks.is_empty() {
+                self.send_getblocks(&blocks, nc, *peer);
+            }
+        }
+    }
+
+    // Blocks of the snapshot download are held until the snapshot is imported, the chain
+    // doesn't have their ancestors. Returns false for any other block.
+    pub fn snapshot_block_received(
+        &self,
+        nc: &CKBProtocolContext,
+        peer: PeerIndex,
+        block: &Block,
+    ) -> bool {
+        let mut download = self.snapshot_download.lock();
+        match *download {
+            Some(ref mut download) if download.is_snapshot_block(&block.header().hash()) => {
+                if !download.insert_block(block.clone(), unix_time_as_millis()) {
+                    warn!(
+                        target: "sync",
+                        "peer={} sends snapshot block {:?} not matching its header",
+                        peer,
+                        block.header().hash()
+                    );
+                    nc.report(peer, Behaviour::InvalidBlock);
+                    return true;
+                }
+            }
+            _ => return false,
+        }
+        if download
+            .as_ref()
+            .map_or(false, |download| download.is_complete())
+        {
+            let complete = download.take().expect("download checked");
+            drop(download);
+            self.finish_fast_sync(complete);
+        }
+        true
+    }
+
+    // Import the verified snapshot together with the checkpointed headers up to it, the
+    // chain continues from the snapshot block.
+    pub fn finish_fast_sync(&self, download: SnapshotDownload) {
+        let block_hash = download.block_hash.clone();
+        let (transactions, blocks) = download.into_parts();
+        let now = unix_time_as_millis();
+        let mut headers = Vec::new();
+        let mut hash = block_hash.clone();
+        while let Some(view) = self.get_header_view(&hash) {
+            if view.number() == 0 {
+                break;
+            }
+            let parent_hash = view.inner().parent_hash().clone();
+            let ext = BlockExt {
+                received_at: now,
+                total_difficulty: view.total_difficulty().clone(),
+                total_uncles_count: view.total_uncles_count(),
+                // the chain up to the snapshot is trusted along with its root
+                valid: Some(true),
+            };
+            headers.push((view.into_inner(), ext));
+            hash = parent_hash;
+        }
+        headers.reverse();
+        info!(
+            target: "sync",
+            "snapshot of block {:?} verified, importing {} headers and {} live transactions",
+            block_hash,
+            headers.len(),
+            transactions.len()
+        );
+
+        let hashes = headers
+            .iter()
+            .map(|(header, _)| header.hash())
+            .collect::<Vec<_>>();
+        let imported = if hash == self.shared.genesis_hash() {
+            self.chain.import_snapshot(SnapshotImport {
+                headers,
+                blocks,
+                transactions,
+            })
+        } else {
+            Err(SharedError::InvalidSnapshot)
+        };
+        match imported {
+            Ok(()) => {
+                let mut status_map = self.status_map.write();
+                for hash in hashes {
+                    status_map.insert(hash, BlockStatus::BLOCK_HAVE_MASK);
+                }
+                // blocks are downloaded from the snapshot block on
+                self.peers.last_common_headers.write().clear();
+            }
+            Err(err) => warn!(
+                target: "sync",
+                "failed to import the snapshot of block {:?}: {:?}", block_hash, err
+            ),
+        }
+        self.fast_sync_done.store(true, Ordering::Release);
+    }
+
+    pub fn snapshot(&self, block_hash: &H256) -> Option<Arc<Snapshot>> {
+        self.snapshots
+            .read()
+            .get(block_hash)
+            .and_then(|snapshot| snapshot.clone())
+    }
+
+    // Build the snapshots of checkpoints which are on our main chain now
+    fn build_missing_snapshots(&self) {
+        let block_hashes = {
+            let snapshots = self.snapshots.read();
+            self.shared
+                .consensus()
+                .snapshot_roots
+                .keys()
+                .filter(|block_hash| !snapshots.contains_key(block_hash))
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        for block_hash in block_hashes {
+            let number = match self.shared.block_number(&block_hash) {
+                Some(number) => number,
+                None => continue,
+            };
+            if self.shared.block_hash(number).as_ref() != Some(&block_hash) {
+                continue;
+            }
+            let snapshot = self.build_snapshot(&block_hash, number);
+            self.snapshots.write().insert(block_hash, snapshot);
+        }
+    }
+
+    fn build_snapshot(&self, block_hash: &H256, number: BlockNumber) -> Option<Arc<Snapshot>> {
+        let root = self.shared.consensus().snapshot_root(block_hash)?;
+        let snapshot = Snapshot::build(self.shared.store().as_ref(), number)?;
+        if snapshot.root() != root {
+            warn!(
+                target: "sync",
+                "snapshot root of block {} is {:?}, chain spec expects {:?}",
+                number,
+                snapshot.root(),
+                root
+            );
+            return None;
+        }
+        info!(
+            target: "sync",
+            "built snapshot of block {} with {} chunks",
+            number,
+            snapshot.chunks_count()
+        );
+        Some(Arc::new(snapshot))
+    }
+
     pub fn is_initial_block_download(&self) -> bool {
         unix_time_as_millis()
             .saturating_sub(self.shared.chain_state().read().tip_header().timestamp())
@@ -345,6 +696,39 @@ impl<CI: ChainIndex> Synchronizer<CI> {
         None
     }
 
+    // Whether the block is the last checkpoint block or one of its ancestors. It is decided on
+    // the checkpoint validated headers, the checkpoint block itself is not stored yet in IBD.
+    pub fn is_checkpointed(&self, header: &Header) -> bool {
+        let checkpoint_hash = match self.consensus().last_checkpoint() {
+            Some((number, hash)) if header.number() <= number => hash.clone(),
+            _ => return false,
+        };
+        if self.checkpoint_chain.read().is_none() {
+            let mut hashes = Vec::new();
+            let mut next = Some(checkpoint_hash);
+            while let Some(hash) = next {
+                match self.get_header(&hash) {
+                    Some(header) => {
+                        next = if header.number() > 0 {
+                            Some(header.parent_hash().clone())
+                        } else {
+                            None
+                        };
+                        hashes.push(hash);
+                    }
+                    None => return false,
+                }
+            }
+            hashes.reverse();
+            *self.checkpoint_chain.write() = Some(hashes);
+        }
+        self.checkpoint_chain
+            .read()
+            .as_ref()
+            .and_then(|hashes| hashes.get(header.number() as usize))
+            .map_or(false, |hash| hash == &header.hash())
+    }
+
     pub fn get_locator_response(&self, block_number: BlockNumber, hash_stop: &H256) -> Vec<Header> {
         let tip_number = self.tip_header().number();
         let max_height = cmp::min(
@@ -412,28 +796,36 @@ impl<CI: ChainIndex> Synchronizer<CI> {
     }
 
     //TODO: process block which we don't request
+    // Returns the error of accepting the block itself, errors of the orphan descendants are
+    // not returned since they may come from other peers.
     #[allow(clippy::single_match)]
-    pub fn process_new_block(&self, peer: PeerIndex, block: Block) {
+    pub fn process_new_block(
+        &self,
+        peer: PeerIndex,
+        block: Block,
+    ) -> Result<(), ProcessBlockError> {
         match self.get_block_status(&block.header().hash()) {
-            BlockStatus::VALID_MASK => {
-                self.insert_new_block(peer, block);
-            }
+            BlockStatus::VALID_MASK => self.insert_new_block(peer, block),
             status => {
                 debug!(target: "sync", "[Synchronizer] process_new_block unexpect status {:?}", status);
+                Ok(())
             }
         }
     }
 
     fn accept_block(&self, peer: PeerIndex, block: &Arc<Block>) -> Result<(), ProcessBlockError> {
-        // TODO: some transactions' verification can be skiped.
-        self.chain.process_block(Arc::clone(&block))?;
+        if self.is_checkpointed(block.header()) {
+            self.chain.process_checkpointed_block(Arc::clone(&block))?;
+        } else {
+            self.chain.process_block(Arc::clone(&block))?;
+        }
         self.mark_block_stored(block.header().hash().clone());
         self.peers.set_last_common_header(peer, &block.header());
         Ok(())
     }
 
     //FIXME: guarantee concurrent block process
-    fn insert_new_block(&self, peer: PeerIndex, block: Block) {
+    fn insert_new_block(&self, peer: PeerIndex, block: Block) -> Result<(), ProcessBlockError> {
         let block = Arc::new(block);
         if self
             .shared
@@ -466,15 +858,16 @@ impl<CI: ChainIndex> Synchronizer<CI> {
                             block.header().number(),
                             block.header().hash()
                         );
-                        self.orphan_block_pool.insert(Block::clone(&block));
+                        self.orphan_block_pool.insert(peer, Block::clone(&block));
                     }
                 }
             } else {
                 debug!(
                     target: "sync", "[Synchronizer] accept_block {:?} error {:?}",
                     block,
-                    accept_ret.unwrap_err()
-                )
+                    accept_ret
+                );
+                return accept_ret;
             }
         } else {
             debug!(
@@ -482,10 +875,11 @@ impl<CI: ChainIndex> Synchronizer<CI> {
                 block.header().number(),
                 block.header().hash()
             );
-            self.orphan_block_pool.insert(Block::clone(&block));
+            self.orphan_block_pool.insert(peer, Block::clone(&block));
         }
 
         debug!(target: "sync", "[Synchronizer] insert_new_block finish");
+        Ok(())
     }
 
     pub fn get_blocks_to_fetch(&self, peer: PeerIndex) -> Option<Vec<H256>> {
@@ -599,6 +993,44 @@ impl<CI: ChainIndex> Synchronizer<CI> {
         }
     }
 
+    // Blocks held back by a stalling peer are given up so other peers can download them,
+    // peers which keep stalling are disconnected.
+    pub fn check_stalling_peers(&self, nc: &CKBProtocolContext) {
+        let now = unix_time_as_millis();
+        let stallers = {
+            let mut blocks_inflight = self.peers.blocks_inflight.write();
+            blocks_inflight
+                .iter_mut()
+                .filter_map(|(peer, inflight)| {
+                    if let Some(hash) = inflight.check_stalling(now) {
+                        debug!(
+                            target: "sync",
+                            "peer={} stalling the block download window at {:?}",
+                            peer,
+                            hash
+                        );
+                    }
+                    if inflight.is_persistent_staller() {
+                        Some(*peer)
+                    } else {
+                        None
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+        for peer in stallers {
+            warn!(target: "sync", "stalling eviction peer={}", peer);
+            nc.report_peer(peer, Severity::Timeout);
+        }
+    }
+
+    fn expire_orphan_blocks(&self) {
+        let expired = self.orphan_block_pool.expire(unix_time_as_millis());
+        if expired > 0 {
+            debug!(target: "sync", "{} orphan blocks expired", expired);
+        }
+    }
+
     fn start_sync_headers(&self, nc: &CKBProtocolContext) {
         let peers: Vec<PeerIndex> = self
             .peers
@@ -648,6 +1080,9 @@ impl<CI: ChainIndex> Synchronizer<CI> {
     }
 
     fn find_blocks_to_fetch(&self, nc: &CKBProtocolContext) {
+        if !self.fast_sync_done.load(Ordering::Acquire) {
+            return;
+        }
         let peers: Vec<PeerIndex> = self
             .peers
             .state
@@ -675,6 +1110,45 @@ impl<CI: ChainIndex> Synchronizer<CI> {
     }
 }
 
+impl<CI> Synchronizer<CI>
+where
+    CI: ChainIndex + 'static,
+{
+    // Building a snapshot replays the chain up to the checkpoint, it is done
+    // once per checkpoint in a background thread instead of when peers ask for it.
+    fn build_snapshots(&self) {
+        if self.shared.consensus().snapshot_roots.is_empty()
+            || self.building_snapshots.swap(true, Ordering::SeqCst)
+        {
+            return;
+        }
+        let synchronizer = self.clone();
+        let spawned = thread::Builder::new()
+            .name("snapshot".to_string())
+            .spawn(move || {
+                synchronizer.build_missing_snapshots();
+                synchronizer
+                    .building_snapshots
+                    .store(false, Ordering::SeqCst);
+            });
+        if let Err(err) = spawned {
+            error!(target: "sync", "failed to spawn snapshot builder: {:?}", err);
+            self.building_snapshots.store(false, Ordering::SeqCst);
+        }
+    }
+}
+
+// Name of the payload of a verified message
+fn sync_message_type(data: &[u8]) -> &'static str {
+    let payload_type = get_root::<SyncMessage>(data).payload_type();
+    // payload type sent by remote peer may be out of range
+    if payload_type as u8 > SyncPayload::SnapshotChunk as u8 {
+        UNKNOWN_MESSAGE_TYPE
+    } else {
+        enum_name_sync_payload(payload_type)
+    }
+}
+
 impl<CI> CKBProtocolHandler for Synchronizer<CI>
 where
     CI: ChainIndex + 'static,
@@ -687,12 +1161,31 @@ where
     }
 
     fn received(&self, nc: Box<CKBProtocolContext>, peer: PeerIndex, data: &[u8]) {
-        // TODO use flatbuffers verifier
+        if !verify_sync_message(data) {
+            debug!(target: "sync", "peer={} sends malformed message", peer);
+            nc.report(peer, Behaviour::MalformedMessage);
+            return;
+        }
         let msg = get_root::<SyncMessage>(&data);
         debug!(target: "sync", "msg {:?}", msg.payload_type());
+        let message_type = sync_message_type(data);
+        if !nc.is_whitelisted(peer) && !self.rate_limiter.check(peer, message_type) {
+            debug!(target: "sync", "peer={} exceeds rate limit of {}", peer, message_type);
+            nc.report(peer, Behaviour::TooManyMessages);
+            return;
+        }
         self.process(nc.as_ref(), peer, msg);
     }
 
+    // called by the network before `received`, so data isn't verified yet
+    fn message_type(&self, data: &[u8]) -> &'static str {
+        if verify_sync_message(data) {
+            sync_message_type(data)
+        } else {
+            UNKNOWN_MESSAGE_TYPE
+        }
+    }
+
     fn connected(&self, nc: Box<CKBProtocolContext>, peer: PeerIndex) {
         debug!(target: "sync", "init_getheaders peer={:?} connected", peer);
         self.on_connected(nc.as_ref(), peer);
@@ -701,6 +1194,8 @@ where
     fn disconnected(&self, _nc: Box<CKBProtocolContext>, peer: PeerIndex) {
         info!(target: "sync", "peer={} SyncProtocol.disconnected", peer);
         self.peers.disconnected(peer);
+        self.rate_limiter.disconnected(peer);
+        self.orphan_block_pool.disconnected(peer);
     }
 
     fn timer_triggered(&self, nc: Box<CKBProtocolContext>, token: TimerToken) {
@@ -710,10 +1205,15 @@ where
                     self.start_sync_headers(nc.as_ref());
                 }
                 BLOCK_FETCH_TOKEN => {
+                    self.start_fast_sync(nc.as_ref());
                     self.find_blocks_to_fetch(nc.as_ref());
                 }
                 TIMEOUT_EVICTION_TOKEN => {
                     self.eviction(nc.as_ref());
+                    self.check_stalling_peers(nc.as_ref());
+                    self.expire_orphan_blocks();
+                    self.request_snapshot_chunks(nc.as_ref());
+                    self.build_snapshots();
                 }
                 _ => unreachable!(),
             }
@@ -728,11 +1228,17 @@ mod tests {
     use self::block_process::BlockProcess;
     use self::headers_process::HeadersProcess;
     use super::*;
+    use crate::types::BlocksInflight;
+    use crate::{
+        BLOCKS_IN_TRANSIT_TARGET_TIME, BLOCK_DOWNLOAD_TIMEOUT, INITIAL_BLOCKS_IN_TRANSIT_PER_PEER,
+        MAX_BLOCK_STALLS,
+    };
     use ckb_chain::chain::ChainBuilder;
     use ckb_chain_spec::consensus::Consensus;
     use ckb_core::block::BlockBuilder;
+    use ckb_core::cell::{CellProvider, CellStatus};
     use ckb_core::header::{Header, HeaderBuilder};
-    use ckb_core::transaction::{CellInput, CellOutput, Transaction, TransactionBuilder};
+    use ckb_core::transaction::{CellInput, CellOutput, OutPoint, Transaction, TransactionBuilder};
     use ckb_db::memorydb::MemoryKeyValueDB;
     use ckb_network::{
         random_peer_id, CKBProtocolContext, Endpoint, Error as NetworkError, PeerIndex, PeerInfo,
@@ -749,6 +1255,7 @@ mod tests {
     use flatbuffers::FlatBufferBuilder;
     use fnv::{FnvHashMap, FnvHashSet};
     use numext_fixed_uint::U256;
+    use std::collections::BTreeMap;
     use std::ops::Deref;
     use std::time::Duration;
 
@@ -1006,7 +1513,9 @@ mod tests {
         let synchronizer = gen_synchronizer(chain_controller2.clone(), shared2.clone());
         let chain1_last_block = blocks.last().cloned().unwrap();
         blocks.into_iter().for_each(|block| {
-            synchronizer.insert_new_block(peer, block);
+            synchronizer
+                .insert_new_block(peer, block)
+                .expect("insert valid block");
         });
         assert_eq!(
             chain1_last_block.header(),
@@ -1047,6 +1556,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_malformed_message() {
+        let (chain_controller, shared, _notify) = start_chain(None, None);
+        let synchronizer = gen_synchronizer(chain_controller, shared);
+
+        let fbb = &mut FlatBufferBuilder::new();
+        let message = SyncMessage::build_get_headers(fbb, &[H256::zero()]);
+        fbb.finish(message, None);
+        let data = fbb.finished_data().to_vec();
+        assert_eq!(synchronizer.message_type(&data), "GetHeaders");
+
+        // the network reads the message type before the message is verified
+        for malformed in &[&[0xff; 3][..], &data[..data.len() / 2]] {
+            assert_eq!(synchronizer.message_type(malformed), UNKNOWN_MESSAGE_TYPE);
+            synchronizer.received(Box::new(mock_network_context(1)), 0, malformed);
+        }
+    }
+
     #[derive(Clone)]
     struct DummyNetworkContext {
         pub sessions: FnvHashMap<PeerIndex, SessionInfo>,
@@ -1059,7 +1586,9 @@ mod tests {
                 peer_id: random_peer_id().unwrap(),
                 endpoint_role: Endpoint::Dialer,
                 last_ping_time: None,
+                ping: None,
                 connected_addr: "/ip4/127.0.0.1".to_multiaddr().expect("parse multiaddr"),
+                connected_time: None,
                 identify_info: None,
             },
             protocol_version: None,
@@ -1094,7 +1623,11 @@ mod tests {
             self.disconnected.lock().insert(peer);
         }
 
-        fn ban_peer(&self, _peer: PeerIndex, _duration: Duration) {}
+        fn report(&self, _peer: PeerIndex, _behaviour: Behaviour) {}
+
+        fn ban_peer(&self, peer: PeerIndex, _duration: Duration) {
+            self.disconnected.lock().insert(peer);
+        }
 
         /// Register a new IO timer. 'IoHandler::timeout' will be called with the token.
         fn register_timer(&self, _token: TimerToken, _delay: Duration) -> Result<(), NetworkError> {
@@ -1118,6 +1651,10 @@ mod tests {
         fn connected_peers(&self) -> Vec<PeerIndex> {
             unimplemented!();
         }
+
+        fn is_whitelisted(&self, _peer_index: PeerIndex) -> bool {
+            false
+        }
     }
 
     fn mock_network_context(peer_num: usize) -> DummyNetworkContext {
@@ -1237,6 +1774,396 @@ mod tests {
         assert!(fork_receiver.recv().is_ok());
     }
 
+    #[test]
+    fn test_reject_headers_conflicting_with_checkpoints() {
+        let build_chain = |nonce_offset: u64| {
+            let (chain_controller, shared, _) = start_chain(None, None);
+            for i in 1..=5 {
+                insert_block(&chain_controller, &shared, i + nonce_offset, i);
+            }
+            (1..=5)
+                .map(|i| shared.block_header(&shared.block_hash(i).unwrap()).unwrap())
+                .collect::<Vec<_>>()
+        };
+        let main_chain = build_chain(0);
+        let fork1 = build_chain(100);
+        let fork2 = build_chain(200);
+
+        let mut checkpoints = BTreeMap::new();
+        checkpoints.insert(3, main_chain[2].hash());
+        let consensus = Consensus::default().set_checkpoints(checkpoints);
+        let (chain_controller, shared, _) = start_chain(Some(consensus), None);
+        let synchronizer = gen_synchronizer(chain_controller, shared);
+
+        let send_headers = |peer: PeerIndex, headers: &[Header]| {
+            let fbb = &mut FlatBufferBuilder::new();
+            let fbs_headers = FbsHeaders::build(fbb, headers);
+            fbb.finish(fbs_headers, None);
+            let fbs_headers = get_root::<FbsHeaders>(fbb.finished_data());
+            HeadersProcess::new(&fbs_headers, &synchronizer, peer, &mock_network_context(0))
+                .execute();
+        };
+        let best_known_number = |peer: PeerIndex| {
+            synchronizer
+                .peers
+                .best_known_header(peer)
+                .map(|h| h.number())
+        };
+
+        // the fork is accepted up to the conflicting checkpoint
+        send_headers(1, &fork1);
+        assert_eq!(best_known_number(1), Some(2));
+        assert_eq!(
+            synchronizer.get_block_status(&fork1[2].hash()),
+            BlockStatus::FAILED_MASK
+        );
+
+        send_headers(2, &main_chain);
+        assert_eq!(best_known_number(2), Some(5));
+
+        // once the checkpoint is known, forks below it are rejected right away
+        send_headers(3, &fork2);
+        assert_eq!(best_known_number(3), None);
+        assert_eq!(
+            synchronizer.get_block_status(&fork2[0].hash()),
+            BlockStatus::FAILED_MASK
+        );
+    }
+
+    #[test]
+    fn test_checkpointed_blocks_in_ibd_order() {
+        let build_chain = |nonce_offset: u64| {
+            let (chain_controller, shared, _) = start_chain(None, None);
+            for i in 1..=5 {
+                insert_block(&chain_controller, &shared, i + nonce_offset, i);
+            }
+            (1..=5)
+                .map(|i| shared.block(&shared.block_hash(i).unwrap()).unwrap())
+                .collect::<Vec<_>>()
+        };
+        let main_chain = build_chain(0);
+        let fork = build_chain(100);
+        let headers = |blocks: &[Block]| {
+            blocks
+                .iter()
+                .map(|block| block.header().clone())
+                .collect::<Vec<_>>()
+        };
+
+        let mut checkpoints = BTreeMap::new();
+        checkpoints.insert(3, main_chain[2].header().hash());
+        let consensus = Consensus::default().set_checkpoints(checkpoints);
+        let (chain_controller, shared, _) = start_chain(Some(consensus), None);
+        let synchronizer = gen_synchronizer(chain_controller, shared);
+
+        let send_headers = |peer: PeerIndex, headers: &[Header]| {
+            let fbb = &mut FlatBufferBuilder::new();
+            let fbs_headers = FbsHeaders::build(fbb, headers);
+            fbb.finish(fbs_headers, None);
+            let fbs_headers = get_root::<FbsHeaders>(fbb.finished_data());
+            HeadersProcess::new(&fbs_headers, &synchronizer, peer, &mock_network_context(0))
+                .execute();
+        };
+        send_headers(1, &headers(&fork[..2]));
+        send_headers(2, &headers(&main_chain));
+
+        // the checkpoint block is not stored when its ancestors arrive
+        for block in &main_chain {
+            let number = block.header().number();
+            assert_eq!(synchronizer.is_checkpointed(block.header()), number <= 3);
+            assert!(synchronizer.process_new_block(2, block.clone()).is_ok());
+            assert_eq!(synchronizer.tip_header().number(), number);
+        }
+        assert!(!synchronizer.is_checkpointed(fork[0].header()));
+        assert!(!synchronizer.is_checkpointed(fork[1].header()));
+    }
+
+    #[test]
+    fn test_parallel_block_download() {
+        let (chain_controller1, shared1, _) = start_chain(None, None);
+        let (chain_controller2, shared2, _) = start_chain(None, None);
+        let num = 40;
+        for i in 1..=num {
+            insert_block(&chain_controller1, &shared1, i, i);
+        }
+        let synchronizer = gen_synchronizer(chain_controller2.clone(), shared2.clone());
+
+        let headers = (1..=num)
+            .map(|i| {
+                shared1
+                    .block_header(&shared1.block_hash(i).unwrap())
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        let fbb = &mut FlatBufferBuilder::new();
+        let fbs_headers = FbsHeaders::build(fbb, &headers);
+        fbb.finish(fbs_headers, None);
+        let fbs_headers = get_root::<FbsHeaders>(fbb.finished_data());
+        let (peer1, peer2, peer3) = (1usize, 2usize, 3usize);
+        for peer in &[peer1, peer2, peer3] {
+            HeadersProcess::new(&fbs_headers, &synchronizer, *peer, &mock_network_context(0))
+                .execute();
+        }
+
+        // blocks are spread over the peers instead of being requested from each of them
+        let blocks1 = synchronizer.get_blocks_to_fetch(peer1).unwrap();
+        let blocks2 = synchronizer.get_blocks_to_fetch(peer2).unwrap();
+        assert_eq!(blocks1.len(), INITIAL_BLOCKS_IN_TRANSIT_PER_PEER);
+        assert_eq!(blocks1[0], shared1.block_hash(1).unwrap());
+        assert_eq!(blocks2[0], shared1.block_hash(17).unwrap());
+        assert!(synchronizer.get_blocks_to_fetch(peer1).is_none());
+
+        // peer1 stalls on block 1, which is then requested from peer3
+        let network_context = mock_network_context(0);
+        let stalling_block = shared1.block_hash(1).unwrap();
+        synchronizer
+            .peers
+            .blocks_inflight
+            .write()
+            .get_mut(&peer1)
+            .unwrap()
+            .mark_stalling(stalling_block.clone(), 0);
+        synchronizer.check_stalling_peers(&network_context);
+        let blocks3 = synchronizer.get_blocks_to_fetch(peer3).unwrap();
+        assert_eq!(blocks3[0], stalling_block);
+        assert_eq!(blocks3[1], shared1.block_hash(33).unwrap());
+        // peer1 does not get the block back
+        let blocks1 = synchronizer.get_blocks_to_fetch(peer1).unwrap_or_default();
+        assert!(!blocks1.contains(&stalling_block));
+        assert!(network_context.disconnected.lock().is_empty());
+
+        // peers stalling again and again are disconnected
+        for i in 2..=MAX_BLOCK_STALLS as u64 {
+            synchronizer
+                .peers
+                .blocks_inflight
+                .write()
+                .get_mut(&peer1)
+                .unwrap()
+                .mark_stalling(shared1.block_hash(i).unwrap(), 0);
+            synchronizer.check_stalling_peers(&network_context);
+        }
+        assert!(network_context.disconnected.lock().contains(&peer1));
+    }
+
+    #[test]
+    fn test_sync_state() {
+        let (chain_controller1, shared1, _) = start_chain(None, None);
+        let (chain_controller2, shared2, _) = start_chain(None, None);
+        for i in 1..=20 {
+            insert_block(&chain_controller1, &shared1, i, i);
+        }
+        for i in 1..=5 {
+            insert_block(&chain_controller2, &shared2, i, i);
+        }
+        let synchronizer = gen_synchronizer(chain_controller2.clone(), shared2.clone());
+
+        let headers = (6..=20)
+            .map(|i| {
+                shared1
+                    .block_header(&shared1.block_hash(i).unwrap())
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        let fbb = &mut FlatBufferBuilder::new();
+        let fbs_headers = FbsHeaders::build(fbb, &headers);
+        fbb.finish(fbs_headers, None);
+        let fbs_headers = get_root::<FbsHeaders>(fbb.finished_data());
+        let peer = 1usize;
+        HeadersProcess::new(&fbs_headers, &synchronizer, peer, &mock_network_context(0)).execute();
+        let blocks_to_fetch = synchronizer.get_blocks_to_fetch(peer).unwrap();
+        let block = shared1.block(&blocks_to_fetch[0]).unwrap();
+        synchronizer.peers.block_received(peer, &block);
+
+        let sync_state = synchronizer.sync_state();
+        assert_eq!(sync_state.best_known_header.number(), 20);
+        assert_eq!(sync_state.tip_number, 5);
+        assert_eq!(sync_state.remaining_blocks(), 15);
+        assert_eq!(sync_state.download_rate, 1);
+        assert_eq!(
+            sync_state.blocks_inflight,
+            vec![(peer, blocks_to_fetch.len() - 1)]
+        );
+        assert_eq!(sync_state.orphan_blocks, 0);
+    }
+
+    #[test]
+    fn test_adaptive_blocks_in_transit_limit() {
+        let mut inflight = BlocksInflight::default();
+        let (hash1, hash2) = (H256::from_slice(&[1; 32]).unwrap(), H256::zero());
+        inflight.insert(hash1.clone());
+        inflight.insert(hash2.clone());
+        assert_eq!(inflight.limit(), INITIAL_BLOCKS_IN_TRANSIT_PER_PEER);
+
+        // fast peers may have more blocks in transit
+        let now = inflight.timestamp + 100;
+        assert!(inflight.block_delivered(&hash1, now));
+        assert_eq!(
+            inflight.limit(),
+            (BLOCKS_IN_TRANSIT_TARGET_TIME / 100) as usize
+        );
+        assert!(!inflight.block_delivered(&hash1, now));
+
+        // slow ones less
+        assert!(inflight.block_delivered(&hash2, now + 2_100));
+        assert_eq!(
+            inflight.limit(),
+            (BLOCKS_IN_TRANSIT_TARGET_TIME / 600) as usize
+        );
+
+        inflight.insert(hash1.clone());
+        assert!(inflight.is_timeout(inflight.timestamp + BLOCK_DOWNLOAD_TIMEOUT + 1));
+        inflight.on_timeout();
+        assert!(inflight.is_empty());
+        assert_eq!(
+            inflight.limit(),
+            (BLOCKS_IN_TRANSIT_TARGET_TIME / 600) as usize / 2
+        );
+    }
+
+    #[test]
+    fn test_fast_sync() {
+        let (chain_controller, shared, _) = start_chain(None, None);
+        for i in 1..=20 {
+            insert_block(&chain_controller, &shared, i, i);
+        }
+        let block_hash = shared.block_hash(15).unwrap();
+        let root = Snapshot::build(shared.store().as_ref(), 15)
+            .unwrap()
+            .root()
+            .clone();
+        let blocks = (1..=20)
+            .map(|i| shared.block(&shared.block_hash(i).unwrap()).unwrap())
+            .collect::<Vec<_>>();
+        let headers = blocks
+            .iter()
+            .map(|block| block.header().clone())
+            .collect::<Vec<_>>();
+
+        let mut snapshot_roots = HashMap::new();
+        snapshot_roots.insert(block_hash.clone(), root);
+        let consensus = Consensus::default().set_snapshot_roots(snapshot_roots);
+        let (chain_controller1, shared1, _) = start_chain(Some(consensus.clone()), None);
+        for i in 1..=20 {
+            insert_block(&chain_controller1, &shared1, i, i);
+        }
+        assert_eq!(shared1.block_hash(15), Some(block_hash.clone()));
+        let server = gen_synchronizer(chain_controller1, shared1);
+        let (chain_controller2, shared2, _) = start_chain(Some(consensus), None);
+        let client = gen_synchronizer(chain_controller2.clone(), shared2.clone());
+        let nc = mock_network_context(0);
+        assert!(!client.fast_sync_done.load(Ordering::Acquire));
+
+        // the server builds its snapshots in the background, not on request
+        server.build_missing_snapshots();
+        let snapshot = server.snapshot(&block_hash).expect("snapshot built");
+
+        let send_chunk = |peer: PeerIndex, index: usize, tamper: bool| {
+            let mut data = snapshot.chunk(index).unwrap().to_vec();
+            if tamper {
+                data[0] ^= 1;
+            }
+            let chunk_hashes = if index == 0 {
+                snapshot.chunk_hashes()
+            } else {
+                &[]
+            };
+            let fbb = &mut FlatBufferBuilder::new();
+            let message = SyncMessage::build_snapshot_chunk(
+                fbb,
+                &block_hash,
+                index as u32,
+                chunk_hashes,
+                &data,
+            );
+            fbb.finish(message, None);
+            let message = get_root::<SyncMessage>(fbb.finished_data());
+            client.process(&nc, peer, message);
+        };
+        let send_block = |peer: PeerIndex, block: &Block| {
+            let fbb = &mut FlatBufferBuilder::new();
+            let fbs_block = FbsBlock::build(fbb, block);
+            fbb.finish(fbs_block, None);
+            let fbs_block = get_root::<FbsBlock>(fbb.finished_data());
+            BlockProcess::new(&fbs_block, &client, peer, &nc).execute();
+        };
+
+        client.peers.on_connected(1, 0, false);
+        // nothing to fast sync from before the snapshot header is known
+        client.start_fast_sync(&nc);
+        assert!(client.snapshot_download.lock().is_none());
+
+        let fbb = &mut FlatBufferBuilder::new();
+        let fbs_headers = FbsHeaders::build(fbb, &headers);
+        fbb.finish(fbs_headers, None);
+        let fbs_headers = get_root::<FbsHeaders>(fbb.finished_data());
+        HeadersProcess::new(&fbs_headers, &client, 1, &nc).execute();
+
+        // blocks are not downloaded during fast sync
+        client.start_fast_sync(&nc);
+        client.find_blocks_to_fetch(&nc);
+        assert!(client
+            .peers
+            .blocks_inflight
+            .read()
+            .get(&1)
+            .map_or(true, |inflight| inflight.is_empty()));
+        assert_eq!(
+            client
+                .snapshot_download
+                .lock()
+                .as_ref()
+                .map(|download| download.block_hash.clone()),
+            Some(block_hash.clone())
+        );
+
+        // chunks not requested from the peer are ignored
+        send_chunk(2, 0, false);
+        assert!(!nc.disconnected.lock().contains(&2));
+        // a bad chunk is rejected on arrival and its peer banned
+        send_chunk(1, 0, true);
+        assert!(nc.disconnected.lock().contains(&1));
+
+        // the chunk is requested again
+        for index in 0..snapshot.chunks_count() {
+            send_chunk(1, index, false);
+        }
+        // the snapshot is imported with the blocks before it, a body not matching its
+        // header is rejected
+        let tampered = BlockBuilder::default()
+            .header(headers[9].clone())
+            .commit_transaction(create_cellbase(100))
+            .build();
+        send_block(1, &tampered);
+        for block in &blocks[5..15] {
+            assert_eq!(client.tip_header().number(), 0);
+            send_block(1, block);
+        }
+        assert!(client.fast_sync_done.load(Ordering::Acquire));
+        assert!(client.snapshot_download.lock().is_none());
+        assert_eq!(client.tip_header().hash(), block_hash);
+        assert_eq!(
+            client.get_block_status(&headers[4].hash()),
+            BlockStatus::BLOCK_HAVE_MASK
+        );
+        assert!(shared2.block(&headers[4].hash()).is_none());
+        assert_eq!(
+            chain_controller2.process_block(Arc::new(blocks[4].clone())),
+            Err(ProcessBlockError::Shared(SharedError::BelowSnapshot))
+        );
+
+        // the chain continues from the snapshot block
+        for block in &blocks[15..] {
+            assert!(client.process_new_block(1, block.clone()).is_ok());
+        }
+        assert_eq!(client.tip_header().number(), 20);
+        let cellbase = &blocks[2].commit_transactions()[0];
+        assert_eq!(
+            shared2.cell(&OutPoint::new(cellbase.hash(), 0)),
+            CellStatus::Live(cellbase.outputs()[0].clone())
+        );
+    }
+
     #[cfg(not(disable_faketime))]
     #[test]
     fn test_header_sync_timeout() {
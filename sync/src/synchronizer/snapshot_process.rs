@@ -0,0 +1,169 @@
+use crate::synchronizer::Synchronizer;
+use crate::BAD_SNAPSHOT_CHUNK_BAN_TIME;
+use ckb_network::{peer_store::Behaviour, CKBProtocolContext, PeerIndex};
+use ckb_protocol::{GetSnapshotChunk, SnapshotChunk, SyncMessage};
+use ckb_shared::index::ChainIndex;
+use faketime::unix_time_as_millis;
+use flatbuffers::FlatBufferBuilder;
+use log::{debug, warn};
+use numext_fixed_hash::H256;
+use std::time::Duration;
+
+pub struct GetSnapshotChunkProcess<'a, CI: ChainIndex + 'a> {
+    message: &'a GetSnapshotChunk<'a>,
+    synchronizer: &'a Synchronizer<CI>,
+    nc: &'a CKBProtocolContext,
+    peer: PeerIndex,
+}
+
+impl<'a, CI> GetSnapshotChunkProcess<'a, CI>
+where
+    CI: ChainIndex + 'a,
+{
+    pub fn new(
+        message: &'a GetSnapshotChunk,
+        synchronizer: &'a Synchronizer<CI>,
+        peer: PeerIndex,
+        nc: &'a CKBProtocolContext,
+    ) -> Self {
+        GetSnapshotChunkProcess {
+            peer,
+            message,
+            nc,
+            synchronizer,
+        }
+    }
+
+    pub fn execute(self) {
+        let block_hash: H256 = match self.message.block_hash() {
+            Some(block_hash) => block_hash.into(),
+            None => {
+                self.nc.report(self.peer, Behaviour::MalformedMessage);
+                return;
+            }
+        };
+        let index = self.message.index();
+        debug!(
+            target: "sync",
+            "peer={} get_snapshot_chunk {:?} {}", self.peer, block_hash, index
+        );
+
+        // silently ignored if we don't serve the snapshot, like unknown blocks,
+        // snapshots are only served once built in the background
+        if let Some(snapshot) = self.synchronizer.snapshot(&block_hash) {
+            match snapshot.chunk(index as usize) {
+                Some(data) => {
+                    // the chunk hashes let the peer verify every chunk on arrival
+                    let chunk_hashes = if index == 0 {
+                        snapshot.chunk_hashes()
+                    } else {
+                        &[]
+                    };
+                    let fbb = &mut FlatBufferBuilder::new();
+                    let message = SyncMessage::build_snapshot_chunk(
+                        fbb,
+                        &block_hash,
+                        index,
+                        chunk_hashes,
+                        data,
+                    );
+                    fbb.finish(message, None);
+                    let _ = self.nc.send(self.peer, fbb.finished_data().to_vec());
+                }
+                None => self.nc.report(self.peer, Behaviour::MalformedMessage),
+            }
+        }
+    }
+}
+
+pub struct SnapshotChunkProcess<'a, CI: ChainIndex + 'a> {
+    message: &'a SnapshotChunk<'a>,
+    synchronizer: &'a Synchronizer<CI>,
+    nc: &'a CKBProtocolContext,
+    peer: PeerIndex,
+}
+
+impl<'a, CI> SnapshotChunkProcess<'a, CI>
+where
+    CI: ChainIndex + 'a,
+{
+    pub fn new(
+        message: &'a SnapshotChunk,
+        synchronizer: &'a Synchronizer<CI>,
+        peer: PeerIndex,
+        nc: &'a CKBProtocolContext,
+    ) -> Self {
+        SnapshotChunkProcess {
+            peer,
+            message,
+            nc,
+            synchronizer,
+        }
+    }
+
+    pub fn execute(self) {
+        let (block_hash, data): (H256, _) = match (self.message.block_hash(), self.message.data()) {
+            (Some(block_hash), Some(data)) => (block_hash.into(), data),
+            _ => {
+                self.nc.report(self.peer, Behaviour::MalformedMessage);
+                return;
+            }
+        };
+        let index = self.message.index() as usize;
+        let chunk_hashes = self
+            .message
+            .chunk_hashes()
+            .filter(|hashes| !hashes.is_empty())
+            .map(|hashes| hashes.iter().map(Into::into).collect::<Vec<H256>>());
+        debug!(
+            target: "sync",
+            "peer={} snapshot_chunk {:?} {}", self.peer, block_hash, index
+        );
+
+        let mut download = self.synchronizer.snapshot_download.lock();
+        let inserted = match *download {
+            Some(ref mut download) if download.block_hash == block_hash => {
+                download.insert(self.peer, index, chunk_hashes, data, unix_time_as_millis())
+            }
+            // unsolicited chunk
+            _ => return,
+        };
+
+        match inserted {
+            Ok(true) => {}
+            Ok(false) => {
+                debug!(
+                    target: "sync",
+                    "peer={} sends snapshot chunk {} not requested from it", self.peer, index
+                );
+                return;
+            }
+            Err(err) => {
+                warn!(
+                    target: "sync",
+                    "peer={} sends bad snapshot chunk {} of block {:?}: {:?}",
+                    self.peer,
+                    index,
+                    block_hash,
+                    err
+                );
+                self.nc.ban_peer(
+                    self.peer,
+                    Duration::from_millis(BAD_SNAPSHOT_CHUNK_BAN_TIME),
+                );
+            }
+        }
+
+        if download
+            .as_ref()
+            .map_or(false, |download| download.is_complete())
+        {
+            let complete = download.take().expect("download checked");
+            drop(download);
+            self.synchronizer.finish_fast_sync(complete);
+            return;
+        }
+        drop(download);
+        self.synchronizer.request_snapshot_chunks(self.nc);
+    }
+}
@@ -1,6 +1,6 @@
 use ckb_network::{
-    CKBProtocolContext, CKBProtocolHandler, Error as NetworkError, PeerIndex, ProtocolId,
-    SessionInfo, Severity, TimerToken,
+    peer_store::Behaviour, CKBProtocolContext, CKBProtocolHandler, Error as NetworkError,
+    PeerIndex, ProtocolId, SessionInfo, Severity, TimerToken,
 };
 use std::collections::HashMap;
 use std::sync::mpsc::{channel, Receiver, Sender};
@@ -60,11 +60,12 @@ impl TestNode {
             .msg_senders
             .insert((protocol, remote_index), remote_sender);
 
+        // each side identifies the other by its own peer index
         self.msg_receivers
-            .insert((protocol, remote_index), remote_receiver);
+            .insert((protocol, local_index), remote_receiver);
         remote
             .msg_receivers
-            .insert((protocol, local_index), local_receiver);
+            .insert((protocol, remote_index), local_receiver);
 
         if let Some(handler) = self.protocols.get(&protocol) {
             handler.connected(
@@ -84,7 +85,7 @@ impl TestNode {
                     msg_senders: remote.msg_senders.clone(),
                     timer_senders: remote.timer_senders.clone(),
                 }),
-                local_index,
+                remote_index,
             )
         }
     }
@@ -94,6 +95,8 @@ impl TestNode {
             for ((protocol, peer), receiver) in &self.msg_receivers {
                 let _ = receiver.try_recv().map(|payload| {
                     if let Some(handler) = self.protocols.get(protocol) {
+                        // the network reads the message type for traffic stats first
+                        handler.message_type(&payload);
                         handler.received(
                             Box::new(TestNetworkContext {
                                 protocol: *protocol,
@@ -164,6 +167,8 @@ impl CKBProtocolContext for TestNetworkContext {
 
     fn report_peer(&self, _peer: PeerIndex, _reason: Severity) {}
 
+    fn report(&self, _peer: PeerIndex, _behaviour: Behaviour) {}
+
     fn register_timer(&self, token: TimerToken, delay: Duration) -> Result<(), NetworkError> {
         if let Some(sender) = self.timer_senders.get(&(self.protocol, token)) {
             let sender = sender.clone();
@@ -194,4 +199,8 @@ impl CKBProtocolContext for TestNetworkContext {
     fn connected_peers(&self) -> Vec<PeerIndex> {
         self.msg_senders.keys().map(|k| k.1).collect::<Vec<_>>()
     }
+
+    fn is_whitelisted(&self, _peer_index: PeerIndex) -> bool {
+        false
+    }
 }
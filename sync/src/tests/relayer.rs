@@ -1,6 +1,6 @@
-use crate::relayer::TX_PROPOSAL_TOKEN;
+use crate::relayer::{TX_ANNOUNCE_TOKEN, TX_PROPOSAL_TOKEN};
 use crate::tests::TestNode;
-use crate::{Relayer, RELAY_PROTOCOL_ID};
+use crate::{Config, Relayer, RELAY_PROTOCOL_ID};
 use ckb_chain::chain::{ChainBuilder, ChainController};
 use ckb_chain_spec::consensus::Consensus;
 use ckb_core::block::BlockBuilder;
@@ -10,9 +10,9 @@ use ckb_core::transaction::{CellInput, CellOutput, OutPoint, TransactionBuilder}
 use ckb_db::memorydb::MemoryKeyValueDB;
 use ckb_notify::NotifyService;
 use ckb_pool::txs_pool::{PoolConfig, TransactionPoolService};
-use ckb_protocol::RelayMessage;
+use ckb_protocol::{verify_relay_message, RelayMessage};
 use ckb_shared::shared::{ChainProvider, Shared, SharedBuilder};
-use ckb_shared::store::ChainKVStore;
+use ckb_shared::store::{ChainKVStore, ChainStore};
 use faketime::{self, unix_time_as_millis};
 use flatbuffers::get_root;
 use flatbuffers::FlatBufferBuilder;
@@ -299,6 +299,356 @@ fn relay_compact_block_with_missing_indexs() {
     assert_eq!(shared2.chain_state().read().tip_number(), 5);
 }
 
+#[test]
+fn relay_transaction_by_hashes() {
+    let faketime_file = faketime::millis_tempfile(0).expect("create faketime file");
+    faketime::enable(&faketime_file);
+    let thread_name = format!("FAKETIME={}", faketime_file.display());
+
+    let (mut node1, shared1, _chain_controller1) = setup_node(&thread_name, 3);
+    let (mut node2, _shared2, _chain_controller2) = setup_node(&thread_name, 3);
+    let (mut node3, _shared3, _chain_controller3) = setup_node(&thread_name, 3);
+
+    node1.connect(&mut node2, RELAY_PROTOCOL_ID);
+    node2.connect(&mut node3, RELAY_PROTOCOL_ID);
+
+    let (signal_tx1, _) = channel();
+    thread::Builder::new()
+        .name(thread_name)
+        .spawn(move || {
+            let last_block = shared1
+                .block(&shared1.chain_state().read().tip_hash())
+                .unwrap();
+            let last_cellbase = last_block.commit_transactions().first().unwrap();
+
+            let tx = TransactionBuilder::default()
+                .input(CellInput::new(
+                    OutPoint::new(last_cellbase.hash().clone(), 0),
+                    create_valid_script(),
+                ))
+                .output(CellOutput::new(50, Vec::new(), H256::zero(), None))
+                .build();
+
+            let fbb = &mut FlatBufferBuilder::new();
+            let message = RelayMessage::build_transaction(fbb, &tx);
+            fbb.finish(message, None);
+            node1.broadcast(RELAY_PROTOCOL_ID, &fbb.finished_data().to_vec());
+
+            node1.start(&signal_tx1, |_| false);
+        })
+        .expect("thread spawn");
+
+    let (signal_tx2, signal_rx2) = channel();
+    thread::spawn(move || {
+        node2.start(&signal_tx2, |data| {
+            let msg = get_root::<RelayMessage>(data);
+            // node3 requests the announced transaction
+            msg.payload_as_get_relay_transactions().is_some()
+        });
+    });
+
+    let (signal_tx3, signal_rx3) = channel();
+    thread::spawn(move || {
+        node3.start(&signal_tx3, |data| {
+            let msg = get_root::<RelayMessage>(data);
+            msg.payload_as_transaction().is_some()
+        });
+    });
+
+    // node2 only announces the transaction hash, node3 gets the transaction by requesting it
+    let timeout = time::Duration::from_secs(10);
+    assert!(signal_rx2.recv_timeout(timeout).is_ok());
+    assert!(signal_rx3.recv_timeout(timeout).is_ok());
+}
+
+#[test]
+fn ignore_malformed_messages() {
+    let faketime_file = faketime::millis_tempfile(0).expect("create faketime file");
+    faketime::enable(&faketime_file);
+    let thread_name = format!("FAKETIME={}", faketime_file.display());
+
+    let (mut node1, shared1, _chain_controller1) = setup_node(&thread_name, 3);
+    let (mut node2, _shared2, _chain_controller2) = setup_node(&thread_name, 3);
+
+    node1.connect(&mut node2, RELAY_PROTOCOL_ID);
+
+    let (signal_tx1, _) = channel();
+    thread::Builder::new()
+        .name(thread_name)
+        .spawn(move || {
+            let last_block = shared1
+                .block(&shared1.chain_state().read().tip_hash())
+                .unwrap();
+            let last_cellbase = last_block.commit_transactions().first().unwrap();
+
+            let tx = TransactionBuilder::default()
+                .input(CellInput::new(
+                    OutPoint::new(last_cellbase.hash().clone(), 0),
+                    create_valid_script(),
+                ))
+                .output(CellOutput::new(50, Vec::new(), H256::zero(), None))
+                .build();
+
+            let fbb = &mut FlatBufferBuilder::new();
+            let message = RelayMessage::build_transaction(fbb, &tx);
+            fbb.finish(message, None);
+            let data = fbb.finished_data().to_vec();
+
+            // garbage and a truncated message before a valid one
+            node1.broadcast(RELAY_PROTOCOL_ID, &[0xff; 3]);
+            node1.broadcast(RELAY_PROTOCOL_ID, &data[..data.len() / 2]);
+            node1.broadcast(RELAY_PROTOCOL_ID, &data);
+
+            node1.start(&signal_tx1, |_| false);
+        })
+        .expect("thread spawn");
+
+    let (signal_tx2, signal_rx2) = channel();
+    thread::spawn(move || {
+        node2.start(&signal_tx2, |data| {
+            verify_relay_message(data)
+                && get_root::<RelayMessage>(data)
+                    .payload_as_transaction()
+                    .is_some()
+        });
+    });
+
+    // node2 still handles the valid message after the malformed ones
+    let timeout = time::Duration::from_secs(10);
+    assert!(signal_rx2.recv_timeout(timeout).is_ok());
+}
+
+#[test]
+fn relay_compact_block_before_validation_in_high_bandwidth_mode() {
+    let faketime_file = faketime::millis_tempfile(0).expect("create faketime file");
+    faketime::enable(&faketime_file);
+    let thread_name = format!("FAKETIME={}", faketime_file.display());
+
+    let (mut node1, shared1, _chain_controller1) = setup_node(&thread_name, 3);
+    let (mut node2, _shared2, _chain_controller2) = setup_node(&thread_name, 3);
+    let (mut node3, _shared3, _chain_controller3) = setup_node(&thread_name, 3);
+
+    node1.connect(&mut node2, RELAY_PROTOCOL_ID);
+    node2.connect(&mut node3, RELAY_PROTOCOL_ID);
+
+    // node3 asks node2 to forward compact blocks in high-bandwidth mode
+    {
+        let fbb = &mut FlatBufferBuilder::new();
+        let message = RelayMessage::build_set_compact_block_mode(fbb, true);
+        fbb.finish(message, None);
+        node3.broadcast(RELAY_PROTOCOL_ID, &fbb.finished_data().to_vec());
+    }
+
+    let (signal_tx2, signal_rx2) = channel();
+    thread::spawn(move || {
+        node2.start(&signal_tx2, |data| {
+            let msg = get_root::<RelayMessage>(data);
+            msg.payload_as_set_compact_block_mode().is_some()
+        });
+    });
+
+    let (signal_tx3, signal_rx3) = channel();
+    thread::spawn(move || {
+        node3.start(&signal_tx3, |data| {
+            let msg = get_root::<RelayMessage>(data);
+            msg.payload_as_compact_block().is_some()
+        });
+    });
+
+    let timeout = time::Duration::from_secs(10);
+    assert!(signal_rx2.recv_timeout(timeout).is_ok());
+
+    // node2 doesn't have the transaction of the block, and node1 never answers
+    // the missing transactions request, so node2 can't validate the block
+    let last_block = shared1
+        .block(&shared1.chain_state().read().tip_hash())
+        .unwrap();
+    let last_cellbase = last_block.commit_transactions().first().unwrap();
+    let tx = TransactionBuilder::default()
+        .input(CellInput::new(
+            OutPoint::new(last_cellbase.hash().clone(), 0),
+            create_valid_script(),
+        ))
+        .output(CellOutput::new(50, Vec::new(), H256::zero(), None))
+        .build();
+    let block = {
+        let number = last_block.header().number() + 1;
+        let timestamp = last_block.header().timestamp() + 1;
+        let difficulty = shared1.calculate_difficulty(&last_block.header()).unwrap();
+        let cellbase = TransactionBuilder::default()
+            .input(CellInput::new_cellbase_input(number))
+            .output(CellOutput::default())
+            .build();
+
+        let header_builder = HeaderBuilder::default()
+            .parent_hash(last_block.header().hash().clone())
+            .number(number)
+            .timestamp(timestamp)
+            .difficulty(difficulty)
+            .cellbase_id(cellbase.hash().clone());
+
+        BlockBuilder::default()
+            .commit_transaction(cellbase)
+            .commit_transaction(tx)
+            .with_header_builder(header_builder)
+    };
+    let fbb = &mut FlatBufferBuilder::new();
+    let message = RelayMessage::build_compact_block(fbb, &block, &HashSet::new());
+    fbb.finish(message, None);
+    node1.broadcast(RELAY_PROTOCOL_ID, &fbb.finished_data().to_vec());
+
+    assert!(signal_rx3.recv_timeout(timeout).is_ok());
+}
+
+#[test]
+fn select_high_bandwidth_provider_after_validation() {
+    let faketime_file = faketime::millis_tempfile(0).expect("create faketime file");
+    faketime::enable(&faketime_file);
+    let thread_name = format!("FAKETIME={}", faketime_file.display());
+
+    let (mut node1, shared1, _chain_controller1) = setup_node(&thread_name, 3);
+    let (mut node2, shared2, _chain_controller2) = setup_node(&thread_name, 3);
+
+    node1.connect(&mut node2, RELAY_PROTOCOL_ID);
+
+    let last_block = shared1
+        .block(&shared1.chain_state().read().tip_hash())
+        .unwrap();
+    let block = {
+        let number = last_block.header().number() + 1;
+        let timestamp = last_block.header().timestamp() + 1;
+        let difficulty = shared1.calculate_difficulty(&last_block.header()).unwrap();
+        let cellbase = TransactionBuilder::default()
+            .input(CellInput::new_cellbase_input(number))
+            .output(CellOutput::default())
+            .build();
+
+        let header_builder = HeaderBuilder::default()
+            .parent_hash(last_block.header().hash().clone())
+            .number(number)
+            .timestamp(timestamp)
+            .difficulty(difficulty)
+            .cellbase_id(cellbase.hash().clone());
+
+        BlockBuilder::default()
+            .commit_transaction(cellbase)
+            .with_header_builder(header_builder)
+    };
+    let fbb = &mut FlatBufferBuilder::new();
+    let message = RelayMessage::build_compact_block(fbb, &block, &HashSet::new());
+    fbb.finish(message, None);
+    node1.broadcast(RELAY_PROTOCOL_ID, &fbb.finished_data().to_vec());
+
+    thread::spawn(move || {
+        let (signal_tx2, _signal_rx2) = channel();
+        node2.start(&signal_tx2, |_| false);
+    });
+
+    // node1 is asked for high-bandwidth mode once node2 has accepted its block
+    let (signal_tx1, signal_rx1) = channel();
+    thread::spawn(move || {
+        node1.start(&signal_tx1, |data| {
+            let msg = get_root::<RelayMessage>(data);
+            msg.payload_as_set_compact_block_mode()
+                .map_or(false, |mode| mode.high_bandwidth())
+        });
+    });
+
+    let timeout = time::Duration::from_secs(10);
+    assert!(signal_rx1.recv_timeout(timeout).is_ok());
+    assert_eq!(
+        shared2.chain_state().read().tip_hash(),
+        block.header().hash()
+    );
+}
+
+#[test]
+fn relay_uncle_by_hash() {
+    let faketime_file = faketime::millis_tempfile(0).expect("create faketime file");
+    faketime::enable(&faketime_file);
+    let thread_name = format!("FAKETIME={}", faketime_file.display());
+
+    let (mut node1, shared1, chain_controller1) = setup_node(&thread_name, 3);
+    let (mut node2, shared2, _chain_controller2) = setup_node(&thread_name, 3);
+    let (mut node3, _shared3, _chain_controller3) = setup_node(&thread_name, 3);
+
+    node1.connect(&mut node2, RELAY_PROTOCOL_ID);
+    node2.connect(&mut node3, RELAY_PROTOCOL_ID);
+
+    // a sibling of the tip, stale on node1 and never seen by node2 and node3
+    let uncle = {
+        let tip = shared1
+            .block_header(&shared1.chain_state().read().tip_hash())
+            .unwrap();
+        let parent = shared1.block_header(tip.parent_hash()).unwrap();
+        let number = tip.number();
+        let difficulty = shared1.calculate_difficulty(&parent).unwrap();
+        let cellbase = TransactionBuilder::default()
+            .input(CellInput::new_cellbase_input(number))
+            .output(CellOutput::new(1, Vec::new(), H256::zero(), None))
+            .build();
+        let header_builder = HeaderBuilder::default()
+            .parent_hash(parent.hash())
+            .number(number)
+            .timestamp(tip.timestamp() + 1)
+            .difficulty(difficulty)
+            .cellbase_id(cellbase.hash().clone());
+        BlockBuilder::default()
+            .commit_transaction(cellbase)
+            .with_header_builder(header_builder)
+    };
+    let uncle_hash = uncle.header().hash();
+    chain_controller1
+        .process_block(Arc::new(uncle))
+        .expect("process block should be OK");
+    assert_ne!(shared1.chain_state().read().tip_hash(), uncle_hash);
+
+    {
+        let fbb = &mut FlatBufferBuilder::new();
+        let message = RelayMessage::build_uncle_announcement(fbb, &uncle_hash);
+        fbb.finish(message, None);
+        node1.broadcast(RELAY_PROTOCOL_ID, &fbb.finished_data().to_vec());
+    }
+
+    let (signal_tx1, _) = channel();
+    thread::Builder::new()
+        .name(thread_name.clone())
+        .spawn(move || {
+            node1.start(&signal_tx1, |_| false);
+        })
+        .expect("thread spawn");
+
+    let (signal_tx2, signal_rx2) = channel();
+    thread::Builder::new()
+        .name(thread_name.clone())
+        .spawn(move || {
+            node2.start(&signal_tx2, |data| {
+                let msg = get_root::<RelayMessage>(data);
+                // node3 requests the uncle node2 announced
+                msg.payload_as_get_uncle_block().is_some()
+            });
+        })
+        .expect("thread spawn");
+
+    let (signal_tx3, signal_rx3) = channel();
+    thread::Builder::new()
+        .name(thread_name)
+        .spawn(move || {
+            node3.start(&signal_tx3, |data| {
+                let msg = get_root::<RelayMessage>(data);
+                msg.payload_as_uncle_block().is_some()
+            });
+        })
+        .expect("thread spawn");
+
+    let timeout = time::Duration::from_secs(10);
+    assert!(signal_rx2.recv_timeout(timeout).is_ok());
+    assert!(signal_rx3.recv_timeout(timeout).is_ok());
+    // node2 keeps the uncle without the block it belongs to
+    assert!(shared2.block_header(&uncle_hash).is_none());
+    assert!(shared2.store().get_uncle_candidate(&uncle_hash).is_some());
+}
+
 fn setup_node(
     thread_name: &str,
     height: u64,
@@ -323,7 +673,7 @@ fn setup_node(
         TransactionPoolService::new(PoolConfig::default(), shared.clone(), notify.clone());
     let tx_pool_controller = tx_pool_service.start(Some(thread_name));
 
-    let chain_service = ChainBuilder::new(shared.clone(), notify)
+    let chain_service = ChainBuilder::new(shared.clone(), notify.clone())
         .verification(false)
         .build();
     let chain_controller = chain_service.start::<&str>(None);
@@ -360,12 +710,18 @@ fn setup_node(
         chain_controller.clone(),
         shared.clone(),
         tx_pool_controller,
+        notify,
         Arc::new(Default::default()),
+        Arc::new(Config::default()),
     );
 
     let mut node = TestNode::default();
     let protocol = Arc::new(relayer) as Arc<_>;
-    node.add_protocol(RELAY_PROTOCOL_ID, &protocol, &[TX_PROPOSAL_TOKEN]);
+    node.add_protocol(
+        RELAY_PROTOCOL_ID,
+        &protocol,
+        &[TX_PROPOSAL_TOKEN, TX_ANNOUNCE_TOKEN],
+    );
     (node, shared, chain_controller)
 }
 
@@ -1,3 +1,9 @@
+use crate::{
+    BLOCKS_IN_TRANSIT_TARGET_TIME, BLOCK_DOWNLOAD_TIMEOUT, BLOCK_STALLING_TIMEOUT,
+    DOWNLOAD_RATE_WINDOW, INITIAL_BLOCKS_IN_TRANSIT_PER_PEER, MAX_BLOCKS_IN_TRANSIT_PER_PEER,
+    MAX_BLOCK_STALLS, MIN_BLOCKS_IN_TRANSIT_PER_PEER, SNAPSHOT_CHUNK_TIMEOUT,
+    SNAPSHOT_DOWNLOAD_TIMEOUT,
+};
 use bloom_filters::{
     BloomFilter, ClassicBloomFilter, DefaultBuildHashKernels, UpdatableBloomFilter,
 };
@@ -5,12 +11,16 @@ use ckb_core::block::Block;
 use ckb_core::header::{BlockNumber, Header};
 use ckb_core::transaction::Transaction;
 use ckb_network::PeerIndex;
-use ckb_util::RwLock;
+use ckb_shared::snapshot::{verify_chunk, verify_chunk_hashes, SnapshotError, SnapshotTransaction};
+use ckb_util::{Mutex, RwLock};
+use ckb_verification::MerkleRootVerifier;
 use faketime::unix_time_as_millis;
 use fnv::{FnvHashMap, FnvHashSet};
 use log::debug;
 use numext_fixed_hash::H256;
 use numext_fixed_uint::U256;
+use std::cmp;
+use std::collections::{BTreeMap, VecDeque};
 use std::hash::{BuildHasher, Hasher};
 
 // State used to enforce CHAIN_SYNC_TIMEOUT
@@ -60,17 +70,247 @@ pub struct PeerState {
 #[derive(Default)]
 pub struct Peers {
     pub state: RwLock<FnvHashMap<PeerIndex, PeerState>>,
-    pub misbehavior: RwLock<FnvHashMap<PeerIndex, u32>>,
     pub blocks_inflight: RwLock<FnvHashMap<PeerIndex, BlocksInflight>>,
     pub best_known_headers: RwLock<FnvHashMap<PeerIndex, HeaderView>>,
     pub last_common_headers: RwLock<FnvHashMap<PeerIndex, Header>>,
     pub transaction_filters: RwLock<FnvHashMap<PeerIndex, TransactionFilter>>,
+    pub download_rate: Mutex<DownloadRate>,
+}
+
+// Receive times of the blocks received during the last DOWNLOAD_RATE_WINDOW
+#[derive(Debug, Default)]
+pub struct DownloadRate {
+    received: VecDeque<u64>,
+}
+
+impl DownloadRate {
+    pub fn record(&mut self, now: u64) {
+        self.prune(now);
+        self.received.push_back(now);
+    }
+
+    pub fn blocks_in_window(&mut self, now: u64) -> usize {
+        self.prune(now);
+        self.received.len()
+    }
+
+    fn prune(&mut self, now: u64) {
+        let since = now.saturating_sub(DOWNLOAD_RATE_WINDOW);
+        while self
+            .received
+            .front()
+            .map_or(false, |received| *received < since)
+        {
+            self.received.pop_front();
+        }
+    }
+}
+
+// Snapshot of the sync progress, for monitoring
+#[derive(Debug, Clone)]
+pub struct SyncState {
+    pub is_initial_block_download: bool,
+    pub best_known_header: HeaderView,
+    pub tip_number: BlockNumber,
+    // blocks received during the last DOWNLOAD_RATE_WINDOW
+    pub download_rate: usize,
+    pub blocks_inflight: Vec<(PeerIndex, usize)>,
+    pub orphan_blocks: usize,
+}
+
+impl SyncState {
+    pub fn remaining_blocks(&self) -> BlockNumber {
+        self.best_known_header
+            .number()
+            .saturating_sub(self.tip_number)
+    }
 }
 
+// Chunks of a live cell set snapshot being downloaded. The first chunk comes with
+// the hashes of all chunks, they are checked against the trusted root from the
+// chain spec and every chunk is then checked against its hash when it arrives.
+// The last blocks up to the snapshot block are downloaded in full along with it,
+// verifying the blocks after the snapshot looks into them.
+#[derive(Debug, Clone)]
+pub struct SnapshotDownload {
+    pub block_hash: H256,
+    pub root: H256,
+    chunk_hashes: Option<Vec<H256>>,
+    transactions: BTreeMap<usize, Vec<SnapshotTransaction>>,
+    // peer and request time of the chunks in transit
+    requested: BTreeMap<usize, (PeerIndex, u64)>,
+    // hashes of the blocks to download in full, the snapshot block last
+    block_hashes: Vec<H256>,
+    blocks: FnvHashMap<H256, Block>,
+    requested_blocks: FnvHashMap<H256, u64>,
+    last_progress: u64,
+}
+
+impl SnapshotDownload {
+    pub fn new(block_hash: H256, root: H256, block_hashes: Vec<H256>, now: u64) -> Self {
+        SnapshotDownload {
+            block_hash,
+            root,
+            chunk_hashes: None,
+            transactions: BTreeMap::new(),
+            requested: BTreeMap::new(),
+            block_hashes,
+            blocks: FnvHashMap::default(),
+            requested_blocks: FnvHashMap::default(),
+            last_progress: now,
+        }
+    }
+
+    // Returns Ok(false) if the chunk was not requested from this peer, it may have
+    // timed out and been requested again. An error means the peer sent a bad chunk.
+    pub fn insert(
+        &mut self,
+        peer: PeerIndex,
+        index: usize,
+        chunk_hashes: Option<Vec<H256>>,
+        data: &[u8],
+        now: u64,
+    ) -> Result<bool, SnapshotError> {
+        match self.requested.get(&index) {
+            Some((requested_from, _)) if *requested_from == peer => {}
+            _ => return Ok(false),
+        }
+        self.requested.remove(&index);
+        if self.chunk_hashes.is_none() {
+            let chunk_hashes = chunk_hashes.ok_or(SnapshotError::RootMismatch)?;
+            verify_chunk_hashes(&chunk_hashes, &self.root)?;
+            self.chunk_hashes = Some(chunk_hashes);
+        }
+        let chunk_hashes = self.chunk_hashes.as_ref().expect("chunk hashes verified");
+        let transactions = verify_chunk(chunk_hashes, index, data)?;
+        self.transactions.insert(index, transactions);
+        self.last_progress = now;
+        Ok(true)
+    }
+
+    pub fn is_snapshot_block(&self, hash: &H256) -> bool {
+        self.block_hashes.contains(hash)
+    }
+
+    // The header is trusted, it is on the checkpointed chain. Returns false if the body
+    // doesn't match it.
+    pub fn insert_block(&mut self, block: Block, now: u64) -> bool {
+        if MerkleRootVerifier::new().verify(&block).is_err()
+            || &block.cal_uncles_hash() != block.header().uncles_hash()
+            || block.uncles().len() != block.header().uncles_count() as usize
+        {
+            return false;
+        }
+        let hash = block.header().hash();
+        self.requested_blocks.remove(&hash);
+        if self.blocks.insert(hash, block).is_none() {
+            self.last_progress = now;
+        }
+        true
+    }
+
+    // Missing blocks which are not in transit, or whose request timed out
+    pub fn blocks_to_request(&mut self, now: u64) -> Vec<H256> {
+        let hashes = self
+            .block_hashes
+            .iter()
+            .filter(|hash| {
+                !self.blocks.contains_key(hash)
+                    && self
+                        .requested_blocks
+                        .get(hash)
+                        .map_or(true, |requested_at| {
+                            now >= *requested_at + BLOCK_DOWNLOAD_TIMEOUT
+                        })
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        for hash in &hashes {
+            self.requested_blocks.insert(hash.clone(), now);
+        }
+        hashes
+    }
+
+    // Neither a chunk nor a block arrived for too long
+    pub fn is_stalled(&self, now: u64) -> bool {
+        now > self.last_progress + SNAPSHOT_DOWNLOAD_TIMEOUT
+    }
+
+    // Missing chunks which are not in transit, or whose request timed out, spread
+    // over the peers. Only the first chunk is requested until the chunk hashes are known.
+    pub fn chunks_to_request(
+        &mut self,
+        peers: &[PeerIndex],
+        now: u64,
+        limit: usize,
+    ) -> Vec<(PeerIndex, usize)> {
+        if peers.is_empty() {
+            return Vec::new();
+        }
+        let count = self.chunk_hashes.as_ref().map_or(1, |hashes| hashes.len());
+        let in_transit = self
+            .requested
+            .values()
+            .filter(|(_, requested_at)| now < *requested_at + SNAPSHOT_CHUNK_TIMEOUT)
+            .count();
+        let indexes = (0..count)
+            .filter(|index| {
+                !self.transactions.contains_key(index)
+                    && self.requested.get(index).map_or(true, |(_, requested_at)| {
+                        now >= *requested_at + SNAPSHOT_CHUNK_TIMEOUT
+                    })
+            })
+            .take(limit.saturating_sub(in_transit))
+            .collect::<Vec<_>>();
+        indexes
+            .into_iter()
+            .enumerate()
+            .map(|(i, index)| {
+                let peer = peers[i % peers.len()];
+                self.requested.insert(index, (peer, now));
+                (peer, index)
+            })
+            .collect()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.chunk_hashes
+            .as_ref()
+            .map_or(false, |hashes| hashes.len() == self.transactions.len())
+            && self.blocks.len() == self.block_hashes.len()
+    }
+
+    // The live transactions and the downloaded blocks in chain order
+    pub fn into_parts(mut self) -> (Vec<SnapshotTransaction>, Vec<Block>) {
+        let blocks = self
+            .block_hashes
+            .iter()
+            .filter_map(|hash| self.blocks.remove(hash))
+            .collect();
+        let transactions = self
+            .transactions
+            .into_iter()
+            .flat_map(|(_, transactions)| transactions)
+            .collect();
+        (transactions, blocks)
+    }
+}
+
+// Blocks requested from a peer. The number of blocks a peer may have in transit
+// adapts to how fast it delivers them while it is busy.
 #[derive(Debug, Clone)]
 pub struct BlocksInflight {
+    // last delivery, or the first request after the peer was idle
     pub timestamp: u64,
     pub blocks: FnvHashSet<H256>,
+    limit: usize,
+    avg_block_interval: Option<u64>,
+    // the block holding back the download window and since when
+    stalling: Option<(H256, u64)>,
+    // blocks given up after a stall, they are requested from other peers
+    stalled_blocks: FnvHashSet<H256>,
+    // consecutive stalls without any delivery in between
+    stalls: u32,
 }
 
 impl Default for BlocksInflight {
@@ -78,6 +318,11 @@ impl Default for BlocksInflight {
         BlocksInflight {
             blocks: FnvHashSet::default(),
             timestamp: unix_time_as_millis(),
+            limit: INITIAL_BLOCKS_IN_TRANSIT_PER_PEER,
+            avg_block_interval: None,
+            stalling: None,
+            stalled_blocks: FnvHashSet::default(),
+            stalls: 0,
         }
     }
 }
@@ -91,11 +336,39 @@ impl BlocksInflight {
         self.len() == 0
     }
 
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.limit.saturating_sub(self.len())
+    }
+
+    pub fn contains(&self, hash: &H256) -> bool {
+        self.blocks.contains(hash)
+    }
+
+    pub fn has_stalled_on(&self, hash: &H256) -> bool {
+        self.stalled_blocks.contains(hash)
+    }
+
     pub fn insert(&mut self, hash: H256) -> bool {
+        // the delivery time is measured from the first request after being idle
+        if self.blocks.is_empty() {
+            self.update_timestamp();
+        }
         self.blocks.insert(hash)
     }
 
     pub fn remove(&mut self, hash: &H256) -> bool {
+        self.stalled_blocks.remove(hash);
+        if self
+            .stalling
+            .as_ref()
+            .map_or(false, |(stalling, _)| stalling == hash)
+        {
+            self.stalling = None;
+        }
         self.blocks.remove(hash)
     }
 
@@ -105,21 +378,72 @@ impl BlocksInflight {
 
     pub fn clear(&mut self) {
         self.blocks.clear();
+        self.stalling = None;
     }
-}
 
-impl Peers {
-    pub fn misbehavior(&self, peer: PeerIndex, score: u32) {
-        if score == 0 {
-            return;
+    // Returns false if the block was not requested from this peer
+    pub fn block_delivered(&mut self, hash: &H256, now: u64) -> bool {
+        if !self.remove(hash) {
+            return false;
         }
+        let interval = now.saturating_sub(self.timestamp);
+        let avg = match self.avg_block_interval {
+            Some(avg) => (avg * 3 + interval) / 4,
+            None => interval,
+        };
+        self.avg_block_interval = Some(avg);
+        self.limit = ((BLOCKS_IN_TRANSIT_TARGET_TIME / cmp::max(avg, 1)) as usize)
+            .max(MIN_BLOCKS_IN_TRANSIT_PER_PEER)
+            .min(MAX_BLOCKS_IN_TRANSIT_PER_PEER);
+        self.timestamp = now;
+        self.stalls = 0;
+        true
+    }
+
+    pub fn is_timeout(&self, now: u64) -> bool {
+        !self.is_empty() && self.timestamp < now.saturating_sub(BLOCK_DOWNLOAD_TIMEOUT)
+    }
+
+    // Nothing was delivered within BLOCK_DOWNLOAD_TIMEOUT, request everything again
+    pub fn on_timeout(&mut self) {
+        self.clear();
+        self.on_stall();
+    }
 
-        let mut map = self.misbehavior.write();
-        map.entry(peer)
-            .and_modify(|e| *e += score)
-            .or_insert_with(|| score);
+    fn on_stall(&mut self) {
+        self.stalls += 1;
+        self.limit = cmp::max(self.limit / 2, MIN_BLOCKS_IN_TRANSIT_PER_PEER);
     }
 
+    pub fn mark_stalling(&mut self, hash: H256, now: u64) {
+        if self.stalling.is_none() && self.contains(&hash) {
+            self.stalling = Some((hash, now));
+        }
+    }
+
+    // Gives up the stalling block after BLOCK_STALLING_TIMEOUT,
+    // returns the block so it can be requested from other peers.
+    pub fn check_stalling(&mut self, now: u64) -> Option<H256> {
+        match self.stalling.take() {
+            Some((hash, since)) if since.saturating_add(BLOCK_STALLING_TIMEOUT) < now => {
+                self.blocks.remove(&hash);
+                self.stalled_blocks.insert(hash.clone());
+                self.on_stall();
+                Some(hash)
+            }
+            stalling => {
+                self.stalling = stalling;
+                None
+            }
+        }
+    }
+
+    pub fn is_persistent_staller(&self) -> bool {
+        self.stalls >= MAX_BLOCK_STALLS
+    }
+}
+
+impl Peers {
     pub fn on_connected(&self, peer: PeerIndex, predicted_headers_sync_time: u64, protect: bool) {
         self.state
             .write()
@@ -167,18 +491,30 @@ impl Peers {
     pub fn disconnected(&self, peer: PeerIndex) {
         self.state.write().remove(&peer);
         self.best_known_headers.write().remove(&peer);
-        // self.misbehavior.write().remove(peer);
         self.blocks_inflight.write().remove(&peer);
         self.last_common_headers.write().remove(&peer);
     }
 
     pub fn block_received(&self, peer: PeerIndex, block: &Block) {
+        let hash = block.header().hash();
+        let now = unix_time_as_millis();
+        self.download_rate.lock().record(now);
         let mut blocks_inflight = self.blocks_inflight.write();
-        debug!(target: "sync", "block_received from peer {} {} {:?}", peer, block.header().number(), block.header().hash());
-        blocks_inflight.entry(peer).and_modify(|inflight| {
-            inflight.remove(&block.header().hash());
-            inflight.update_timestamp();
-        });
+        debug!(
+            target: "sync",
+            "block_received from peer {} {} {:?}",
+            peer,
+            block.header().number(),
+            hash
+        );
+        for (inflight_peer, inflight) in blocks_inflight.iter_mut() {
+            if *inflight_peer == peer {
+                inflight.block_delivered(&hash, now);
+            } else {
+                // a stalled block may have been requested from several peers
+                inflight.remove(&hash);
+            }
+        }
     }
 
     pub fn set_last_common_header(&self, peer: PeerIndex, header: &Header) {
@@ -13,6 +13,12 @@ pub enum Error {
     InvalidMessage,
     #[fail(display = "invalid recovery_id")]
     InvalidRecoveryId,
+    #[fail(display = "invalid multisig config")]
+    InvalidMultisigConfig,
+    #[fail(display = "signer is not in multisig pubkeys")]
+    UnknownSigner,
+    #[fail(display = "not enough signatures")]
+    NotEnoughSignatures,
     #[fail(display = "{}", _0)]
     Other(String),
 }
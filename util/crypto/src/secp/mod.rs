@@ -11,12 +11,14 @@ lazy_static! {
 
 mod error;
 mod generator;
+mod multisig;
 mod privkey;
 mod pubkey;
 mod signature;
 
 pub use self::error::Error;
 pub use self::generator::Generator;
+pub use self::multisig::{MultisigConfig, MAX_MULTISIG_PUBKEYS};
 pub use self::privkey::Privkey;
 pub use self::pubkey::Pubkey;
 pub use self::signature::Signature;
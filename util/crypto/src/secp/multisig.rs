@@ -0,0 +1,175 @@
+use super::error::Error;
+use super::pubkey::Pubkey;
+use super::signature::Signature;
+use super::Message;
+use faster_hex::hex_string;
+
+/// Maximum number of pubkeys supported by the multisig system cell
+pub const MAX_MULTISIG_PUBKEYS: usize = 16;
+
+/// M-of-N multisig lock config, the layout of `signed_args` must match
+/// what the multisig system cell(script/testdata/multisig.rs) expects.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MultisigConfig {
+    threshold: u8,
+    pubkeys: Vec<Pubkey>,
+}
+
+fn hex(bytes: &[u8]) -> Vec<u8> {
+    hex_string(bytes).expect("hex string").into_bytes()
+}
+
+impl MultisigConfig {
+    pub fn new(threshold: u8, pubkeys: Vec<Pubkey>) -> Result<Self, Error> {
+        if threshold == 0
+            || pubkeys.len() > MAX_MULTISIG_PUBKEYS
+            || threshold as usize > pubkeys.len()
+        {
+            return Err(Error::InvalidMultisigConfig);
+        }
+        for (i, pubkey) in pubkeys.iter().enumerate() {
+            if pubkeys[i + 1..].contains(pubkey) {
+                return Err(Error::InvalidMultisigConfig);
+            }
+        }
+        Ok(MultisigConfig { threshold, pubkeys })
+    }
+
+    pub fn threshold(&self) -> u8 {
+        self.threshold
+    }
+
+    pub fn pubkeys(&self) -> &[Pubkey] {
+        &self.pubkeys
+    }
+
+    /// Signed args of the lock script: threshold, pubkeys count, then
+    /// the serialized pubkeys, all in lower case hex format.
+    pub fn signed_args(&self) -> Vec<Vec<u8>> {
+        let mut args = Vec::with_capacity(self.pubkeys.len() + 2);
+        args.push(hex(&[self.threshold]));
+        args.push(hex(&[self.pubkeys.len() as u8]));
+        args.extend(self.pubkeys.iter().map(|pubkey| hex(&pubkey.serialize())));
+        args
+    }
+
+    /// Recovers the signer of each signature and orders the signatures the
+    /// same way as the pubkeys, duplicated signers are only kept once.
+    pub fn sort_signatures(
+        &self,
+        message: &Message,
+        signatures: &[Signature],
+    ) -> Result<Vec<Signature>, Error> {
+        let mut indexed = Vec::with_capacity(signatures.len());
+        for signature in signatures {
+            let signer = signature.recover(message)?;
+            let index = self
+                .pubkeys
+                .iter()
+                .position(|pubkey| pubkey == &signer)
+                .ok_or(Error::UnknownSigner)?;
+            indexed.push((index, signature.clone()));
+        }
+        indexed.sort_by_key(|(index, _)| *index);
+        indexed.dedup_by_key(|(index, _)| *index);
+        Ok(indexed
+            .into_iter()
+            .map(|(_, signature)| signature)
+            .collect())
+    }
+
+    /// Builds the signature part of the unlock args, in the order required
+    /// by the script. Only the first `threshold` signatures are used.
+    pub fn signature_args(
+        &self,
+        message: &Message,
+        signatures: &[Signature],
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        let sorted = self.sort_signatures(message, signatures)?;
+        if sorted.len() < self.threshold as usize {
+            return Err(Error::NotEnoughSignatures);
+        }
+        Ok(sorted
+            .iter()
+            .take(self.threshold as usize)
+            .map(|signature| hex(&signature.serialize_der()))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Generator, Privkey};
+    use super::*;
+
+    fn keypairs(n: usize) -> Vec<(Privkey, Pubkey)> {
+        (0..n)
+            .map(|_| Generator::new().random_keypair().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let pubkeys: Vec<Pubkey> = keypairs(3).into_iter().map(|(_, p)| p).collect();
+        assert_eq!(
+            MultisigConfig::new(0, pubkeys.clone()),
+            Err(Error::InvalidMultisigConfig)
+        );
+        assert_eq!(
+            MultisigConfig::new(4, pubkeys.clone()),
+            Err(Error::InvalidMultisigConfig)
+        );
+        let duplicated = vec![pubkeys[0].clone(), pubkeys[0].clone()];
+        assert_eq!(
+            MultisigConfig::new(1, duplicated),
+            Err(Error::InvalidMultisigConfig)
+        );
+        assert!(MultisigConfig::new(2, pubkeys).is_ok());
+    }
+
+    #[test]
+    fn test_signed_args() {
+        let pubkeys: Vec<Pubkey> = keypairs(3).into_iter().map(|(_, p)| p).collect();
+        let config = MultisigConfig::new(2, pubkeys.clone()).unwrap();
+        let args = config.signed_args();
+        assert_eq!(args.len(), 5);
+        assert_eq!(args[0], b"02".to_vec());
+        assert_eq!(args[1], b"03".to_vec());
+        assert_eq!(args[2], hex(&pubkeys[0].serialize()));
+    }
+
+    #[test]
+    fn test_signature_args() {
+        let keys = keypairs(3);
+        let pubkeys: Vec<Pubkey> = keys.iter().map(|(_, p)| p.clone()).collect();
+        let config = MultisigConfig::new(2, pubkeys).unwrap();
+        let message = Message::default();
+
+        let sig0 = keys[0].0.sign_recoverable(&message).unwrap();
+        let sig2 = keys[2].0.sign_recoverable(&message).unwrap();
+
+        assert_eq!(
+            config.signature_args(&message, &[sig2.clone()]),
+            Err(Error::NotEnoughSignatures)
+        );
+        assert_eq!(
+            config.signature_args(&message, &[sig2.clone(), sig2.clone()]),
+            Err(Error::NotEnoughSignatures)
+        );
+
+        let args = config
+            .signature_args(&message, &[sig2.clone(), sig0.clone()])
+            .unwrap();
+        assert_eq!(
+            args,
+            vec![hex(&sig0.serialize_der()), hex(&sig2.serialize_der())]
+        );
+
+        let (other, _) = Generator::new().random_keypair().unwrap();
+        let unknown = other.sign_recoverable(&message).unwrap();
+        assert_eq!(
+            config.signature_args(&message, &[sig0, unknown]),
+            Err(Error::UnknownSigner)
+        );
+    }
+}
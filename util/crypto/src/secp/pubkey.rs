@@ -7,7 +7,7 @@ use secp256k1::key;
 use secp256k1::Message as SecpMessage;
 use std::{fmt, ops};
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Pubkey {
     inner: H512,
 }
@@ -52,6 +52,13 @@ impl Pubkey {
         Ok(())
     }
 
+    /// Parses a serialized pubkey, both compressed and uncompressed forms are accepted
+    pub fn from_slice(data: &[u8]) -> Result<Self, Error> {
+        let context = &SECP256K1;
+        let pubkey = key::PublicKey::from_slice(context, data)?;
+        Ok(pubkey.into())
+    }
+
     pub fn serialize(&self) -> Vec<u8> {
         let context = &SECP256K1;
 
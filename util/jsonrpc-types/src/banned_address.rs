@@ -0,0 +1,10 @@
+use serde_derive::{Deserialize, Serialize};
+
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+pub struct BannedAddress {
+    // IP subnet, e.g. "192.168.0.0/16", a single IP has full prefix length
+    pub address: String,
+    // unix timestamp in milliseconds when the ban expires
+    pub ban_until: u64,
+    pub reason: String,
+}
@@ -1,10 +1,16 @@
+mod banned_address;
 mod block_template;
 mod blockchain;
 mod bytes;
 mod cell;
 mod local_node;
+mod net_time;
+mod peer;
 mod proposal_short_id;
+mod sync_state;
+mod traffic_stats;
 
+pub use self::banned_address::BannedAddress;
 pub use self::block_template::{
     BlockTemplate, CellbaseTemplate, TransactionTemplate, UncleTemplate,
 };
@@ -12,4 +18,8 @@ pub use self::blockchain::{Block, Header, OutPoint, Transaction, UncleBlock};
 pub use self::bytes::Bytes;
 pub use self::cell::{CellOutputWithOutPoint, CellWithStatus};
 pub use self::local_node::{LocalNode, NodeAddress};
+pub use self::net_time::NetworkTime;
+pub use self::peer::{Peer, PeerBestKnownHeader, PeerIdentifyInfo};
+pub use self::sync_state::{PeerInflightBlocks, SyncState};
+pub use self::traffic_stats::{MessageTraffic, PeerTraffic, TrafficStats};
 pub use jsonrpc_core::types::{error, id, params, request, response, version};
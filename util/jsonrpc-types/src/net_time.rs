@@ -0,0 +1,12 @@
+use serde_derive::{Deserialize, Serialize};
+
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+pub struct NetworkTime {
+    // local time adjusted by the offset, used to check block timestamps
+    pub network_time: u64,
+    // local time minus network time, the median of the samples,
+    // 0 if there are too few samples or the median is over the tolerant offset
+    pub offset: i64,
+    // local time minus peer time, collected from outbound peers
+    pub samples: Vec<i64>,
+}
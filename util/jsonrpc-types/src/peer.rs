@@ -0,0 +1,32 @@
+use numext_fixed_hash::H256;
+use numext_fixed_uint::U256;
+use serde_derive::{Deserialize, Serialize};
+
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+pub struct Peer {
+    pub node_id: String,
+    pub address: String,
+    pub is_outbound: bool,
+    pub network_group: String,
+    pub score: i32,
+    pub identify_info: Option<PeerIdentifyInfo>,
+    // ping round-trip time in milliseconds
+    pub ping: Option<u64>,
+    // unix timestamp in milliseconds
+    pub connected_time: Option<u64>,
+    pub best_known_header: Option<PeerBestKnownHeader>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+pub struct PeerIdentifyInfo {
+    pub client_version: String,
+    pub protocol_version: String,
+    pub supported_protocols: Vec<String>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+pub struct PeerBestKnownHeader {
+    pub number: u64,
+    pub hash: H256,
+    pub total_difficulty: U256,
+}
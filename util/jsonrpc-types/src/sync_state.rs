@@ -0,0 +1,22 @@
+use numext_fixed_hash::H256;
+use serde_derive::{Deserialize, Serialize};
+
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+pub struct SyncState {
+    pub is_initial_block_download: bool,
+    pub best_known_header_number: u64,
+    pub best_known_header_hash: H256,
+    pub tip_number: u64,
+    // best_known_header_number - tip_number
+    pub remaining_blocks: u64,
+    // blocks received during the last minute
+    pub download_rate: u64,
+    pub inflight_blocks: Vec<PeerInflightBlocks>,
+    pub orphan_blocks: u64,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+pub struct PeerInflightBlocks {
+    pub node_id: String,
+    pub count: u64,
+}
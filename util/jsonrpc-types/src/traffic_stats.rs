@@ -0,0 +1,26 @@
+use serde_derive::{Deserialize, Serialize};
+
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+pub struct TrafficStats {
+    // totals since the node started
+    pub protocols: Vec<MessageTraffic>,
+    // connected peers only
+    pub peers: Vec<PeerTraffic>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+pub struct PeerTraffic {
+    pub node_id: String,
+    pub messages: Vec<MessageTraffic>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash, Debug)]
+pub struct MessageTraffic {
+    pub protocol_id: String,
+    // payload variant name, e.g. "Headers" or "CompactBlock"
+    pub message_type: String,
+    pub sent_messages: u64,
+    pub sent_bytes: u64,
+    pub received_messages: u64,
+    pub received_bytes: u64,
+}
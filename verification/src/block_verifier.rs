@@ -4,7 +4,9 @@ use crate::{InputVerifier, TransactionVerifier, Verifier};
 use ckb_core::block::Block;
 use ckb_core::cell::{resolve_transaction, CellProvider, CellStatus, ResolvedTransaction};
 use ckb_core::header::Header;
+use ckb_core::script::Script;
 use ckb_core::transaction::{Capacity, CellInput, OutPoint};
+use ckb_core::uncle::UncleBlock;
 use ckb_core::Cycle;
 use ckb_merkle_tree::merkle_root;
 use ckb_shared::shared::ChainProvider;
@@ -99,6 +101,14 @@ impl<CP: ChainProvider + Clone> CellbaseVerifier<CP> {
         {
             return Err(Error::Cellbase(CellbaseError::InvalidInput));
         }
+        // cellbase inputs are not unique enough to derive type ids from
+        if cellbase_transaction
+            .outputs()
+            .iter()
+            .any(|output| output.type_.as_ref().map_or(false, Script::is_type_id))
+        {
+            return Err(Error::Cellbase(CellbaseError::InvalidTypeId));
+        }
         let block_reward = self.provider.block_reward(block.header().number());
         let mut fee = 0;
         for transaction in block.commit_transactions().iter().skip(1) {
@@ -165,7 +175,7 @@ impl DuplicateVerifier {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct MerkleRootVerifier {}
 
 impl MerkleRootVerifier {
@@ -353,24 +363,7 @@ impl<CP: ChainProvider + Clone> UnclesVerifier<CP> {
                 return Err(Error::Uncles(UnclesError::InvalidInclude(uncle_hash)));
             }
 
-            let proposals = uncle
-                .proposal_transactions()
-                .iter()
-                .map(|id| id.hash())
-                .collect::<Vec<_>>();
-
-            if uncle_header.txs_proposal() != &merkle_root(&proposals[..]) {
-                return Err(Error::Uncles(UnclesError::ProposalTransactionsRoot));
-            }
-
-            let mut seen = HashSet::with_capacity(uncle.proposal_transactions().len());
-            if !uncle
-                .proposal_transactions()
-                .iter()
-                .all(|id| seen.insert(id))
-            {
-                return Err(Error::Uncles(UnclesError::ProposalTransactionDuplicate));
-            }
+            verify_uncle_proposals(uncle)?;
 
             if !self
                 .provider
@@ -388,14 +381,43 @@ impl<CP: ChainProvider + Clone> UnclesVerifier<CP> {
     }
 }
 
+// also checked on relayed uncles before they become candidates
+pub fn verify_uncle_proposals(uncle: &UncleBlock) -> Result<(), Error> {
+    let proposals = uncle
+        .proposal_transactions()
+        .iter()
+        .map(|id| id.hash())
+        .collect::<Vec<_>>();
+
+    if uncle.header().txs_proposal() != &merkle_root(&proposals[..]) {
+        return Err(Error::Uncles(UnclesError::ProposalTransactionsRoot));
+    }
+
+    let mut seen = HashSet::with_capacity(uncle.proposal_transactions().len());
+    if !uncle
+        .proposal_transactions()
+        .iter()
+        .all(|id| seen.insert(id))
+    {
+        return Err(Error::Uncles(UnclesError::ProposalTransactionDuplicate));
+    }
+    Ok(())
+}
+
 #[derive(Clone)]
 pub struct TransactionsVerifier {
     max_cycles: Cycle,
+    // the blocks are the last checkpoint block or its ancestors, their transactions are
+    // assumed valid, only inputs are resolved and no script is executed
+    checkpointed: bool,
 }
 
 impl TransactionsVerifier {
-    pub fn new(max_cycles: Cycle) -> Self {
-        TransactionsVerifier { max_cycles }
+    pub fn new(max_cycles: Cycle, checkpointed: bool) -> Self {
+        TransactionsVerifier {
+            max_cycles,
+            checkpointed,
+        }
     }
 
     pub fn verify<F: Fn(&OutPoint) -> CellStatus>(
@@ -439,7 +461,12 @@ impl TransactionsVerifier {
             .par_iter()
             .enumerate()
             .map(|(index, tx)| {
-                if let Some(cycles) = txs_verify_cache
+                if self.checkpointed {
+                    InputVerifier::new(&tx)
+                        .verify()
+                        .map_err(|e| Error::Transactions((index, e)))
+                        .map(|_| (None, 0))
+                } else if let Some(cycles) = txs_verify_cache
                     .as_ref()
                     .and_then(|cache| cache.get(&tx.transaction.hash()))
                 {
@@ -59,6 +59,7 @@ pub enum CellbaseError {
     InvalidReward,
     InvalidQuantity,
     InvalidPosition,
+    InvalidTypeId,
 }
 
 #[derive(Debug, PartialEq, Clone, Eq)]
@@ -114,7 +115,7 @@ pub enum DifficultyError {
     AncestorNotFound,
 }
 
-#[derive(Debug, PartialEq, Clone, Copy, Eq)]
+#[derive(Debug, PartialEq, Clone, Eq)]
 pub enum TransactionError {
     NullInput,
     /// Occur output's bytes_len exceed capacity
@@ -4,7 +4,6 @@ use crate::shared::ALLOWED_FUTURE_BLOCKTIME;
 use ckb_core::header::Header;
 use ckb_pow::PowEngine;
 use ckb_shared::block_median_time_context::BlockMedianTimeContext;
-use faketime::unix_time_as_millis;
 use numext_fixed_uint::U256;
 use std::marker::PhantomData;
 use std::sync::Arc;
@@ -59,9 +58,9 @@ pub struct TimestampVerifier<'a, M> {
 impl<'a, M: BlockMedianTimeContext> TimestampVerifier<'a, M> {
     pub fn new(block_median_time_context: M, header: &'a Header) -> Self {
         TimestampVerifier {
+            now: block_median_time_context.now(),
             block_median_time_context,
             header,
-            now: unix_time_as_millis(),
         }
     }
 
@@ -7,9 +7,13 @@ mod transaction_verifier;
 #[cfg(test)]
 mod tests;
 
-pub use crate::block_verifier::{BlockVerifier, HeaderResolverWrapper, TransactionsVerifier};
+pub use crate::block_verifier::{
+    verify_uncle_proposals, BlockVerifier, HeaderResolverWrapper, MerkleRootVerifier,
+    TransactionsVerifier,
+};
 pub use crate::error::{Error, TransactionError};
 pub use crate::header_verifier::{HeaderResolver, HeaderVerifier};
+pub use crate::shared::ALLOWED_FUTURE_BLOCKTIME;
 pub use crate::transaction_verifier::{InputVerifier, TransactionVerifier};
 
 pub trait Verifier {
@@ -1,8 +1,13 @@
-use super::super::block_verifier::{BlockVerifier, CellbaseVerifier, EmptyVerifier};
+use super::super::block_verifier::{
+    BlockVerifier, CellbaseVerifier, EmptyVerifier, TransactionsVerifier,
+};
 use super::super::error::{CellbaseError, Error as VerifyError};
 use super::dummy::DummyChainProvider;
 use crate::Verifier;
+use ckb_chain_spec::consensus::Consensus;
 use ckb_core::block::BlockBuilder;
+use ckb_core::cell::CellStatus;
+use ckb_core::script::Script;
 use ckb_core::transaction::{CellInput, CellOutput, OutPoint, Transaction, TransactionBuilder};
 use ckb_core::Capacity;
 use ckb_shared::error::SharedError;
@@ -231,6 +236,34 @@ pub fn test_cellbase_with_two_outputs_and_more_rewards_than_maximum() {
     );
 }
 
+#[test]
+pub fn test_cellbase_with_type_id() {
+    let cellbase_transaction = TransactionBuilder::default()
+        .input(CellInput::new_cellbase_input(0))
+        .output(CellOutput::new(
+            100,
+            Vec::new(),
+            H256::default(),
+            Some(Script::type_id(&OutPoint::null(), 0)),
+        ))
+        .build();
+
+    let block = BlockBuilder::default()
+        .commit_transaction(cellbase_transaction)
+        .build();
+
+    let provider = DummyChainProvider {
+        block_reward: 100,
+        transaction_fees: HashMap::new(),
+    };
+
+    let verifier = CellbaseVerifier::new(provider);
+    assert_eq!(
+        verifier.verify(&block),
+        Err(VerifyError::Cellbase(CellbaseError::InvalidTypeId))
+    );
+}
+
 #[test]
 pub fn test_empty_transactions() {
     let block = BlockBuilder::default().build();
@@ -253,3 +286,28 @@ pub fn test_empty_transactions() {
         Err(VerifyError::CommitTransactionsEmpty)
     );
 }
+
+#[test]
+pub fn test_skip_scripts_of_checkpointed_blocks() {
+    // the unlock script can't be executed, only the lock hash matches
+    let unlock = Script::default();
+    let transaction = TransactionBuilder::default()
+        .input(CellInput::new(
+            OutPoint::new(H256::from_trimmed_hex_str("1").unwrap(), 0),
+            unlock.clone(),
+        ))
+        .output(CellOutput::new(100, Vec::new(), H256::default(), None))
+        .build();
+    let cell_resolver =
+        |_: &OutPoint| CellStatus::Live(CellOutput::new(100, Vec::new(), unlock.type_hash(), None));
+    let block = BlockBuilder::default()
+        .commit_transaction(create_cellbase_transaction())
+        .commit_transaction(transaction)
+        .build();
+
+    let max_cycles = Consensus::default().max_block_cycles();
+    let verifier = TransactionsVerifier::new(max_cycles, true);
+    assert!(verifier.verify(&mut None, &block, cell_resolver).is_ok());
+    let verifier = TransactionsVerifier::new(max_cycles, false);
+    assert!(verifier.verify(&mut None, &block, cell_resolver).is_err());
+}
@@ -1,9 +1,10 @@
 use super::super::transaction_verifier::{
-    CapacityVerifier, DuplicateInputsVerifier, EmptyVerifier, NullVerifier,
+    CapacityVerifier, DuplicateInputsVerifier, EmptyVerifier, InputVerifier, NullVerifier,
 };
 use crate::error::TransactionError;
 use ckb_core::cell::CellStatus;
 use ckb_core::cell::ResolvedTransaction;
+use ckb_core::script::Script;
 use ckb_core::transaction::{CellInput, CellOutput, OutPoint, TransactionBuilder};
 use numext_fixed_hash::H256;
 
@@ -98,3 +99,57 @@ pub fn test_duplicate_inputs() {
         Some(TransactionError::DuplicateInputs)
     );
 }
+
+#[test]
+pub fn test_unlock_versions_cannot_open_zero_lock() {
+    let zero_lock_cell = CellOutput::new(100, Vec::new(), H256::zero(), None);
+    for version in 0..=2 {
+        let unlock = Script::new(version, Vec::new(), None, Some(vec![1; 10]), Vec::new());
+        assert_ne!(unlock.type_hash(), H256::zero());
+
+        let transaction = TransactionBuilder::default()
+            .input(CellInput::new(
+                OutPoint::new(H256::from_trimmed_hex_str("1").unwrap(), 0),
+                unlock,
+            ))
+            .build();
+        let rtx = ResolvedTransaction {
+            transaction,
+            dep_cells: Vec::new(),
+            input_cells: vec![CellStatus::Live(zero_lock_cell.clone())],
+        };
+        let verifier = InputVerifier::new(&rtx);
+
+        assert_eq!(
+            verifier.verify().err(),
+            Some(TransactionError::InvalidScript)
+        );
+    }
+}
+
+#[test]
+pub fn test_type_hash_includes_version() {
+    let script = Script::new(0, Vec::new(), None, Some(vec![1; 10]), Vec::new());
+    let mut upgraded = script.clone();
+    upgraded.version = 1;
+    assert_ne!(script.type_hash(), upgraded.type_hash());
+
+    let cell = CellOutput::new(100, Vec::new(), script.type_hash(), None);
+    let transaction = TransactionBuilder::default()
+        .input(CellInput::new(
+            OutPoint::new(H256::from_trimmed_hex_str("1").unwrap(), 0),
+            upgraded,
+        ))
+        .build();
+    let rtx = ResolvedTransaction {
+        transaction,
+        dep_cells: Vec::new(),
+        input_cells: vec![CellStatus::Live(cell)],
+    };
+    let verifier = InputVerifier::new(&rtx);
+
+    assert_eq!(
+        verifier.verify().err(),
+        Some(TransactionError::InvalidScript)
+    );
+}